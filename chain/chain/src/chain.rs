@@ -2029,6 +2029,65 @@ impl Chain {
         }
         Ok(res)
     }
+
+    /// Applies the chunk of the given shard in the given block again, the same way it was applied
+    /// when the block was processed. Nothing is saved, this is only used to observe the execution.
+    pub fn reapply_chunk(
+        &mut self,
+        block_hash: &CryptoHash,
+        shard_id: ShardId,
+    ) -> Result<ApplyTransactionResult, Error> {
+        let block = self.get_block(block_hash)?.clone();
+        let prev_block = self.get_block(block.header().prev_hash())?.clone();
+        let chunk_header = block
+            .chunks()
+            .get(shard_id as usize)
+            .cloned()
+            .ok_or_else(|| ErrorKind::Other(format!("Shard {} does not exist", shard_id)))?;
+        if chunk_header.height_included() == block.header().height() {
+            let mut chain_store_update = ChainStoreUpdate::new(&mut self.store);
+            let receipt_proof_response = chain_store_update.get_incoming_receipts_for_shard(
+                shard_id,
+                *block_hash,
+                prev_block.chunks()[shard_id as usize].height_included(),
+            )?;
+            let receipts = collect_receipts_from_response(&receipt_proof_response);
+            let chunk = self.store.get_chunk_clone_from_header(&chunk_header)?;
+            let chunk_inner = chunk.cloned_header().take_inner();
+            self.runtime_adapter.apply_transactions(
+                shard_id,
+                &chunk_inner.prev_state_root,
+                chunk_header.height_included(),
+                block.header().raw_timestamp(),
+                &chunk_header.prev_block_hash(),
+                block.hash(),
+                &receipts,
+                chunk.transactions(),
+                &chunk_inner.validator_proposals,
+                prev_block.header().gas_price(),
+                chunk_inner.gas_limit,
+                &block.header().challenges_result(),
+                *block.header().random_value(),
+            )
+        } else {
+            let prev_extra = self.get_chunk_extra(prev_block.hash(), shard_id)?.clone();
+            self.runtime_adapter.apply_transactions(
+                shard_id,
+                &prev_extra.state_root,
+                block.header().height(),
+                block.header().raw_timestamp(),
+                prev_block.hash(),
+                block.hash(),
+                &[],
+                &[],
+                &prev_extra.validator_proposals,
+                block.header().gas_price(),
+                prev_extra.gas_limit,
+                &block.header().challenges_result(),
+                *block.header().random_value(),
+            )
+        }
+    }
 }
 
 /// Implement block merkle proof retrieval.
@@ -2840,6 +2899,7 @@ impl<'a> ChainUpdate<'a> {
                     // Save receipt and transaction results.
                     self.chain_store_update.save_outcomes_with_proofs(
                         &block.hash(),
+                        block.header().height(),
                         shard_id,
                         apply_result.outcomes,
                         outcome_paths,
@@ -3571,6 +3631,7 @@ impl<'a> ChainUpdate<'a> {
         // Saving transaction results.
         self.chain_store_update.save_outcomes_with_proofs(
             block_header.hash(),
+            block_header.height(),
             shard_id,
             apply_result.outcomes,
             outcome_proofs,
@@ -5,7 +5,7 @@ pub use chain::{collect_receipts, Chain, MAX_ORPHAN_SIZE};
 pub use doomslug::{Doomslug, DoomslugBlockProductionReadiness, DoomslugThresholdMode};
 pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_view};
 pub use near_chain_primitives::{Error, ErrorKind};
-pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
+pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate, LatestStateChange};
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{
     Block, BlockHeader, BlockStatus, ChainGenesis, Provenance, ReceiptResult, RuntimeAdapter,
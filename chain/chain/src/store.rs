@@ -1,4 +1,6 @@
 use std::collections::hash_map::Entry;
+#[cfg(feature = "protocol_feature_evm")]
+use std::collections::BTreeSet;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::io;
@@ -12,6 +14,8 @@ use tracing::debug;
 use near_chain_primitives::error::{Error, ErrorKind};
 use near_primitives::block::{Approval, Tip};
 use near_primitives::errors::InvalidTxError;
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::evm::{get_evm_log_index_key, parse_evm_log_index_key, EvmLog};
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::receipt::Receipt;
@@ -23,16 +27,21 @@ use near_primitives::syncing::{
     get_num_state_parts, ReceiptProofResponse, ReceiptResponse, ShardStateSyncResponseHeader,
     StateHeaderKey, StatePartKey,
 };
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::transaction::{ExecutionOutcome, ExecutionStatus};
 use near_primitives::transaction::{
     ExecutionOutcomeWithId, ExecutionOutcomeWithIdAndProof, SignedTransaction,
 };
 use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
-    AccountId, BlockExtra, BlockHeight, ChunkExtra, EpochId, GCCount, NumBlocks, ShardId,
-    StateChanges, StateChangesExt, StateChangesKinds, StateChangesKindsExt, StateChangesRequest,
+    AccountId, BlockExtra, BlockHeight, ChunkExtra, EpochId, GCCount, NumBlocks,
+    RawStateChangesWithTrieKey, ShardId, StateChanges, StateChangesExt, StateChangesKinds,
+    StateChangesKindsExt, StateChangesRequest,
 };
 use near_primitives::utils::{get_block_shard_id, index_to_bytes, to_timestamp};
 use near_primitives::views::LightClientBlockView;
+#[cfg(feature = "protocol_feature_evm")]
+use near_store::ColEvmLogIndex;
 use near_store::{
     read_with_cache, ColBlock, ColBlockExtra, ColBlockHeader, ColBlockHeight, ColBlockInfo,
     ColBlockMerkleTree, ColBlockMisc, ColBlockOrdinal, ColBlockPerHeight, ColBlockRefCount,
@@ -320,6 +329,35 @@ pub trait ChainStoreAccess {
     fn is_height_processed(&mut self, height: BlockHeight) -> Result<bool, Error>;
 }
 
+/// Maximum number of blocks `ChainStore::get_latest_state_change` looks through.
+pub const MAX_STATE_CHANGES_LOOKUP_DEPTH: NumBlocks = 1000;
+
+/// EVM log index prefixes of the logs of a successful receipt of the EVM account.
+#[cfg(feature = "protocol_feature_evm")]
+fn evm_log_index_prefixes(outcome: &ExecutionOutcome) -> Vec<Vec<u8>> {
+    if !near_runtime_utils::is_account_evm(&outcome.executor_id)
+        || matches!(outcome.status, ExecutionStatus::Failure(_))
+    {
+        return vec![];
+    }
+    outcome
+        .logs
+        .iter()
+        .filter_map(|log| EvmLog::from_hex(log))
+        .flat_map(|log| log.index_prefixes())
+        .collect()
+}
+
+/// Result of `ChainStore::get_latest_state_change`.
+#[derive(Debug, PartialEq)]
+pub enum LatestStateChange {
+    /// The value was last changed by one of the looked up blocks, `None` if it was deleted.
+    Changed(Option<Vec<u8>>),
+    /// The value was not changed by the looked up blocks, so it is the same as in the state
+    /// after the block with this hash.
+    Unchanged(CryptoHash),
+}
+
 /// All chain-related database operations.
 pub struct ChainStore {
     store: Arc<Store>,
@@ -541,6 +579,64 @@ impl ChainStore {
             Ok(self.get_block_header(hash)?.height())
         }
     }
+
+    /// Finds the latest change of the value under `trie_key` made by the block with hash
+    /// `block_hash` or its ancestors. The lookup is done in the recorded state changes
+    /// (`ColStateChanges`), so it doesn't need the state trie of the block.
+    ///
+    /// At most `MAX_STATE_CHANGES_LOOKUP_DEPTH` blocks are looked through, and the lookup stops
+    /// at the tail. If the value was not changed by them, the block the state has to be read at
+    /// instead is returned.
+    pub fn get_latest_state_change(
+        &mut self,
+        trie_key: &TrieKey,
+        block_hash: &CryptoHash,
+    ) -> Result<LatestStateChange, Error> {
+        let tail = self.tail()?;
+        let mut header = self.get_block_header(block_hash)?.clone();
+        for _ in 0..MAX_STATE_CHANGES_LOOKUP_DEPTH {
+            if header.height() <= tail {
+                break;
+            }
+            let storage_key = KeyForStateChanges::new_from_trie_key(header.hash(), trie_key);
+            let changes: Option<RawStateChangesWithTrieKey> =
+                self.store.get_ser(ColStateChanges, storage_key.as_ref())?;
+            if let Some(change) = changes.and_then(|mut changes| changes.changes.pop()) {
+                return Ok(LatestStateChange::Changed(change.data));
+            }
+            header = self.get_block_header(header.prev_hash())?.clone();
+        }
+        Ok(LatestStateChange::Unchanged(*header.hash()))
+    }
+
+    /// Returns the blocks in the height range with EVM logs having one of the index prefixes of
+    /// every group, see `EvmLogFilter::index_prefixes`. The blocks may be on forks.
+    #[cfg(feature = "protocol_feature_evm")]
+    pub fn get_evm_log_blocks(
+        &self,
+        prefix_groups: &[Vec<Vec<u8>>],
+        from_height: BlockHeight,
+        to_height: BlockHeight,
+    ) -> BTreeSet<(BlockHeight, CryptoHash)> {
+        let mut result: Option<BTreeSet<_>> = None;
+        for group in prefix_groups {
+            let mut blocks = BTreeSet::new();
+            for prefix in group {
+                for (key, _) in self.store.iter_prefix(ColEvmLogIndex, prefix) {
+                    if let Some((height, block_hash)) = parse_evm_log_index_key(prefix, &key) {
+                        if from_height <= height && height <= to_height {
+                            blocks.insert((height, block_hash));
+                        }
+                    }
+                }
+            }
+            result = Some(match result {
+                Some(result) => result.intersection(&blocks).cloned().collect(),
+                None => blocks,
+            });
+        }
+        result.unwrap_or_default()
+    }
 }
 
 impl ChainStoreAccess for ChainStore {
@@ -1182,6 +1278,8 @@ struct ChainStoreCacheUpdate {
     block_ordinal_to_hash: HashMap<NumBlocks, CryptoHash>,
     gc_count: HashMap<DBCol, GCCount>,
     processed_block_heights: HashSet<BlockHeight>,
+    #[cfg(feature = "protocol_feature_evm")]
+    evm_log_index: HashSet<Vec<u8>>,
 }
 
 /// Provides layer to update chain without touching the underlying database.
@@ -1909,9 +2007,12 @@ impl<'a> ChainStoreUpdate<'a> {
         self.chain_store_cache_update.incoming_receipts.insert((*hash, shard_id), receipt_proof);
     }
 
+    /// Saves the outcomes of the chunk applied in the block, and indexes their EVM logs.
+    #[cfg_attr(not(feature = "protocol_feature_evm"), allow(unused_variables))]
     pub fn save_outcomes_with_proofs(
         &mut self,
         block_hash: &CryptoHash,
+        block_height: BlockHeight,
         shard_id: ShardId,
         outcomes: Vec<ExecutionOutcomeWithId>,
         proofs: Vec<MerklePath>,
@@ -1919,6 +2020,14 @@ impl<'a> ChainStoreUpdate<'a> {
         let mut outcome_ids = Vec::with_capacity(outcomes.len());
         for (outcome_with_id, proof) in outcomes.into_iter().zip(proofs.into_iter()) {
             outcome_ids.push(outcome_with_id.id);
+            #[cfg(feature = "protocol_feature_evm")]
+            for prefix in evm_log_index_prefixes(&outcome_with_id.outcome) {
+                self.chain_store_cache_update.evm_log_index.insert(get_evm_log_index_key(
+                    &prefix,
+                    block_height,
+                    block_hash,
+                ));
+            }
             self.chain_store_cache_update
                 .outcomes
                 .entry(outcome_with_id.id)
@@ -2306,6 +2415,8 @@ impl<'a> ChainStoreUpdate<'a> {
     pub fn gc_outcomes(&mut self, block: &Block) -> Result<(), Error> {
         let block_hash = block.hash();
         let mut store_update = self.store().store_update();
+        #[cfg(feature = "protocol_feature_evm")]
+        let mut evm_log_index = HashSet::new();
         for chunk_header in
             block.chunks().iter().filter(|h| h.height_included() == block.header().height())
         {
@@ -2313,6 +2424,16 @@ impl<'a> ChainStoreUpdate<'a> {
             let outcome_ids = self.get_outcomes_by_block_hash_and_shard_id(block_hash, shard_id)?;
             for outcome_id in outcome_ids {
                 let mut outcomes_with_id = self.get_outcomes_by_id(&outcome_id)?;
+                #[cfg(feature = "protocol_feature_evm")]
+                for outcome in outcomes_with_id.iter().filter(|o| &o.block_hash == block_hash) {
+                    for prefix in evm_log_index_prefixes(&outcome.outcome_with_id.outcome) {
+                        evm_log_index.insert(get_evm_log_index_key(
+                            &prefix,
+                            block.header().height(),
+                            block_hash,
+                        ));
+                    }
+                }
                 outcomes_with_id.retain(|outcome| &outcome.block_hash != block_hash);
                 if outcomes_with_id.is_empty() {
                     self.gc_col(ColTransactionResult, &outcome_id.as_ref().into());
@@ -2326,6 +2447,10 @@ impl<'a> ChainStoreUpdate<'a> {
             }
             self.gc_col(ColOutcomeIds, &get_block_shard_id(block_hash, shard_id));
         }
+        #[cfg(feature = "protocol_feature_evm")]
+        for key in evm_log_index {
+            self.gc_col(ColEvmLogIndex, &key);
+        }
         self.merge(store_update);
         Ok(())
     }
@@ -2426,6 +2551,9 @@ impl<'a> ChainStoreUpdate<'a> {
             DBCol::ColOutcomeIds => {
                 store_update.delete(col, key);
             }
+            DBCol::ColEvmLogIndex => {
+                store_update.delete(col, key);
+            }
             DBCol::ColStateDlInfos => {
                 store_update.delete(col, key);
             }
@@ -2625,6 +2753,10 @@ impl<'a> ChainStoreUpdate<'a> {
                 &ids,
             )?;
         }
+        #[cfg(feature = "protocol_feature_evm")]
+        for key in self.chain_store_cache_update.evm_log_index.iter() {
+            store_update.set(ColEvmLogIndex, key, &[]);
+        }
         for (receipt_id, shard_id) in self.chain_store_cache_update.receipt_id_to_shard_id.iter() {
             let data = shard_id.try_to_vec()?;
             store_update.update_refcount(ColReceiptIdToShardId, receipt_id.as_ref(), &data, 1);
@@ -2906,15 +3038,21 @@ mod tests {
     use near_primitives::epoch_manager::BlockInfo;
     use near_primitives::errors::InvalidTxError;
     use near_primitives::hash::hash;
-    use near_primitives::types::{BlockHeight, EpochId, GCCount, NumBlocks};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::{
+        BlockHeight, EpochId, GCCount, NumBlocks, RawStateChange, RawStateChangesWithTrieKey,
+        StateChangeCause,
+    };
     use near_primitives::utils::index_to_bytes;
     use near_primitives::validator_signer::InMemoryValidatorSigner;
     use near_store::test_utils::create_test_store;
-    use near_store::DBCol;
+    use near_store::{DBCol, KeyForStateChanges};
     #[cfg(feature = "expensive_tests")]
     use {crate::store_validator::StoreValidator, near_chain_configs::GenesisConfig};
 
-    use crate::store::{ChainStoreAccess, GCMode};
+    use crate::store::{
+        ChainStoreAccess, GCMode, LatestStateChange, MAX_STATE_CHANGES_LOOKUP_DEPTH,
+    };
     use crate::test_utils::KeyValueRuntime;
     use crate::{Chain, ChainGenesis, DoomslugThresholdMode};
 
@@ -3132,6 +3270,48 @@ mod tests {
         assert_ne!(epoch_id_to_hash, epoch_id_to_hash1);
     }
 
+    /// Test that the latest change of a value is found in the recorded state changes, and that
+    /// the lookup stops at the tail or after `MAX_STATE_CHANGES_LOOKUP_DEPTH` blocks.
+    #[test]
+    fn test_get_latest_state_change() {
+        let mut chain = get_chain();
+        let genesis = chain.get_block_by_height(0).unwrap().clone();
+        let signer =
+            Arc::new(InMemoryValidatorSigner::from_seed("test1", KeyType::ED25519, "test1"));
+        let mut blocks = vec![genesis];
+        for i in 1..=MAX_STATE_CHANGES_LOOKUP_DEPTH + 4 {
+            let block = Block::empty_with_height(blocks.last().unwrap(), i, &*signer);
+            let mut store_update = chain.mut_store().store_update();
+            store_update.save_block_header(block.header().clone()).unwrap();
+            store_update.commit().unwrap();
+            blocks.push(block);
+        }
+
+        // The value is set by the block at height 2 and deleted by the block at height 4.
+        let trie_key = TrieKey::Account { account_id: "test1".to_string() };
+        let mut store_update = chain.mut_store().store().store_update();
+        for (height, data) in vec![(2, Some(vec![1])), (4, None)] {
+            let changes = RawStateChangesWithTrieKey {
+                trie_key: trie_key.clone(),
+                changes: vec![RawStateChange { cause: StateChangeCause::InitialState, data }],
+            };
+            let key = KeyForStateChanges::new_from_trie_key(blocks[height].hash(), &trie_key);
+            store_update.set_ser(DBCol::ColStateChanges, key.as_ref(), &changes).unwrap();
+        }
+        store_update.commit().unwrap();
+
+        let mut lookup = |height: usize| {
+            chain.mut_store().get_latest_state_change(&trie_key, blocks[height].hash()).unwrap()
+        };
+        assert_eq!(lookup(1), LatestStateChange::Unchanged(*blocks[0].hash()));
+        assert_eq!(lookup(2), LatestStateChange::Changed(Some(vec![1])));
+        assert_eq!(lookup(3), LatestStateChange::Changed(Some(vec![1])));
+        assert_eq!(lookup(5), LatestStateChange::Changed(None));
+        let depth = MAX_STATE_CHANGES_LOOKUP_DEPTH as usize;
+        assert_eq!(lookup(depth + 3), LatestStateChange::Changed(None));
+        assert_eq!(lookup(depth + 4), LatestStateChange::Unchanged(*blocks[4].hash()));
+    }
+
     /// Test that garbage collection works properly. The blocks behind gc head should be garbage
     /// collected while the blocks that are ahead of it should not.
     #[test]
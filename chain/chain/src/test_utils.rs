@@ -40,7 +40,9 @@ use near_store::{
 
 use crate::chain::{Chain, NUM_EPOCHS_TO_KEEP_STORE_DATA};
 use crate::store::ChainStoreAccess;
-use crate::types::{ApplyTransactionResult, BlockHeaderInfo, ChainGenesis};
+use crate::types::{
+    ApplyTransactionResult, BlockHeaderInfo, ChainGenesis, GasEstimation, SimulateTransactionResult,
+};
 #[cfg(feature = "protocol_feature_block_header_v3")]
 use crate::Doomslug;
 use crate::{BlockHeader, DoomslugThresholdMode, RuntimeAdapter};
@@ -738,6 +740,33 @@ impl RuntimeAdapter for KeyValueRuntime {
         unimplemented!();
     }
 
+    fn simulate_transaction(
+        &self,
+        _state_roots: &HashMap<ShardId, StateRoot>,
+        _block_height: BlockHeight,
+        _block_timestamp: u64,
+        _block_hash: &CryptoHash,
+        _gas_price: Balance,
+        _random_seed: CryptoHash,
+        _transaction: &SignedTransaction,
+        _verify_signature: bool,
+    ) -> Result<SimulateTransactionResult, Error> {
+        unimplemented!();
+    }
+
+    fn estimate_gas(
+        &self,
+        _state_roots: &HashMap<ShardId, StateRoot>,
+        _block_height: BlockHeight,
+        _block_timestamp: u64,
+        _block_hash: &CryptoHash,
+        _gas_price: Balance,
+        _random_seed: CryptoHash,
+        _transaction: &SignedTransaction,
+    ) -> Result<GasEstimation, Error> {
+        unimplemented!();
+    }
+
     fn query(
         &self,
         _shard_id: ShardId,
@@ -765,6 +794,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                 ),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
             QueryRequest::ViewCode { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::ViewCode(ContractCodeView {
@@ -773,6 +803,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                 }),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
             QueryRequest::ViewAccessKeyList { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::AccessKeyList(AccessKeyList {
@@ -783,19 +814,23 @@ impl RuntimeAdapter for KeyValueRuntime {
                 }),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
             QueryRequest::ViewAccessKey { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::AccessKey(AccessKey::full_access().into()),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
             QueryRequest::ViewState { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::ViewState(ViewStateResult {
                     values: Default::default(),
                     proof: vec![],
+                    next_cursor: None,
                 }),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
             QueryRequest::CallFunction { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::CallResult(CallResult {
@@ -804,6 +839,7 @@ impl RuntimeAdapter for KeyValueRuntime {
                 }),
                 block_height,
                 block_hash: *block_hash,
+                state_proof: None,
             }),
         }
     }
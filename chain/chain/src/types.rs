@@ -21,7 +21,7 @@ use near_primitives::sharding::{ChunkHash, ReceiptList, ShardChunkHeader};
 use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash,
-    NumBlocks, ShardId, StateRoot, StateRootNode, ValidatorStake,
+    NumBlocks, RawStateChangesWithTrieKey, ShardId, StateRoot, StateRootNode, ValidatorStake,
 };
 use near_primitives::version::{
     ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
@@ -87,6 +87,30 @@ pub struct ApplyTransactionResult {
     pub proof: Option<PartialStorage>,
 }
 
+/// Execution of a transaction and of all the receipts it produced, applied on top of a block
+/// without persisting the resulting state.
+#[derive(Default)]
+pub struct SimulateTransactionResult {
+    /// Outcomes of the transaction and of its receipts, in the order they were executed.
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    /// Receipts sent by the transaction and by its receipts.
+    pub receipts: Vec<Receipt>,
+    /// State changes caused by the transaction and by its receipts.
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    /// Receipts that were not executed yet when the simulation reached its limit of blocks.
+    pub pending_receipts: Vec<Receipt>,
+}
+
+/// Minimal prepaid gas for the function calls of a transaction, found by simulating it.
+pub struct GasEstimation {
+    /// Prepaid gas to attach to every function call action of the transaction.
+    pub prepaid_gas: Gas,
+    /// Tokens the signer needs to send the transaction with this prepaid gas, deposits included.
+    pub total_cost: Balance,
+    /// Execution of the transaction with this prepaid gas.
+    pub simulation: SimulateTransactionResult,
+}
+
 impl ApplyTransactionResult {
     /// Returns root and paths for all the outcomes in the result.
     pub fn compute_outcomes_proof(
@@ -531,6 +555,39 @@ pub trait RuntimeAdapter: Send + Sync {
         random_value: CryptoHash,
     ) -> Result<ApplyTransactionResult, Error>;
 
+    /// Applies the transaction on top of the post-state of the given block, then the receipts it
+    /// produced block after block until there are none left. The delayed receipts of the shards
+    /// are not applied, and the simulation fails if it burns too much gas or takes too long.
+    /// `state_roots` must contain the state roots of all the shards the execution touches.
+    /// Nothing is written to the store.
+    fn simulate_transaction(
+        &self,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        block_hash: &CryptoHash,
+        gas_price: Balance,
+        random_seed: CryptoHash,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+    ) -> Result<SimulateTransactionResult, Error>;
+
+    /// Finds the minimal prepaid gas which, attached to every function call action of the
+    /// transaction, lets the transaction and all the receipts it produces succeed. The execution
+    /// is simulated as in `simulate_transaction`, the signature is not checked and the balance of
+    /// the signer is only checked for the estimated prepaid gas. The simulations share a time and
+    /// gas budget, once it runs out the smallest prepaid gas known to be enough is returned.
+    fn estimate_gas(
+        &self,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        block_hash: &CryptoHash,
+        gas_price: Balance,
+        random_seed: CryptoHash,
+        transaction: &SignedTransaction,
+    ) -> Result<GasEstimation, Error>;
+
     /// Query runtime with given `path` and `data`.
     fn query(
         &self,
@@ -13,6 +13,7 @@ use near_chain::validate::validate_chunk_proofs;
 use near_chain::{
     byzantine_assert, ChainStore, ChainStoreAccess, ChainStoreUpdate, ErrorKind, RuntimeAdapter,
 };
+use near_chain_configs::TransactionPoolConfig;
 #[cfg(feature = "protocol_feature_forward_chunk_parts")]
 use near_network::types::PartialEncodedChunkForwardMsg;
 use near_network::types::{
@@ -20,6 +21,7 @@ use near_network::types::{
     PartialEncodedChunkResponseMsg,
 };
 use near_network::NetworkRequests;
+use near_pool::types::InsertTransactionResult;
 use near_pool::{PoolIteratorWrapper, TransactionPool};
 use near_primitives::block::{BlockHeader, Tip};
 use near_primitives::hash::{hash, CryptoHash};
@@ -378,6 +380,7 @@ pub struct ShardsManager {
     me: Option<AccountId>,
 
     tx_pools: HashMap<ShardId, TransactionPool>,
+    pool_config: TransactionPoolConfig,
 
     runtime_adapter: Arc<dyn RuntimeAdapter>,
     network_adapter: Arc<dyn NetworkAdapter>,
@@ -395,10 +398,12 @@ impl ShardsManager {
         me: Option<AccountId>,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         network_adapter: Arc<dyn NetworkAdapter>,
+        pool_config: TransactionPoolConfig,
     ) -> Self {
         Self {
             me: me.clone(),
             tx_pools: HashMap::new(),
+            pool_config,
             runtime_adapter: runtime_adapter.clone(),
             network_adapter,
             encoded_chunks: EncodedChunksCache::new(),
@@ -770,9 +775,18 @@ impl ShardsManager {
         self.encoded_chunks.get_chunk_headers_for_block(&prev_block_hash)
     }
 
-    /// Returns true if transaction is not in the pool before call
-    pub fn insert_transaction(&mut self, shard_id: ShardId, tx: SignedTransaction) -> bool {
-        self.tx_pools.entry(shard_id).or_insert_with(TransactionPool::new).insert_transaction(tx)
+    /// Inserts the transaction into the pool of the shard, which may reject it or evict other
+    /// transactions when full
+    pub fn insert_transaction(
+        &mut self,
+        shard_id: ShardId,
+        tx: SignedTransaction,
+    ) -> InsertTransactionResult {
+        let pool_config = &self.pool_config;
+        self.tx_pools
+            .entry(shard_id)
+            .or_insert_with(|| TransactionPool::with_config(pool_config.clone()))
+            .insert_transaction(tx)
     }
 
     pub fn remove_transactions(
@@ -790,9 +804,10 @@ impl ShardsManager {
         shard_id: ShardId,
         transactions: &Vec<SignedTransaction>,
     ) {
+        let pool_config = &self.pool_config;
         self.tx_pools
             .entry(shard_id)
-            .or_insert_with(TransactionPool::new)
+            .or_insert_with(|| TransactionPool::with_config(pool_config.clone()))
             .reintroduce_transactions(transactions.clone());
     }
 
@@ -1740,8 +1755,12 @@ mod test {
     fn test_request_partial_encoded_chunk_from_self() {
         let runtime_adapter = Arc::new(KeyValueRuntime::new(create_test_store()));
         let network_adapter = Arc::new(MockNetworkAdapter::default());
-        let mut shards_manager =
-            ShardsManager::new(Some("test".to_string()), runtime_adapter, network_adapter.clone());
+        let mut shards_manager = ShardsManager::new(
+            Some("test".to_string()),
+            runtime_adapter,
+            network_adapter.clone(),
+            TransactionPoolConfig::default(),
+        );
         shards_manager.requested_partial_encoded_chunks.insert(
             ChunkHash(hash(&[1])),
             ChunkRequestInfo {
@@ -1795,6 +1814,7 @@ mod test {
             Some("test".to_string()),
             runtime_adapter.clone(),
             network_adapter.clone(),
+            TransactionPoolConfig::default(),
         );
         let signer = InMemoryValidatorSigner::from_seed("test", KeyType::ED25519, "test");
         let mut rs = ReedSolomonWrapper::new(4, 10);
@@ -1960,6 +1980,7 @@ mod test {
             Some(fixture.mock_chunk_part_owner.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
+            TransactionPoolConfig::default(),
         );
         let partial_encoded_chunk = fixture.make_partial_encoded_chunk(&fixture.mock_part_ords);
         let result = shards_manager
@@ -2016,6 +2037,7 @@ mod test {
             Some(fixture.mock_shard_tracker.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
+            TransactionPoolConfig::default(),
         );
         let (most_parts, other_parts) = {
             let mut most_parts = fixture.mock_chunk_parts.clone();
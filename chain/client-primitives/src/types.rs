@@ -1,6 +1,7 @@
 #[cfg(feature = "metric_recorder")]
 use near_network::recorder::MetricRecorder;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -15,15 +16,18 @@ use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::sharding::ChunkHash;
+use near_primitives::trace::FunctionCallTrace;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, MaybeBlockId, ShardId, TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockId, BlockReference, FunctionArgs, MaybeBlockId, ShardId,
+    TransactionOrReceiptId,
 };
 use near_primitives::utils::generate_random_string;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView, StateChangesRequestView,
-    StateChangesView, ValidatorStakeView,
+    BackupView, BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    FinalExecutionOutcomeViewEnum, GasEstimationView, GasPriceView, LightClientBlockLiteView,
+    LightClientBlockView, QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView,
+    StateChangesRequestView, StateChangesView, TransactionSimulationView, ValidatorStakeView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 
@@ -406,6 +410,72 @@ impl Message for GetExecutionOutcomesForBlock {
     type Result = Result<HashMap<ShardId, Vec<ExecutionOutcomeWithIdView>>, String>;
 }
 
+/// EVM logs emitted by successful receipts in a range of blocks.
+#[cfg(feature = "protocol_feature_evm")]
+pub struct GetEvmLogs {
+    pub filter: near_primitives::evm::EvmLogFilter,
+}
+
+#[cfg(feature = "protocol_feature_evm")]
+impl Message for GetEvmLogs {
+    type Result = Result<Vec<near_primitives::evm::EvmLogView>, String>;
+}
+
+/// Executes a view function call with tracing enabled.
+pub struct DebugTraceCall {
+    pub block_reference: BlockReference,
+    pub account_id: AccountId,
+    pub method_name: String,
+    pub args: FunctionArgs,
+}
+
+impl Message for DebugTraceCall {
+    type Result = Result<Vec<FunctionCallTrace>, String>;
+}
+
+/// Applies again the chunks that executed the receipts of a transaction, with tracing enabled,
+/// and returns the traces of the function calls made by these receipts.
+pub struct DebugTraceTransaction {
+    pub tx_hash: CryptoHash,
+    pub signer_account_id: AccountId,
+}
+
+impl Message for DebugTraceTransaction {
+    type Result = Result<Vec<FunctionCallTrace>, String>;
+}
+
+/// Applies a transaction on top of the state of a block without persisting or broadcasting it.
+pub struct SimulateTransaction {
+    pub block_reference: BlockReference,
+    pub transaction: SignedTransaction,
+    pub verify_signature: bool,
+}
+
+impl Message for SimulateTransaction {
+    type Result = Result<TransactionSimulationView, String>;
+}
+
+/// Finds the minimal prepaid gas for the function calls of a transaction by simulating it on top
+/// of the state of a block. The signature of the transaction is not checked.
+pub struct EstimateGas {
+    pub block_reference: BlockReference,
+    pub transaction: SignedTransaction,
+}
+
+impl Message for EstimateGas {
+    type Result = Result<GasEstimationView, String>;
+}
+
+/// Creates a consistent snapshot of the node database in a directory of the node host, which must
+/// not exist.
+pub struct CreateBackup {
+    pub output_dir: PathBuf,
+}
+
+impl Message for CreateBackup {
+    type Result = Result<BackupView, String>;
+}
+
 pub struct GetBlockProof {
     pub block_hash: CryptoHash,
     pub head_block_hash: CryptoHash,
@@ -451,6 +521,52 @@ impl Message for GetReceipt {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 }
 
+/// Queries an account or an access key at the given height. Unlike `Query`, the value is
+/// looked up in the recorded state changes rather than in the state trie of the block, so it
+/// works for any height an archival node has seen.
+pub struct GetStateAtHeight {
+    pub block_id: BlockId,
+    pub request: QueryRequest,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetStateAtHeightError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Block not found: {0}")]
+    UnknownBlock(String),
+    #[error("The state at block #{block_height} is garbage collected on this node, the earliest available height is #{earliest_available_height}")]
+    GarbageCollected { block_height: BlockHeight, earliest_available_height: BlockHeight },
+    #[error("The node does not track shard {0}")]
+    UnavailableShard(ShardId),
+    #[error("Account {0} does not exist at this height")]
+    UnknownAccount(AccountId),
+    #[error("Access key {0} does not exist at this height")]
+    UnknownAccessKey(near_crypto::PublicKey),
+    #[error("Only `view_account` and `view_access_key` requests are supported")]
+    UnsupportedRequest,
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetStateAtHeightError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error.kind() {
+            near_chain_primitives::ErrorKind::IOErr(s) => Self::IOError(s),
+            near_chain_primitives::ErrorKind::DBNotFoundErr(s) => Self::UnknownBlock(s),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl Message for GetStateAtHeight {
+    type Result = Result<QueryResponse, GetStateAtHeightError>;
+}
+
 pub struct GetProtocolConfig(pub BlockReference);
 
 impl Message for GetProtocolConfig {
@@ -21,6 +21,7 @@ use near_chain_configs::ClientConfig;
 use near_chunks::{ProcessPartialEncodedChunkResult, ShardsManager};
 use near_network::types::PartialEncodedChunkResponseMsg;
 use near_network::{FullPeerInfo, NetworkAdapter, NetworkClientResponses, NetworkRequests};
+use near_pool::types::InsertTransactionResult;
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::challenge::{Challenge, ChallengeBody};
 use near_primitives::hash::CryptoHash;
@@ -109,6 +110,7 @@ impl Client {
             validator_signer.as_ref().map(|x| x.validator_id().clone()),
             runtime_adapter.clone(),
             network_adapter.clone(),
+            config.transaction_pool.clone(),
         );
         let sync_status = SyncStatus::AwaitingPeers;
         let header_sync = HeaderSync::new(
@@ -1461,7 +1463,24 @@ impl Client {
                     shard_id,
                     is_forwarded
                 );
-                self.shards_mgr.insert_transaction(shard_id, tx.clone());
+                let rejection_reason = match self
+                    .shards_mgr
+                    .insert_transaction(shard_id, tx.clone())
+                {
+                    InsertTransactionResult::Success | InsertTransactionResult::Duplicate => None,
+                    InsertTransactionResult::NoSpaceLeft => {
+                        Some("The transaction pool is full".to_string())
+                    }
+                    InsertTransactionResult::SignerLimitExceeded => {
+                        Some("The signer has too many transactions in the pool".to_string())
+                    }
+                };
+                // Forwarding a transaction our own pool has no room for would only shift the
+                // load onto other validators, so the sender learns about it instead.
+                if let Some(reason) = rejection_reason {
+                    debug!(target: "client", "Transaction pool rejected tx {}: {}", tx.get_hash(), reason);
+                    return Ok(NetworkClientResponses::PoolRejected(reason));
+                }
 
                 // Active validator:
                 //   possibly forward to next epoch validators
@@ -1660,6 +1679,7 @@ mod test {
                     &genesis,
                     vec![],
                     vec![],
+                    None,
                 )) as Arc<dyn RuntimeAdapter>
             })
             .collect()
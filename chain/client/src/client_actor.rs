@@ -1437,6 +1437,7 @@ impl ClientActor {
                     &act.network_info,
                     validator_info,
                 );
+                act.client.chain.store().store().update_metrics();
 
                 act.log_summary(ctx);
             },
@@ -2,13 +2,17 @@
 extern crate lazy_static;
 
 pub use near_client_primitives::types::{
-    Error, GetBlock, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk,
-    GetExecutionOutcome, GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
+    CreateBackup, DebugTraceCall, DebugTraceTransaction, Error, EstimateGas, GetBlock,
+    GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk, GetExecutionOutcome,
+    GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock, GetGasPrice, GetNetworkInfo,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateAtHeight, GetStateChanges,
     GetStateChangesInBlock, GetStateChangesWithCauseInBlock, GetValidatorInfo, GetValidatorOrdered,
-    Query, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
+    Query, SimulateTransaction, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
 };
 
+#[cfg(feature = "protocol_feature_evm")]
+pub use near_client_primitives::types::GetEvmLogs;
+
 pub use crate::client::Client;
 pub use crate::client_actor::{start_client, ClientActor};
 #[cfg(feature = "adversarial")]
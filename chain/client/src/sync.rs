@@ -13,7 +13,7 @@ use rand::seq::{IteratorRandom, SliceRandom};
 use rand::{thread_rng, Rng};
 
 use near_chain::{Chain, RuntimeAdapter};
-use near_network::types::{AccountOrPeerIdOrHash, NetworkResponses, ReasonForBan};
+use near_network::types::{AccountOrPeerIdOrHash, NetworkResponses, PeerScoreEvent, ReasonForBan};
 use near_network::{FullPeerInfo, NetworkAdapter, NetworkRequests};
 use near_primitives::block::Tip;
 use near_primitives::hash::CryptoHash;
@@ -530,6 +530,16 @@ impl StateSync {
         Ok((request_block, have_block))
     }
 
+    /// Lowers the score of the peer which didn't answer a state request in time.
+    fn report_timeout(&self, target: &Option<AccountOrPeerIdOrHash>) {
+        if let Some(AccountOrPeerIdOrHash::PeerId(peer_id)) = target {
+            self.network_adapter.do_send(NetworkRequests::UpdatePeerScore {
+                peer_id: peer_id.clone(),
+                event: PeerScoreEvent::Timeout,
+            });
+        }
+    }
+
     pub fn sync_shards_status(
         &mut self,
         me: &Option<AccountId>,
@@ -593,6 +603,9 @@ impl StateSync {
                         let prev = shard_sync_download.downloads[0].prev_update_time;
                         let error = shard_sync_download.downloads[0].error;
                         download_timeout = now - prev > self.timeout;
+                        if download_timeout {
+                            self.report_timeout(&shard_sync_download.downloads[0].last_target);
+                        }
                         if download_timeout || error {
                             shard_sync_download.downloads[0].run_me.store(true, Ordering::SeqCst);
                             shard_sync_download.downloads[0].error = false;
@@ -611,6 +624,9 @@ impl StateSync {
                             let prev = part_download.prev_update_time;
                             let error = part_download.error;
                             let part_timeout = now - prev > self.timeout;
+                            if part_timeout {
+                                self.report_timeout(&part_download.last_target);
+                            }
                             if part_timeout || error {
                                 download_timeout |= part_timeout;
                                 part_download.run_me.store(true, Ordering::SeqCst);
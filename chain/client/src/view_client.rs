@@ -2,18 +2,19 @@
 //! Useful for querying from RPC.
 
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
 use actix::{Actor, Addr, Handler, SyncArbiter, SyncContext};
+use borsh::BorshDeserialize;
 use cached::{Cached, SizedCache};
 use log::{debug, error, info, trace, warn};
 
 use near_chain::{
     get_epoch_block_producers_view, Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode,
-    ErrorKind, RuntimeAdapter,
+    ErrorKind, LatestStateChange, RuntimeAdapter,
 };
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
 #[cfg(feature = "adversarial")]
@@ -23,38 +24,57 @@ use near_network::types::{
     StateResponseInfoV1, StateResponseInfoV2,
 };
 use near_network::{NetworkAdapter, NetworkRequests};
+use near_primitives::account::{AccessKey, Account};
 use near_primitives::block::{Block, BlockHeader, GenesisId, Tip};
+use near_primitives::errors::StorageError;
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::evm::{EvmLog, EvmLogView};
 use near_primitives::hash::CryptoHash;
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::hash::Digest;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::receipt::{Receipt, ReceiptEnum};
 use near_primitives::sharding::ShardChunk;
 use near_primitives::syncing::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV1,
     ShardStateSyncResponseV2,
 };
+use near_primitives::trace::{self, FunctionCallTrace};
+#[cfg(feature = "protocol_feature_evm")]
+use near_primitives::transaction::{Action, ExecutionOutcomeWithId, ExecutionStatus};
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockId, BlockReference, Finality, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockId, BlockReference, Finality, MaybeBlockId, ShardId, StateChanges,
+    StateChangesExt, StateRoot, TransactionOrReceiptId,
 };
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
-    LightClientBlockView, QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView,
-    StateChangesView, ValidatorStakeView,
+    BackupView, BlockView, ChunkView, EpochValidatorInfo, ExecutionGasView,
+    ExecutionOutcomeWithIdView, FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum,
+    FinalExecutionStatus, GasEstimationView, GasPriceView, LightClientBlockView, QueryRequest,
+    QueryResponse, QueryResponseKind, ReceiptView, StateChangesKindsView, StateChangesView,
+    TransactionSimulationView, ValidatorStakeView,
 };
 
 use crate::{
     sync, GetChunk, GetExecutionOutcomeResponse, GetNextLightClientBlock, GetStateChanges,
     GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
 };
+#[cfg(feature = "protocol_feature_evm")]
+use near_client_primitives::types::GetEvmLogs;
 use near_client_primitives::types::{
-    Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree,
-    GetChunkError, GetExecutionOutcome, GetExecutionOutcomesForBlock, GetGasPrice,
-    GetProtocolConfig, GetProtocolConfigError, GetReceipt, GetReceiptError,
-    GetStateChangesWithCauseInBlock, Query, TxStatus, TxStatusError,
+    CreateBackup, DebugTraceCall, DebugTraceTransaction, Error, EstimateGas, GetBlock,
+    GetBlockError, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError,
+    GetExecutionOutcome, GetExecutionOutcomesForBlock, GetGasPrice, GetProtocolConfig,
+    GetProtocolConfigError, GetReceipt, GetReceiptError, GetStateAtHeight, GetStateAtHeightError,
+    GetStateChangesWithCauseInBlock, Query, SimulateTransaction, TxStatus, TxStatusError,
 };
 use near_performance_metrics_macros::perf;
 use near_performance_metrics_macros::perf_with_debug;
+#[cfg(feature = "protocol_feature_evm")]
+use near_runtime_utils::is_account_evm;
+use near_store::backup::create_backup;
 
 /// Max number of queries that we keep.
 const QUERY_REQUEST_LIMIT: usize = 500;
@@ -62,6 +82,10 @@ const QUERY_REQUEST_LIMIT: usize = 500;
 const REQUEST_WAIT_TIME: u64 = 1000;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
+/// Max number of blocks scanned by a single EVM logs request, both for block ranges scanned in
+/// full and for blocks found in the EVM log index
+#[cfg(feature = "protocol_feature_evm")]
+const MAX_EVM_LOGS_BLOCK_RANGE: BlockHeight = 1000;
 
 /// Request and response manager across all instances of ViewClientActor.
 pub struct ViewClientRequestManager {
@@ -276,6 +300,137 @@ impl ViewClientActor {
         }
     }
 
+    /// Header of the referenced block and the post-state roots of the shards this node tracks
+    /// at that block, which transactions are simulated on top of.
+    fn get_simulation_state(
+        &mut self,
+        block_reference: BlockReference,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<(BlockHeader, HashMap<ShardId, StateRoot>), String> {
+        let block = self.handle(GetBlock(block_reference), ctx).map_err(|e| e.to_string())?;
+        let header =
+            self.chain.get_block_header(&block.header.hash).map_err(|e| e.to_string())?.clone();
+        // Shards this node doesn't track are left out, the simulation fails if it reaches them.
+        let state_roots = (0..self.runtime_adapter.num_shards())
+            .filter_map(|shard_id| {
+                self.chain
+                    .get_chunk_extra(header.hash(), shard_id)
+                    .ok()
+                    .map(|chunk_extra| (shard_id, chunk_extra.state_root))
+            })
+            .collect();
+        Ok((header, state_roots))
+    }
+
+    fn get_state_at_height(
+        &mut self,
+        msg: GetStateAtHeight,
+    ) -> Result<QueryResponse, GetStateAtHeightError> {
+        let header = match msg.block_id {
+            BlockId::Height(block_height) => self.chain.get_header_by_height(block_height),
+            BlockId::Hash(block_hash) => self.chain.get_block_header(&block_hash),
+        }?
+        .clone();
+        let tail = self.chain.tail()?;
+        if header.height() < tail {
+            return Err(GetStateAtHeightError::GarbageCollected {
+                block_height: header.height(),
+                earliest_available_height: tail,
+            });
+        }
+
+        let (account_id, trie_key) = match &msg.request {
+            QueryRequest::ViewAccount { account_id, .. } => {
+                (account_id.clone(), TrieKey::Account { account_id: account_id.clone() })
+            }
+            QueryRequest::ViewAccessKey { account_id, public_key } => (
+                account_id.clone(),
+                TrieKey::AccessKey {
+                    account_id: account_id.clone(),
+                    public_key: public_key.clone(),
+                },
+            ),
+            _ => return Err(GetStateAtHeightError::UnsupportedRequest),
+        };
+        let shard_id = self.runtime_adapter.account_id_to_shard_id(&account_id);
+        if !self.runtime_adapter.cares_about_shard(
+            self.validator_account_id.as_ref(),
+            header.prev_hash(),
+            shard_id,
+            true,
+        ) {
+            return Err(GetStateAtHeightError::UnavailableShard(shard_id));
+        }
+
+        let value =
+            match self.chain.mut_store().get_latest_state_change(&trie_key, header.hash())? {
+                LatestStateChange::Changed(value) => value,
+                // The value is read from the state after the block the lookup stopped at.
+                LatestStateChange::Unchanged(block_hash) => {
+                    let state_root = match self.chain.get_chunk_extra(&block_hash, shard_id) {
+                        Ok(chunk_extra) => chunk_extra.state_root,
+                        Err(err) => {
+                            return Err(match err.kind() {
+                                ErrorKind::DBNotFoundErr(_) => {
+                                    GetStateAtHeightError::GarbageCollected {
+                                        block_height: header.height(),
+                                        earliest_available_height: tail,
+                                    }
+                                }
+                                _ => err.into(),
+                            })
+                        }
+                    };
+                    self.runtime_adapter
+                        .get_view_trie_for_shard(shard_id)
+                        .get(&state_root, &trie_key.to_vec())
+                        .map_err(|err| match err {
+                            StorageError::StorageInternalError => {
+                                GetStateAtHeightError::IOError(err.to_string())
+                            }
+                            StorageError::TrieNodeMissing => {
+                                GetStateAtHeightError::GarbageCollected {
+                                    block_height: header.height(),
+                                    earliest_available_height: tail,
+                                }
+                            }
+                            StorageError::StorageInconsistentState(s) => {
+                                GetStateAtHeightError::Unreachable(s)
+                            }
+                        })?
+                }
+            };
+        let value = match value {
+            Some(value) => value,
+            None => {
+                return Err(match msg.request {
+                    QueryRequest::ViewAccessKey { public_key, .. } => {
+                        GetStateAtHeightError::UnknownAccessKey(public_key)
+                    }
+                    _ => GetStateAtHeightError::UnknownAccount(account_id),
+                });
+            }
+        };
+        let kind = match msg.request {
+            QueryRequest::ViewAccount { .. } => QueryResponseKind::ViewAccount(
+                Account::try_from_slice(&value)
+                    .map_err(|err| GetStateAtHeightError::Unreachable(err.to_string()))?
+                    .into(),
+            ),
+            _ => QueryResponseKind::AccessKey(
+                AccessKey::try_from_slice(&value)
+                    .map_err(|err| GetStateAtHeightError::Unreachable(err.to_string()))?
+                    .into(),
+            ),
+        };
+        Ok(QueryResponse {
+            kind,
+            block_height: header.height(),
+            block_hash: *header.hash(),
+            state_proof: None,
+        })
+    }
+
     fn request_receipt_outcome(
         &mut self,
         receipt_id: CryptoHash,
@@ -773,6 +928,281 @@ impl Handler<GetExecutionOutcomesForBlock> for ViewClientActor {
     }
 }
 
+#[cfg(feature = "protocol_feature_evm")]
+impl Handler<GetEvmLogs> for ViewClientActor {
+    type Result = Result<Vec<EvmLogView>, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetEvmLogs, _: &mut Self::Context) -> Self::Result {
+        let filter = msg.filter;
+        if filter.to_block < filter.from_block {
+            return Err("Invalid block range".to_string());
+        }
+        let prefix_groups = filter.index_prefixes();
+        let mut blocks = vec![];
+        if prefix_groups.is_empty() {
+            // Without an address or a topic to look up in the index every block is scanned.
+            if filter.to_block - filter.from_block >= MAX_EVM_LOGS_BLOCK_RANGE {
+                return Err(format!("Block range exceeds {} blocks", MAX_EVM_LOGS_BLOCK_RANGE));
+            }
+            for height in filter.from_block..=filter.to_block {
+                match self.chain.mut_store().get_block_hash_by_height(height) {
+                    Ok(block_hash) => blocks.push((height, block_hash)),
+                    // There is no block at this height
+                    Err(err) if matches!(err.kind(), ErrorKind::DBNotFoundErr(_)) => {}
+                    Err(err) => return Err(err.to_string()),
+                }
+            }
+        } else {
+            let indexed_blocks = self.chain.store().get_evm_log_blocks(
+                &prefix_groups,
+                filter.from_block,
+                filter.to_block,
+            );
+            for (height, block_hash) in indexed_blocks {
+                // The index also has the blocks on forks
+                match self.chain.mut_store().get_block_hash_by_height(height) {
+                    Ok(canonical_hash) if canonical_hash == block_hash => {
+                        blocks.push((height, block_hash))
+                    }
+                    Ok(_) => {}
+                    Err(err) if matches!(err.kind(), ErrorKind::DBNotFoundErr(_)) => {}
+                    Err(err) => return Err(err.to_string()),
+                }
+            }
+            if blocks.len() as u64 > MAX_EVM_LOGS_BLOCK_RANGE {
+                return Err(format!("Logs are in more than {} blocks", MAX_EVM_LOGS_BLOCK_RANGE));
+            }
+        }
+        let mut logs = vec![];
+        for (height, block_hash) in blocks {
+            let outcomes =
+                self.chain.get_block_execution_outcomes(&block_hash).map_err(|e| e.to_string())?;
+            for (_, outcomes) in outcomes {
+                for (transaction_index, outcome) in outcomes.into_iter().enumerate() {
+                    let ExecutionOutcomeWithId { id: receipt_id, outcome } =
+                        outcome.outcome_with_id;
+                    if !is_account_evm(&outcome.executor_id)
+                        || matches!(outcome.status, ExecutionStatus::Failure(_))
+                    {
+                        continue;
+                    }
+                    let mut transaction_hash = None;
+                    for (log_index, log) in outcome.logs.iter().enumerate() {
+                        let log = match EvmLog::from_hex(log) {
+                            Some(log) if filter.matches(&log) => log,
+                            _ => continue,
+                        };
+                        if transaction_hash.is_none() {
+                            let receipt = self
+                                .chain
+                                .mut_store()
+                                .get_receipt(&receipt_id)
+                                .map_err(|e| e.to_string())?;
+                            transaction_hash =
+                                Some(receipt.and_then(evm_transaction_hash).unwrap_or(receipt_id));
+                        }
+                        logs.push(EvmLogView {
+                            log,
+                            block_hash,
+                            block_height: height,
+                            receipt_id,
+                            transaction_hash: transaction_hash.expect("just set"),
+                            transaction_index: transaction_index as u64,
+                            log_index: log_index as u64,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(logs)
+    }
+}
+
+/// Hash of the Ethereum transaction the receipt relays to `raw_call` of the EVM account, the one
+/// returned by `eth_sendRawTransaction`.
+#[cfg(feature = "protocol_feature_evm")]
+fn evm_transaction_hash(receipt: &Receipt) -> Option<CryptoHash> {
+    match &receipt.receipt {
+        ReceiptEnum::Action(action_receipt) => {
+            action_receipt.actions.iter().find_map(|action| match action {
+                Action::FunctionCall(call) if call.method_name == "raw_call" => {
+                    Some(CryptoHash(Digest(keccak_hash::keccak(&call.args).0)))
+                }
+                _ => None,
+            })
+        }
+        ReceiptEnum::Data(_) => None,
+    }
+}
+
+impl Handler<DebugTraceCall> for ViewClientActor {
+    type Result = Result<Vec<FunctionCallTrace>, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: DebugTraceCall, _: &mut Self::Context) -> Self::Result {
+        let query = Query::new(
+            msg.block_reference,
+            QueryRequest::CallFunction {
+                account_id: msg.account_id,
+                method_name: msg.method_name,
+                args: msg.args,
+            },
+        );
+        trace::start_tracing();
+        let response = self.handle_query(query);
+        let traces = trace::finish_tracing();
+        match response? {
+            Some(response) => match response.kind {
+                // The call failed before the contract was executed, e.g. there is no such account.
+                QueryResponseKind::Error(err) if traces.is_empty() => Err(err.error),
+                _ => Ok(traces),
+            },
+            None => Err("The account is in a shard that is not tracked by this node".to_string()),
+        }
+    }
+}
+
+impl Handler<DebugTraceTransaction> for ViewClientActor {
+    type Result = Result<Vec<FunctionCallTrace>, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: DebugTraceTransaction, _: &mut Self::Context) -> Self::Result {
+        let outcome =
+            self.chain.get_final_transaction_result(&msg.tx_hash).map_err(|e| e.to_string())?;
+        if outcome.transaction.signer_id != msg.signer_account_id {
+            return Err(format!(
+                "Transaction {} is not signed by {}",
+                msg.tx_hash, msg.signer_account_id
+            ));
+        }
+        let receipt_ids: HashSet<CryptoHash> =
+            outcome.receipts_outcome.iter().map(|outcome| outcome.id).collect();
+        // Chunks that executed the receipts, each is applied once in the order of execution.
+        let mut chunks: Vec<(CryptoHash, ShardId)> = vec![];
+        for receipt_outcome in outcome.receipts_outcome.iter() {
+            let shard_id =
+                self.runtime_adapter.account_id_to_shard_id(&receipt_outcome.outcome.executor_id);
+            if !chunks.contains(&(receipt_outcome.block_hash, shard_id)) {
+                chunks.push((receipt_outcome.block_hash, shard_id));
+            }
+        }
+        let mut traces = vec![];
+        for (block_hash, shard_id) in chunks {
+            trace::start_tracing();
+            let apply_result = self.chain.reapply_chunk(&block_hash, shard_id);
+            let chunk_traces = trace::finish_tracing();
+            apply_result.map_err(|e| e.to_string())?;
+            traces.extend(chunk_traces.into_iter().filter(|trace| {
+                trace.receipt_id.map_or(false, |receipt_id| receipt_ids.contains(&receipt_id))
+            }));
+        }
+        Ok(traces)
+    }
+}
+
+impl Handler<SimulateTransaction> for ViewClientActor {
+    type Result = Result<TransactionSimulationView, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: SimulateTransaction, ctx: &mut Self::Context) -> Self::Result {
+        let (header, state_roots) = self.get_simulation_state(msg.block_reference, ctx)?;
+        let result = self
+            .runtime_adapter
+            .simulate_transaction(
+                &state_roots,
+                header.height(),
+                header.raw_timestamp(),
+                header.hash(),
+                header.gas_price(),
+                *header.random_value(),
+                &msg.transaction,
+                msg.verify_signature,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let state_changes = StateChanges::from_changes(result.state_changes.into_iter().map(Ok))
+            .map_err(|e| e.to_string())?;
+        Ok(TransactionSimulationView {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            gas_burnt: result.outcomes.iter().map(|outcome| outcome.outcome.gas_burnt).sum(),
+            tokens_burnt: result.outcomes.iter().map(|outcome| outcome.outcome.tokens_burnt).sum(),
+            outcomes: result
+                .outcomes
+                .into_iter()
+                .map(|outcome| ExecutionOutcomeWithIdView {
+                    proof: vec![],
+                    block_hash: *header.hash(),
+                    id: outcome.id,
+                    outcome: outcome.outcome.into(),
+                })
+                .collect(),
+            receipts: result.receipts.into_iter().map(Into::into).collect(),
+            state_changes: state_changes.into_iter().map(Into::into).collect(),
+            pending_receipts: result.pending_receipts.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl Handler<EstimateGas> for ViewClientActor {
+    type Result = Result<GasEstimationView, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: EstimateGas, ctx: &mut Self::Context) -> Self::Result {
+        let (header, state_roots) = self.get_simulation_state(msg.block_reference, ctx)?;
+        let estimation = self
+            .runtime_adapter
+            .estimate_gas(
+                &state_roots,
+                header.height(),
+                header.raw_timestamp(),
+                header.hash(),
+                header.gas_price(),
+                *header.random_value(),
+                &msg.transaction,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let outcomes = estimation.simulation.outcomes;
+        Ok(GasEstimationView {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            prepaid_gas: estimation.prepaid_gas,
+            total_cost: estimation.total_cost,
+            gas_burnt: outcomes.iter().map(|outcome| outcome.outcome.gas_burnt).sum(),
+            tokens_burnt: outcomes.iter().map(|outcome| outcome.outcome.tokens_burnt).sum(),
+            breakdown: outcomes
+                .into_iter()
+                .map(|outcome| ExecutionGasView {
+                    id: outcome.id,
+                    executor_id: outcome.outcome.executor_id,
+                    gas_burnt: outcome.outcome.gas_burnt,
+                    tokens_burnt: outcome.outcome.tokens_burnt,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl Handler<CreateBackup> for ViewClientActor {
+    type Result = Result<BackupView, String>;
+
+    #[perf]
+    fn handle(&mut self, msg: CreateBackup, _: &mut Self::Context) -> Self::Result {
+        let metadata = create_backup(self.chain.store().store(), &msg.output_dir)
+            .map_err(|e| e.to_string())?;
+        info!(target: "client", "Created backup of the database at height {} in {}", metadata.head_height, msg.output_dir.display());
+        Ok(BackupView {
+            path: msg.output_dir.display().to_string(),
+            db_version: metadata.db_version,
+            genesis_hash: metadata.genesis_hash,
+            head_height: metadata.head_height,
+            head_hash: metadata.head_hash,
+        })
+    }
+}
+
 impl Handler<GetReceipt> for ViewClientActor {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 
@@ -786,6 +1216,15 @@ impl Handler<GetReceipt> for ViewClientActor {
     }
 }
 
+impl Handler<GetStateAtHeight> for ViewClientActor {
+    type Result = Result<QueryResponse, GetStateAtHeightError>;
+
+    #[perf]
+    fn handle(&mut self, msg: GetStateAtHeight, _: &mut Self::Context) -> Self::Result {
+        self.get_state_at_height(msg)
+    }
+}
+
 impl Handler<GetBlockProof> for ViewClientActor {
     type Result = Result<GetBlockProofResponse, String>;
 
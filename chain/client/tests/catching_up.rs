@@ -322,6 +322,7 @@ mod tests {
                                                             BlockReference::latest(),
                                                             QueryRequest::ViewAccount {
                                                                 account_id: account_to.clone(),
+                                                                include_proof: false,
                                                             },
                                                         ))
                                                         .then(move |res| {
@@ -534,6 +535,7 @@ mod tests {
                                                                 QueryRequest::ViewAccount {
                                                                     account_id: flat_validators[j]
                                                                         .to_string(),
+                                                                    include_proof: false,
                                                                 },
                                                             ))
                                                             .then(move |res| {
@@ -64,6 +64,7 @@ fn test_keyvalue_runtime_balances() {
                             BlockReference::latest(),
                             QueryRequest::ViewAccount {
                                 account_id: flat_validators[i].to_string(),
+                                include_proof: false,
                             },
                         ))
                         .then(move |res| {
@@ -211,7 +212,10 @@ mod tests {
                         .1
                         .send(Query::new(
                             BlockReference::latest(),
-                            QueryRequest::ViewAccount { account_id: account_id.clone() },
+                            QueryRequest::ViewAccount {
+                                account_id: account_id.clone(),
+                                include_proof: false,
+                            },
                         ))
                         .then(move |x| {
                             test_cross_shard_tx_callback(
@@ -311,6 +315,7 @@ mod tests {
                                     BlockReference::latest(),
                                     QueryRequest::ViewAccount {
                                         account_id: validators[i].to_string(),
+                                        include_proof: false,
                                     },
                                 ))
                                 .then(move |x| {
@@ -363,7 +368,10 @@ mod tests {
                         .1
                         .send(Query::new(
                             BlockReference::latest(),
-                            QueryRequest::ViewAccount { account_id: account_id.clone() },
+                            QueryRequest::ViewAccount {
+                                account_id: account_id.clone(),
+                                include_proof: false,
+                            },
                         ))
                         .then(move |x| {
                             test_cross_shard_tx_callback(
@@ -488,6 +496,7 @@ mod tests {
                                 BlockReference::latest(),
                                 QueryRequest::ViewAccount {
                                     account_id: flat_validators[i].to_string(),
+                                    include_proof: false,
                                 },
                             ))
                             .then(move |x| {
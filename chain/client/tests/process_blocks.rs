@@ -15,7 +15,7 @@ use near_chain::validate::validate_chunk_with_chunk_extra;
 use near_chain::{
     Block, ChainGenesis, ChainStore, ChainStoreAccess, ErrorKind, Provenance, RuntimeAdapter,
 };
-use near_chain_configs::{ClientConfig, Genesis};
+use near_chain_configs::{ClientConfig, Genesis, TransactionPoolConfig};
 use near_chunks::{ChunkStatus, ShardsManager};
 use near_client::test_utils::{create_chunk_on_height, setup_mock_all_validators};
 use near_client::test_utils::{setup_client, setup_mock, TestEnv};
@@ -61,6 +61,7 @@ pub fn create_nightshade_runtimes(genesis: &Genesis, n: usize) -> Vec<Arc<dyn Ru
                 genesis,
                 vec![],
                 vec![],
+                None,
             )) as Arc<dyn RuntimeAdapter>
         })
         .collect()
@@ -858,6 +859,54 @@ fn test_process_invalid_tx() {
     );
 }
 
+/// Transactions the pool has no room for are reported to the sender instead of being accepted.
+#[test]
+fn test_process_tx_rejected_by_pool() {
+    init_test_logger();
+    let network_adapter = Arc::new(MockNetworkAdapter::default());
+    let mut client = setup_client(
+        create_test_store(),
+        vec![vec!["test1"]],
+        1,
+        1,
+        Some("test1"),
+        false,
+        network_adapter.clone(),
+        ChainGenesis::test(),
+    );
+    client.shards_mgr = ShardsManager::new(
+        Some("test1".to_string()),
+        client.runtime_adapter.clone(),
+        network_adapter,
+        TransactionPoolConfig { max_transactions_per_signer: Some(1), ..Default::default() },
+    );
+    let signer = InMemorySigner::from_seed("test1", KeyType::ED25519, "test1");
+    let genesis_hash = *client.chain.genesis().hash();
+    let tx1 = SignedTransaction::send_money(
+        1,
+        "test1".to_string(),
+        "test0".to_string(),
+        &signer,
+        1,
+        genesis_hash,
+    );
+    let tx2 = SignedTransaction::send_money(
+        2,
+        "test1".to_string(),
+        "test0".to_string(),
+        &signer,
+        1,
+        genesis_hash,
+    );
+    assert_eq!(client.process_tx(tx1, false, false), NetworkClientResponses::ValidTx);
+    assert_eq!(
+        client.process_tx(tx2, false, false),
+        NetworkClientResponses::PoolRejected(
+            "The signer has too many transactions in the pool".to_string()
+        )
+    );
+}
+
 /// If someone produce a block with Utc::now() + 1 min, we should produce a block with valid timestamp
 #[test]
 fn test_time_attack() {
@@ -1709,6 +1758,7 @@ fn test_incorrect_validator_key_produce_block() {
         &genesis,
         vec![],
         vec![],
+        None,
     ));
     let signer = Arc::new(InMemoryValidatorSigner::from_seed("test0", KeyType::ED25519, "seed"));
     let mut config = ClientConfig::test(true, 10, 20, 2, false);
@@ -1799,7 +1849,10 @@ fn test_data_reset_before_state_sync() {
             &head.prev_block_hash,
             &head.last_block_hash,
             head_block.header().epoch_id(),
-            &QueryRequest::ViewAccount { account_id: "test_account".to_string() },
+            &QueryRequest::ViewAccount {
+                account_id: "test_account".to_string(),
+                include_proof: false,
+            },
         )
         .unwrap();
     assert!(matches!(response.kind, QueryResponseKind::ViewAccount(_)));
@@ -1813,7 +1866,7 @@ fn test_data_reset_before_state_sync() {
         &head.prev_block_hash,
         &head.last_block_hash,
         head_block.header().epoch_id(),
-        &QueryRequest::ViewAccount { account_id: "test_account".to_string() },
+        &QueryRequest::ViewAccount { account_id: "test_account".to_string(), include_proof: false },
     );
     // TODO(#3742): ViewClient still has data in cache by current design.
     assert!(response.is_ok());
@@ -2309,7 +2362,7 @@ fn test_query_final_state() {
                 &final_head.prev_block_hash,
                 last_final_block.hash(),
                 last_final_block.header().epoch_id(),
-                &QueryRequest::ViewAccount { account_id },
+                &QueryRequest::ViewAccount { account_id, include_proof: false },
             )
             .unwrap();
         match response.kind {
@@ -30,7 +30,10 @@ fn query_client() {
                 view_client
                     .send(Query::new(
                         BlockReference::latest(),
-                        QueryRequest::ViewAccount { account_id: "test".to_owned() },
+                        QueryRequest::ViewAccount {
+                            account_id: "test".to_owned(),
+                            include_proof: false,
+                        },
                     ))
                     .then(|res| {
                         match res.unwrap().unwrap().unwrap().kind {
@@ -173,3 +176,144 @@ fn test_execution_outcome_for_chunk() {
         })
         .unwrap();
 }
+
+/// EVM logs are looked up in the log index, and report the relayed Ethereum transaction.
+#[cfg(feature = "protocol_feature_evm")]
+#[test]
+fn test_get_evm_logs() {
+    use borsh::BorshSerialize;
+
+    use near_chain::{ChainGenesis, ChainStoreAccess};
+    use near_chain_configs::ClientConfig;
+    use near_client::test_utils::TestEnv;
+    use near_client::{start_view_client, GetEvmLogs};
+    use near_primitives::evm::{EvmLog, EvmLogFilter};
+    use near_primitives::hash::{hash, CryptoHash, Digest};
+    use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
+    use near_primitives::transaction::{
+        Action, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus, FunctionCallAction,
+    };
+    use near_store::DBCol;
+
+    init_test_logger();
+    let mut env = TestEnv::new(ChainGenesis::test(), 1, 1);
+    for height in 1..=4 {
+        env.produce_block(0, height);
+    }
+    let block = (1..=4)
+        .map(|height| env.clients[0].chain.get_block_by_height(height).unwrap().clone())
+        .filter(|block| block.chunks()[0].height_included() == block.header().height())
+        .last()
+        .expect("a block with a new chunk");
+    let height = block.header().height();
+
+    let raw_transaction = vec![1, 2, 3];
+    let receipt = Receipt {
+        predecessor_id: "test0".to_string(),
+        receiver_id: "evm".to_string(),
+        receipt_id: hash(b"raw_call"),
+        receipt: ReceiptEnum::Action(ActionReceipt {
+            signer_id: "test0".to_string(),
+            signer_public_key: InMemorySigner::from_seed("test0", KeyType::ED25519, "test0")
+                .public_key,
+            gas_price: 0,
+            output_data_receivers: vec![],
+            input_data_ids: vec![],
+            actions: vec![Action::FunctionCall(FunctionCallAction {
+                method_name: "raw_call".to_string(),
+                args: raw_transaction.clone(),
+                gas: 0,
+                deposit: 0,
+            })],
+        }),
+    };
+    let logs = vec![
+        EvmLog { address: [1; 20], topics: vec![[2; 32], [3; 32]], data: vec![4] },
+        EvmLog { address: [1; 20], topics: vec![[5; 32]], data: vec![] },
+    ];
+    let outcome = ExecutionOutcomeWithId {
+        id: receipt.receipt_id,
+        outcome: ExecutionOutcome {
+            logs: logs.iter().map(|log| log.to_hex()).collect(),
+            executor_id: "evm".to_string(),
+            status: ExecutionStatus::SuccessValue(vec![]),
+            ..Default::default()
+        },
+    };
+    let chain_store = env.clients[0].chain.mut_store();
+    let mut store_update = chain_store.store().store_update();
+    store_update.update_refcount(
+        DBCol::ColReceipts,
+        receipt.receipt_id.as_ref(),
+        &receipt.try_to_vec().unwrap(),
+        1,
+    );
+    store_update.commit().unwrap();
+    let mut chain_store_update = chain_store.store_update();
+    chain_store_update.save_outcomes_with_proofs(
+        block.hash(),
+        height,
+        0,
+        vec![outcome],
+        vec![vec![]],
+    );
+    chain_store_update.commit().unwrap();
+
+    let chain_genesis = env.chain_genesis.clone();
+    let runtime_adapter = env.clients[0].runtime_adapter.clone();
+    let network_adapter = env.network_adapters[0].clone();
+    let transaction_hash = CryptoHash(Digest(keccak_hash::keccak(&raw_transaction).0));
+    System::builder()
+        .stop_on_panic(true)
+        .run(move || {
+            let view_client = start_view_client(
+                None,
+                chain_genesis,
+                runtime_adapter,
+                network_adapter,
+                ClientConfig::test(true, 10, 20, 1, false),
+                #[cfg(feature = "adversarial")]
+                std::sync::Arc::new(std::sync::RwLock::new(
+                    near_client::AdversarialControls::default(),
+                )),
+            );
+            actix::spawn(async move {
+                let filter = |addresses, topics| EvmLogFilter {
+                    from_block: 0,
+                    to_block: height + 1,
+                    addresses,
+                    topics,
+                };
+                let get_logs = |filter| view_client.send(GetEvmLogs { filter });
+
+                // Found in the index by topic.
+                let found =
+                    get_logs(filter(vec![], vec![None, Some(vec![[3; 32]])])).await.unwrap();
+                let found = found.unwrap();
+                assert_eq!(found.len(), 1);
+                assert_eq!(found[0].log, logs[0]);
+                assert_eq!(found[0].block_height, height);
+                assert_eq!(found[0].block_hash, *block.hash());
+                assert_eq!(found[0].transaction_hash, transaction_hash);
+                assert_eq!(found[0].transaction_index, 0);
+                assert_eq!(found[0].log_index, 0);
+
+                // Found in the index by address.
+                let found = get_logs(filter(vec![[1; 20]], vec![])).await.unwrap().unwrap();
+                assert_eq!(found.iter().map(|log| log.log_index).collect::<Vec<_>>(), vec![0, 1]);
+
+                // Not in the index.
+                let found = get_logs(filter(vec![[2; 20]], vec![])).await.unwrap().unwrap();
+                assert!(found.is_empty());
+
+                // Scanned without an address or a topic.
+                let found = get_logs(filter(vec![], vec![])).await.unwrap().unwrap();
+                assert_eq!(found.len(), 2);
+                assert_eq!(found[1].log, logs[1]);
+
+                System::current().stop();
+            });
+            near_network::test_utils::wait_or_panic(5000);
+        })
+        .unwrap();
+}
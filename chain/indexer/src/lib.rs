@@ -7,14 +7,18 @@
 //! See the [example] for further details.
 //!
 //! [example]: https://github.com/nearprotocol/nearcore/tree/master/tools/indexer/example
+use std::sync::Arc;
+
 use tokio::sync::mpsc;
 
 pub use neard::{get_default_home, init_configs, NearConfig};
+mod sinks;
 mod streamer;
 
+pub use self::sinks::{IndexerSink, JsonFileSink, SinkConfig, SinkError, SqliteSink};
 pub use self::streamer::{
-    IndexerChunkView, IndexerExecutionOutcomeWithReceipt, IndexerTransactionWithOutcome,
-    StreamerMessage,
+    AccountMatcher, ActionKind, IndexerChunkView, IndexerExecutionOutcomeWithReceipt,
+    IndexerTransactionWithOutcome, StreamerEvent, StreamerMessage, StreamerMessageFilter,
 };
 pub use near_primitives;
 
@@ -46,9 +50,15 @@ pub enum SyncModeEnum {
     /// Real-time syncing, always taking the latest finalized block to stream
     LatestSynced,
     /// Starts syncing from the block NEAR Indexer was interrupted last time
+    /// (the block after the last one acknowledged when running a sink)
     FromInterruption,
     /// Specific block height to start syncing from
     BlockHeight(u64),
+    /// Streams the final blocks from `from` to `to` inclusive and stops, reading up to
+    /// `parallelism` blocks from the store concurrently while still streaming them in height
+    /// order. Streaming stops at the first block that can't be read, it is never skipped.
+    /// Meant for backfilling history from archival nodes
+    Range { from: u64, to: u64, parallelism: usize },
 }
 
 /// Enum to define whether await for node to be fully synced or stream while syncing (useful for indexing from genesis)
@@ -69,6 +79,8 @@ pub struct IndexerConfig {
     pub sync_mode: SyncModeEnum,
     /// Whether await for node to be synced or not
     pub await_for_node_synced: AwaitForNodeSyncedEnum,
+    /// Limits the data included into `StreamerMessage`, everything is streamed if `None`
+    pub filter: Option<StreamerMessageFilter>,
 }
 
 /// This is the core component, which handles `nearcore` and internal `streamer`.
@@ -77,6 +89,8 @@ pub struct Indexer {
     near_config: neard::NearConfig,
     view_client: actix::Addr<near_client::ViewClientActor>,
     client: actix::Addr<near_client::ClientActor>,
+    store_fetcher: streamer::StoreFetcher,
+    checkpoint: streamer::Checkpoint,
 }
 
 impl Indexer {
@@ -91,9 +105,21 @@ impl Indexer {
             ",
             indexer_config.home_dir.join("config.json").display()
         );
+        let store = neard::init_and_migrate_store(&indexer_config.home_dir, &near_config);
+        let runtime = Arc::new(neard::NightshadeRuntime::with_config(
+            &indexer_config.home_dir,
+            Arc::clone(&store),
+            &near_config,
+        ));
+        let store_fetcher = streamer::StoreFetcher::new(
+            Arc::clone(&store),
+            runtime.clone(),
+            near_config.genesis.config.genesis_height,
+        );
         let (client, view_client, _) =
-            neard::start_with_config(&indexer_config.home_dir, near_config.clone());
-        Self { view_client, client, near_config, indexer_config }
+            neard::start_with_runtime(near_config.clone(), store, runtime);
+        let checkpoint = streamer::Checkpoint::open(&indexer_config.home_dir);
+        Self { view_client, client, store_fetcher, near_config, indexer_config, checkpoint }
     }
 
     /// Boots up `near_indexer::streamer`, so it monitors the new blocks with chunks, transactions, receipts, and execution outcomes inside. The returned stream handler should be drained and handled on the user side.
@@ -102,13 +128,49 @@ impl Indexer {
         actix::spawn(streamer::start(
             self.view_client.clone(),
             self.client.clone(),
+            self.store_fetcher.clone(),
             self.near_config.clone(),
             self.indexer_config.clone(),
+            self.checkpoint.clone(),
+            streamer::CheckpointMode::OnSend,
             sender,
         ));
         receiver
     }
 
+    /// Boots up the optimistic streamer, which streams every new head of the chain without waiting for it to be final. When the head switches to another fork, the streamed blocks that are no longer on the canonical chain are reported with `StreamerEvent::Reverted` before the blocks of the new fork. `SyncModeEnum` is ignored, streaming starts from the current head, and the checkpoint is never advanced.
+    pub fn optimistic_streamer(&self) -> mpsc::Receiver<streamer::StreamerEvent> {
+        let (sender, receiver) = mpsc::channel(16);
+        actix::spawn(streamer::start_optimistic(
+            self.view_client.clone(),
+            self.client.clone(),
+            self.near_config.clone(),
+            self.indexer_config.clone(),
+            sender,
+        ));
+        receiver
+    }
+
+    /// Boots up `near_indexer::streamer` and hands every `StreamerMessage` to the `sink`. Unlike `streamer`, the checkpoint `SyncModeEnum::FromInterruption` resumes from only advances once the sink has flushed the block. Resolves when the streamer stops or when the sink keeps failing after `SinkConfig::max_retries` retries.
+    pub async fn run_sink<S: IndexerSink>(
+        &self,
+        sink: S,
+        sink_config: SinkConfig,
+    ) -> Result<(), SinkError> {
+        let (sender, receiver) = mpsc::channel(sink_config.buffer_size);
+        actix::spawn(streamer::start(
+            self.view_client.clone(),
+            self.client.clone(),
+            self.store_fetcher.clone(),
+            self.near_config.clone(),
+            self.indexer_config.clone(),
+            self.checkpoint.clone(),
+            streamer::CheckpointMode::OnAcknowledge,
+            sender,
+        ));
+        sinks::run(sink, receiver, self.checkpoint.clone(), sink_config).await
+    }
+
     /// Expose neard config
     pub fn near_config(&self) -> &neard::NearConfig {
         &self.near_config
@@ -0,0 +1,140 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::{IndexerSink, SinkError};
+use crate::StreamerMessage;
+
+/// Size of the chunks read backwards while looking for the last complete line
+const READ_CHUNK_SIZE: u64 = 4096;
+
+/// Appends every `StreamerMessage` as a line of JSON to a file.
+///
+/// Lines are buffered until the sink is flushed. A flush that fails halfway is rolled back to
+/// the last complete line, and so is a partial line left by a crash when the file is opened,
+/// so the file only ever holds complete lines.
+///
+/// Blocks delivered again after a restart are appended again, so readers should deduplicate
+/// lines by `block.header.height`.
+pub struct JsonFileSink {
+    file: File,
+    /// Length of the file up to the end of its last complete line
+    len: u64,
+    /// Lines handled since the last flush
+    pending: Vec<u8>,
+}
+
+impl JsonFileSink {
+    /// Opens the file for appending, creating it if it does not exist
+    pub fn open(path: &Path) -> Result<Self, SinkError> {
+        let mut file = OpenOptions::new().create(true).read(true).write(true).open(path)?;
+        let len = complete_lines_len(&mut file)?;
+        file.set_len(len)?;
+        Ok(Self { file, len, pending: vec![] })
+    }
+}
+
+/// Returns the length of the file up to the end of its last complete line
+fn complete_lines_len(file: &mut File) -> std::io::Result<u64> {
+    let mut len = file.metadata()?.len();
+    let mut buffer = [0u8; READ_CHUNK_SIZE as usize];
+    while len > 0 {
+        let chunk_size = std::cmp::min(len, READ_CHUNK_SIZE);
+        let chunk = &mut buffer[..chunk_size as usize];
+        file.seek(SeekFrom::Start(len - chunk_size))?;
+        file.read_exact(chunk)?;
+        if let Some(position) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(len - chunk_size + position as u64 + 1);
+        }
+        len -= chunk_size;
+    }
+    Ok(0)
+}
+
+/// Writes `bytes` at `offset` and syncs them to the disk
+fn write_at(file: &mut File, offset: u64, bytes: &[u8]) -> std::io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(bytes)?;
+    file.sync_data()
+}
+
+impl IndexerSink for JsonFileSink {
+    fn handle(&mut self, streamer_message: &StreamerMessage) -> Result<(), SinkError> {
+        // Serialize upfront so a failed serialization does not leave a partial line behind
+        let mut line = serde_json::to_vec(streamer_message)?;
+        line.push(b'\n');
+        self.pending.extend_from_slice(&line);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Err(err) = write_at(&mut self.file, self.len, &self.pending) {
+            // The retry writes the same lines again, starting from the last complete one
+            let _ = self.file.set_len(self.len);
+            return Err(err.into());
+        }
+        self.len += self.pending.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use super::*;
+    use crate::sinks::tests::streamer_message;
+
+    fn read_heights(path: &Path) -> Vec<u64> {
+        std::io::BufReader::new(File::open(path).unwrap())
+            .lines()
+            .map(|line| {
+                let message: StreamerMessage = serde_json::from_str(&line.unwrap()).unwrap();
+                message.block.header.height
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_json_file_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocks.json");
+        let mut sink = JsonFileSink::open(&path).unwrap();
+        sink.handle(&streamer_message(1)).unwrap();
+        sink.handle(&streamer_message(2)).unwrap();
+        // Lines are only written once flushed.
+        assert_eq!(read_heights(&path), Vec::<u64>::new());
+        sink.flush().unwrap();
+        assert_eq!(read_heights(&path), vec![1, 2]);
+        sink.handle(&streamer_message(3)).unwrap();
+        drop(sink);
+
+        let mut sink = JsonFileSink::open(&path).unwrap();
+        sink.handle(&streamer_message(3)).unwrap();
+        sink.flush().unwrap();
+        sink.flush().unwrap();
+        assert_eq!(read_heights(&path), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_json_file_sink_drops_partial_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocks.json");
+        let mut line = serde_json::to_vec(&streamer_message(1)).unwrap();
+        line.push(b'\n');
+        let partial_line = serde_json::to_vec(&streamer_message(2)).unwrap();
+        let mut contents = line.clone();
+        contents.extend_from_slice(&partial_line[..partial_line.len() / 2]);
+        std::fs::write(&path, &contents).unwrap();
+
+        let mut sink = JsonFileSink::open(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), line.len() as u64);
+        sink.handle(&streamer_message(2)).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(read_heights(&path), vec![1, 2]);
+    }
+}
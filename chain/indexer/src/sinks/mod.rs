@@ -0,0 +1,266 @@
+//! Sinks persist the stream of `StreamerMessage`s with at-least-once delivery.
+//!
+//! Every message is handed to the sink, retried with exponential backoff on failure. Every
+//! `SinkConfig::checkpoint_interval` blocks the sink is flushed and the height of the last
+//! handled block becomes the checkpoint `SyncModeEnum::FromInterruption` resumes from, so
+//! messages handled after the last checkpoint may be delivered again after a restart.
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time;
+use tracing::{info, warn};
+
+use crate::streamer::{Checkpoint, StreamerMessage};
+
+pub use self::json_file::JsonFileSink;
+pub use self::sqlite::SqliteSink;
+
+mod json_file;
+mod sqlite;
+
+const INDEXER: &str = "indexer";
+
+/// Destination for the messages streamed by the indexer.
+///
+/// Calls are made from a blocking thread, one at a time, so implementations may do blocking I/O.
+pub trait IndexerSink: Send + 'static {
+    /// Persists the message. As messages after the last checkpoint are delivered again after
+    /// a failure or restart, handling the same block twice must be harmless.
+    fn handle(&mut self, streamer_message: &StreamerMessage) -> Result<(), SinkError>;
+
+    /// Makes all the messages handled so far durable. Called before advancing the checkpoint.
+    fn flush(&mut self) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
+
+/// Error occurs when a sink fails to persist messages
+#[derive(Debug)]
+pub enum SinkError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Sqlite(rusqlite::Error),
+    Other(String),
+}
+
+impl std::fmt::Display for SinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SinkError::Io(err) => write!(f, "IO error: {}", err),
+            SinkError::Serialization(err) => write!(f, "Serialization error: {}", err),
+            SinkError::Sqlite(err) => write!(f, "SQLite error: {}", err),
+            SinkError::Other(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+impl From<std::io::Error> for SinkError {
+    fn from(error: std::io::Error) -> Self {
+        SinkError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SinkError {
+    fn from(error: serde_json::Error) -> Self {
+        SinkError::Serialization(error)
+    }
+}
+
+impl From<rusqlite::Error> for SinkError {
+    fn from(error: rusqlite::Error) -> Self {
+        SinkError::Sqlite(error)
+    }
+}
+
+/// Sink configuration to be provided to `Indexer::run_sink`
+#[derive(Debug, Clone)]
+pub struct SinkConfig {
+    /// Number of messages buffered for the sink before the streamer waits for it to catch up
+    pub buffer_size: usize,
+    /// Number of handled blocks after which the sink is flushed and the checkpoint advanced
+    pub checkpoint_interval: u64,
+    /// Number of retries of a failed sink call before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after every failed attempt
+    pub retry_delay: Duration,
+    /// Upper bound of the delay between retries
+    pub max_retry_delay: Duration,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: 16,
+            checkpoint_interval: 1,
+            max_retries: 10,
+            retry_delay: Duration::from_millis(100),
+            max_retry_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Drains `stream` into `sink`, advancing `checkpoint` as the sink acknowledges blocks.
+/// Returns once the stream ends or the sink fails after exhausting the retries.
+pub(crate) async fn run<S: IndexerSink>(
+    mut sink: S,
+    mut stream: mpsc::Receiver<StreamerMessage>,
+    checkpoint: Checkpoint,
+    config: SinkConfig,
+) -> Result<(), SinkError> {
+    let mut unflushed_block_height = None;
+    let mut unflushed_blocks = 0;
+    while let Some(streamer_message) = stream.recv().await {
+        let block_height = streamer_message.block.header.height;
+        let streamer_message = Arc::new(streamer_message);
+        sink = call_with_retries(sink, &config, move |sink| sink.handle(&streamer_message)).await?;
+        unflushed_block_height = Some(block_height);
+        unflushed_blocks += 1;
+        if unflushed_blocks >= config.checkpoint_interval {
+            sink = call_with_retries(sink, &config, |sink| sink.flush()).await?;
+            checkpoint.set_last_synced_block_height(block_height);
+            unflushed_block_height = None;
+            unflushed_blocks = 0;
+        }
+    }
+    if let Some(block_height) = unflushed_block_height {
+        call_with_retries(sink, &config, |sink| sink.flush()).await?;
+        checkpoint.set_last_synced_block_height(block_height);
+    }
+    info!(target: INDEXER, "Streamer stopped, sink is done");
+    Ok(())
+}
+
+/// Runs `call` on `sink` in a blocking thread, retrying with exponential backoff on failure.
+async fn call_with_retries<S, F>(mut sink: S, config: &SinkConfig, call: F) -> Result<S, SinkError>
+where
+    S: IndexerSink,
+    F: Fn(&mut S) -> Result<(), SinkError> + Clone + Send + 'static,
+{
+    let mut retry_delay = config.retry_delay;
+    let mut attempt = 0;
+    loop {
+        let attempt_call = call.clone();
+        let (returned_sink, result) = tokio::task::spawn_blocking(move || {
+            let result = attempt_call(&mut sink);
+            (sink, result)
+        })
+        .await
+        .map_err(|err| SinkError::Other(format!("Sink panicked: {}", err)))?;
+        sink = returned_sink;
+        match result {
+            Ok(()) => return Ok(sink),
+            Err(err) if attempt < config.max_retries => {
+                attempt += 1;
+                warn!(
+                    target: INDEXER,
+                    "Sink failed, retrying in {:?} (attempt {} of {}): {}",
+                    retry_delay,
+                    attempt,
+                    config.max_retries,
+                    err
+                );
+                time::sleep(retry_delay).await;
+                retry_delay = std::cmp::min(retry_delay * 2, config.max_retry_delay);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use near_primitives::block::Block;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::BlockHeight;
+    use near_primitives::utils::from_timestamp;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_primitives::views;
+
+    use super::*;
+
+    /// Message of an empty block at the given height
+    pub(crate) fn streamer_message(height: BlockHeight) -> StreamerMessage {
+        let block = Block::genesis(
+            PROTOCOL_VERSION,
+            vec![],
+            from_timestamp(0),
+            height,
+            100,
+            1_000,
+            CryptoHash::default(),
+        );
+        StreamerMessage {
+            block: views::BlockView::from_author_block("test".into(), block),
+            chunks: vec![],
+            state_changes: vec![],
+        }
+    }
+
+    /// Fails the first `failures` calls, then records the heights of the handled blocks
+    struct FlakySink {
+        failures: Arc<AtomicU32>,
+        handled: Arc<std::sync::Mutex<Vec<BlockHeight>>>,
+    }
+
+    impl IndexerSink for FlakySink {
+        fn handle(&mut self, streamer_message: &StreamerMessage) -> Result<(), SinkError> {
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(SinkError::Other("Sink is not ready".to_string()));
+            }
+            self.handled.lock().unwrap().push(streamer_message.block.header.height);
+            Ok(())
+        }
+    }
+
+    fn run_sink(
+        failures: u32,
+        heights: &[BlockHeight],
+    ) -> (Result<(), SinkError>, Vec<BlockHeight>, Option<BlockHeight>) {
+        let home_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(neard::get_store_path(home_dir.path())).unwrap();
+        let checkpoint = Checkpoint::open(home_dir.path());
+        let handled = Arc::new(std::sync::Mutex::new(vec![]));
+        let sink =
+            FlakySink { failures: Arc::new(AtomicU32::new(failures)), handled: handled.clone() };
+        let config = SinkConfig {
+            checkpoint_interval: 2,
+            max_retries: 2,
+            retry_delay: Duration::from_millis(1),
+            ..SinkConfig::default()
+        };
+        let (sender, receiver) = mpsc::channel(heights.len());
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let result = runtime.block_on(async {
+            for height in heights {
+                sender.send(streamer_message(*height)).await.unwrap();
+            }
+            drop(sender);
+            run(sink, receiver, checkpoint.clone(), config).await
+        });
+        let handled = handled.lock().unwrap().clone();
+        (result, handled, checkpoint.last_synced_block_height())
+    }
+
+    #[test]
+    fn test_run_retries_failed_calls() {
+        let (result, handled, checkpoint) = run_sink(2, &[1, 2, 3]);
+        assert!(result.is_ok());
+        assert_eq!(handled, vec![1, 2, 3]);
+        // The last block is checkpointed once the stream ends, before the interval is reached.
+        assert_eq!(checkpoint, Some(3));
+    }
+
+    #[test]
+    fn test_run_gives_up_after_max_retries() {
+        let (result, handled, checkpoint) = run_sink(3, &[1, 2, 3]);
+        assert!(result.is_err());
+        assert_eq!(handled, Vec::<BlockHeight>::new());
+        assert_eq!(checkpoint, None);
+    }
+}
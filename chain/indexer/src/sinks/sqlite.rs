@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use super::{IndexerSink, SinkError};
+use crate::StreamerMessage;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS blocks (
+        height INTEGER PRIMARY KEY,
+        hash TEXT NOT NULL,
+        message TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS transactions (
+        hash TEXT PRIMARY KEY,
+        block_height INTEGER NOT NULL,
+        signer_id TEXT NOT NULL,
+        receiver_id TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS transactions_block_height ON transactions (block_height);
+";
+
+/// Stores every `StreamerMessage` as JSON in the `blocks` table of a SQLite database, along
+/// with its transactions in the `transactions` table.
+///
+/// Rows are keyed by block height and transaction hash, so blocks delivered again after a
+/// restart replace the rows written the first time.
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+impl SqliteSink {
+    /// Opens the database, creating it and the tables if they do not exist
+    pub fn open(path: &Path) -> Result<Self, SinkError> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+}
+
+impl IndexerSink for SqliteSink {
+    fn handle(&mut self, streamer_message: &StreamerMessage) -> Result<(), SinkError> {
+        // Writes between two flushes are committed at once. SQLite rolls back the transaction by
+        // itself on some errors, so whether one is open is asked from the connection.
+        if self.connection.is_autocommit() {
+            self.connection.execute_batch("BEGIN")?;
+        }
+        let block_height = streamer_message.block.header.height as i64;
+        self.connection.execute(
+            "INSERT OR REPLACE INTO blocks (height, hash, message) VALUES (?1, ?2, ?3)",
+            params![
+                block_height,
+                streamer_message.block.header.hash.to_string(),
+                serde_json::to_string(streamer_message)?,
+            ],
+        )?;
+        for chunk in &streamer_message.chunks {
+            for transaction in &chunk.transactions {
+                self.connection.execute(
+                    "INSERT OR REPLACE INTO transactions (hash, block_height, signer_id, receiver_id)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        transaction.transaction.hash.to_string(),
+                        block_height,
+                        transaction.transaction.signer_id,
+                        transaction.transaction.receiver_id,
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        if !self.connection.is_autocommit() {
+            self.connection.execute_batch("COMMIT")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::tests::streamer_message;
+
+    fn block_heights(path: &Path) -> Vec<i64> {
+        let connection = Connection::open(path).unwrap();
+        let mut statement =
+            connection.prepare("SELECT height FROM blocks ORDER BY height").unwrap();
+        let heights = statement.query_map(params![], |row| row.get(0)).unwrap();
+        heights.map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_sqlite_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocks.sqlite");
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.handle(&streamer_message(1)).unwrap();
+        sink.handle(&streamer_message(2)).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(block_heights(&path), vec![1, 2]);
+
+        // Writes that are not flushed are rolled back.
+        sink.handle(&streamer_message(3)).unwrap();
+        drop(sink);
+        assert_eq!(block_heights(&path), vec![1, 2]);
+
+        // Blocks delivered again replace the rows written the first time.
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.handle(&streamer_message(2)).unwrap();
+        sink.handle(&streamer_message(3)).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(block_heights(&path), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sqlite_sink_retries_failed_insert() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocks.sqlite");
+        let mut sink = SqliteSink::open(&path).unwrap();
+        sink.connection
+            .execute_batch(
+                "CREATE TABLE failing_heights (height INTEGER);
+                 INSERT INTO failing_heights (height) VALUES (2);
+                 CREATE TRIGGER fail_insert BEFORE INSERT ON blocks
+                 WHEN EXISTS (SELECT 1 FROM failing_heights WHERE height = NEW.height)
+                 BEGIN SELECT RAISE(ABORT, 'forced failure'); END;",
+            )
+            .unwrap();
+
+        sink.handle(&streamer_message(1)).unwrap();
+        assert!(sink.handle(&streamer_message(2)).is_err());
+        // The failed insert leaves the transaction open with the rows written before it.
+        assert!(!sink.connection.is_autocommit());
+        sink.connection.execute_batch("DELETE FROM failing_heights").unwrap();
+        sink.handle(&streamer_message(2)).unwrap();
+        sink.flush().unwrap();
+        assert!(sink.connection.is_autocommit());
+        assert_eq!(block_heights(&path), vec![1, 2]);
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use rocksdb::DB;
+
+use near_primitives::types::BlockHeight;
+
+const LAST_SYNCED_BLOCK_HEIGHT: &[u8] = b"last_synced_block_height";
+
+/// Defines when the streamer advances the checkpoint `SyncModeEnum::FromInterruption` resumes from
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum CheckpointMode {
+    /// Once the block is sent to the receiver, so the last sent block is streamed again on restart
+    OnSend,
+    /// Once the consumer acknowledges the block, so streaming resumes from the following block
+    OnAcknowledge,
+}
+
+/// Height of the last block handled by the indexer, stored in the `indexer` database
+/// next to the node's store
+#[derive(Clone)]
+pub(crate) struct Checkpoint {
+    db: Arc<DB>,
+}
+
+impl Checkpoint {
+    pub(crate) fn open(home_dir: &Path) -> Self {
+        let mut indexer_db_path = neard::get_store_path(home_dir);
+        indexer_db_path.push_str("/indexer");
+        // TODO: implement proper error handling
+        Self { db: Arc::new(DB::open_default(indexer_db_path).unwrap()) }
+    }
+
+    pub(crate) fn last_synced_block_height(&self) -> Option<BlockHeight> {
+        self.db
+            .get(LAST_SYNCED_BLOCK_HEIGHT)
+            .unwrap()
+            .map(|value| String::from_utf8(value).unwrap().parse::<u64>().unwrap())
+    }
+
+    pub(crate) fn set_last_synced_block_height(&self, block_height: BlockHeight) {
+        self.db.put(LAST_SYNCED_BLOCK_HEIGHT, &block_height.to_string()).unwrap();
+    }
+}
@@ -12,3 +12,9 @@ impl From<MailboxError> for FailedToFetchData {
         FailedToFetchData::MailboxError(actix_error)
     }
 }
+
+impl From<near_chain::Error> for FailedToFetchData {
+    fn from(error: near_chain::Error) -> Self {
+        FailedToFetchData::String(error.to_string())
+    }
+}
@@ -11,7 +11,7 @@ pub use near_primitives::{types, views};
 
 use super::errors::FailedToFetchData;
 use super::types::IndexerExecutionOutcomeWithReceipt;
-use super::INDEXER;
+use super::{StreamerMessageFilter, INDEXER};
 
 pub(crate) async fn fetch_status(
     client: &Addr<near_client::ClientActor>,
@@ -33,6 +33,16 @@ pub(crate) async fn fetch_latest_block(
         .map_err(|err| FailedToFetchData::String(err.to_string()))
 }
 
+/// Fetches the head of the chain, which may be orphaned later on
+pub(crate) async fn fetch_head_block(
+    client: &Addr<near_client::ViewClientActor>,
+) -> Result<views::BlockView, FailedToFetchData> {
+    client
+        .send(near_client::GetBlock(types::BlockReference::Finality(types::Finality::None)))
+        .await?
+        .map_err(|err| FailedToFetchData::String(err.to_string()))
+}
+
 /// Fetches specific block by it's height
 pub(crate) async fn fetch_block_by_height(
     client: &Addr<near_client::ViewClientActor>,
@@ -77,9 +87,12 @@ async fn fetch_single_chunk(
 
 /// Fetch all ExecutionOutcomeWithId for current block
 /// Returns a HashMap where the key is Receipt id or Transaction hash and the value is ExecutionOutcome wth id and proof
+/// Receipts are only fetched for the outcomes of executors matching the `filter`, the other
+/// outcomes are kept without their receipt, so that transactions are still paired with theirs.
 pub(crate) async fn fetch_outcomes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
+    filter: Option<&StreamerMessageFilter>,
 ) -> Result<
     HashMap<near_primitives::types::ShardId, Vec<IndexerExecutionOutcomeWithReceipt>>,
     FailedToFetchData,
@@ -96,6 +109,16 @@ pub(crate) async fn fetch_outcomes(
     for (shard_id, shard_outcomes) in outcomes {
         let mut outcomes_with_receipts: Vec<IndexerExecutionOutcomeWithReceipt> = vec![];
         for outcome in shard_outcomes {
+            if !filter.map_or(true, |filter| filter.matches_executor(&outcome.outcome.executor_id))
+            {
+                outcomes_with_receipts.push(IndexerExecutionOutcomeWithReceipt {
+                    #[cfg(feature = "protocol_feature_evm")]
+                    evm_logs: evm_logs(&outcome),
+                    execution_outcome: outcome,
+                    receipt: None,
+                });
+                continue;
+            }
             let receipt = match fetch_receipt_by_id(&client, outcome.id).await {
                 Ok(res) => res,
                 Err(e) => {
@@ -108,8 +131,12 @@ pub(crate) async fn fetch_outcomes(
                     None
                 }
             };
-            outcomes_with_receipts
-                .push(IndexerExecutionOutcomeWithReceipt { execution_outcome: outcome, receipt });
+            outcomes_with_receipts.push(IndexerExecutionOutcomeWithReceipt {
+                #[cfg(feature = "protocol_feature_evm")]
+                evm_logs: evm_logs(&outcome),
+                execution_outcome: outcome,
+                receipt,
+            });
         }
         shard_execution_outcomes_with_receipts.insert(shard_id, outcomes_with_receipts);
     }
@@ -117,6 +144,24 @@ pub(crate) async fn fetch_outcomes(
     Ok(shard_execution_outcomes_with_receipts)
 }
 
+/// Decodes the EVM logs of a successful outcome of the EVM account.
+#[cfg(feature = "protocol_feature_evm")]
+pub(crate) fn evm_logs(
+    outcome: &views::ExecutionOutcomeWithIdView,
+) -> Vec<near_primitives::evm::EvmLog> {
+    if !near_runtime_utils::is_account_evm(&outcome.outcome.executor_id)
+        || matches!(outcome.outcome.status, views::ExecutionStatusView::Failure(_))
+    {
+        return vec![];
+    }
+    outcome
+        .outcome
+        .logs
+        .iter()
+        .filter_map(|log| near_primitives::evm::EvmLog::from_hex(log))
+        .collect()
+}
+
 async fn fetch_receipt_by_id(
     client: &Addr<near_client::ViewClientActor>,
     receipt_id: CryptoHash,
@@ -0,0 +1,242 @@
+use std::str::FromStr;
+
+use near_primitives::types::AccountId;
+use near_primitives::views;
+
+/// Matches an account either by its exact ID or, when written as `*.suffix`, by a suffix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountMatcher {
+    Exact(AccountId),
+    /// Matches every account ending with the suffix, including the leading `.`
+    Suffix(String),
+}
+
+impl AccountMatcher {
+    pub fn matches(&self, account_id: &str) -> bool {
+        match self {
+            AccountMatcher::Exact(expected) => account_id == expected.as_str(),
+            AccountMatcher::Suffix(suffix) => account_id.ends_with(suffix.as_str()),
+        }
+    }
+}
+
+impl FromStr for AccountMatcher {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.strip_prefix('*') {
+            Some(suffix) => AccountMatcher::Suffix(suffix.to_string()),
+            None => AccountMatcher::Exact(s.to_string()),
+        })
+    }
+}
+
+/// Kind of an action in a transaction or receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    CreateAccount,
+    DeployContract,
+    FunctionCall,
+    Transfer,
+    Stake,
+    AddKey,
+    DeleteKey,
+    DeleteAccount,
+}
+
+impl From<&views::ActionView> for ActionKind {
+    fn from(action: &views::ActionView) -> Self {
+        match action {
+            views::ActionView::CreateAccount => ActionKind::CreateAccount,
+            views::ActionView::DeployContract { .. } => ActionKind::DeployContract,
+            views::ActionView::FunctionCall { .. } => ActionKind::FunctionCall,
+            views::ActionView::Transfer { .. } => ActionKind::Transfer,
+            views::ActionView::Stake { .. } => ActionKind::Stake,
+            views::ActionView::AddKey { .. } => ActionKind::AddKey,
+            views::ActionView::DeleteKey { .. } => ActionKind::DeleteKey,
+            views::ActionView::DeleteAccount { .. } => ActionKind::DeleteAccount,
+        }
+    }
+}
+
+/// Limits transactions, receipts, execution outcomes and state changes included into
+/// `StreamerMessage`. Every non-empty criterion has to match; empty ones match everything.
+#[derive(Debug, Clone, Default)]
+pub struct StreamerMessageFilter {
+    /// Matches transactions by signer or receiver, receipts by predecessor or receiver,
+    /// execution outcomes by executor and state changes by the affected account
+    pub accounts: Vec<AccountMatcher>,
+    /// Matches transactions and receipts with at least one action of these kinds
+    pub action_kinds: Vec<ActionKind>,
+    /// Matches transactions and receipts with at least one function call to these methods
+    pub method_names: Vec<String>,
+}
+
+impl StreamerMessageFilter {
+    fn matches_accounts(&self, account_ids: &[&str]) -> bool {
+        self.accounts.is_empty()
+            || self
+                .accounts
+                .iter()
+                .any(|matcher| account_ids.iter().any(|account_id| matcher.matches(account_id)))
+    }
+
+    fn matches_actions(&self, actions: &[views::ActionView]) -> bool {
+        let matches_action_kinds = self.action_kinds.is_empty()
+            || actions.iter().any(|action| self.action_kinds.contains(&ActionKind::from(action)));
+        let matches_method_names = self.method_names.is_empty()
+            || actions.iter().any(|action| match action {
+                views::ActionView::FunctionCall { method_name, .. } => {
+                    self.method_names.contains(method_name)
+                }
+                _ => false,
+            });
+        matches_action_kinds && matches_method_names
+    }
+
+    fn has_action_criteria(&self) -> bool {
+        !self.action_kinds.is_empty() || !self.method_names.is_empty()
+    }
+
+    pub(crate) fn matches_transaction(&self, transaction: &views::SignedTransactionView) -> bool {
+        self.matches_accounts(&[transaction.signer_id.as_str(), transaction.receiver_id.as_str()])
+            && self.matches_actions(&transaction.actions)
+    }
+
+    /// Data receipts carry no actions, so they only match filters without action criteria
+    pub(crate) fn matches_receipt(&self, receipt: &views::ReceiptView) -> bool {
+        self.matches_accounts(&[receipt.predecessor_id.as_str(), receipt.receiver_id.as_str()])
+            && match &receipt.receipt {
+                views::ReceiptEnumView::Action { actions, .. } => self.matches_actions(actions),
+                views::ReceiptEnumView::Data { .. } => !self.has_action_criteria(),
+            }
+    }
+
+    /// Outcomes of other executors never match, so their receipts don't have to be fetched
+    pub(crate) fn matches_executor(&self, executor_id: &str) -> bool {
+        self.matches_accounts(&[executor_id])
+    }
+
+    /// Outcomes are matched by their executor and by the actions of their receipt, outcomes
+    /// without a known receipt only match filters without action criteria
+    pub(crate) fn matches_outcome(
+        &self,
+        outcome: &crate::IndexerExecutionOutcomeWithReceipt,
+    ) -> bool {
+        self.matches_executor(&outcome.execution_outcome.outcome.executor_id)
+            && match outcome.receipt.as_ref().map(|receipt| &receipt.receipt) {
+                Some(views::ReceiptEnumView::Action { actions, .. }) => {
+                    self.matches_actions(actions)
+                }
+                Some(views::ReceiptEnumView::Data { .. }) | None => !self.has_action_criteria(),
+            }
+    }
+
+    pub(crate) fn matches_state_change(
+        &self,
+        state_change: &views::StateChangeWithCauseView,
+    ) -> bool {
+        let account_id = match &state_change.value {
+            views::StateChangeValueView::AccountUpdate { account_id, .. }
+            | views::StateChangeValueView::AccountDeletion { account_id }
+            | views::StateChangeValueView::AccessKeyUpdate { account_id, .. }
+            | views::StateChangeValueView::AccessKeyDeletion { account_id, .. }
+            | views::StateChangeValueView::DataUpdate { account_id, .. }
+            | views::StateChangeValueView::DataDeletion { account_id, .. }
+            | views::StateChangeValueView::ContractCodeUpdate { account_id, .. }
+            | views::StateChangeValueView::ContractCodeDeletion { account_id } => account_id,
+        };
+        self.matches_accounts(&[account_id.as_str()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_matcher() {
+        let exact: AccountMatcher = "alice.near".parse().unwrap();
+        assert!(exact.matches("alice.near"));
+        assert!(!exact.matches("bob.alice.near"));
+        let suffix: AccountMatcher = "*.pool.near".parse().unwrap();
+        assert_eq!(suffix, AccountMatcher::Suffix(".pool.near".to_string()));
+        assert!(suffix.matches("staking.pool.near"));
+        assert!(!suffix.matches("pool.near"));
+    }
+
+    #[test]
+    fn test_matches_actions() {
+        let filter = StreamerMessageFilter {
+            accounts: vec![],
+            action_kinds: vec![ActionKind::FunctionCall],
+            method_names: vec!["ft_transfer".to_string()],
+        };
+        let function_call = |method_name: &str| views::ActionView::FunctionCall {
+            method_name: method_name.to_string(),
+            args: String::new(),
+            gas: 0,
+            deposit: 0,
+        };
+        assert!(filter.matches_actions(&[
+            views::ActionView::Transfer { deposit: 1 },
+            function_call("ft_transfer")
+        ]));
+        assert!(!filter.matches_actions(&[function_call("ft_balance_of")]));
+        assert!(!filter.matches_actions(&[views::ActionView::Transfer { deposit: 1 }]));
+        assert!(StreamerMessageFilter::default().matches_actions(&[]));
+    }
+
+    #[test]
+    fn test_matches_outcome_by_paired_receipt() {
+        let filter = StreamerMessageFilter {
+            accounts: vec!["alice.near".parse().unwrap()],
+            action_kinds: vec![],
+            method_names: vec!["ft_transfer".to_string()],
+        };
+        let receipt_id = near_primitives::hash::hash(b"receipt");
+        let receipt = views::ReceiptView {
+            predecessor_id: "alice.near".to_string(),
+            receiver_id: "alice.near".to_string(),
+            receipt_id,
+            receipt: views::ReceiptEnumView::Action {
+                signer_id: "alice.near".to_string(),
+                signer_public_key: near_crypto::PublicKey::empty(near_crypto::KeyType::ED25519),
+                gas_price: 0,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![views::ActionView::FunctionCall {
+                    method_name: "ft_transfer".to_string(),
+                    args: String::new(),
+                    gas: 0,
+                    deposit: 0,
+                }],
+            },
+        };
+        let mut outcome = crate::IndexerExecutionOutcomeWithReceipt {
+            execution_outcome: views::ExecutionOutcomeWithIdView {
+                proof: vec![],
+                block_hash: Default::default(),
+                id: receipt_id,
+                outcome: views::ExecutionOutcomeView {
+                    logs: vec![],
+                    receipt_ids: vec![],
+                    gas_burnt: 0,
+                    tokens_burnt: 0,
+                    executor_id: "alice.near".to_string(),
+                    status: views::ExecutionStatusView::SuccessValue(String::new()),
+                },
+            },
+            receipt: None,
+            #[cfg(feature = "protocol_feature_evm")]
+            evm_logs: vec![],
+        };
+        // Method names can only be matched once the local receipt is paired with its outcome.
+        assert!(!filter.matches_outcome(&outcome));
+        outcome.receipt = Some(receipt);
+        assert!(filter.matches_outcome(&outcome));
+        // The receipt sent by a matching account is executed by another one.
+        outcome.execution_outcome.outcome.executor_id = "bob.near".to_string();
+        assert!(!filter.matches_outcome(&outcome));
+    }
+}
@@ -1,41 +1,55 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use actix::Addr;
-use rocksdb::DB;
+use futures::stream::StreamExt;
 use tokio::sync::mpsc;
 use tokio::time;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
+use near_primitives::types::{Balance, BlockHeight, ShardId};
 pub use near_primitives::views;
 
 use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
 
+pub(crate) use self::checkpoint::{Checkpoint, CheckpointMode};
 use self::errors::FailedToFetchData;
 use self::fetchers::{
-    fetch_block_by_height, fetch_chunks, fetch_latest_block, fetch_outcomes, fetch_state_changes,
-    fetch_status,
+    fetch_block_by_hash, fetch_block_by_height, fetch_chunks, fetch_latest_block, fetch_outcomes,
+    fetch_state_changes, fetch_status,
 };
+pub use self::filter::{AccountMatcher, ActionKind, StreamerMessageFilter};
+pub(crate) use self::optimistic::start as start_optimistic;
+pub(crate) use self::store_fetchers::StoreFetcher;
 pub use self::types::{
     IndexerChunkView, IndexerExecutionOutcomeWithReceipt, IndexerTransactionWithOutcome,
-    StreamerMessage,
+    StreamerEvent, StreamerMessage,
 };
 use self::utils::convert_transactions_sir_into_local_receipts;
 
+mod checkpoint;
 mod errors;
 mod fetchers;
+mod filter;
+mod optimistic;
+mod store_fetchers;
 mod types;
 mod utils;
 
 const INDEXER: &str = "indexer";
 const INTERVAL: Duration = Duration::from_millis(500);
+/// Number of times reading a block from the store is retried before the backfill stops
+const MAX_BACKFILL_RETRIES: usize = 3;
 
 /// This function supposed to return the entire `StreamerMessage`.
 /// It fetches the block and all related parts (chunks, outcomes, state changes etc.)
-/// and returns everything together in one struct
+/// and returns everything together in one struct.
 async fn build_streamer_message(
     client: &Addr<near_client::ViewClientActor>,
     block: views::BlockView,
     near_config: &neard::NearConfig,
+    filter: Option<&StreamerMessageFilter>,
 ) -> Result<StreamerMessage, FailedToFetchData> {
     let chunks_to_fetch = block
         .chunks
@@ -49,9 +63,36 @@ async fn build_streamer_message(
         })
         .collect::<Vec<_>>();
     let chunks = fetch_chunks(&client, chunks_to_fetch).await?;
+    let shards_outcomes = fetch_outcomes(&client, block.header.hash, filter).await?;
+    let state_changes = fetch_state_changes(&client, block.header.hash).await?;
+    let prev_block_gas_price =
+        fetch_block_by_hash(&client, block.header.prev_hash).await?.header.gas_price;
+
+    Ok(assemble_streamer_message(
+        block,
+        chunks,
+        shards_outcomes,
+        state_changes,
+        prev_block_gas_price,
+        near_config,
+        filter,
+    ))
+}
 
+/// Pairs the transactions and the receipts of the chunks with their execution outcomes.
+/// Transactions, receipts, outcomes and state changes not matching the `filter` are dropped
+/// once the local receipts are converted and paired with their outcomes, so that outcomes of
+/// local receipts are matched by their receipts as well.
+fn assemble_streamer_message(
+    block: views::BlockView,
+    chunks: Vec<views::ChunkView>,
+    mut shards_outcomes: HashMap<ShardId, Vec<IndexerExecutionOutcomeWithReceipt>>,
+    mut state_changes: views::StateChangesView,
+    prev_block_gas_price: Balance,
+    near_config: &neard::NearConfig,
+    filter: Option<&StreamerMessageFilter>,
+) -> StreamerMessage {
     let mut local_receipts: Vec<views::ReceiptView> = vec![];
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
     let mut indexer_chunks: Vec<IndexerChunkView> = vec![];
 
     for chunk in chunks {
@@ -62,7 +103,7 @@ async fn build_streamer_message(
             .expect("Execution outcomes for given shard should be present");
         let mut receipt_outcomes = outcomes.split_off(transactions.len());
 
-        let indexer_transactions = transactions
+        let mut indexer_transactions = transactions
             .into_iter()
             .zip(outcomes.into_iter())
             .map(|(transaction, outcome)| {
@@ -75,15 +116,14 @@ async fn build_streamer_message(
             .collect::<Vec<IndexerTransactionWithOutcome>>();
 
         let chunk_local_receipts = convert_transactions_sir_into_local_receipts(
-            &client,
             near_config,
             indexer_transactions
                 .iter()
                 .filter(|tx| tx.transaction.signer_id == tx.transaction.receiver_id)
                 .collect::<Vec<&IndexerTransactionWithOutcome>>(),
             &block,
-        )
-        .await?;
+            prev_block_gas_price,
+        );
 
         local_receipts.extend_from_slice(&chunk_local_receipts);
 
@@ -101,6 +141,12 @@ async fn build_streamer_message(
             }
         }
 
+        if let Some(filter) = filter {
+            indexer_transactions.retain(|tx| filter.matches_transaction(&tx.transaction));
+            chunk_receipts.retain(|receipt| filter.matches_receipt(receipt));
+            receipt_outcomes.retain(|outcome| filter.matches_outcome(outcome));
+        }
+
         indexer_chunks.push(IndexerChunkView {
             author,
             header,
@@ -110,9 +156,11 @@ async fn build_streamer_message(
         });
     }
 
-    let state_changes = fetch_state_changes(&client, block.header.hash).await?;
+    if let Some(filter) = filter {
+        state_changes.retain(|state_change| filter.matches_state_change(state_change));
+    }
 
-    Ok(StreamerMessage { block, chunks: indexer_chunks, state_changes })
+    StreamerMessage { block, chunks: indexer_chunks, state_changes }
 }
 
 /// Function that starts Streamer's busy loop. Every half a seconds it fetches the status
@@ -122,16 +170,29 @@ async fn build_streamer_message(
 pub(crate) async fn start(
     view_client: Addr<near_client::ViewClientActor>,
     client: Addr<near_client::ClientActor>,
+    store_fetcher: StoreFetcher,
     near_config: neard::NearConfig,
     indexer_config: IndexerConfig,
+    checkpoint: Checkpoint,
+    checkpoint_mode: CheckpointMode,
     blocks_sink: mpsc::Sender<StreamerMessage>,
 ) {
     info!(target: INDEXER, "Starting Streamer...");
-    let mut indexer_db_path = neard::get_store_path(&indexer_config.home_dir);
-    indexer_db_path.push_str("/indexer");
-
-    // TODO: implement proper error handling
-    let db = DB::open_default(indexer_db_path).unwrap();
+    if let crate::SyncModeEnum::Range { from, to, parallelism } = indexer_config.sync_mode {
+        backfill(
+            store_fetcher,
+            near_config,
+            indexer_config.filter,
+            checkpoint,
+            checkpoint_mode,
+            blocks_sink,
+            from,
+            to,
+            parallelism,
+        )
+        .await;
+        return;
+    }
     let mut last_synced_block_height: Option<near_primitives::types::BlockHeight> = None;
 
     'main: loop {
@@ -162,13 +223,15 @@ pub(crate) async fn start(
         } else {
             match indexer_config.sync_mode {
                 crate::SyncModeEnum::FromInterruption => {
-                    match db.get(b"last_synced_block_height").unwrap() {
-                        Some(value) => String::from_utf8(value).unwrap().parse::<u64>().unwrap(),
-                        None => latest_block_height,
+                    match (checkpoint.last_synced_block_height(), checkpoint_mode) {
+                        (Some(height), CheckpointMode::OnSend) => height,
+                        (Some(height), CheckpointMode::OnAcknowledge) => height + 1,
+                        (None, _) => latest_block_height,
                     }
                 }
                 crate::SyncModeEnum::LatestSynced => latest_block_height,
                 crate::SyncModeEnum::BlockHeight(height) => height,
+                crate::SyncModeEnum::Range { .. } => unreachable!("Range is streamed by backfill"),
             }
         };
 
@@ -180,7 +243,13 @@ pub(crate) async fn start(
         );
         for block_height in start_syncing_block_height..=latest_block_height {
             if let Ok(block) = fetch_block_by_height(&view_client, block_height).await {
-                let response = build_streamer_message(&view_client, block, &near_config).await;
+                let response = build_streamer_message(
+                    &view_client,
+                    block,
+                    &near_config,
+                    indexer_config.filter.as_ref(),
+                )
+                .await;
 
                 match response {
                     Ok(streamer_message) => {
@@ -202,8 +271,106 @@ pub(crate) async fn start(
                     }
                 }
             }
-            db.put(b"last_synced_block_height", &block_height.to_string()).unwrap();
+            if let CheckpointMode::OnSend = checkpoint_mode {
+                checkpoint.set_last_synced_block_height(block_height);
+            }
             last_synced_block_height = Some(block_height);
         }
     }
 }
+
+/// Streams the blocks from `from` to `to` inclusive and stops. Up to `parallelism` blocks are
+/// read from the store and built concurrently, yet `StreamerMessage`s are sent in strict height
+/// order. A block that still can't be read after `MAX_BACKFILL_RETRIES` retries stops the
+/// backfill, so no block is ever skipped; the checkpoint points at the last block streamed.
+async fn backfill(
+    store_fetcher: StoreFetcher,
+    near_config: neard::NearConfig,
+    filter: Option<StreamerMessageFilter>,
+    checkpoint: Checkpoint,
+    checkpoint_mode: CheckpointMode,
+    blocks_sink: mpsc::Sender<StreamerMessage>,
+    from: BlockHeight,
+    to: BlockHeight,
+    parallelism: usize,
+) {
+    if !near_config.client_config.archive {
+        warn!(
+            target: INDEXER,
+            "Backfilling from a non-archival node, garbage collected blocks can't be streamed"
+        );
+    }
+    info!(target: INDEXER, "Backfilling blocks #{} to #{}, {} at a time", from, to, parallelism);
+    let near_config = Arc::new(near_config);
+    let filter = Arc::new(filter);
+    let mut responses = futures::stream::iter(from..=to)
+        .map(|block_height| {
+            let response = fetch_from_store_with_retries(
+                store_fetcher.clone(),
+                Arc::clone(&near_config),
+                Arc::clone(&filter),
+                block_height,
+            );
+            async move { (block_height, response.await) }
+        })
+        .buffered(std::cmp::max(parallelism, 1));
+
+    while let Some((block_height, response)) = responses.next().await {
+        match response {
+            Ok(Some(streamer_message)) => {
+                if blocks_sink.send(streamer_message).await.is_err() {
+                    info!(
+                        target: INDEXER,
+                        "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
+                    );
+                    return;
+                }
+            }
+            Ok(None) => debug!(target: INDEXER, "No block at height #{}", block_height),
+            Err(err) => {
+                error!(
+                    target: INDEXER,
+                    "Failed to read block #{} from the store, stopping the backfill: {:?}",
+                    block_height,
+                    err
+                );
+                return;
+            }
+        }
+        if let CheckpointMode::OnSend = checkpoint_mode {
+            checkpoint.set_last_synced_block_height(block_height);
+        }
+    }
+    info!(target: INDEXER, "Backfill of blocks #{} to #{} is done", from, to);
+}
+
+/// Builds the `StreamerMessage` of the block at `block_height` from the store on the blocking
+/// thread pool, retrying up to `MAX_BACKFILL_RETRIES` times.
+async fn fetch_from_store_with_retries(
+    store_fetcher: StoreFetcher,
+    near_config: Arc<neard::NearConfig>,
+    filter: Arc<Option<StreamerMessageFilter>>,
+    block_height: BlockHeight,
+) -> Result<Option<StreamerMessage>, FailedToFetchData> {
+    let mut retries = 0;
+    loop {
+        let (store_fetcher, near_config, filter) =
+            (store_fetcher.clone(), Arc::clone(&near_config), Arc::clone(&filter));
+        let response = tokio::task::spawn_blocking(move || {
+            store_fetcher.build_streamer_message(block_height, &near_config, (*filter).as_ref())
+        })
+        .await
+        .unwrap_or_else(|err| Err(FailedToFetchData::String(err.to_string())));
+        match response {
+            Err(err) if retries < MAX_BACKFILL_RETRIES => {
+                warn!(
+                    target: INDEXER,
+                    "Failed to read block #{} from the store, retrying: {:?}", block_height, err
+                );
+                retries += 1;
+                time::sleep(INTERVAL).await;
+            }
+            response => return response,
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Streams blocks as soon as they become the head of the chain, before they are final.
+//! When the head switches to another fork, the streamed blocks that are no longer on the
+//! canonical chain are reverted before the blocks of the new fork are streamed.
+use std::future::Future;
+
+use actix::Addr;
+use tokio::sync::mpsc;
+use tokio::time;
+use tracing::{debug, info};
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+use super::errors::FailedToFetchData;
+use super::fetchers::{fetch_block_by_hash, fetch_head_block, fetch_latest_block, fetch_status};
+use super::{build_streamer_message, views, StreamerEvent, INDEXER, INTERVAL};
+use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
+
+pub(crate) async fn start(
+    view_client: Addr<near_client::ViewClientActor>,
+    client: Addr<near_client::ClientActor>,
+    near_config: neard::NearConfig,
+    indexer_config: IndexerConfig,
+    events_sink: mpsc::Sender<StreamerEvent>,
+) {
+    info!(target: INDEXER, "Starting optimistic Streamer...");
+    // Streamed blocks on the current branch in height order, down to the last final block
+    let mut branch: Vec<(BlockHeight, CryptoHash)> = vec![];
+
+    loop {
+        time::sleep(INTERVAL).await;
+        if let AwaitForNodeSyncedEnum::WaitForFullSync = indexer_config.await_for_node_synced {
+            if let Ok(status) = fetch_status(&client).await {
+                if status.sync_info.syncing {
+                    continue;
+                }
+            }
+        }
+        let head = match fetch_head_block(&view_client).await {
+            Ok(head) => head,
+            Err(_) => continue,
+        };
+        if branch.last().map(|(_, hash)| *hash) == Some(head.header.hash) {
+            continue;
+        }
+
+        let final_block_height = match fetch_latest_block(&view_client).await {
+            Ok(final_block) => final_block.header.height,
+            Err(_) => continue,
+        };
+
+        let fetch_block = |hash| fetch_block_by_hash(&view_client, hash);
+        let (new_blocks, fork_point) =
+            match find_new_blocks(fetch_block, head, &branch, final_block_height).await {
+                Ok(result) => result,
+                Err(err) => {
+                    debug!(target: INDEXER, "Failed to fetch the new blocks: {:#?}", err);
+                    continue;
+                }
+            };
+
+        // Revert the blocks after the fork point, from the highest one down
+        let reverted = branch.split_off(fork_point);
+        for (block_height, block_hash) in reverted.into_iter().rev() {
+            debug!(target: INDEXER, "Reverting block #{} {}", block_height, block_hash);
+            if events_sink.send(StreamerEvent::Reverted { block_hash, block_height }).await.is_err()
+            {
+                info!(target: INDEXER, "Listener doesn't listen. terminating...");
+                return;
+            }
+        }
+
+        for block in new_blocks {
+            let block_height = block.header.height;
+            let block_hash = block.header.hash;
+            match build_streamer_message(
+                &view_client,
+                block,
+                &near_config,
+                indexer_config.filter.as_ref(),
+            )
+            .await
+            {
+                Ok(streamer_message) => {
+                    if events_sink.send(StreamerEvent::Block(streamer_message)).await.is_err() {
+                        info!(target: INDEXER, "Listener doesn't listen. terminating...");
+                        return;
+                    }
+                }
+                Err(err) => {
+                    // The block and its descendants are found again and retried on the next
+                    // poll, as the block isn't added to the branch
+                    debug!(target: INDEXER, "Missing data, retrying block #{} later", block_height);
+                    debug!(target: INDEXER, "{:#?}", err);
+                    break;
+                }
+            }
+            branch.push((block_height, block_hash));
+        }
+
+        // Final blocks cannot be reverted, keep only the last one as the anchor of the branch
+        if let Some(anchor) = branch.iter().rposition(|(height, _)| *height <= final_block_height) {
+            branch.drain(..anchor);
+        }
+    }
+}
+
+/// Walks back from `head` to the last streamed block it descends from. Returns the blocks after
+/// it in height order, and the index in `branch` after which streamed blocks are orphaned.
+/// Streamed blocks up to `final_block_height` are never reverted.
+async fn find_new_blocks<F, Fut>(
+    mut fetch_block: F,
+    head: views::BlockView,
+    branch: &[(BlockHeight, CryptoHash)],
+    final_block_height: BlockHeight,
+) -> Result<(Vec<views::BlockView>, usize), FailedToFetchData>
+where
+    F: FnMut(CryptoHash) -> Fut,
+    Fut: Future<Output = Result<views::BlockView, FailedToFetchData>>,
+{
+    let (lowest_height, lowest_hash) = match branch.first() {
+        Some(lowest) => *lowest,
+        None => return Ok((vec![head], 0)),
+    };
+    let mut new_blocks = vec![];
+    let mut block = head;
+    while block.header.height >= lowest_height {
+        if let Some(index) = branch.iter().position(|(_, hash)| *hash == block.header.hash) {
+            new_blocks.reverse();
+            return Ok((new_blocks, index + 1));
+        }
+        let prev_hash = block.header.prev_hash;
+        new_blocks.push(block);
+        block = fetch_block(prev_hash).await?;
+    }
+    // The head forked off below all the streamed blocks, which can only happen while none of
+    // them is final.
+    if lowest_height <= final_block_height {
+        return Err(FailedToFetchData::String(format!(
+            "The head doesn't descend from the final block #{} {}",
+            lowest_height, lowest_hash
+        )));
+    }
+    new_blocks.reverse();
+    Ok((new_blocks, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use futures::executor::block_on;
+    use futures::future;
+
+    use near_crypto::KeyType;
+    use near_primitives::block::Block;
+    use near_primitives::utils::from_timestamp;
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    use super::*;
+
+    struct TestChain {
+        blocks: HashMap<CryptoHash, Block>,
+        signer: InMemoryValidatorSigner,
+    }
+
+    impl TestChain {
+        fn new() -> (Self, Block) {
+            let next_bp_hash = CryptoHash::default();
+            let genesis = Block::genesis(
+                PROTOCOL_VERSION,
+                vec![],
+                from_timestamp(0),
+                0,
+                100,
+                1_000,
+                next_bp_hash,
+            );
+            let signer = InMemoryValidatorSigner::from_seed("test", KeyType::ED25519, "test");
+            let blocks = vec![(*genesis.hash(), genesis.clone())].into_iter().collect();
+            (Self { blocks, signer }, genesis)
+        }
+
+        fn produce(&mut self, prev: &Block, height: BlockHeight) -> Block {
+            let block = Block::empty_with_height(prev, height, &self.signer);
+            self.blocks.insert(*block.hash(), block.clone());
+            block
+        }
+
+        fn find_new_blocks(
+            &self,
+            head: &Block,
+            branch: &[&Block],
+            final_block_height: BlockHeight,
+        ) -> Result<(Vec<BlockHeight>, usize), FailedToFetchData> {
+            let view =
+                |block: &Block| views::BlockView::from_author_block("test".into(), block.clone());
+            let fetch_block =
+                |hash| {
+                    future::ready(self.blocks.get(&hash).map(view).ok_or_else(|| {
+                        FailedToFetchData::String(format!("Unknown block {}", hash))
+                    }))
+                };
+            let branch: Vec<_> =
+                branch.iter().map(|block| (block.header().height(), *block.hash())).collect();
+            let (new_blocks, fork_point) =
+                block_on(find_new_blocks(fetch_block, view(head), &branch, final_block_height))?;
+            Ok((new_blocks.into_iter().map(|block| block.header.height).collect(), fork_point))
+        }
+    }
+
+    #[test]
+    fn test_find_new_blocks_on_the_same_branch() {
+        let (mut chain, genesis) = TestChain::new();
+        let b1 = chain.produce(&genesis, 1);
+        let b2 = chain.produce(&b1, 2);
+        let b3 = chain.produce(&b2, 3);
+        let b4 = chain.produce(&b3, 4);
+        // Blocks produced between two polls are all streamed.
+        assert_eq!(chain.find_new_blocks(&b4, &[&b1, &b2], 1).unwrap(), (vec![3, 4], 2));
+        assert_eq!(chain.find_new_blocks(&b2, &[], 1).unwrap(), (vec![2], 0));
+    }
+
+    #[test]
+    fn test_find_new_blocks_on_a_fork() {
+        let (mut chain, genesis) = TestChain::new();
+        let b1 = chain.produce(&genesis, 1);
+        let a2 = chain.produce(&b1, 2);
+        let a3 = chain.produce(&a2, 3);
+        let b4 = chain.produce(&b1, 4);
+        let b5 = chain.produce(&b4, 5);
+        // The blocks after the last common block are reverted, the final block is kept.
+        assert_eq!(chain.find_new_blocks(&b5, &[&b1, &a2, &a3], 1).unwrap(), (vec![4, 5], 1));
+    }
+
+    #[test]
+    fn test_find_new_blocks_below_the_streamed_blocks() {
+        let (mut chain, genesis) = TestChain::new();
+        let a2 = chain.produce(&genesis, 2);
+        let b1 = chain.produce(&genesis, 1);
+        let b3 = chain.produce(&b1, 3);
+        // None of the streamed blocks is final, so all of them can be reverted.
+        assert_eq!(chain.find_new_blocks(&b3, &[&a2], 0).unwrap(), (vec![3], 0));
+        // Final blocks are never reverted.
+        assert!(chain.find_new_blocks(&b3, &[&a2], 2).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Reads the blocks and all related parts straight from the store, so that `SyncModeEnum::Range`
+//! can build many `StreamerMessage`s at once without going through the `ViewClientActor`
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use near_chain::{ChainStore, ChainStoreAccess, ErrorKind, RuntimeAdapter};
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::views;
+use near_store::Store;
+
+use super::errors::FailedToFetchData;
+#[cfg(feature = "protocol_feature_evm")]
+use super::fetchers::evm_logs;
+use super::{
+    assemble_streamer_message, IndexerExecutionOutcomeWithReceipt, StreamerMessage,
+    StreamerMessageFilter,
+};
+
+#[derive(Clone)]
+pub(crate) struct StoreFetcher {
+    store: Arc<Store>,
+    runtime_adapter: Arc<dyn RuntimeAdapter>,
+    genesis_height: BlockHeight,
+}
+
+impl StoreFetcher {
+    pub(crate) fn new(
+        store: Arc<Store>,
+        runtime_adapter: Arc<dyn RuntimeAdapter>,
+        genesis_height: BlockHeight,
+    ) -> Self {
+        Self { store, runtime_adapter, genesis_height }
+    }
+
+    /// Builds the `StreamerMessage` of the final block at `block_height`, or returns `None` when
+    /// no block was produced at this height. Heights above the final head and garbage collected
+    /// heights are errors, as well as any data of the block missing from the store.
+    pub(crate) fn build_streamer_message(
+        &self,
+        block_height: BlockHeight,
+        near_config: &neard::NearConfig,
+        filter: Option<&StreamerMessageFilter>,
+    ) -> Result<Option<StreamerMessage>, FailedToFetchData> {
+        let mut chain_store = ChainStore::new(Arc::clone(&self.store), self.genesis_height);
+        let final_height = chain_store.final_head()?.height;
+        if block_height > final_height {
+            return Err(FailedToFetchData::String(format!(
+                "Block #{} is not final yet, the final head is #{}",
+                block_height, final_height
+            )));
+        }
+        let tail = chain_store.tail()?;
+        if block_height < tail {
+            return Err(FailedToFetchData::String(format!(
+                "Block #{} is garbage collected, the earliest available block is #{}",
+                block_height, tail
+            )));
+        }
+        let block_hash = match chain_store.get_block_hash_by_height(block_height) {
+            Ok(block_hash) => block_hash,
+            Err(err) => match err.kind() {
+                ErrorKind::DBNotFoundErr(_) => return Ok(None),
+                _ => return Err(err.into()),
+            },
+        };
+
+        let block = chain_store.get_block(&block_hash)?.clone();
+        let prev_block_gas_price = if block_height == self.genesis_height {
+            block.header().gas_price()
+        } else {
+            chain_store.get_block_header(block.header().prev_hash())?.gas_price()
+        };
+        let chunk_headers = block
+            .chunks()
+            .iter()
+            .filter(|chunk_header| chunk_header.height_included() == block_height)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut chunks = Vec::with_capacity(chunk_headers.len());
+        let mut shards_outcomes: HashMap<ShardId, Vec<IndexerExecutionOutcomeWithReceipt>> =
+            HashMap::new();
+        for chunk_header in chunk_headers {
+            let chunk = chain_store.get_chunk(&chunk_header.chunk_hash())?.clone();
+            let epoch_id = self
+                .runtime_adapter
+                .get_epoch_id_from_prev_block(&chunk_header.prev_block_hash())?;
+            let author = self.runtime_adapter.get_chunk_producer(
+                &epoch_id,
+                chunk_header.height_created(),
+                chunk_header.shard_id(),
+            )?;
+            chunks.push(views::ChunkView::from_author_chunk(author, chunk));
+
+            let mut outcomes = vec![];
+            for id in chain_store
+                .get_outcomes_by_block_hash_and_shard_id(&block_hash, chunk_header.shard_id())?
+            {
+                for outcome in chain_store.get_outcomes_by_id(&id)? {
+                    if outcome.block_hash != block_hash {
+                        continue;
+                    }
+                    let outcome = views::ExecutionOutcomeWithIdView::from(outcome);
+                    let receipt = if filter.map_or(true, |filter| {
+                        filter.matches_executor(&outcome.outcome.executor_id)
+                    }) {
+                        chain_store.get_receipt(&outcome.id)?.cloned().map(Into::into)
+                    } else {
+                        None
+                    };
+                    outcomes.push(IndexerExecutionOutcomeWithReceipt {
+                        #[cfg(feature = "protocol_feature_evm")]
+                        evm_logs: evm_logs(&outcome),
+                        execution_outcome: outcome,
+                        receipt,
+                    });
+                }
+            }
+            shards_outcomes.insert(chunk_header.shard_id(), outcomes);
+        }
+
+        let state_changes = chain_store
+            .get_state_changes_with_cause_in_block(&block_hash)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let block_author =
+            self.runtime_adapter.get_block_producer(block.header().epoch_id(), block_height)?;
+        let block = views::BlockView::from_author_block(block_author, block);
+
+        Ok(Some(assemble_streamer_message(
+            block,
+            chunks,
+            shards_outcomes,
+            state_changes,
+            prev_block_gas_price,
+            near_config,
+            filter,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tokio::sync::mpsc;
+
+    use near_chain::ChainGenesis;
+    use near_chain_configs::Genesis;
+    use near_client::test_utils::TestEnv;
+    use near_store::test_utils::create_test_store;
+    use neard::config::GenesisExt;
+
+    use super::*;
+    use crate::streamer::{backfill, Checkpoint, CheckpointMode};
+
+    /// Produces the blocks up to height 7 with height 3 skipped, and a transaction sent from
+    /// `test1` to itself, which is converted into a local receipt.
+    fn setup() -> (StoreFetcher, neard::NearConfig, BlockHeight) {
+        let store = create_test_store();
+        let genesis = Genesis::test(vec!["test0", "test1"], 1);
+        let runtime = Arc::new(neard::NightshadeRuntime::new(
+            Path::new("."),
+            store.clone(),
+            &genesis,
+            vec![],
+            vec![],
+            None,
+        ));
+        let mut env = TestEnv::new_with_runtime(
+            ChainGenesis::from(&genesis),
+            1,
+            1,
+            vec![runtime.clone() as Arc<dyn RuntimeAdapter>],
+        );
+        env.send_money(0);
+        for height in [1, 2, 4, 5, 6, 7].iter() {
+            env.produce_block(0, *height);
+        }
+        let final_height = env.clients[0].chain.final_head().unwrap().height;
+        let store_fetcher = StoreFetcher::new(store, runtime, genesis.config.genesis_height);
+        (store_fetcher, neard::load_test_config("test0", 0, genesis), final_height)
+    }
+
+    #[test]
+    fn test_build_streamer_message() {
+        let (store_fetcher, near_config, final_height) = setup();
+        assert!(final_height >= 4);
+        assert!(store_fetcher.build_streamer_message(3, &near_config, None).unwrap().is_none());
+        assert!(store_fetcher
+            .build_streamer_message(final_height + 1, &near_config, None)
+            .is_err());
+
+        let messages = (1..=final_height)
+            .filter_map(|height| {
+                store_fetcher.build_streamer_message(height, &near_config, None).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let chunk = messages
+            .iter()
+            .flat_map(|message| message.chunks.iter())
+            .find(|chunk| !chunk.transactions.is_empty())
+            .expect("The transaction should be streamed");
+        let receipt_id = chunk.transactions[0].outcome.execution_outcome.outcome.receipt_ids[0];
+        assert!(chunk.receipts.iter().any(|receipt| receipt.receipt_id == receipt_id));
+        let outcome = chunk
+            .receipt_execution_outcomes
+            .iter()
+            .find(|outcome| outcome.execution_outcome.id == receipt_id)
+            .expect("The local receipt should be executed in the same chunk");
+        assert_eq!(outcome.receipt.as_ref().map(|receipt| receipt.receipt_id), Some(receipt_id));
+    }
+
+    #[test]
+    fn test_backfill() {
+        let (store_fetcher, near_config, final_height) = setup();
+        let home_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(neard::get_store_path(home_dir.path())).unwrap();
+        let checkpoint = Checkpoint::open(home_dir.path());
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+
+        // The range ends past the final head, so the backfill stops after the final block.
+        let (sender, mut receiver) = mpsc::channel(16);
+        let heights = runtime.block_on(async {
+            backfill(
+                store_fetcher,
+                near_config,
+                None,
+                checkpoint.clone(),
+                CheckpointMode::OnSend,
+                sender,
+                1,
+                final_height + 2,
+                4,
+            )
+            .await;
+            let mut heights = vec![];
+            while let Some(message) = receiver.recv().await {
+                heights.push(message.block.header.height);
+            }
+            heights
+        });
+        let expected_heights = (1..=final_height).filter(|height| *height != 3).collect::<Vec<_>>();
+        assert_eq!(heights, expected_heights);
+        assert_eq!(checkpoint.last_synced_block_height(), Some(final_height));
+    }
+}
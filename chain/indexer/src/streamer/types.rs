@@ -1,15 +1,27 @@
+use serde::{Deserialize, Serialize};
+
 pub use near_primitives::hash::CryptoHash;
 pub use near_primitives::{types, views};
 
 /// Resulting struct represents block with chunks
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StreamerMessage {
     pub block: views::BlockView,
     pub chunks: Vec<IndexerChunkView>,
     pub state_changes: views::StateChangesView,
 }
 
-#[derive(Debug)]
+/// Event streamed by `Indexer::optimistic_streamer`
+#[derive(Debug, Serialize, Deserialize)]
+pub enum StreamerEvent {
+    /// Block that is not final yet
+    Block(StreamerMessage),
+    /// Previously streamed block that is no longer on the canonical chain as the head switched
+    /// to another fork. Blocks are reverted from the highest one down.
+    Reverted { block_hash: CryptoHash, block_height: types::BlockHeight },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct IndexerChunkView {
     pub author: types::AccountId,
     pub header: views::ChunkHeaderView,
@@ -18,14 +30,17 @@ pub struct IndexerChunkView {
     pub receipt_execution_outcomes: Vec<IndexerExecutionOutcomeWithReceipt>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IndexerTransactionWithOutcome {
     pub transaction: views::SignedTransactionView,
     pub outcome: IndexerExecutionOutcomeWithReceipt,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IndexerExecutionOutcomeWithReceipt {
     pub execution_outcome: views::ExecutionOutcomeWithIdView,
     pub receipt: Option<views::ReceiptView>,
+    /// Logs emitted by the EVM contracts when the outcome is a successful EVM call
+    #[cfg(feature = "protocol_feature_evm")]
+    pub evm_logs: Vec<near_primitives::evm::EvmLog>,
 }
@@ -1,23 +1,19 @@
 use std::convert::TryFrom;
 
-use actix::Addr;
-
+use near_primitives::types::Balance;
 use near_primitives::views;
 use node_runtime::config::tx_cost;
 
-use super::errors::FailedToFetchData;
-use super::fetchers::fetch_block_by_hash;
 use super::IndexerTransactionWithOutcome;
 
-pub(crate) async fn convert_transactions_sir_into_local_receipts(
-    client: &Addr<near_client::ViewClientActor>,
+/// Converts the transactions signed by their receivers into the receipts they were converted to,
+/// which never show up in chunks since they are executed right away
+pub(crate) fn convert_transactions_sir_into_local_receipts(
     near_config: &neard::NearConfig,
     txs: Vec<&IndexerTransactionWithOutcome>,
     block: &views::BlockView,
-) -> Result<Vec<views::ReceiptView>, FailedToFetchData> {
-    let prev_block = fetch_block_by_hash(&client, block.header.prev_hash).await?;
-    let prev_block_gas_price = prev_block.header.gas_price;
-
+    prev_block_gas_price: Balance,
+) -> Vec<views::ReceiptView> {
     let local_receipts: Vec<views::ReceiptView> =
         txs.into_iter()
             .map(|tx| {
@@ -63,5 +59,5 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
             })
             .collect();
 
-    Ok(local_receipts)
+    local_receipts
 }
@@ -74,6 +74,18 @@ impl Request {
     }
 }
 
+impl From<Notification> for Request {
+    /// A notification is processed as a request without an ID, whose reply is never sent.
+    fn from(notification: Notification) -> Self {
+        Request {
+            jsonrpc: Version,
+            method: notification.method,
+            params: notification.params,
+            id: Value::Null,
+        }
+    }
+}
+
 /// A response to an RPC.
 ///
 /// It is created by the methods on [Request](struct.Request.html).
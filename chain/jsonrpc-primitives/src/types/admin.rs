@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcCreateBackupRequest {
+    /// Directory on the node host, it must not exist
+    pub output_dir: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcCreateBackupResponse {
+    #[serde(flatten)]
+    pub backup: near_primitives::views::BackupView,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcPeerRequest {
+    pub peer_id: near_primitives::network::PeerId,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcPeerConnectRequest {
+    /// Peer in the `peer_id@host:port` format of `network.boot_nodes`
+    pub addr: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcPeersListResponse {
+    pub peers: Vec<near_primitives::views::KnownPeerView>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RpcAdminError {
+    #[error(
+        "Admin methods are disabled on this address, they are enabled by `rpc.enable_admin_rpc` \
+         and only served on `rpc.admin_addr`"
+    )]
+    Disabled,
+    #[error("Failed to create the backup: {0}")]
+    BackupFailed(String),
+    #[error("Peer {0} is not known")]
+    UnknownPeer(near_primitives::network::PeerId),
+    #[error("Peer {0} is not connected")]
+    PeerNotConnected(near_primitives::network::PeerId),
+    #[error("Invalid peer address {0}, expected `peer_id@host:port`")]
+    InvalidPeerAddr(String),
+    #[error("The node reached its limits. Try again later. More details: {0}")]
+    InternalError(String),
+}
+
+impl RpcCreateBackupRequest {
+    /// Accepts `[output_dir]` or an object.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((output_dir,)) = crate::utils::parse_params::<(String,)>(value.clone()) {
+            return Ok(Self { output_dir });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl RpcPeerRequest {
+    /// Accepts `[peer_id]` or an object.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((peer_id,)) =
+            crate::utils::parse_params::<(near_primitives::network::PeerId,)>(value.clone())
+        {
+            return Ok(Self { peer_id });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl RpcPeerConnectRequest {
+    /// Accepts `[addr]` or an object.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((addr,)) = crate::utils::parse_params::<(String,)>(value.clone()) {
+            return Ok(Self { addr });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl From<RpcCreateBackupRequest> for near_client_primitives::types::CreateBackup {
+    fn from(request: RpcCreateBackupRequest) -> Self {
+        Self { output_dir: request.output_dir.into() }
+    }
+}
+
+impl From<String> for RpcAdminError {
+    fn from(error: String) -> Self {
+        Self::BackupFailed(error)
+    }
+}
+
+impl From<actix::MailboxError> for RpcAdminError {
+    fn from(error: actix::MailboxError) -> Self {
+        Self::InternalError(error.to_string())
+    }
+}
+
+impl From<RpcAdminError> for crate::errors::RpcError {
+    fn from(error: RpcAdminError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        Self::new(-32_000, "Server error".to_string(), error_data)
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use near_primitives::serialize::base64_format;
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcDebugTraceCallRequest {
+    #[serde(flatten)]
+    pub block_reference: near_primitives::types::BlockReference,
+    pub account_id: near_primitives::types::AccountId,
+    pub method_name: String,
+    #[serde(rename = "args_base64", with = "base64_format")]
+    pub args: near_primitives::types::FunctionArgs,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcDebugTraceTransactionRequest {
+    pub tx_hash: near_primitives::hash::CryptoHash,
+    pub sender_account_id: near_primitives::types::AccountId,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcDebugTraceResponse {
+    pub traces: Vec<near_primitives::trace::FunctionCallTrace>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RpcDebugTraceError {
+    #[error("Debug methods are disabled on this node, they are enabled by `rpc.enable_debug_rpc`")]
+    Disabled,
+    #[error("Failed to trace the execution: {0}")]
+    TraceFailed(String),
+    #[error("The node reached its limits. Try again later. More details: {0}")]
+    InternalError(String),
+}
+
+impl RpcDebugTraceCallRequest {
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl RpcDebugTraceTransactionRequest {
+    /// Accepts `[tx_hash, sender_account_id]` like the `tx` method, or an object.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((tx_hash, sender_account_id)) = crate::utils::parse_params::<(
+            near_primitives::hash::CryptoHash,
+            near_primitives::types::AccountId,
+        )>(value.clone())
+        {
+            return Ok(Self { tx_hash, sender_account_id });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl From<RpcDebugTraceCallRequest> for near_client_primitives::types::DebugTraceCall {
+    fn from(request: RpcDebugTraceCallRequest) -> Self {
+        Self {
+            block_reference: request.block_reference,
+            account_id: request.account_id,
+            method_name: request.method_name,
+            args: request.args,
+        }
+    }
+}
+
+impl From<RpcDebugTraceTransactionRequest>
+    for near_client_primitives::types::DebugTraceTransaction
+{
+    fn from(request: RpcDebugTraceTransactionRequest) -> Self {
+        Self { tx_hash: request.tx_hash, signer_account_id: request.sender_account_id }
+    }
+}
+
+impl From<String> for RpcDebugTraceError {
+    fn from(error: String) -> Self {
+        Self::TraceFailed(error)
+    }
+}
+
+impl From<actix::MailboxError> for RpcDebugTraceError {
+    fn from(error: actix::MailboxError) -> Self {
+        Self::InternalError(error.to_string())
+    }
+}
+
+impl From<RpcDebugTraceError> for crate::errors::RpcError {
+    fn from(error: RpcDebugTraceError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        Self::new(-32_000, "Server error".to_string(), error_data)
+    }
+}
@@ -1,4 +1,8 @@
+pub mod admin;
 pub mod blocks;
 pub mod chunks;
 pub mod config;
+pub mod debug;
 pub mod receipts;
+pub mod simulate;
+pub mod state_at_height;
@@ -0,0 +1,146 @@
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::BlockReference;
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcSimulateTransactionRequest {
+    #[serde(flatten)]
+    pub block_reference: BlockReference,
+    #[serde(rename = "signed_tx_base64", with = "base64_signed_transaction")]
+    pub signed_transaction: SignedTransaction,
+    /// The signature is checked unless this is set, which allows to simulate unsigned transactions
+    #[serde(default)]
+    pub skip_signature_check: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcSimulateTransactionResponse {
+    #[serde(flatten)]
+    pub simulation: near_primitives::views::TransactionSimulationView,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcEstimateGasRequest {
+    #[serde(flatten)]
+    pub block_reference: BlockReference,
+    /// The signature is not checked, so the transaction can be signed with any key
+    #[serde(rename = "signed_tx_base64", with = "base64_signed_transaction")]
+    pub signed_transaction: SignedTransaction,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcEstimateGasResponse {
+    #[serde(flatten)]
+    pub estimation: near_primitives::views::GasEstimationView,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RpcSimulateTransactionError {
+    #[error(
+        "Simulation methods are disabled on this node, they are enabled by `rpc.enable_simulation_rpc`"
+    )]
+    Disabled,
+    #[error("Failed to simulate the transaction: {0}")]
+    SimulationFailed(String),
+    #[error("The node reached its limits. Try again later. More details: {0}")]
+    InternalError(String),
+}
+
+fn decode_signed_transaction(encoded: &str) -> Result<SignedTransaction, String> {
+    let bytes = near_primitives::serialize::from_base64(encoded).map_err(|err| err.to_string())?;
+    SignedTransaction::try_from_slice(&bytes)
+        .map_err(|err| format!("Failed to decode transaction: {}", err))
+}
+
+mod base64_signed_transaction {
+    use borsh::BorshSerialize;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use near_primitives::serialize::to_base64;
+    use near_primitives::transaction::SignedTransaction;
+
+    pub fn serialize<S>(transaction: &SignedTransaction, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bytes = transaction.try_to_vec().map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&to_base64(&bytes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SignedTransaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        super::decode_signed_transaction(&s).map_err(de::Error::custom)
+    }
+}
+
+impl RpcSimulateTransactionRequest {
+    /// Accepts `[signed_tx_base64]` like `EXPERIMENTAL_check_tx`, which is simulated on top of
+    /// the latest block, or an object with a block reference.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((encoded,)) = crate::utils::parse_params::<(String,)>(value.clone()) {
+            let signed_transaction =
+                decode_signed_transaction(&encoded).map_err(crate::errors::RpcParseError)?;
+            return Ok(Self {
+                block_reference: BlockReference::latest(),
+                signed_transaction,
+                skip_signature_check: false,
+            });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl RpcEstimateGasRequest {
+    /// Accepts `[signed_tx_base64]`, estimated on top of the latest block, or an object with a
+    /// block reference.
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        if let Ok((encoded,)) = crate::utils::parse_params::<(String,)>(value.clone()) {
+            let signed_transaction =
+                decode_signed_transaction(&encoded).map_err(crate::errors::RpcParseError)?;
+            return Ok(Self { block_reference: BlockReference::latest(), signed_transaction });
+        }
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl From<RpcSimulateTransactionRequest> for near_client_primitives::types::SimulateTransaction {
+    fn from(request: RpcSimulateTransactionRequest) -> Self {
+        Self {
+            block_reference: request.block_reference,
+            transaction: request.signed_transaction,
+            verify_signature: !request.skip_signature_check,
+        }
+    }
+}
+
+impl From<RpcEstimateGasRequest> for near_client_primitives::types::EstimateGas {
+    fn from(request: RpcEstimateGasRequest) -> Self {
+        Self { block_reference: request.block_reference, transaction: request.signed_transaction }
+    }
+}
+
+impl From<String> for RpcSimulateTransactionError {
+    fn from(error: String) -> Self {
+        Self::SimulationFailed(error)
+    }
+}
+
+impl From<actix::MailboxError> for RpcSimulateTransactionError {
+    fn from(error: actix::MailboxError) -> Self {
+        Self::InternalError(error.to_string())
+    }
+}
+
+impl From<RpcSimulateTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcSimulateTransactionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        Self::new(-32_000, "Server error".to_string(), error_data)
+    }
+}
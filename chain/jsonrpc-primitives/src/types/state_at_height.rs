@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcStateAtHeightRequest {
+    pub block_id: near_primitives::types::BlockId,
+    #[serde(flatten)]
+    pub request: near_primitives::views::QueryRequest,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcStateAtHeightResponse {
+    #[serde(flatten)]
+    pub query_response: near_primitives::views::QueryResponse,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+pub enum RpcStateAtHeightError {
+    #[error("Block not found: {0}")]
+    UnknownBlock(String),
+    #[error("The state at block #{block_height} is garbage collected on this node, the earliest available height is #{earliest_available_height}")]
+    GarbageCollected {
+        block_height: near_primitives::types::BlockHeight,
+        earliest_available_height: near_primitives::types::BlockHeight,
+    },
+    #[error("The node does not track shard {0}")]
+    UnavailableShard(near_primitives::types::ShardId),
+    #[error("Account {0} does not exist at this height")]
+    UnknownAccount(near_primitives::types::AccountId),
+    #[error("Access key {0} does not exist at this height")]
+    UnknownAccessKey(near_crypto::PublicKey),
+    #[error("Only `view_account` and `view_access_key` requests are supported")]
+    UnsupportedRequest,
+    #[error("The node reached its limits. Try again later. More details: {0}")]
+    InternalError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl RpcStateAtHeightRequest {
+    pub fn parse(value: Option<Value>) -> Result<Self, crate::errors::RpcParseError> {
+        crate::utils::parse_params::<Self>(value)
+    }
+}
+
+impl From<RpcStateAtHeightRequest> for near_client_primitives::types::GetStateAtHeight {
+    fn from(request: RpcStateAtHeightRequest) -> Self {
+        Self { block_id: request.block_id, request: request.request }
+    }
+}
+
+impl From<near_client_primitives::types::GetStateAtHeightError> for RpcStateAtHeightError {
+    fn from(error: near_client_primitives::types::GetStateAtHeightError) -> Self {
+        match error {
+            near_client_primitives::types::GetStateAtHeightError::IOError(s) => {
+                Self::InternalError(s)
+            }
+            near_client_primitives::types::GetStateAtHeightError::UnknownBlock(s) => {
+                Self::UnknownBlock(s)
+            }
+            near_client_primitives::types::GetStateAtHeightError::GarbageCollected {
+                block_height,
+                earliest_available_height,
+            } => Self::GarbageCollected { block_height, earliest_available_height },
+            near_client_primitives::types::GetStateAtHeightError::UnavailableShard(shard_id) => {
+                Self::UnavailableShard(shard_id)
+            }
+            near_client_primitives::types::GetStateAtHeightError::UnknownAccount(account_id) => {
+                Self::UnknownAccount(account_id)
+            }
+            near_client_primitives::types::GetStateAtHeightError::UnknownAccessKey(public_key) => {
+                Self::UnknownAccessKey(public_key)
+            }
+            near_client_primitives::types::GetStateAtHeightError::UnsupportedRequest => {
+                Self::UnsupportedRequest
+            }
+            near_client_primitives::types::GetStateAtHeightError::Unreachable(s) => {
+                near_metrics::inc_counter_vec(
+                    &crate::metrics::RPC_UNREACHABLE_ERROR_COUNT,
+                    &["RpcStateAtHeightError", &s],
+                );
+                Self::Unreachable(s)
+            }
+        }
+    }
+}
+
+impl From<actix::MailboxError> for RpcStateAtHeightError {
+    fn from(error: actix::MailboxError) -> Self {
+        Self::InternalError(error.to_string())
+    }
+}
+
+impl From<RpcStateAtHeightError> for crate::errors::RpcError {
+    fn from(error: RpcStateAtHeightError) -> Self {
+        // The error is passed as a structured value, so clients can tell apart garbage collected
+        // data (and find out the earliest height available on the node) from the other errors.
+        let error_data =
+            serde_json::to_value(&error).unwrap_or_else(|_| Value::String(error.to_string()));
+
+        Self::new(-32_000, "Server error".to_string(), Some(error_data))
+    }
+}
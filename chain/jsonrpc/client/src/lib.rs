@@ -234,6 +234,47 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_receipt", request)
     }
 
+    pub fn admin_create_backup(
+        &self,
+        request: near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::admin::RpcCreateBackupResponse> {
+        call_method(&self.client, &self.server_addr, "admin_create_backup", request)
+    }
+
+    pub fn admin_peer_ban(
+        &self,
+        request: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> RpcRequest<()> {
+        call_method(&self.client, &self.server_addr, "admin_peer_ban", request)
+    }
+
+    pub fn admin_peer_unban(
+        &self,
+        request: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> RpcRequest<()> {
+        call_method(&self.client, &self.server_addr, "admin_peer_unban", request)
+    }
+
+    pub fn admin_peer_connect(
+        &self,
+        request: near_jsonrpc_primitives::types::admin::RpcPeerConnectRequest,
+    ) -> RpcRequest<()> {
+        call_method(&self.client, &self.server_addr, "admin_peer_connect", request)
+    }
+
+    pub fn admin_peer_disconnect(
+        &self,
+        request: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> RpcRequest<()> {
+        call_method(&self.client, &self.server_addr, "admin_peer_disconnect", request)
+    }
+
+    pub fn admin_peers_list(
+        &self,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::admin::RpcPeersListResponse> {
+        call_method(&self.client, &self.server_addr, "admin_peers_list", [] as [(); 0])
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_protocol_config(
         &self,
@@ -243,8 +284,12 @@ impl JsonRpcClient {
     }
 }
 
-fn create_client() -> Client {
-    Client::builder()
+fn create_client(auth_token: Option<&str>) -> Client {
+    let mut builder = Client::builder();
+    if let Some(auth_token) = auth_token {
+        builder = builder.bearer_auth(auth_token);
+    }
+    builder
         .timeout(CONNECT_TIMEOUT)
         .connector(
             Connector::new()
@@ -257,7 +302,13 @@ fn create_client() -> Client {
 
 /// Create new JSON RPC client that connects to the given address.
 pub fn new_client(server_addr: &str) -> JsonRpcClient {
-    JsonRpcClient::new(server_addr, create_client())
+    JsonRpcClient::new(server_addr, create_client(None))
+}
+
+/// Create new JSON RPC client that connects to the given address and passes the given bearer
+/// token, as required by the server of `rpc.admin_addr` with `rpc.admin_auth_token`.
+pub fn new_client_with_auth(server_addr: &str, auth_token: &str) -> JsonRpcClient {
+    JsonRpcClient::new(server_addr, create_client(Some(auth_token)))
 }
 
 http_client!(pub struct HttpClient {
@@ -266,5 +317,5 @@ http_client!(pub struct HttpClient {
 
 /// Create new HTTP client that connects to the given address.
 pub fn new_http_client(server_addr: &str) -> HttpClient {
-    HttpClient::new(server_addr, create_client())
+    HttpClient::new(server_addr, create_client(None))
 }
@@ -0,0 +1,457 @@
+//! Ethereum-compatible JSON-RPC methods (`eth_*`) on top of the EVM account.
+//!
+//! Reads are served with view calls to the EVM account and `eth_sendRawTransaction` relays the
+//! signed Ethereum transaction to its `raw_call` method in a NEAR transaction signed by the
+//! configured relayer account. Logs emitted by EVM contracts are looked up by the view client.
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use borsh::BorshSerialize;
+use ethereum_types::{Address, H256, U256};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout};
+
+use near_client::{GetBlock, GetEvmLogs, Query};
+use near_crypto::InMemorySigner;
+use near_evm_runner::types::{AddressArg, EthSignedTransaction, GetStorageAtArgs, ViewCallArgs};
+use near_evm_runner::utils::u256_to_arr;
+use near_jsonrpc_primitives::errors::RpcError;
+use near_network::NetworkClientResponses;
+use near_primitives::evm::{EvmLogFilter, EvmLogView};
+use near_primitives::hash::{CryptoHash, Digest};
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{BlockHeight, BlockId, BlockReference, Finality, Gas, Nonce};
+use near_primitives::views::{QueryRequest, QueryResponse, QueryResponseKind};
+
+use crate::{metrics, parse_params, pool_rejected_err, JsonRpcHandler, ServerError};
+
+/// Account that hosts the EVM
+const EVM_ACCOUNT_ID: &str = "evm";
+
+fn default_relayer_prepaid_gas() -> Gas {
+    300_000_000_000_000
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EthRpcConfig {
+    /// Enables `eth_sendRawTransaction`. The relayed transactions are paid by the relayer account,
+    /// so the method is only served on the authenticated `rpc.admin_addr`.
+    #[serde(default)]
+    pub enable_relayer: bool,
+    /// Key file of the account that signs the NEAR transactions relaying
+    /// `eth_sendRawTransaction`. The method is disabled when it is not set.
+    #[serde(default)]
+    pub relayer_key_file: Option<PathBuf>,
+    /// Gas attached to the relayed transactions.
+    #[serde(default = "default_relayer_prepaid_gas")]
+    pub relayer_prepaid_gas: Gas,
+}
+
+impl Default for EthRpcConfig {
+    fn default() -> Self {
+        Self {
+            enable_relayer: false,
+            relayer_key_file: None,
+            relayer_prepaid_gas: default_relayer_prepaid_gas(),
+        }
+    }
+}
+
+/// Signs the NEAR transactions relaying Ethereum transactions. Shared by all the RPC workers so
+/// that concurrent relayed transactions get distinct nonces.
+#[derive(Clone)]
+pub(crate) struct EthRelayer {
+    signer: Arc<InMemorySigner>,
+    prepaid_gas: Gas,
+    last_nonce: Arc<Mutex<Nonce>>,
+}
+
+impl EthRelayer {
+    pub(crate) fn from_config(config: &EthRpcConfig) -> Option<Self> {
+        if !config.enable_relayer {
+            return None;
+        }
+        config.relayer_key_file.as_ref().map(|key_file| Self {
+            signer: Arc::new(InMemorySigner::from_file(key_file)),
+            prepaid_gas: config.relayer_prepaid_gas,
+            last_nonce: Arc::new(Mutex::new(0)),
+        })
+    }
+}
+
+/// Object accepted by `eth_call`. Gas and gas price are ignored as view calls are not paid for.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EthCallRequest {
+    from: Option<Address>,
+    to: Option<Address>,
+    value: Option<U256>,
+    data: Option<String>,
+}
+
+/// Parses the positional parameter at `index`. Missing trailing parameters are treated as `null`
+/// so that optional ones such as the block tag can be omitted.
+fn parse_param<T: DeserializeOwned>(params: &[Value], index: usize) -> Result<T, RpcError> {
+    serde_json::from_value(params.get(index).cloned().unwrap_or(Value::Null))
+        .map_err(|err| RpcError::invalid_params(format!("Failed parsing args: {}", err)))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<OneOrMany<T>> for Vec<T> {
+    fn from(value: OneOrMany<T>) -> Self {
+        match value {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// Filter accepted by `eth_getLogs`. `blockHash` takes precedence over the block range.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EthLogFilterRequest {
+    from_block: Option<String>,
+    to_block: Option<String>,
+    block_hash: Option<H256>,
+    address: Option<OneOrMany<Address>>,
+    #[serde(default)]
+    topics: Vec<Option<OneOrMany<H256>>>,
+}
+
+/// Formats the log the way `eth_getLogs` returns it. The transaction is the Ethereum transaction
+/// relayed to `raw_call`, or the receipt that emitted the log for other calls. The transaction
+/// index is the index of the receipt outcome in the block, and the log index counts the logs of
+/// that receipt.
+fn to_eth_log(log: EvmLogView) -> Value {
+    serde_json::json!({
+        "removed": false,
+        "logIndex": to_quantity(log.log_index.into()),
+        "transactionIndex": to_quantity(log.transaction_index.into()),
+        "transactionHash": to_data(log.transaction_hash.as_ref()),
+        "blockHash": to_data(log.block_hash.as_ref()),
+        "blockNumber": to_quantity(log.block_height.into()),
+        "address": to_data(&log.log.address),
+        "data": to_data(&log.log.data),
+        "topics": log.log.topics.iter().map(|topic| to_data(&topic[..])).collect::<Vec<_>>(),
+    })
+}
+
+fn to_quantity(value: U256) -> Value {
+    Value::String(format!("{:#x}", value))
+}
+
+fn to_data(value: &[u8]) -> Value {
+    Value::String(format!("0x{}", hex::encode(value)))
+}
+
+fn parse_data(value: &str) -> Result<Vec<u8>, RpcError> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|err| RpcError::invalid_params(format!("Failed to decode data: {}", err)))
+}
+
+fn parse_u256(value: &[u8]) -> Result<U256, RpcError> {
+    if value.len() != 32 {
+        return Err(RpcError::server_error(Some(ServerError::InternalError)));
+    }
+    Ok(U256::from_big_endian(value))
+}
+
+impl JsonRpcHandler {
+    pub(crate) async fn eth_request(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "eth_blockNumber" => self.eth_block_number().await,
+            "eth_call" => self.eth_call(params).await,
+            "eth_chainId" => self.eth_chain_id(),
+            "eth_getBalance" => self.eth_get_balance(params).await,
+            "eth_getCode" => self.eth_get_code(params).await,
+            "eth_getLogs" => self.eth_get_logs(params).await,
+            "eth_getStorageAt" => self.eth_get_storage_at(params).await,
+            "eth_getTransactionCount" => self.eth_get_transaction_count(params).await,
+            "eth_sendRawTransaction" => self.eth_send_raw_transaction(params).await,
+            _ => Err(RpcError::method_not_found(method.to_string())),
+        }
+    }
+
+    fn eth_chain_id(&self) -> Result<Value, RpcError> {
+        Ok(to_quantity(near_chain_configs::evm_chain_id(&self.genesis_config.chain_id).into()))
+    }
+
+    async fn eth_block_number(&self) -> Result<Value, RpcError> {
+        Ok(to_quantity(self.block_height(BlockReference::latest()).await?.into()))
+    }
+
+    async fn eth_get_logs(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let request = parse_param::<EthLogFilterRequest>(&params, 0)?;
+        let (from_block, to_block) = match request.block_hash {
+            Some(block_hash) => {
+                let block_reference = BlockReference::BlockId(BlockId::Hash(CryptoHash(Digest(
+                    block_hash.to_fixed_bytes(),
+                ))));
+                let height = self.block_height(block_reference).await?;
+                (height, height)
+            }
+            None => (
+                self.block_height(self.parse_block_tag(request.from_block)?).await?,
+                self.block_height(self.parse_block_tag(request.to_block)?).await?,
+            ),
+        };
+        let filter = EvmLogFilter {
+            from_block,
+            to_block,
+            addresses: request
+                .address
+                .map(Vec::from)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            topics: request
+                .topics
+                .into_iter()
+                .map(|topics| {
+                    topics.map(|topics| Vec::from(topics).into_iter().map(Into::into).collect())
+                })
+                .collect(),
+        };
+        let logs = self
+            .view_client_addr
+            .send(GetEvmLogs { filter })
+            .await
+            .map_err(|err| RpcError::server_error(Some(ServerError::from(err))))?
+            .map_err(|err| RpcError::server_error(Some(err)))?;
+        Ok(Value::Array(logs.into_iter().map(to_eth_log).collect()))
+    }
+
+    async fn eth_get_balance(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let address = parse_param::<Address>(&params, 0)?;
+        let block = parse_param(&params, 1)?;
+        let args =
+            AddressArg { address: address.into() }.try_to_vec().expect("Failed to serialize");
+        let result = self.evm_view("get_balance", args, block).await?;
+        Ok(to_quantity(parse_u256(&result)?))
+    }
+
+    async fn eth_get_transaction_count(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let address = parse_param::<Address>(&params, 0)?;
+        let block = parse_param(&params, 1)?;
+        let args =
+            AddressArg { address: address.into() }.try_to_vec().expect("Failed to serialize");
+        let result = self.evm_view("get_nonce", args, block).await?;
+        Ok(to_quantity(parse_u256(&result)?))
+    }
+
+    async fn eth_get_code(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let address = parse_param::<Address>(&params, 0)?;
+        let block = parse_param(&params, 1)?;
+        let args =
+            AddressArg { address: address.into() }.try_to_vec().expect("Failed to serialize");
+        let result = self.evm_view("get_code", args, block).await?;
+        Ok(to_data(&result))
+    }
+
+    async fn eth_get_storage_at(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let address = parse_param::<Address>(&params, 0)?;
+        let position = parse_param::<U256>(&params, 1)?;
+        let block = parse_param(&params, 2)?;
+        let args = GetStorageAtArgs { address: address.into(), key: u256_to_arr(&position) }
+            .try_to_vec()
+            .expect("Failed to serialize");
+        let result = self.evm_view("get_storage_at", args, block).await?;
+        Ok(to_data(&result))
+    }
+
+    async fn eth_call(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let params = parse_params::<Vec<Value>>(params)?;
+        let request = parse_param::<EthCallRequest>(&params, 0)?;
+        let block = parse_param(&params, 1)?;
+        let input = match request.data {
+            Some(data) => parse_data(&data)?,
+            None => vec![],
+        };
+        let args = ViewCallArgs {
+            sender: request.from.unwrap_or_default().into(),
+            // The zero address makes the view call simulate a deployment of `input`.
+            address: request.to.unwrap_or_default().into(),
+            amount: u256_to_arr(&request.value.unwrap_or_default()),
+            input,
+        }
+        .try_to_vec()
+        .expect("Failed to serialize");
+        let result = self.evm_view("view", args, block).await?;
+        Ok(to_data(&result))
+    }
+
+    /// Relays a signed Ethereum transaction to the EVM account and returns its Ethereum hash.
+    /// The transaction is validated first so that the relayer doesn't pay for garbage.
+    async fn eth_send_raw_transaction(&self, params: Option<Value>) -> Result<Value, RpcError> {
+        let relayer = self.eth_relayer.as_ref().ok_or_else(|| {
+            RpcError::server_error(Some(
+                "eth_sendRawTransaction is disabled, it is enabled by `eth_rpc.enable_relayer` with \
+                 `eth_rpc.relayer_key_file` and only served on `rpc.admin_addr`",
+            ))
+        })?;
+        let (data,) = parse_params::<(String,)>(params)?;
+        let raw_transaction = parse_data(&data)?;
+        let transaction = rlp::decode::<EthSignedTransaction>(&raw_transaction).map_err(|err| {
+            RpcError::invalid_params(format!("Failed to decode transaction: {}", err))
+        })?;
+        let evm_chain_id = near_chain_configs::evm_chain_id(&self.genesis_config.chain_id);
+        if transaction.chain_id().map_or(false, |chain_id| chain_id != evm_chain_id) {
+            return Err(RpcError::invalid_params("Invalid chain id"));
+        }
+        if transaction.sender().is_none() {
+            return Err(RpcError::invalid_params("Invalid signature"));
+        }
+        let transaction_hash = keccak_hash::keccak(&raw_transaction);
+
+        let block_hash = self
+            .view_client_addr
+            .send(GetBlock(BlockReference::Finality(Finality::Final)))
+            .await
+            .map_err(|err| RpcError::server_error(Some(ServerError::from(err))))?
+            .map_err(|err| RpcError::server_error(Some(err.to_string())))?
+            .header
+            .hash;
+        let signer = &relayer.signer;
+        // Held until the transaction is submitted so concurrent requests don't reuse the nonce.
+        let mut last_nonce = relayer.last_nonce.lock().await;
+        let access_key = self
+            .query_response(
+                BlockReference::latest(),
+                QueryRequest::ViewAccessKey {
+                    account_id: signer.account_id.clone(),
+                    public_key: signer.public_key.clone(),
+                },
+            )
+            .await?;
+        let nonce = match access_key.kind {
+            QueryResponseKind::AccessKey(access_key) => {
+                std::cmp::max(access_key.nonce, *last_nonce) + 1
+            }
+            _ => return Err(RpcError::server_error(Some(ServerError::InternalError))),
+        };
+        let relayed_transaction = SignedTransaction::call(
+            nonce,
+            signer.account_id.clone(),
+            EVM_ACCOUNT_ID.to_string(),
+            signer.as_ref(),
+            0,
+            "raw_call".to_string(),
+            raw_transaction,
+            relayer.prepaid_gas,
+            block_hash,
+        );
+        match self.send_tx(relayed_transaction, false).await? {
+            NetworkClientResponses::ValidTx | NetworkClientResponses::RequestRouted => {
+                *last_nonce = nonce;
+                Ok(to_data(transaction_hash.as_bytes()))
+            }
+            NetworkClientResponses::InvalidTx(err) => {
+                Err(RpcError::server_error(Some(ServerError::TxExecutionError(err.into()))))
+            }
+            NetworkClientResponses::NoResponse => {
+                Err(RpcError::server_error(Some(ServerError::Timeout)))
+            }
+            NetworkClientResponses::PoolRejected(reason) => Err(pool_rejected_err(reason)),
+            _ => Err(RpcError::server_error(Some(ServerError::InternalError))),
+        }
+    }
+
+    /// Calls a view method of the EVM account at the given Ethereum block tag and returns the
+    /// result.
+    async fn evm_view(
+        &self,
+        method_name: &str,
+        args: Vec<u8>,
+        block: Option<String>,
+    ) -> Result<Vec<u8>, RpcError> {
+        let block_reference = self.parse_block_tag(block)?;
+        let response = self
+            .query_response(
+                block_reference,
+                QueryRequest::CallFunction {
+                    account_id: EVM_ACCOUNT_ID.to_string(),
+                    method_name: method_name.to_string(),
+                    args: args.into(),
+                },
+            )
+            .await?;
+        match response.kind {
+            QueryResponseKind::CallResult(result) => Ok(result.result),
+            QueryResponseKind::Error(err) => Err(RpcError::server_error(Some(err.error))),
+            _ => Err(RpcError::server_error(Some(ServerError::InternalError))),
+        }
+    }
+
+    /// Maps an Ethereum block tag (`latest`, `pending`, `earliest` or a block number) onto a
+    /// block reference. The tag is optional and defaults to `latest`.
+    fn parse_block_tag(&self, block: Option<String>) -> Result<BlockReference, RpcError> {
+        match block.as_deref() {
+            None | Some("latest") | Some("pending") => Ok(BlockReference::latest()),
+            Some("earliest") => {
+                Ok(BlockReference::BlockId(BlockId::Height(self.genesis_config.genesis_height)))
+            }
+            Some(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16)
+                .map(|height| BlockReference::BlockId(BlockId::Height(height)))
+                .map_err(|_| RpcError::invalid_params(format!("Invalid block tag {}", number))),
+        }
+    }
+
+    /// Returns the height of the referenced block.
+    async fn block_height(&self, block_reference: BlockReference) -> Result<BlockHeight, RpcError> {
+        if let BlockReference::BlockId(BlockId::Height(height)) = block_reference {
+            return Ok(height);
+        }
+        let block = self
+            .view_client_addr
+            .send(GetBlock(block_reference))
+            .await
+            .map_err(|err| RpcError::server_error(Some(ServerError::from(err))))?
+            .map_err(|err| RpcError::server_error(Some(err.to_string())))?;
+        Ok(block.header.height)
+    }
+
+    /// Sends the query to the view client until it is answered, as queries about shards the node
+    /// doesn't track are routed to its peers.
+    async fn query_response(
+        &self,
+        block_reference: BlockReference,
+        request: QueryRequest,
+    ) -> Result<QueryResponse, RpcError> {
+        let query = Query::new(block_reference, request);
+        timeout(self.polling_config.polling_timeout, async {
+            loop {
+                match self.view_client_addr.send(query.clone()).await {
+                    Ok(Ok(Some(response))) => break Ok(response),
+                    Ok(Ok(None)) => {}
+                    Ok(Err(err)) => break Err(RpcError::server_error(Some(err))),
+                    Err(err) => break Err(RpcError::server_error(Some(err.to_string()))),
+                }
+                sleep(self.polling_config.polling_interval).await;
+            }
+        })
+        .await
+        .map_err(|_| {
+            near_metrics::inc_counter(&metrics::RPC_TIMEOUT_TOTAL);
+            RpcError::server_error(Some("query has timed out".to_string()))
+        })?
+    }
+}
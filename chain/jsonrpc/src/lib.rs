@@ -1,10 +1,14 @@
 use std::fmt::Display;
+use std::str::FromStr;
 use std::string::FromUtf8Error;
+use std::sync::Arc;
 use std::time::Duration;
 
 use actix::{Addr, MailboxError};
 use actix_cors::Cors;
-use actix_web::{http, middleware, web, App, Error as HttpError, HttpResponse, HttpServer};
+use actix_web::{
+    http, middleware, web, App, Error as HttpError, HttpRequest, HttpResponse, HttpServer,
+};
 use borsh::BorshDeserialize;
 use futures::Future;
 use futures::{FutureExt, TryFutureExt};
@@ -16,10 +20,11 @@ use tokio::time::{sleep, timeout};
 
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    ClientActor, GetBlock, GetBlockProof, GetChunk, GetExecutionOutcome, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, Query, Status, TxStatus,
-    TxStatusError, ViewClientActor,
+    ClientActor, CreateBackup, DebugTraceCall, DebugTraceTransaction, EstimateGas, GetBlock,
+    GetBlockProof, GetChunk, GetExecutionOutcome, GetGasPrice, GetNetworkInfo,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateAtHeight, GetStateChanges,
+    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, Query, SimulateTransaction,
+    Status, TxStatus, TxStatusError, ViewClientActor,
 };
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
@@ -32,9 +37,12 @@ use near_jsonrpc_primitives::rpc::{
 };
 use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use near_metrics::{Encoder, TextEncoder};
+use near_network::types::{NetworkAdapter, PeerInfo, ReasonForBan};
 #[cfg(feature = "adversarial")]
 use near_network::types::{NetworkAdversarialMessage, NetworkViewClientMessages};
-use near_network::{NetworkClientMessages, NetworkClientResponses};
+use near_network::{
+    NetworkClientMessages, NetworkClientResponses, NetworkRequests, NetworkResponses,
+};
 use near_primitives::errors::{InvalidTxError, TxExecutionError};
 use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::{from_base, from_base64, BaseEncode};
@@ -45,7 +53,13 @@ use near_primitives::views::{
 };
 use near_runtime_utils::is_valid_account_id;
 
+#[cfg(feature = "protocol_feature_evm")]
+mod eth_rpc;
 mod metrics;
+mod subscriptions;
+
+#[cfg(feature = "protocol_feature_evm")]
+pub use eth_rpc::EthRpcConfig;
 
 /// Max size of the query path (soft-deprecated)
 const QUERY_DATA_MAX_SIZE: usize = 10 * 1024;
@@ -65,15 +79,22 @@ impl Default for RpcPollingConfig {
     }
 }
 
+fn default_batch_max_size() -> usize {
+    100
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of requests in a single JSON-RPC batch.
+    #[serde(default = "default_batch_max_size")]
+    pub batch_max_size: usize,
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self { json_payload_max_size: 10 * 1024 * 1024, batch_max_size: default_batch_max_size() }
     }
 }
 
@@ -84,6 +105,30 @@ pub struct RpcConfig {
     pub polling_config: RpcPollingConfig,
     #[serde(default)]
     pub limits_config: RpcLimitsConfig,
+    /// Enables the `debug_*` methods, which execute contracts again with tracing and are
+    /// expensive for the node.
+    #[serde(default)]
+    pub enable_debug_rpc: bool,
+    /// Enables `simulate_tx` and `estimate_gas`, which apply transactions and their receipts on a
+    /// fork of the state and are expensive for the node.
+    #[serde(default)]
+    pub enable_simulation_rpc: bool,
+    /// Enables the `admin_*` methods, which act on the node host. They are only served on
+    /// `admin_addr`.
+    #[serde(default)]
+    pub enable_admin_rpc: bool,
+    /// Address of a separate server for the `admin_*` methods. It should only be reachable from
+    /// the node host.
+    #[serde(default)]
+    pub admin_addr: Option<String>,
+    /// Token the requests to `admin_addr` must pass in the `Authorization: Bearer` header,
+    /// required if `admin_addr` is set.
+    #[serde(default)]
+    pub admin_auth_token: Option<String>,
+    /// Ethereum-compatible `eth_*` methods.
+    #[cfg(feature = "protocol_feature_evm")]
+    #[serde(default)]
+    pub eth_rpc: EthRpcConfig,
 }
 
 impl Default for RpcConfig {
@@ -93,6 +138,13 @@ impl Default for RpcConfig {
             cors_allowed_origins: vec!["*".to_owned()],
             polling_config: Default::default(),
             limits_config: Default::default(),
+            enable_debug_rpc: false,
+            enable_simulation_rpc: false,
+            enable_admin_rpc: false,
+            admin_addr: None,
+            admin_auth_token: None,
+            #[cfg(feature = "protocol_feature_evm")]
+            eth_rpc: Default::default(),
         }
     }
 }
@@ -131,6 +183,11 @@ fn jsonify<T: serde::Serialize>(
         .map_err(|err| RpcError::server_error(Some(err)))
 }
 
+/// Error returned when the transaction is valid, but the node's transaction pool rejected it
+fn pool_rejected_err(reason: String) -> RpcError {
+    RpcError::server_error(Some(format!("Transaction pool rejected the transaction: {}", reason)))
+}
+
 fn parse_tx(params: Option<Value>) -> Result<SignedTransaction, RpcError> {
     let (encoded,) = parse_params::<(String,)>(params)?;
     let bytes = from_base64_or_parse_err(encoded)?;
@@ -179,6 +236,12 @@ impl From<ServerError> for RpcError {
     }
 }
 
+/// Compares `a` and `b` in a time independent of their content, so that a token can't be guessed
+/// byte by byte from the time its checks take.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 fn timeout_err() -> RpcError {
     RpcError::server_error(Some(ServerError::Timeout))
 }
@@ -186,18 +249,83 @@ fn timeout_err() -> RpcError {
 struct JsonRpcHandler {
     client_addr: Addr<ClientActor>,
     view_client_addr: Addr<ViewClientActor>,
+    network_adapter: Arc<dyn NetworkAdapter>,
     polling_config: RpcPollingConfig,
+    limits_config: RpcLimitsConfig,
     genesis_config: GenesisConfig,
+    enable_debug_rpc: bool,
+    enable_simulation_rpc: bool,
+    enable_admin_rpc: bool,
+    /// Token required from the requests, only set on the `rpc.admin_addr` server.
+    admin_auth_token: Option<String>,
+    #[cfg(feature = "protocol_feature_evm")]
+    eth_relayer: Option<eth_rpc::EthRelayer>,
 }
 
 impl JsonRpcHandler {
-    pub async fn process(&self, message: Message) -> Result<Message, HttpError> {
+    /// Whether the request passes `admin_auth_token` as a bearer token, if it is required.
+    fn is_authorized(&self, request: &HttpRequest) -> bool {
+        let token = match &self.admin_auth_token {
+            Some(token) => token,
+            None => return true,
+        };
+        request
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map_or(false, |request_token| {
+                constant_time_eq(request_token.as_bytes(), token.as_bytes())
+            })
+    }
+
+    /// Returns the response to the message, or `None` when nothing has to be answered.
+    pub async fn process(&self, message: Message) -> Result<Option<Message>, HttpError> {
         let id = message.id();
         match message {
             Message::Request(request) => {
-                Ok(Message::response(id, self.process_request(request).await))
+                Ok(Some(Message::response(id, self.process_request(request).await)))
             }
-            _ => Ok(Message::error(RpcError::invalid_request())),
+            Message::Batch(messages) => Ok(self.process_batch(messages).await),
+            _ => Ok(Some(Message::error(RpcError::invalid_request()))),
+        }
+    }
+
+    /// Processes all the requests and notifications of a batch concurrently and returns the
+    /// responses to the requests in the same order, or `None` if the batch only holds
+    /// notifications. Elements that are neither (including nested batches) are answered with an
+    /// error without affecting the rest of the batch.
+    async fn process_batch(&self, messages: Vec<Message>) -> Option<Message> {
+        if messages.is_empty() {
+            return Some(Message::error(RpcError::invalid_request()));
+        }
+        if messages.len() > self.limits_config.batch_max_size {
+            return Some(Message::error(RpcError::server_error(Some(format!(
+                "Batch size {} exceeds the limit of {}",
+                messages.len(),
+                self.limits_config.batch_max_size
+            )))));
+        }
+        near_metrics::inc_counter(&metrics::HTTP_RPC_BATCH_REQUEST_COUNT);
+        let responses = futures::future::join_all(messages.into_iter().map(|message| async move {
+            let id = message.id();
+            match message {
+                Message::Request(request) => {
+                    Some(Message::response(id, self.process_request(request).await))
+                }
+                Message::Notification(notification) => {
+                    let _ = self.process_request(notification.into()).await;
+                    None
+                }
+                _ => Some(Message::error(RpcError::invalid_request())),
+            }
+        }))
+        .await;
+        let responses: Vec<_> = responses.into_iter().flatten().collect();
+        if responses.is_empty() {
+            None
+        } else {
+            Some(Message::Batch(responses))
         }
     }
 
@@ -231,6 +359,44 @@ impl JsonRpcHandler {
 
         let response: Result<Value, RpcError> = match request.method.as_ref() {
             // Handlers ordered alphabetically
+            "admin_create_backup" => {
+                let rpc_create_backup_request =
+                    near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest::parse(
+                        request.params,
+                    )?;
+                let backup = self.admin_create_backup(rpc_create_backup_request).await?;
+                serde_json::to_value(backup).map_err(|err| RpcError::parse_error(err.to_string()))
+            }
+            "admin_peer_ban" => {
+                let rpc_peer_request =
+                    near_jsonrpc_primitives::types::admin::RpcPeerRequest::parse(request.params)?;
+                self.admin_peer_ban(rpc_peer_request).await?;
+                Ok(Value::Null)
+            }
+            "admin_peer_connect" => {
+                let rpc_peer_connect_request =
+                    near_jsonrpc_primitives::types::admin::RpcPeerConnectRequest::parse(
+                        request.params,
+                    )?;
+                self.admin_peer_connect(rpc_peer_connect_request).await?;
+                Ok(Value::Null)
+            }
+            "admin_peer_disconnect" => {
+                let rpc_peer_request =
+                    near_jsonrpc_primitives::types::admin::RpcPeerRequest::parse(request.params)?;
+                self.admin_peer_disconnect(rpc_peer_request).await?;
+                Ok(Value::Null)
+            }
+            "admin_peer_unban" => {
+                let rpc_peer_request =
+                    near_jsonrpc_primitives::types::admin::RpcPeerRequest::parse(request.params)?;
+                self.admin_peer_unban(rpc_peer_request).await?;
+                Ok(Value::Null)
+            }
+            "admin_peers_list" => {
+                let peers = self.admin_peers_list().await?;
+                serde_json::to_value(peers).map_err(|err| RpcError::parse_error(err.to_string()))
+            }
             "block" => {
                 let rpc_block_request =
                     near_jsonrpc_primitives::types::blocks::RpcBlockRequest::parse(request.params)?;
@@ -269,17 +435,62 @@ impl JsonRpcHandler {
                 let receipt = self.receipt(rpc_receipt_request).await?;
                 serde_json::to_value(receipt).map_err(|err| RpcError::parse_error(err.to_string()))
             }
+            "EXPERIMENTAL_state_at_height" => {
+                let rpc_state_at_height_request =
+                    near_jsonrpc_primitives::types::state_at_height::RpcStateAtHeightRequest::parse(
+                        request.params,
+                    )?;
+                let state = self.state_at_height(rpc_state_at_height_request).await?;
+                serde_json::to_value(state).map_err(|err| RpcError::parse_error(err.to_string()))
+            }
             "EXPERIMENTAL_tx_status" => self.tx_status_common(request.params, true).await,
+            "debug_trace_call" => {
+                let rpc_debug_trace_call_request =
+                    near_jsonrpc_primitives::types::debug::RpcDebugTraceCallRequest::parse(
+                        request.params,
+                    )?;
+                let traces = self.debug_trace_call(rpc_debug_trace_call_request).await?;
+                serde_json::to_value(traces).map_err(|err| RpcError::parse_error(err.to_string()))
+            }
+            "debug_trace_transaction" => {
+                let rpc_debug_trace_transaction_request =
+                    near_jsonrpc_primitives::types::debug::RpcDebugTraceTransactionRequest::parse(
+                        request.params,
+                    )?;
+                let traces =
+                    self.debug_trace_transaction(rpc_debug_trace_transaction_request).await?;
+                serde_json::to_value(traces).map_err(|err| RpcError::parse_error(err.to_string()))
+            }
             "EXPERIMENTAL_validators_ordered" => self.validators_ordered(request.params).await,
+            "estimate_gas" => {
+                let rpc_estimate_gas_request =
+                    near_jsonrpc_primitives::types::simulate::RpcEstimateGasRequest::parse(
+                        request.params,
+                    )?;
+                let estimation = self.estimate_gas(rpc_estimate_gas_request).await?;
+                serde_json::to_value(estimation)
+                    .map_err(|err| RpcError::parse_error(err.to_string()))
+            }
             "gas_price" => self.gas_price(request.params).await,
             "health" => self.health().await,
             "light_client_proof" => self.light_client_execution_outcome_proof(request.params).await,
             "next_light_client_block" => self.next_light_client_block(request.params).await,
             "network_info" => self.network_info().await,
             "query" => self.query(request.params).await,
+            "simulate_tx" => {
+                let rpc_simulate_transaction_request =
+                    near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionRequest::parse(
+                        request.params,
+                    )?;
+                let simulation = self.simulate_tx(rpc_simulate_transaction_request).await?;
+                serde_json::to_value(simulation)
+                    .map_err(|err| RpcError::parse_error(err.to_string()))
+            }
             "status" => self.status().await,
             "tx" => self.tx_status_common(request.params, false).await,
             "validators" => self.validators(request.params).await,
+            #[cfg(feature = "protocol_feature_evm")]
+            method if method.starts_with("eth_") => self.eth_request(method, request.params).await,
             _ => Err(RpcError::method_not_found(request.method.clone())),
         };
 
@@ -296,12 +507,12 @@ impl JsonRpcHandler {
     async fn send_tx_async(&self, params: Option<Value>) -> Result<Value, RpcError> {
         let tx = parse_tx(params)?;
         let hash = (&tx.get_hash()).to_base();
-        self.client_addr.do_send(NetworkClientMessages::Transaction {
-            transaction: tx,
-            is_forwarded: false,
-            check_only: false,
-        });
-        Ok(Value::String(hash))
+        // The transaction isn't executed before returning, yet the caller learns if the
+        // transaction pool had no room for it.
+        match self.send_tx(tx, false).await? {
+            NetworkClientResponses::PoolRejected(reason) => Err(pool_rejected_err(reason)),
+            _ => Ok(Value::String(hash)),
+        }
     }
 
     async fn tx_exists(
@@ -501,6 +712,7 @@ impl JsonRpcHandler {
             NetworkClientResponses::DoesNotTrackShard => {
                 Err(RpcError::server_error(Some(does_not_track_shard_err.to_string())))
             }
+            NetworkClientResponses::PoolRejected(reason) => Err(pool_rejected_err(reason)),
             _ => {
                 // this is only possible if something went wrong with the node internally.
                 Err(RpcError::server_error(Some(ServerError::InternalError)))
@@ -529,6 +741,7 @@ impl JsonRpcHandler {
             NetworkClientResponses::NoResponse => {
                 Err(RpcError::server_error(Some(ServerError::Timeout)))
             }
+            NetworkClientResponses::PoolRejected(reason) => Err(pool_rejected_err(reason)),
             _ => Err(RpcError::server_error(Some(ServerError::InternalError))),
         }
     }
@@ -595,7 +808,7 @@ impl JsonRpcHandler {
             let maybe_extra_arg = path_parts.next();
 
             let request = match query_command {
-                "account" => QueryRequest::ViewAccount { account_id },
+                "account" => QueryRequest::ViewAccount { account_id, include_proof: false },
                 "code" => QueryRequest::ViewCode { account_id },
                 "access_key" => match maybe_extra_arg {
                     None => QueryRequest::ViewAccessKeyList { account_id },
@@ -606,7 +819,13 @@ impl JsonRpcHandler {
                             .map_err(|_| RpcError::server_error(Some("Invalid public key")))?,
                     },
                 },
-                "contract" => QueryRequest::ViewState { account_id, prefix: data.into() },
+                "contract" => QueryRequest::ViewState {
+                    account_id,
+                    prefix: data.into(),
+                    from_key: None,
+                    limit: None,
+                    include_proof: false,
+                },
                 "call" => match maybe_extra_arg {
                     Some(method_name) => QueryRequest::CallFunction {
                         account_id,
@@ -657,6 +876,20 @@ impl JsonRpcHandler {
         })?
     }
 
+    async fn state_at_height(
+        &self,
+        request_data: near_jsonrpc_primitives::types::state_at_height::RpcStateAtHeightRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::state_at_height::RpcStateAtHeightResponse,
+        near_jsonrpc_primitives::types::state_at_height::RpcStateAtHeightError,
+    > {
+        let query_response =
+            self.view_client_addr.send(GetStateAtHeight::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::state_at_height::RpcStateAtHeightResponse {
+            query_response,
+        })
+    }
+
     async fn tx_status_common(
         &self,
         params: Option<Value>,
@@ -727,6 +960,184 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn admin_create_backup(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::admin::RpcCreateBackupResponse,
+        near_jsonrpc_primitives::types::admin::RpcAdminError,
+    > {
+        // The backup is written to the node host, only authenticated requests may ask for it.
+        if !self.enable_admin_rpc || self.admin_auth_token.is_none() {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        let backup = self.view_client_addr.send(CreateBackup::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::admin::RpcCreateBackupResponse { backup })
+    }
+
+    async fn admin_peer_ban(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> Result<(), near_jsonrpc_primitives::types::admin::RpcAdminError> {
+        if !self.enable_admin_rpc {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        let peer_id = request_data.peer_id;
+        match self
+            .network_adapter
+            .send(NetworkRequests::BanPeer {
+                peer_id: peer_id.clone(),
+                ban_reason: ReasonForBan::Manual,
+            })
+            .await?
+        {
+            NetworkResponses::UnknownPeer => {
+                Err(near_jsonrpc_primitives::types::admin::RpcAdminError::UnknownPeer(peer_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn admin_peer_unban(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> Result<(), near_jsonrpc_primitives::types::admin::RpcAdminError> {
+        if !self.enable_admin_rpc {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        let peer_id = request_data.peer_id;
+        match self
+            .network_adapter
+            .send(NetworkRequests::UnbanPeer { peer_id: peer_id.clone() })
+            .await?
+        {
+            NetworkResponses::UnknownPeer => {
+                Err(near_jsonrpc_primitives::types::admin::RpcAdminError::UnknownPeer(peer_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn admin_peer_connect(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcPeerConnectRequest,
+    ) -> Result<(), near_jsonrpc_primitives::types::admin::RpcAdminError> {
+        if !self.enable_admin_rpc {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        let peer_info = match PeerInfo::from_str(&request_data.addr) {
+            Ok(peer_info) if peer_info.addr.is_some() => peer_info,
+            _ => {
+                return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::InvalidPeerAddr(
+                    request_data.addr,
+                ))
+            }
+        };
+        self.network_adapter.send(NetworkRequests::ConnectPeer { peer_info }).await?;
+        Ok(())
+    }
+
+    async fn admin_peer_disconnect(
+        &self,
+        request_data: near_jsonrpc_primitives::types::admin::RpcPeerRequest,
+    ) -> Result<(), near_jsonrpc_primitives::types::admin::RpcAdminError> {
+        if !self.enable_admin_rpc {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        let peer_id = request_data.peer_id;
+        match self
+            .network_adapter
+            .send(NetworkRequests::DisconnectPeer { peer_id: peer_id.clone() })
+            .await?
+        {
+            NetworkResponses::UnknownPeer => {
+                Err(near_jsonrpc_primitives::types::admin::RpcAdminError::PeerNotConnected(peer_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn admin_peers_list(
+        &self,
+    ) -> Result<
+        near_jsonrpc_primitives::types::admin::RpcPeersListResponse,
+        near_jsonrpc_primitives::types::admin::RpcAdminError,
+    > {
+        if !self.enable_admin_rpc {
+            return Err(near_jsonrpc_primitives::types::admin::RpcAdminError::Disabled);
+        }
+        match self.network_adapter.send(NetworkRequests::FetchKnownPeers).await? {
+            NetworkResponses::KnownPeers(peers) => {
+                Ok(near_jsonrpc_primitives::types::admin::RpcPeersListResponse { peers })
+            }
+            _ => Err(near_jsonrpc_primitives::types::admin::RpcAdminError::InternalError(
+                "Unexpected response of the network".to_string(),
+            )),
+        }
+    }
+
+    async fn debug_trace_call(
+        &self,
+        request_data: near_jsonrpc_primitives::types::debug::RpcDebugTraceCallRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::debug::RpcDebugTraceResponse,
+        near_jsonrpc_primitives::types::debug::RpcDebugTraceError,
+    > {
+        if !self.enable_debug_rpc {
+            return Err(near_jsonrpc_primitives::types::debug::RpcDebugTraceError::Disabled);
+        }
+        let traces = self.view_client_addr.send(DebugTraceCall::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::debug::RpcDebugTraceResponse { traces })
+    }
+
+    async fn debug_trace_transaction(
+        &self,
+        request_data: near_jsonrpc_primitives::types::debug::RpcDebugTraceTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::debug::RpcDebugTraceResponse,
+        near_jsonrpc_primitives::types::debug::RpcDebugTraceError,
+    > {
+        if !self.enable_debug_rpc {
+            return Err(near_jsonrpc_primitives::types::debug::RpcDebugTraceError::Disabled);
+        }
+        let traces =
+            self.view_client_addr.send(DebugTraceTransaction::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::debug::RpcDebugTraceResponse { traces })
+    }
+
+    async fn simulate_tx(
+        &self,
+        request_data: near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionResponse,
+        near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionError,
+    > {
+        if !self.enable_simulation_rpc {
+            return Err(
+                near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionError::Disabled,
+            );
+        }
+        let simulation =
+            self.view_client_addr.send(SimulateTransaction::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionResponse { simulation })
+    }
+
+    async fn estimate_gas(
+        &self,
+        request_data: near_jsonrpc_primitives::types::simulate::RpcEstimateGasRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::simulate::RpcEstimateGasResponse,
+        near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionError,
+    > {
+        if !self.enable_simulation_rpc {
+            return Err(
+                near_jsonrpc_primitives::types::simulate::RpcSimulateTransactionError::Disabled,
+            );
+        }
+        let estimation = self.view_client_addr.send(EstimateGas::from(request_data)).await??;
+        Ok(near_jsonrpc_primitives::types::simulate::RpcEstimateGasResponse { estimation })
+    }
+
     async fn changes_in_block(&self, params: Option<Value>) -> Result<Value, RpcError> {
         let RpcStateChangesInBlockRequest { block_reference } = parse_params(params)?;
         // TODO refactor it. Changed to keep it working before refactoring
@@ -956,12 +1367,19 @@ impl JsonRpcHandler {
 }
 
 fn rpc_handler(
+    request: HttpRequest,
     message: web::Json<Message>,
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
+    let authorized = handler.is_authorized(&request);
     let response = async move {
-        let message = handler.process(message.0).await?;
-        Ok(HttpResponse::Ok().json(message))
+        if !authorized {
+            return Ok(HttpResponse::Unauthorized().finish());
+        }
+        match handler.process(message.0).await? {
+            Some(message) => Ok(HttpResponse::Ok().json(message)),
+            None => Ok(HttpResponse::Ok().finish()),
+        }
     };
     response.boxed()
 }
@@ -1036,18 +1454,69 @@ pub fn start_http(
     genesis_config: GenesisConfig,
     client_addr: Addr<ClientActor>,
     view_client_addr: Addr<ViewClientActor>,
-) {
-    let RpcConfig { addr, cors_allowed_origins, polling_config, limits_config } = config;
+    network_adapter: Arc<dyn NetworkAdapter>,
+) -> std::io::Result<()> {
+    #[cfg(feature = "protocol_feature_evm")]
+    let eth_relayer = eth_rpc::EthRelayer::from_config(&config.eth_rpc);
+    let RpcConfig {
+        addr,
+        cors_allowed_origins,
+        polling_config,
+        limits_config,
+        enable_debug_rpc,
+        enable_simulation_rpc,
+        enable_admin_rpc,
+        admin_addr,
+        admin_auth_token,
+        ..
+    } = config;
+    let subscriptions_hub = web::Data::new(subscriptions::SubscriptionsHub::start(
+        view_client_addr.clone(),
+        polling_config.polling_interval,
+    ));
+    let json_payload_max_size = limits_config.json_payload_max_size;
+    let new_handler =
+        move |enable_admin_rpc: bool, admin_auth_token: Option<String>| JsonRpcHandler {
+            // Relayed transactions are paid by the relayer account, so they are only accepted from
+            // authenticated requests.
+            #[cfg(feature = "protocol_feature_evm")]
+            eth_relayer: admin_auth_token.as_ref().and(eth_relayer.clone()),
+            client_addr: client_addr.clone(),
+            view_client_addr: view_client_addr.clone(),
+            network_adapter: network_adapter.clone(),
+            polling_config,
+            limits_config: limits_config.clone(),
+            genesis_config: genesis_config.clone(),
+            enable_debug_rpc,
+            enable_simulation_rpc,
+            enable_admin_rpc,
+            admin_auth_token,
+        };
+    // The admin methods act on the node host, so they are never served without authentication.
+    let admin_server = match admin_addr {
+        Some(admin_addr) => {
+            let admin_auth_token = admin_auth_token.ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "`rpc.admin_addr` requires `rpc.admin_auth_token`",
+                )
+            })?;
+            Some((admin_addr, admin_auth_token))
+        }
+        None => {
+            if enable_admin_rpc {
+                tracing::warn!(target: "jsonrpc", "`rpc.enable_admin_rpc` has no effect without `rpc.admin_addr`, the admin methods are only served there");
+            }
+            None
+        }
+    };
+    let main_handler = new_handler.clone();
     HttpServer::new(move || {
         App::new()
             .wrap(get_cors(&cors_allowed_origins))
-            .data(JsonRpcHandler {
-                client_addr: client_addr.clone(),
-                view_client_addr: view_client_addr.clone(),
-                polling_config,
-                genesis_config: genesis_config.clone(),
-            })
-            .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
+            .data(main_handler(false, None))
+            .app_data(web::JsonConfig::default().limit(json_payload_max_size))
+            .app_data(subscriptions_hub.clone())
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
             .service(
@@ -1062,10 +1531,25 @@ pub fn start_http(
             )
             .service(web::resource("/network_info").route(web::get().to(network_info_handler)))
             .service(web::resource("/metrics").route(web::get().to(prometheus_handler)))
+            .service(web::resource("/ws").route(web::get().to(subscriptions::ws_handler)))
     })
-    .bind(addr)
-    .unwrap()
+    .bind(addr)?
     .workers(4)
     .shutdown_timeout(5)
     .run();
+
+    if let Some((admin_addr, admin_auth_token)) = admin_server {
+        HttpServer::new(move || {
+            App::new()
+                .data(new_handler(enable_admin_rpc, Some(admin_auth_token.clone())))
+                .app_data(web::JsonConfig::default().limit(json_payload_max_size))
+                .wrap(middleware::Logger::default())
+                .service(web::resource("/").route(web::post().to(rpc_handler)))
+        })
+        .bind(admin_addr)?
+        .workers(1)
+        .shutdown_timeout(5)
+        .run();
+    }
+    Ok(())
 }
@@ -25,6 +25,11 @@ lazy_static! {
             "Total count of HTTP RPC requests received, by method",
             &["method"]
         );
+    pub static ref HTTP_RPC_BATCH_REQUEST_COUNT: near_metrics::Result<IntCounter> =
+        near_metrics::try_create_int_counter(
+            "near_rpc_batch_total_count",
+            "Total count of HTTP RPC batch requests received"
+        );
     pub static ref HTTP_STATUS_REQUEST_COUNT: near_metrics::Result<IntCounter> =
         near_metrics::try_create_int_counter(
             "near_http_status_requests_total",
@@ -36,4 +41,15 @@ lazy_static! {
             "Total count of errors by method and message",
             &["method", "err_code"]
         );
+    pub static ref WS_CONNECTION_COUNT: near_metrics::Result<IntCounter> =
+        near_metrics::try_create_int_counter(
+            "near_rpc_ws_connections_total",
+            "Total count of WebSocket connections opened"
+        );
+    pub static ref WS_RPC_REQUEST_COUNT: near_metrics::Result<IntCounterVec> =
+        near_metrics::try_create_int_counter_vec(
+            "near_rpc_ws_total_count",
+            "Total count of WebSocket RPC requests received, by method",
+            &["method"]
+        );
 }
@@ -0,0 +1,455 @@
+//! WebSocket subscriptions API.
+//!
+//! A WebSocket connection speaks the same JSON-RPC 2.0 dialect as the HTTP endpoint, so every
+//! regular method can be called over it. In addition, the following methods create
+//! subscriptions, which push `subscription` notifications to the client until the connection
+//! is closed or `unsubscribe` is called:
+//!
+//! * `subscribe_new_blocks` - every new block on the canonical chain (`BlockView`);
+//! * `subscribe_final_blocks` - every new final block (`BlockView`);
+//! * `subscribe_account_changes(account_id)` - account changes in every new block
+//!   (`RpcStateChangesResponse`);
+//! * `subscribe_tx(tx_hash, sender_account_id)` - the final outcome of the transaction
+//!   (`FinalExecutionOutcomeView`), the subscription is closed right after it is delivered.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::{
+    Actor, ActorContext, Addr, AsyncContext, Handler, SpawnHandle, StreamHandler, WrapFuture,
+};
+use actix_web::{web, Error as HttpError, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::{Future, FutureExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+
+use near_client::{GetBlock, GetStateChanges, TxStatus, TxStatusError, ViewClientActor};
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{self, Message};
+use near_jsonrpc_primitives::rpc::RpcStateChangesResponse;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, BlockHeight, BlockId, BlockReference, Finality};
+use near_primitives::views::{
+    BlockView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, StateChangesRequestView,
+};
+use near_runtime_utils::is_valid_account_id;
+
+use crate::{metrics, parse_params, JsonRpcHandler};
+
+/// Capacity of the broadcast channels used to fan out new blocks to the subscribers.
+/// Subscribers that fall behind by more than this number of blocks skip the missed blocks.
+const BLOCKS_CHANNEL_CAPACITY: usize = 64;
+
+/// Maximum number of active subscriptions on a single WebSocket connection.
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 64;
+
+/// Maximum number of blocks the poller fetches in one iteration if it falls behind the head.
+const MAX_BLOCKS_PER_POLL: BlockHeight = 32;
+
+/// Shared source of new blocks for all the WebSocket subscriptions of the node.
+#[derive(Clone)]
+pub(crate) struct SubscriptionsHub {
+    new_blocks: broadcast::Sender<Arc<BlockView>>,
+    final_blocks: broadcast::Sender<Arc<BlockView>>,
+}
+
+impl SubscriptionsHub {
+    /// Creates the hub and spawns the task that feeds it with blocks from the view client.
+    pub(crate) fn start(
+        view_client_addr: Addr<ViewClientActor>,
+        polling_interval: Duration,
+    ) -> Self {
+        let (new_blocks, _) = broadcast::channel(BLOCKS_CHANNEL_CAPACITY);
+        let (final_blocks, _) = broadcast::channel(BLOCKS_CHANNEL_CAPACITY);
+        let hub = Self { new_blocks, final_blocks };
+        actix::spawn(hub.clone().poll_blocks(view_client_addr, polling_interval));
+        hub
+    }
+
+    async fn poll_blocks(
+        self,
+        view_client_addr: Addr<ViewClientActor>,
+        polling_interval: Duration,
+    ) {
+        let mut last_new_height = None;
+        let mut last_final_height = None;
+        loop {
+            sleep(polling_interval).await;
+            Self::poll_finality(
+                &view_client_addr,
+                Finality::None,
+                &self.new_blocks,
+                &mut last_new_height,
+            )
+            .await;
+            Self::poll_finality(
+                &view_client_addr,
+                Finality::Final,
+                &self.final_blocks,
+                &mut last_final_height,
+            )
+            .await;
+        }
+    }
+
+    /// Fetches the head of the chain with the given finality and broadcasts all the blocks
+    /// produced since the last poll.
+    async fn poll_finality(
+        view_client_addr: &Addr<ViewClientActor>,
+        finality: Finality,
+        sender: &broadcast::Sender<Arc<BlockView>>,
+        last_height: &mut Option<BlockHeight>,
+    ) {
+        if sender.receiver_count() == 0 {
+            // Nobody listens, so there is no reason to catch up on the blocks later.
+            *last_height = None;
+            return;
+        }
+        let head = match view_client_addr.send(GetBlock(BlockReference::Finality(finality))).await {
+            Ok(Ok(block)) => block,
+            _ => return,
+        };
+        let head_height = head.header.height;
+        let start_height = match *last_height {
+            Some(height) if height >= head_height => return,
+            Some(height) => {
+                std::cmp::max(height + 1, head_height.saturating_sub(MAX_BLOCKS_PER_POLL))
+            }
+            None => head_height,
+        };
+        for height in start_height..head_height {
+            // Heights may be skipped, so missing blocks are not an error.
+            if let Ok(Ok(block)) = view_client_addr
+                .send(GetBlock(BlockReference::BlockId(BlockId::Height(height))))
+                .await
+            {
+                let _ = sender.send(Arc::new(block));
+            }
+        }
+        let _ = sender.send(Arc::new(head));
+        *last_height = Some(head_height);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct Outgoing(Message);
+
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct SubscriptionClosed(u64);
+
+/// Actor serving a single WebSocket connection.
+struct WsSession {
+    handler: web::Data<JsonRpcHandler>,
+    hub: SubscriptionsHub,
+    subscriptions: HashMap<u64, SpawnHandle>,
+    next_subscription_id: u64,
+}
+
+impl Actor for WsSession {
+    type Context = ws::WebsocketContext<Self>;
+}
+
+impl Handler<Outgoing> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: Outgoing, ctx: &mut Self::Context) {
+        let text: String = msg.0.into();
+        ctx.text(text);
+    }
+}
+
+impl Handler<SubscriptionClosed> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: SubscriptionClosed, _ctx: &mut Self::Context) {
+        self.subscriptions.remove(&msg.0);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Text(text)) => self.process_text(&text, ctx),
+            Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                tracing::debug!(target: "jsonrpc", "WebSocket protocol error: {}", err);
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl WsSession {
+    fn process_text(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let request = match message::from_str(text) {
+            Ok(Message::Request(request)) => request,
+            Ok(batch @ Message::Batch(_)) => return self.forward(batch, ctx),
+            Ok(_) => return ctx.notify(Outgoing(Message::error(RpcError::invalid_request()))),
+            Err(broken) => return ctx.notify(Outgoing(broken.reply())),
+        };
+        near_metrics::inc_counter_vec(&metrics::WS_RPC_REQUEST_COUNT, &[request.method.as_ref()]);
+        let id = request.id.clone();
+        let result = match request.method.as_ref() {
+            "subscribe_new_blocks" => {
+                let receiver = self.hub.new_blocks.subscribe();
+                self.subscribe(ctx, |subscription_id, addr| {
+                    stream_blocks(subscription_id, addr, receiver)
+                })
+            }
+            "subscribe_final_blocks" => {
+                let receiver = self.hub.final_blocks.subscribe();
+                self.subscribe(ctx, |subscription_id, addr| {
+                    stream_blocks(subscription_id, addr, receiver)
+                })
+            }
+            "subscribe_account_changes" => {
+                parse_params::<(AccountId,)>(request.params).and_then(|(account_id,)| {
+                    if !is_valid_account_id(&account_id) {
+                        return Err(RpcError::invalid_params(format!(
+                            "Invalid account id: {}",
+                            account_id
+                        )));
+                    }
+                    let view_client_addr = self.handler.view_client_addr.clone();
+                    let receiver = self.hub.new_blocks.subscribe();
+                    self.subscribe(ctx, |subscription_id, addr| {
+                        stream_account_changes(
+                            subscription_id,
+                            addr,
+                            receiver,
+                            view_client_addr,
+                            account_id,
+                        )
+                    })
+                })
+            }
+            "subscribe_tx" => parse_params::<(CryptoHash, AccountId)>(request.params).and_then(
+                |(tx_hash, signer_account_id)| {
+                    if !is_valid_account_id(&signer_account_id) {
+                        return Err(RpcError::invalid_params(format!(
+                            "Invalid account id: {}",
+                            signer_account_id
+                        )));
+                    }
+                    let view_client_addr = self.handler.view_client_addr.clone();
+                    let receiver = self.hub.new_blocks.subscribe();
+                    self.subscribe(ctx, |subscription_id, addr| {
+                        stream_tx_outcome(
+                            subscription_id,
+                            addr,
+                            receiver,
+                            view_client_addr,
+                            tx_hash,
+                            signer_account_id,
+                        )
+                    })
+                },
+            ),
+            "unsubscribe" => parse_params::<(u64,)>(request.params).map(|(subscription_id,)| {
+                match self.subscriptions.remove(&subscription_id) {
+                    Some(handle) => Value::Bool(ctx.cancel_future(handle)),
+                    None => Value::Bool(false),
+                }
+            }),
+            // Every other method is served the same way as over HTTP.
+            _ => return self.forward(Message::Request(request), ctx),
+        };
+        ctx.notify(Outgoing(Message::response(id, result)));
+    }
+
+    /// Processes the message with the regular JSON-RPC handler and sends the response back.
+    fn forward(&mut self, message: Message, ctx: &mut ws::WebsocketContext<Self>) {
+        let handler = self.handler.clone();
+        let addr = ctx.address();
+        ctx.spawn(
+            async move {
+                if let Ok(Some(response)) = handler.process(message).await {
+                    addr.do_send(Outgoing(response));
+                }
+            }
+            .into_actor(self),
+        );
+    }
+
+    /// Spawns a subscription future on the connection and returns its id to the client.
+    fn subscribe<F, Fut>(
+        &mut self,
+        ctx: &mut ws::WebsocketContext<Self>,
+        make_future: F,
+    ) -> Result<Value, RpcError>
+    where
+        F: FnOnce(u64, Addr<Self>) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            return Err(RpcError::server_error(Some(format!(
+                "Too many subscriptions, the limit is {}",
+                MAX_SUBSCRIPTIONS_PER_CONNECTION
+            ))));
+        }
+        let subscription_id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        let addr = ctx.address();
+        let future = make_future(subscription_id, addr.clone()).map(move |_| {
+            addr.do_send(SubscriptionClosed(subscription_id));
+        });
+        let handle = ctx.spawn(future.into_actor(self));
+        self.subscriptions.insert(subscription_id, handle);
+        Ok(Value::from(subscription_id))
+    }
+}
+
+fn notification<T: Serialize>(subscription_id: u64, result: &T) -> Outgoing {
+    Outgoing(Message::notification(
+        "subscription".to_string(),
+        Some(json!({ "subscription": subscription_id, "result": result })),
+    ))
+}
+
+fn error_notification(subscription_id: u64, error: RpcError) -> Outgoing {
+    Outgoing(Message::notification(
+        "subscription".to_string(),
+        Some(json!({ "subscription": subscription_id, "error": error })),
+    ))
+}
+
+/// Receives the next block, skipping the blocks the subscriber has lagged behind on.
+/// Returns `None` once the hub is gone.
+async fn next_block(receiver: &mut broadcast::Receiver<Arc<BlockView>>) -> Option<Arc<BlockView>> {
+    loop {
+        match receiver.recv().await {
+            Ok(block) => return Some(block),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(
+                    target: "jsonrpc",
+                    "WebSocket subscriber lagged behind, skipped {} blocks",
+                    skipped
+                );
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn stream_blocks(
+    subscription_id: u64,
+    addr: Addr<WsSession>,
+    mut receiver: broadcast::Receiver<Arc<BlockView>>,
+) {
+    while let Some(block) = next_block(&mut receiver).await {
+        addr.do_send(notification(subscription_id, &*block));
+    }
+}
+
+async fn stream_account_changes(
+    subscription_id: u64,
+    addr: Addr<WsSession>,
+    mut receiver: broadcast::Receiver<Arc<BlockView>>,
+    view_client_addr: Addr<ViewClientActor>,
+    account_id: AccountId,
+) {
+    while let Some(block) = next_block(&mut receiver).await {
+        let block_hash = block.header.hash;
+        let changes = view_client_addr
+            .send(GetStateChanges {
+                block_hash,
+                state_changes_request: StateChangesRequestView::AccountChanges {
+                    account_ids: vec![account_id.clone()],
+                },
+            })
+            .await;
+        match changes {
+            Ok(Ok(changes)) if changes.is_empty() => {}
+            Ok(Ok(changes)) => addr.do_send(notification(
+                subscription_id,
+                &RpcStateChangesResponse { block_hash, changes },
+            )),
+            Ok(Err(err)) => {
+                tracing::debug!(target: "jsonrpc", "Failed to fetch account changes: {}", err)
+            }
+            Err(err) => {
+                tracing::debug!(target: "jsonrpc", "Failed to fetch account changes: {}", err)
+            }
+        }
+    }
+}
+
+async fn stream_tx_outcome(
+    subscription_id: u64,
+    addr: Addr<WsSession>,
+    mut receiver: broadcast::Receiver<Arc<BlockView>>,
+    view_client_addr: Addr<ViewClientActor>,
+    tx_hash: CryptoHash,
+    signer_account_id: AccountId,
+) {
+    // The transaction could have been executed before the subscription was created, so check
+    // its status right away and then after every new block.
+    loop {
+        let tx_status = view_client_addr
+            .send(TxStatus {
+                tx_hash,
+                signer_account_id: signer_account_id.clone(),
+                fetch_receipt: false,
+            })
+            .await;
+        match tx_status {
+            Ok(Ok(Some(outcome))) => {
+                let status = match &outcome {
+                    FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(outcome) => {
+                        &outcome.status
+                    }
+                    FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(outcome) => {
+                        &outcome.final_outcome.status
+                    }
+                };
+                match status {
+                    FinalExecutionStatus::SuccessValue(_) | FinalExecutionStatus::Failure(_) => {
+                        addr.do_send(notification(subscription_id, &outcome));
+                        return;
+                    }
+                    FinalExecutionStatus::NotStarted | FinalExecutionStatus::Started => {}
+                }
+            }
+            Ok(Ok(None)) | Ok(Err(TxStatusError::MissingTransaction(_))) => {}
+            Ok(Err(err)) => {
+                addr.do_send(error_notification(
+                    subscription_id,
+                    RpcError::server_error(Some(String::from(err))),
+                ));
+                return;
+            }
+            Err(err) => {
+                tracing::debug!(target: "jsonrpc", "Failed to fetch transaction status: {}", err)
+            }
+        }
+        if next_block(&mut receiver).await.is_none() {
+            return;
+        }
+    }
+}
+
+pub(crate) async fn ws_handler(
+    request: HttpRequest,
+    stream: web::Payload,
+    handler: web::Data<JsonRpcHandler>,
+    hub: web::Data<SubscriptionsHub>,
+) -> Result<HttpResponse, HttpError> {
+    near_metrics::inc_counter(&metrics::WS_CONNECTION_COUNT);
+    let session = WsSession {
+        handler,
+        hub: hub.get_ref().clone(),
+        subscriptions: HashMap::new(),
+        next_subscription_id: 0,
+    };
+    ws::start(session, &request, stream)
+}
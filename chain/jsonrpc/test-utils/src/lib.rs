@@ -1,10 +1,12 @@
+use std::sync::Arc;
+
 use actix::Addr;
 
 use near_chain_configs::GenesisConfig;
 use near_client::test_utils::setup_no_network_with_validity_period;
 use near_client::ViewClientActor;
 use near_jsonrpc::{start_http, RpcConfig};
-use near_network::test_utils::open_port;
+use near_network::test_utils::{open_port, MockNetworkAdapter};
 use near_primitives::types::NumBlocks;
 
 lazy_static::lazy_static! {
@@ -41,7 +43,9 @@ pub fn start_all_with_validity_period(
         TEST_GENESIS_CONFIG.clone(),
         client_addr.clone(),
         view_client_addr.clone(),
-    );
+        Arc::new(MockNetworkAdapter::default()),
+    )
+    .unwrap();
     (view_client_addr, addr)
 }
 
@@ -5,14 +5,18 @@ use futures::{future, FutureExt};
 use serde_json::json;
 
 use near_crypto::{KeyType, PublicKey, Signature};
-use near_jsonrpc::client::new_client;
+use near_jsonrpc::client::{new_client, new_client_with_auth};
 use near_jsonrpc_client::ChunkId;
 use near_jsonrpc_primitives::rpc::RpcQueryRequest;
 use near_jsonrpc_primitives::rpc::RpcValidatorsOrderedRequest;
+use near_jsonrpc_primitives::types::admin::{RpcPeerConnectRequest, RpcPeerRequest};
 use near_logger_utils::init_test_logger;
 use near_network::test_utils::WaitOrTimeout;
+use near_network::types::ReasonForBan;
+use near_network::{NetworkRequests, PeerInfo};
 use near_primitives::account::{AccessKey, AccessKeyPermission};
 use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
 use near_primitives::types::{BlockId, BlockReference, ShardId, SyncCheckpoint};
 use near_primitives::views::{QueryRequest, QueryResponseKind};
 
@@ -162,21 +166,30 @@ fn test_query_account() {
         let query_response_1 = client
             .query(RpcQueryRequest {
                 block_reference: BlockReference::latest(),
-                request: QueryRequest::ViewAccount { account_id: "test".to_string() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".to_string(),
+                    include_proof: false,
+                },
             })
             .await
             .unwrap();
         let query_response_2 = client
             .query(RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Height(0)),
-                request: QueryRequest::ViewAccount { account_id: "test".to_string() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".to_string(),
+                    include_proof: false,
+                },
             })
             .await
             .unwrap();
         let query_response_3 = client
             .query(RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Hash(block_hash)),
-                request: QueryRequest::ViewAccount { account_id: "test".to_string() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "test".to_string(),
+                    include_proof: false,
+                },
             })
             .await
             .unwrap();
@@ -301,6 +314,9 @@ fn test_query_state() {
                 request: QueryRequest::ViewState {
                     account_id: "test".to_string(),
                     prefix: vec![].into(),
+                    from_key: None,
+                    limit: None,
+                    include_proof: false,
                 },
             })
             .await
@@ -562,6 +578,73 @@ fn test_invalid_methods() {
     });
 }
 
+/// Send a batch of requests and check that responses come back in order with per-element errors.
+#[test]
+fn test_batch_request() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": [0]},
+            {"jsonrpc": "2.0", "id": 2, "method": "non_existing_method", "params": []},
+            {"jsonrpc": "2.0", "id": 3, "method": "gas_price", "params": [null]},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .header("Content-Type", "application/json")
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        let response =
+            serde_json::from_value::<serde_json::Value>(response.json().await.unwrap()).unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], serde_json::json!(1));
+        assert_eq!(responses[0]["result"]["header"]["height"], serde_json::json!(0));
+        assert_eq!(responses[1]["id"], serde_json::json!(2));
+        assert!(responses[1]["error"] != serde_json::json!(null));
+        assert_eq!(responses[2]["id"], serde_json::json!(3));
+        assert!(responses[2]["result"]["gas_price"] != serde_json::json!(null));
+    });
+}
+
+/// Send batches with notifications and check that notifications are never answered.
+#[test]
+fn test_batch_request_notifications() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "method": "gas_price", "params": [null]},
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": [0]},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .header("Content-Type", "application/json")
+            .send_json(&json)
+            .await
+            .unwrap();
+        let response =
+            serde_json::from_value::<serde_json::Value>(response.json().await.unwrap()).unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], serde_json::json!(1));
+
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "method": "gas_price", "params": [null]},
+            {"jsonrpc": "2.0", "method": "status", "params": []},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .header("Content-Type", "application/json")
+            .send_json(&json)
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(response.body().await.unwrap().is_empty());
+    });
+}
+
 #[test]
 #[ignore] // https://github.com/nearprotocol/nearcore/issues/2789
 fn test_query_view_account_non_existing_account_must_return_error() {
@@ -569,7 +652,10 @@ fn test_query_view_account_non_existing_account_must_return_error() {
         let query_response = client
             .query(RpcQueryRequest {
                 block_reference: BlockReference::latest(),
-                request: QueryRequest::ViewAccount { account_id: "invalidaccount".to_string() },
+                request: QueryRequest::ViewAccount {
+                    account_id: "invalidaccount".to_string(),
+                    include_proof: false,
+                },
             })
             .await
             .unwrap();
@@ -659,6 +745,9 @@ fn test_view_state_non_existing_account_invalid_prefix() {
                 request: QueryRequest::ViewState {
                     account_id: "\u{0}\u{0}\u{0}\u{0}\u{0}\u{4}\u{0}\u{0}\u{0}\u{8}\u{0}\u{0}\u{0}\u{0}\u{0}eeeeeeeeeeeeeeeeeeeeeeeeeeeee".to_string(),
                     prefix: "eeeeeeeeeeee".as_bytes().to_vec().into(),
+                    from_key: None,
+                    limit: None,
+                    include_proof: false,
                 },
             })
             .await
@@ -727,3 +816,240 @@ fn test_get_chunk_with_object_in_params() {
         assert_eq!(chunk.header.chunk_hash, same_chunk.header.chunk_hash);
     });
 }
+
+/// Debug methods are disabled unless `rpc.enable_debug_rpc` is set.
+#[test]
+fn test_debug_trace_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let trace_call = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "debug_trace_call",
+            json!({
+                "finality": "final",
+                "account_id": "test",
+                "method_name": "method",
+                "args_base64": "",
+            }),
+        )
+        .await;
+        assert!(trace_call.is_err());
+        let trace_transaction = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "debug_trace_transaction",
+            json!(["11111111111111111111111111111111", "test"]),
+        )
+        .await;
+        assert!(trace_transaction.is_err());
+    });
+}
+
+/// Admin methods are disabled unless `rpc.enable_admin_rpc` is set.
+#[test]
+fn test_admin_create_backup_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let backup_dir = std::env::temp_dir().join("test_admin_create_backup_disabled");
+        let backup = client
+            .admin_create_backup(near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest {
+                output_dir: backup_dir.to_str().unwrap().to_string(),
+            })
+            .await;
+        assert!(backup.is_err());
+        assert!(!backup_dir.exists());
+    });
+}
+
+/// Backups are not made for requests to the public address, even with the admin methods enabled.
+#[test]
+fn test_admin_create_backup_public_addr() {
+    init_test_logger();
+
+    System::builder()
+        .stop_on_panic(true)
+        .run(|| {
+            let network_adapter = test_utils::AdminNetworkAdapter {
+                known_peer: PeerId::random(),
+                requests: Default::default(),
+            };
+            let (_view_client_addr, addr, _admin_addr) = test_utils::start_all_with_admin(
+                test_utils::NodeType::NonValidator,
+                network_adapter,
+            );
+            let client = new_client(&format!("http://{}", addr));
+
+            actix::spawn(async move {
+                let backup_dir = std::env::temp_dir().join("test_admin_create_backup_public_addr");
+                let backup = client
+                    .admin_create_backup(
+                        near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest {
+                            output_dir: backup_dir.to_str().unwrap().to_string(),
+                        },
+                    )
+                    .await;
+                assert!(backup.is_err());
+                assert!(!backup_dir.exists());
+                System::current().stop();
+            });
+        })
+        .unwrap();
+}
+
+#[cfg(feature = "protocol_feature_evm")]
+#[test]
+fn test_eth_rpc() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "eth_chainId", "params": []},
+            {"jsonrpc": "2.0", "id": 2, "method": "eth_blockNumber", "params": []},
+            {"jsonrpc": "2.0", "id": 3, "method": "eth_sendRawTransaction", "params": ["0x00"]},
+            {"jsonrpc": "2.0", "id": 4, "method": "eth_getBalance", "params": ["0x00", "latest"]},
+            {"jsonrpc": "2.0", "id": 5, "method": "eth_getLogs", "params": [{"fromBlock": "earliest"}]},
+            {"jsonrpc": "2.0", "id": 6, "method": "eth_getLogs", "params": [{"fromBlock": "0x10", "toBlock": "0x1"}]},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .header("Content-Type", "application/json")
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        let response =
+            serde_json::from_value::<serde_json::Value>(response.json().await.unwrap()).unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses[0]["result"], serde_json::json!("0x4e454154"));
+        assert!(responses[1]["result"].as_str().unwrap().starts_with("0x"));
+        // There is no relayer key file configured.
+        assert!(responses[2]["error"] != serde_json::json!(null));
+        // The address is too short.
+        assert!(responses[3]["error"] != serde_json::json!(null));
+        // No EVM contract emitted logs yet.
+        assert_eq!(responses[4]["result"], serde_json::json!([]));
+        assert!(responses[5]["error"] != serde_json::json!(null));
+    });
+}
+
+/// Peer management methods are admin methods too.
+#[test]
+fn test_admin_peers_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        assert!(client.admin_peers_list().await.is_err());
+        let peer_id: near_primitives::network::PeerId =
+            near_crypto::SecretKey::from_random(KeyType::ED25519).public_key().into();
+        let ban = client
+            .admin_peer_ban(near_jsonrpc_primitives::types::admin::RpcPeerRequest { peer_id })
+            .await;
+        assert!(ban.is_err());
+    });
+}
+
+/// Admin methods are only served on `rpc.admin_addr` to the requests passing the token.
+#[test]
+fn test_admin_unauthorized() {
+    init_test_logger();
+
+    System::builder()
+        .stop_on_panic(true)
+        .run(|| {
+            let network_adapter = test_utils::AdminNetworkAdapter {
+                known_peer: PeerId::random(),
+                requests: Default::default(),
+            };
+            let (_view_client_addr, addr, admin_addr) = test_utils::start_all_with_admin(
+                test_utils::NodeType::NonValidator,
+                network_adapter.clone(),
+            );
+            let public_client = new_client(&format!("http://{}", addr));
+            let no_token_client = new_client(&format!("http://{}", admin_addr));
+            let wrong_token_client =
+                new_client_with_auth(&format!("http://{}", admin_addr), "wrong-token");
+
+            actix::spawn(async move {
+                assert!(public_client.admin_peers_list().await.is_err());
+                assert!(no_token_client.admin_peers_list().await.is_err());
+                assert!(wrong_token_client.admin_peers_list().await.is_err());
+                assert!(network_adapter.requests.read().unwrap().is_empty());
+                System::current().stop();
+            });
+        })
+        .unwrap();
+}
+
+/// Ban, unban, connect and list peers through the admin address.
+#[test]
+fn test_admin_peers() {
+    init_test_logger();
+
+    System::builder()
+        .stop_on_panic(true)
+        .run(|| {
+            let known_peer = PeerId::random();
+            let unknown_peer = PeerId::random();
+            let network_adapter = test_utils::AdminNetworkAdapter {
+                known_peer: known_peer.clone(),
+                requests: Default::default(),
+            };
+            let (_view_client_addr, _addr, admin_addr) = test_utils::start_all_with_admin(
+                test_utils::NodeType::NonValidator,
+                network_adapter.clone(),
+            );
+            let client = new_client_with_auth(
+                &format!("http://{}", admin_addr),
+                test_utils::TEST_ADMIN_AUTH_TOKEN,
+            );
+
+            actix::spawn(async move {
+                let peers = client.admin_peers_list().await.unwrap().peers;
+                assert_eq!(peers.len(), 1);
+                assert_eq!(peers[0].peer_id, known_peer);
+
+                client
+                    .admin_peer_ban(RpcPeerRequest { peer_id: known_peer.clone() })
+                    .await
+                    .unwrap();
+                client
+                    .admin_peer_unban(RpcPeerRequest { peer_id: known_peer.clone() })
+                    .await
+                    .unwrap();
+                assert!(client
+                    .admin_peer_ban(RpcPeerRequest { peer_id: unknown_peer.clone() })
+                    .await
+                    .is_err());
+
+                let peer_addr = format!("{}@127.0.0.1:24567", known_peer);
+                client.admin_peer_connect(RpcPeerConnectRequest { addr: peer_addr }).await.unwrap();
+                // The address is required to connect.
+                assert!(client
+                    .admin_peer_connect(RpcPeerConnectRequest { addr: known_peer.to_string() })
+                    .await
+                    .is_err());
+
+                let requests = network_adapter.requests.read().unwrap().clone();
+                assert_eq!(
+                    requests,
+                    vec![
+                        NetworkRequests::FetchKnownPeers,
+                        NetworkRequests::BanPeer {
+                            peer_id: known_peer.clone(),
+                            ban_reason: ReasonForBan::Manual,
+                        },
+                        NetworkRequests::UnbanPeer { peer_id: known_peer.clone() },
+                        NetworkRequests::BanPeer {
+                            peer_id: unknown_peer,
+                            ban_reason: ReasonForBan::Manual,
+                        },
+                        NetworkRequests::ConnectPeer {
+                            peer_info: PeerInfo {
+                                id: known_peer,
+                                addr: Some("127.0.0.1:24567".parse().unwrap()),
+                                account_id: None,
+                            },
+                        },
+                    ]
+                );
+                System::current().stop();
+            });
+        })
+        .unwrap();
+}
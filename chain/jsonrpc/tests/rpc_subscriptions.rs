@@ -0,0 +1,55 @@
+use actix::System;
+use actix_web::client::{ws, Client};
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+
+use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_logger_utils::init_test_logger;
+
+pub mod test_utils;
+
+/// Subscribe to new blocks over WebSocket and receive a notification.
+#[test]
+fn test_subscribe_new_blocks() {
+    init_test_logger();
+
+    System::builder()
+        .stop_on_panic(true)
+        .run(|| {
+            let (_view_client_addr, addr) = test_utils::start_all(test_utils::NodeType::Validator);
+
+            actix::spawn(async move {
+                let (_response, mut connection) =
+                    Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+                let request = json!({
+                    "jsonrpc": "2.0",
+                    "method": "subscribe_new_blocks",
+                    "id": "dontcare",
+                });
+                connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+
+                let mut subscription_id = None;
+                while let Some(frame) = connection.next().await {
+                    let bytes = match frame.unwrap() {
+                        ws::Frame::Text(bytes) => bytes,
+                        _ => continue,
+                    };
+                    match from_slice(&bytes).unwrap() {
+                        Message::Response(response) => {
+                            subscription_id = Some(response.result.unwrap());
+                        }
+                        Message::Notification(notification) => {
+                            assert_eq!(notification.method, "subscription");
+                            let params = notification.params.unwrap();
+                            assert_eq!(Some(&params["subscription"]), subscription_id.as_ref());
+                            assert!(params["result"]["header"]["height"].as_u64().is_some());
+                            break;
+                        }
+                        message => panic!("Unexpected message: {:?}", message),
+                    }
+                }
+                System::current().stop();
+            });
+        })
+        .unwrap();
+}
@@ -3,6 +3,7 @@ use std::sync::{Arc, Mutex};
 use actix::{Actor, System};
 use borsh::BorshSerialize;
 use futures::{future, FutureExt, TryFutureExt};
+use serde_json::json;
 
 use near_crypto::{InMemorySigner, KeyType};
 use near_jsonrpc::client::new_client;
@@ -12,7 +13,7 @@ use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::serialize::{to_base, to_base64};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::BlockReference;
-use near_primitives::views::FinalExecutionStatus;
+use near_primitives::views::{FinalExecutionStatus, GasEstimationView, TransactionSimulationView};
 
 #[macro_use]
 pub mod test_utils;
@@ -236,3 +237,60 @@ fn test_check_invalid_tx() {
         }
     });
 }
+
+/// Simulation methods are disabled unless `rpc.enable_simulation_rpc` is set. The simulation
+/// itself is tested against the runtime in `neard`.
+#[test]
+fn test_simulate_tx_disabled() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let block_hash = client.block(BlockReference::latest()).await.unwrap().header.hash;
+        let signer = InMemorySigner::from_seed("test1", KeyType::ED25519, "test1");
+        let tx = SignedTransaction::send_money(
+            1,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            100,
+            block_hash,
+        );
+        let bytes = tx.try_to_vec().unwrap();
+        let simulation = test_utils::call_method::<TransactionSimulationView>(
+            &client.client,
+            &client.server_addr,
+            "simulate_tx",
+            json!([to_base64(&bytes)]),
+        )
+        .await;
+        assert!(simulation.is_err());
+    });
+}
+
+/// `estimate_gas` is disabled unless `rpc.enable_simulation_rpc` is set. The estimation itself is
+/// tested against the runtime in `neard`.
+#[test]
+fn test_estimate_gas_disabled() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let block_hash = client.block(BlockReference::latest()).await.unwrap().header.hash;
+        let signer = InMemorySigner::from_seed("test1", KeyType::ED25519, "test1");
+        let tx = SignedTransaction::call(
+            1,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            0,
+            "method".to_string(),
+            vec![],
+            10u64.pow(12),
+            block_hash,
+        );
+        let bytes = tx.try_to_vec().unwrap();
+        let estimation = test_utils::call_method::<GasEstimationView>(
+            &client.client,
+            &client.server_addr,
+            "estimate_gas",
+            json!({"finality": "final", "signed_tx_base64": to_base64(&bytes)}),
+        )
+        .await;
+        assert!(estimation.is_err());
+    });
+}
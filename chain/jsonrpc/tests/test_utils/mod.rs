@@ -1,4 +1,7 @@
-use actix::Addr;
+use std::sync::{Arc, RwLock};
+
+use actix::{Addr, MailboxError};
+use futures::future::BoxFuture;
 use futures::{future, future::LocalBoxFuture, FutureExt, TryFutureExt};
 use serde_json::json;
 
@@ -6,8 +9,12 @@ use near_chain_configs::GenesisConfig;
 use near_client::test_utils::setup_no_network_with_validity_period;
 use near_client::ViewClientActor;
 use near_jsonrpc::{start_http, RpcConfig};
-use near_network::test_utils::open_port;
+use near_network::test_utils::{open_port, MockNetworkAdapter};
+use near_network::types::NetworkAdapter;
+use near_network::{NetworkRequests, NetworkResponses};
+use near_primitives::network::PeerId;
 use near_primitives::types::NumBlocks;
+use near_primitives::views::{KnownPeerStatusView, KnownPeerView};
 
 use near_jsonrpc_primitives::message::{from_slice, Message};
 
@@ -45,10 +52,86 @@ pub fn start_all_with_validity_period(
         TEST_GENESIS_CONFIG.clone(),
         client_addr.clone(),
         view_client_addr.clone(),
-    );
+        Arc::new(MockNetworkAdapter::default()),
+    )
+    .unwrap();
     (view_client_addr, addr)
 }
 
+pub const TEST_ADMIN_AUTH_TOKEN: &str = "test-admin-token";
+
+/// Network adapter answering the peer requests of the admin methods like the peer manager of a
+/// node knowing only `known_peer`.
+#[derive(Clone)]
+pub struct AdminNetworkAdapter {
+    pub known_peer: PeerId,
+    pub requests: Arc<RwLock<Vec<NetworkRequests>>>,
+}
+
+impl NetworkAdapter for AdminNetworkAdapter {
+    fn send(
+        &self,
+        msg: NetworkRequests,
+    ) -> BoxFuture<'static, Result<NetworkResponses, MailboxError>> {
+        let response = match &msg {
+            NetworkRequests::BanPeer { peer_id, .. }
+            | NetworkRequests::UnbanPeer { peer_id }
+            | NetworkRequests::DisconnectPeer { peer_id }
+                if peer_id != &self.known_peer =>
+            {
+                NetworkResponses::UnknownPeer
+            }
+            NetworkRequests::FetchKnownPeers => NetworkResponses::KnownPeers(vec![KnownPeerView {
+                peer_id: self.known_peer.clone(),
+                addr: None,
+                account_id: None,
+                status: KnownPeerStatusView::NotConnected,
+                score: 0.0,
+                connection: None,
+            }]),
+            _ => NetworkResponses::NoResponse,
+        };
+        self.do_send(msg);
+        future::ok(response).boxed()
+    }
+
+    fn do_send(&self, msg: NetworkRequests) {
+        self.requests.write().unwrap().push(msg);
+    }
+}
+
+/// Starts the RPC with the admin methods served on a separate address, returned after the address
+/// of the public methods.
+pub fn start_all_with_admin(
+    node_type: NodeType,
+    network_adapter: AdminNetworkAdapter,
+) -> (Addr<ViewClientActor>, String, String) {
+    let (client_addr, view_client_addr) = setup_no_network_with_validity_period(
+        vec!["test1", "test2"],
+        if let NodeType::Validator = node_type { "test1" } else { "other" },
+        true,
+        100,
+        false,
+    );
+
+    let addr = format!("127.0.0.1:{}", open_port());
+    let admin_addr = format!("127.0.0.1:{}", open_port());
+    let mut config = RpcConfig::new(&addr);
+    config.enable_admin_rpc = true;
+    config.admin_addr = Some(admin_addr.clone());
+    config.admin_auth_token = Some(TEST_ADMIN_AUTH_TOKEN.to_string());
+
+    start_http(
+        config,
+        TEST_GENESIS_CONFIG.clone(),
+        client_addr.clone(),
+        view_client_addr.clone(),
+        Arc::new(network_adapter),
+    )
+    .unwrap();
+    (view_client_addr, addr, admin_addr)
+}
+
 #[allow(unused_macros)] // Suppress Rustc warnings even though this macro is used.
 macro_rules! test_with_client {
     ($node_type:expr, $client:ident, $block:expr) => {
@@ -0,0 +1,549 @@
+//! Capture of the messages exchanged with peers and their replay against a node.
+//!
+//! When `NetworkConfig::capture` is set, the messages sent to and received from every peer are
+//! appended to the capture file, each record being its length as a little endian `u32` followed
+//! by the borsh encoded `CapturedMessage`. Handshakes are always captured, as replaying needs the
+//! chain info they carry. Peer actors queue the records for a dedicated writer thread, so they
+//! never wait for the file.
+//!
+//! `replay_capture` connects to a node as a new peer and sends it the captured messages again,
+//! so the way a node handled the traffic of a peer can be reproduced offline.
+use std::cmp;
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use chrono::Utc;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio_util::codec::Framed;
+use tracing::warn;
+
+use near_crypto::SecretKey;
+use near_primitives::network::PeerId;
+use near_primitives::utils::to_timestamp;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::codec::{bytes_to_peer_message, decompress_frame, peer_message_to_bytes, Codec};
+use crate::encryption::{
+    is_encrypted_transport_version, SessionAccept, SessionCipher, SessionInitiator,
+};
+use crate::routing::EdgeInfo;
+use crate::types::{Handshake, HandshakeFailureReason, PeerMessage, ReasonForBan};
+
+/// Where and which messages to capture.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CaptureConfig {
+    /// File the messages are appended to.
+    pub path: PathBuf,
+    /// Types of the messages to capture, as named by `PeerMessage::msg_variant`.
+    /// All messages are captured if empty.
+    #[serde(default)]
+    pub message_types: Vec<String>,
+    /// Size in bytes the capture file is rotated at: it is moved to `rotated_capture_path`,
+    /// replacing the previous one, and a new capture file is started. Unbounded if not set.
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+}
+
+/// Path the capture file at `path` is moved to when it is rotated.
+pub fn rotated_capture_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    rotated.into()
+}
+
+/// Number of records queued for the writer thread. Records are dropped when it is full.
+const CAPTURE_QUEUE_SIZE: usize = 10_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureDirection {
+    Sent,
+    Received,
+}
+
+/// A message sent to or received from a peer.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CapturedMessage {
+    /// Nanoseconds since the unix epoch.
+    pub timestamp: u64,
+    pub direction: CaptureDirection,
+    /// Peer the message was sent to or received from.
+    pub peer_id: PeerId,
+    /// The borsh encoded `PeerMessage`, uncompressed.
+    pub message: Vec<u8>,
+}
+
+impl CapturedMessage {
+    pub fn peer_message(&self) -> io::Result<PeerMessage> {
+        bytes_to_peer_message(&self.message)
+    }
+}
+
+/// Queues the messages passing the filter for the capture writer thread. Every peer actor has
+/// a clone, the writer thread stops once all of them are dropped.
+#[derive(Clone)]
+pub struct MessageCapture {
+    sender: SyncSender<CapturedMessage>,
+    message_types: Arc<HashSet<String>>,
+    /// Number of records dropped since the writer thread last caught up.
+    dropped: Arc<AtomicU64>,
+    /// Dropped after the sender, so the writer thread stops once the last capture is dropped.
+    _writer_thread: Arc<WriterThread>,
+}
+
+/// Waits for the queued records to be written when the last capture is dropped.
+struct WriterThread(Option<JoinHandle<()>>);
+
+impl Drop for WriterThread {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl MessageCapture {
+    pub fn open(config: &CaptureConfig) -> io::Result<Self> {
+        let mut writer = CaptureWriter::open(config)?;
+        let (sender, receiver) = mpsc::sync_channel(CAPTURE_QUEUE_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let handle = {
+            let dropped = dropped.clone();
+            thread::Builder::new()
+                .name("capture".to_string())
+                .spawn(move || writer.run(receiver, &dropped))?
+        };
+        Ok(Self {
+            sender,
+            message_types: Arc::new(config.message_types.iter().cloned().collect()),
+            dropped,
+            _writer_thread: Arc::new(WriterThread(Some(handle))),
+        })
+    }
+
+    /// Records `message`, encoded as `bytes`, if its type is captured.
+    pub fn record(
+        &self,
+        direction: CaptureDirection,
+        peer_id: &PeerId,
+        message: &PeerMessage,
+        bytes: &[u8],
+    ) {
+        let is_handshake =
+            matches!(message, PeerMessage::Handshake(_) | PeerMessage::HandshakeV2(_));
+        if !is_handshake
+            && !self.message_types.is_empty()
+            && !self.message_types.contains(message.msg_variant())
+        {
+            return;
+        }
+        let record = CapturedMessage {
+            timestamp: to_timestamp(Utc::now()),
+            direction,
+            peer_id: peer_id.clone(),
+            message: bytes.to_vec(),
+        };
+        match self.sender.try_send(record) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!(target: "network", "Failed to capture {} of {}: writer stopped", message, peer_id);
+            }
+        }
+    }
+}
+
+/// Writes the queued records to the capture file, rotating it at the max size.
+struct CaptureWriter {
+    path: PathBuf,
+    max_file_size: Option<u64>,
+    file: BufWriter<File>,
+    size: u64,
+}
+
+impl CaptureWriter {
+    fn open(config: &CaptureConfig) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: config.path.clone(),
+            max_file_size: config.max_file_size,
+            file: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn run(&mut self, receiver: Receiver<CapturedMessage>, dropped: &AtomicU64) {
+        while let Ok(record) = receiver.recv() {
+            for record in std::iter::once(record).chain(receiver.try_iter()) {
+                if let Err(err) = self.write(&record) {
+                    warn!(target: "network", "Failed to capture a message of {}: {}", record.peer_id, err);
+                }
+            }
+            // Once the queue is empty, keep the capture readable if the node crashes, which is
+            // often what is investigated.
+            if let Err(err) = self.file.flush() {
+                warn!(target: "network", "Failed to flush the capture: {}", err);
+            }
+            let dropped = dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                warn!(target: "network", "Capture queue was full, dropped {} messages", dropped);
+            }
+        }
+    }
+
+    fn write(&mut self, record: &CapturedMessage) -> io::Result<()> {
+        let bytes = record.try_to_vec()?;
+        let record_size = 4 + bytes.len() as u64;
+        if self.max_file_size.map_or(false, |max| self.size > 0 && self.size + record_size > max) {
+            self.rotate()?;
+        }
+        self.file.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        self.file.write_all(&bytes)?;
+        self.size += record_size;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        fs::rename(&self.path, rotated_capture_path(&self.path))?;
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Reads the records of a capture file in order.
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CapturedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut len = [0; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            // The end of the capture, possibly cut in the middle of the length of a record.
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(err) => return Some(Err(err)),
+        }
+        let mut record = vec![0; u32::from_le_bytes(len) as usize];
+        Some(
+            self.reader
+                .read_exact(&mut record)
+                .and_then(|()| CapturedMessage::try_from_slice(&record)),
+        )
+    }
+}
+
+/// Number of captured messages sent and skipped by `replay_capture`.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct ReplayStats {
+    pub sent: usize,
+    pub skipped: usize,
+}
+
+/// Whether `message` can be sent by another peer than the one it was captured from.
+/// Messages establishing the connection or its edge are signed for the captured peer.
+fn is_replayable(message: &PeerMessage) -> bool {
+    match message {
+        PeerMessage::Handshake(_)
+        | PeerMessage::HandshakeV2(_)
+        | PeerMessage::HandshakeFailure(..)
+        | PeerMessage::LastEdge(_)
+        | PeerMessage::RequestUpdateNonce(_)
+        | PeerMessage::ResponseUpdateNonce(_)
+        | PeerMessage::Disconnect => false,
+        _ => true,
+    }
+}
+
+fn ban_error(reason: ReasonForBan) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", reason))
+}
+
+async fn next_frame(framed: &mut Framed<TcpStream, Codec>) -> io::Result<Vec<u8>> {
+    match framed.next().await {
+        Some(frame) => frame?.map_err(ban_error),
+        None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Node closed the connection")),
+    }
+}
+
+/// Seals `frame` if the connection is encrypted.
+fn seal(cipher: &mut Option<SessionCipher>, frame: Vec<u8>) -> Vec<u8> {
+    match cipher {
+        Some(cipher) => cipher.seal(&frame),
+        None => frame,
+    }
+}
+
+/// Opens `frame` if the connection is encrypted.
+fn open(cipher: &mut Option<SessionCipher>, frame: Vec<u8>) -> io::Result<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.open(&frame).map_err(ban_error),
+        None => Ok(frame),
+    }
+}
+
+/// Connects to the node listening on `addr` as the peer of `secret_key` and sends it the messages
+/// of `records` received from peers, in order. The chain info of the handshake is taken from
+/// the first captured handshake. If `paced`, messages are sent with the delays they were received
+/// with, otherwise as fast as possible.
+pub async fn replay_capture<I>(
+    addr: SocketAddr,
+    secret_key: &SecretKey,
+    records: I,
+    paced: bool,
+) -> io::Result<ReplayStats>
+where
+    I: IntoIterator<Item = CapturedMessage>,
+{
+    let records: Vec<_> = records.into_iter().collect();
+    let chain_info = records
+        .iter()
+        .find_map(|record| match record.peer_message() {
+            Ok(PeerMessage::Handshake(handshake)) => Some(handshake.chain_info),
+            Ok(PeerMessage::HandshakeV2(handshake)) => Some(handshake.chain_info.into()),
+            _ => None,
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No handshake captured"))?;
+
+    let me = PeerId::from(secret_key.public_key());
+    let mut framed = Framed::new(TcpStream::connect(addr).await?, Codec::new());
+
+    let (mut cipher, version, mut target) = if is_encrypted_transport_version(PROTOCOL_VERSION) {
+        let initiator = SessionInitiator::new(PROTOCOL_VERSION);
+        framed.send(initiator.init().to_bytes()).await?;
+        let accept =
+            SessionAccept::from_bytes(&next_frame(&mut framed).await?).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Node didn't accept the encrypted session",
+                )
+            })?;
+        let (mut cipher, confirm) =
+            initiator.finish(&accept, me.clone(), secret_key).map_err(ban_error)?;
+        framed.send(cipher.seal(&confirm)).await?;
+        (Some(cipher), cmp::min(accept.version, PROTOCOL_VERSION), accept.peer_id)
+    } else {
+        // The `PeerId` of the node is learnt from its answer to a handshake sent to another peer.
+        (None, PROTOCOL_VERSION, me.clone())
+    };
+
+    'handshake: loop {
+        let handshake = PeerMessage::Handshake(Handshake::new(
+            version,
+            me.clone(),
+            target.clone(),
+            None,
+            chain_info.clone(),
+            EdgeInfo::new(me.clone(), target.clone(), 1, secret_key),
+        ));
+        framed.send(seal(&mut cipher, peer_message_to_bytes(&handshake)?)).await?;
+        loop {
+            let frame = open(&mut cipher, next_frame(&mut framed).await?)?;
+            match bytes_to_peer_message(&decompress_frame(frame, None).map_err(ban_error)?)? {
+                PeerMessage::Handshake(_) => break 'handshake,
+                PeerMessage::HandshakeFailure(peer_info, HandshakeFailureReason::InvalidTarget)
+                    if peer_info.id != target =>
+                {
+                    target = peer_info.id;
+                    continue 'handshake;
+                }
+                PeerMessage::HandshakeFailure(_, reason) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionRefused,
+                        format!("Handshake failed: {:?}", reason),
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // The node keeps sending messages, which are dropped without being read.
+    let (mut sink, mut stream) = framed.split::<Vec<u8>>();
+    actix::spawn(async move { while let Some(Ok(_)) = stream.next().await {} });
+
+    let mut stats = ReplayStats::default();
+    let mut last_timestamp = None;
+    for record in records {
+        if record.direction != CaptureDirection::Received || !is_replayable(&record.peer_message()?)
+        {
+            stats.skipped += 1;
+            continue;
+        }
+        if let (true, Some(last_timestamp)) = (paced, last_timestamp) {
+            let delay = record.timestamp.saturating_sub(last_timestamp);
+            tokio::time::sleep(Duration::from_nanos(delay)).await;
+        }
+        last_timestamp = Some(record.timestamp);
+        sink.send(seal(&mut cipher, record.message)).await?;
+        stats.sent += 1;
+    }
+    sink.close().await?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::hash::hash;
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    use crate::capture::{
+        rotated_capture_path, CaptureConfig, CaptureDirection, CaptureReader, CapturedMessage,
+        MessageCapture,
+    };
+    use crate::codec::peer_message_to_bytes;
+    use crate::routing::EdgeInfo;
+    use crate::test_utils::random_peer_id;
+    use crate::types::{Handshake, PeerMessage};
+
+    fn record(capture: &MessageCapture, direction: CaptureDirection, message: PeerMessage) {
+        let bytes = peer_message_to_bytes(&message).unwrap();
+        capture.record(direction, &random_peer_id(), &message, &bytes);
+    }
+
+    #[test]
+    fn capture_and_read() {
+        let dir = tempfile::Builder::new().prefix("capture").tempdir().unwrap();
+        let config = CaptureConfig {
+            path: dir.path().join("capture"),
+            message_types: vec![],
+            max_file_size: None,
+        };
+        let capture = MessageCapture::open(&config).unwrap();
+        record(&capture, CaptureDirection::Sent, PeerMessage::PeersRequest);
+        record(&capture, CaptureDirection::Received, PeerMessage::BlockRequest(hash(&[1])));
+        // Waits for the writer thread.
+        drop(capture);
+
+        let records: Vec<_> =
+            CaptureReader::open(&config.path).unwrap().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, CaptureDirection::Sent);
+        assert_eq!(records[0].peer_message().unwrap(), PeerMessage::PeersRequest);
+        assert_eq!(records[1].direction, CaptureDirection::Received);
+        assert_eq!(records[1].peer_message().unwrap(), PeerMessage::BlockRequest(hash(&[1])));
+        assert!(records[0].timestamp <= records[1].timestamp);
+
+        // Captures are appended to.
+        let capture = MessageCapture::open(&config).unwrap();
+        record(&capture, CaptureDirection::Sent, PeerMessage::Disconnect);
+        drop(capture);
+        assert_eq!(CaptureReader::open(&config.path).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn capture_rotation() {
+        let dir = tempfile::Builder::new().prefix("capture").tempdir().unwrap();
+        let record_size = {
+            let config = CaptureConfig {
+                path: dir.path().join("size"),
+                message_types: vec![],
+                max_file_size: None,
+            };
+            let capture = MessageCapture::open(&config).unwrap();
+            record(&capture, CaptureDirection::Sent, PeerMessage::PeersRequest);
+            drop(capture);
+            std::fs::metadata(&config.path).unwrap().len()
+        };
+        let config = CaptureConfig {
+            path: dir.path().join("capture"),
+            message_types: vec![],
+            max_file_size: Some(record_size * 2),
+        };
+        let capture = MessageCapture::open(&config).unwrap();
+        for _ in 0..5 {
+            record(&capture, CaptureDirection::Sent, PeerMessage::PeersRequest);
+        }
+        drop(capture);
+        assert_eq!(CaptureReader::open(&rotated_capture_path(&config.path)).unwrap().count(), 2);
+        assert_eq!(CaptureReader::open(&config.path).unwrap().count(), 1);
+        assert_eq!(std::fs::metadata(&config.path).unwrap().len(), record_size);
+    }
+
+    #[test]
+    fn capture_filter() {
+        let dir = tempfile::Builder::new().prefix("capture").tempdir().unwrap();
+        let config = CaptureConfig {
+            path: dir.path().join("capture"),
+            message_types: vec!["BlockRequest".to_string()],
+            max_file_size: None,
+        };
+        let capture = MessageCapture::open(&config).unwrap();
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let (peer0, peer1) = (random_peer_id(), random_peer_id());
+        let handshake = PeerMessage::Handshake(Handshake::new(
+            PROTOCOL_VERSION,
+            peer0.clone(),
+            peer1.clone(),
+            None,
+            Default::default(),
+            EdgeInfo::new(peer0, peer1, 1, &secret_key),
+        ));
+        record(&capture, CaptureDirection::Received, handshake.clone());
+        record(&capture, CaptureDirection::Received, PeerMessage::PeersRequest);
+        record(&capture, CaptureDirection::Received, PeerMessage::BlockRequest(hash(&[1])));
+        drop(capture);
+
+        let messages: Vec<_> = CaptureReader::open(&config.path)
+            .unwrap()
+            .map(|record| record.unwrap().peer_message().unwrap())
+            .collect();
+        assert_eq!(messages, vec![handshake, PeerMessage::BlockRequest(hash(&[1]))]);
+    }
+
+    #[test]
+    fn read_truncated_capture() {
+        let record = CapturedMessage {
+            timestamp: 1,
+            direction: CaptureDirection::Received,
+            peer_id: random_peer_id(),
+            message: peer_message_to_bytes(&PeerMessage::PeersRequest).unwrap(),
+        };
+        let bytes = borsh::BorshSerialize::try_to_vec(&record).unwrap();
+        let mut capture = (bytes.len() as u32).to_le_bytes().to_vec();
+        capture.extend(&bytes);
+        capture.extend(&[1, 0]);
+
+        let mut reader = CaptureReader::new(Cursor::new(capture.clone()));
+        assert_eq!(reader.next().unwrap().unwrap(), record);
+        assert!(reader.next().is_none());
+
+        // A malformed record is an error.
+        capture.extend(&[0, 0, 7]);
+        let mut reader = CaptureReader::new(Cursor::new(capture));
+        assert_eq!(reader.next().unwrap().unwrap(), record);
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -1,12 +1,29 @@
+use std::convert::TryInto;
 use std::io::{Error, ErrorKind};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytes::{Buf, BufMut, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::types::{PeerMessage, ReasonForBan};
+use near_primitives::checked_feature;
+use near_primitives::version::ProtocolVersion;
+
+use crate::types::{Compression, PeerMessage, ReasonForBan};
 
 const NETWORK_MESSAGE_MAX_SIZE: u32 = 512 << 20; // 512MB
+/// First byte of compressed frames, it is not a tag of `PeerMessage`.
+const COMPRESSED_FRAME_TAG: u8 = u8::MAX - 1;
+/// Frames shorter than this are sent uncompressed.
+const COMPRESSION_THRESHOLD: usize = 1024;
+/// LZ4 doesn't compress better than this, each byte encodes at most 255 bytes of a match.
+const MAX_LZ4_COMPRESSION_RATIO: usize = 255;
+/// Compression algorithms this node decompresses, in order of preference.
+pub const SUPPORTED_COMPRESSION: &[Compression] = &[Compression::Lz4];
+
+/// Whether peers talking at `version` announce the compression algorithms they support.
+pub fn is_compression_version(version: ProtocolVersion) -> bool {
+    checked_feature!("protocol_feature_peer_compression", PeerCompression, version)
+}
 
 pub struct Codec {
     max_length: u32,
@@ -65,6 +82,57 @@ impl Decoder for Codec {
     }
 }
 
+/// Compresses frames above `COMPRESSION_THRESHOLD`, unless compression makes them larger.
+pub fn compress_frame(frame: Vec<u8>, compression: Compression) -> Vec<u8> {
+    if frame.len() < COMPRESSION_THRESHOLD {
+        return frame;
+    }
+    let mut compressed = vec![COMPRESSED_FRAME_TAG];
+    compression.serialize(&mut compressed).expect("Serializing to a vector doesn't fail");
+    match compression {
+        Compression::Lz4 => compressed.extend(lz4_flex::compress_prepend_size(&frame)),
+    }
+    if compressed.len() < frame.len() {
+        compressed
+    } else {
+        frame
+    }
+}
+
+/// Decompresses `frame` if it was compressed by `compress_frame`. Compressed frames are only
+/// accepted with the `compression` negotiated in the handshake.
+pub fn decompress_frame(
+    frame: Vec<u8>,
+    compression: Option<Compression>,
+) -> Result<Vec<u8>, ReasonForBan> {
+    let mut payload = match frame.split_first() {
+        Some((&COMPRESSED_FRAME_TAG, payload)) => payload,
+        _ => return Ok(frame),
+    };
+    let frame_compression =
+        Compression::deserialize(&mut payload).map_err(|_| ReasonForBan::DecompressionFailed)?;
+    if compression != Some(frame_compression) {
+        return Err(ReasonForBan::DecompressionFailed);
+    }
+    match frame_compression {
+        Compression::Lz4 => {
+            let size = payload
+                .get(..4)
+                .map(|size| u32::from_le_bytes(size.try_into().unwrap()))
+                .ok_or(ReasonForBan::DecompressionFailed)?;
+            // The decompressed frame is allocated upfront, don't let peers claim more than the
+            // payload can hold nor bypass the size limit of the frames.
+            if size > NETWORK_MESSAGE_MAX_SIZE
+                || size as usize > payload.len() * MAX_LZ4_COMPRESSION_RATIO
+            {
+                return Err(ReasonForBan::Abusive);
+            }
+            lz4_flex::decompress_size_prepended(payload)
+                .map_err(|_| ReasonForBan::DecompressionFailed)
+        }
+    }
+}
+
 pub fn peer_message_to_bytes(peer_message: &PeerMessage) -> Result<Vec<u8>, std::io::Error> {
     peer_message.try_to_vec()
 }
@@ -245,11 +313,86 @@ mod test {
                 archival: false,
             },
             edge_info: EdgeInfo::default(),
+            compression: if is_compression_version(PROTOCOL_VERSION) {
+                SUPPORTED_COMPRESSION.to_vec()
+            } else {
+                vec![]
+            },
         };
         let msg = PeerMessage::Handshake(fake_handshake);
         test_codec(msg);
     }
 
+    #[cfg(feature = "protocol_feature_peer_compression")]
+    #[test]
+    fn test_peer_message_handshake_before_compression() {
+        use near_primitives::version::{ProtocolFeature, PROTOCOL_FEATURES_TO_VERSION_MAPPING};
+
+        let peer_info = PeerInfo::random();
+        let mut handshake = Handshake::new(
+            PROTOCOL_FEATURES_TO_VERSION_MAPPING[&ProtocolFeature::PeerCompression] - 1,
+            peer_info.id.clone(),
+            peer_info.id,
+            None,
+            PeerChainInfoV2 {
+                genesis_id: Default::default(),
+                height: 0,
+                tracked_shards: vec![],
+                archival: false,
+            },
+            EdgeInfo::default(),
+        );
+        // Older peers don't know about compression, so it isn't sent to them.
+        let decoded = bytes_to_peer_message(
+            &peer_message_to_bytes(&PeerMessage::Handshake(handshake.clone())).unwrap(),
+        )
+        .unwrap();
+        handshake.compression = vec![];
+        assert_eq!(decoded, PeerMessage::Handshake(handshake));
+    }
+
+    #[test]
+    fn test_compression() {
+        let small_frame = vec![1; COMPRESSION_THRESHOLD - 1];
+        assert_eq!(compress_frame(small_frame.clone(), Compression::Lz4), small_frame);
+
+        let frame = vec![1; 100 * COMPRESSION_THRESHOLD];
+        let compressed = compress_frame(frame.clone(), Compression::Lz4);
+        assert!(compressed.len() < frame.len());
+        assert_eq!(compressed[0], COMPRESSED_FRAME_TAG);
+        assert_eq!(decompress_frame(compressed.clone(), Some(Compression::Lz4)).unwrap(), frame);
+        assert_eq!(decompress_frame(small_frame.clone(), None).unwrap(), small_frame);
+        // Compressed frames are only accepted once compression is negotiated.
+        assert_eq!(decompress_frame(compressed, None), Err(ReasonForBan::DecompressionFailed));
+
+        // Incompressible frames are sent as they are.
+        let frame: Vec<u8> = (0..COMPRESSION_THRESHOLD as u64)
+            .flat_map(|i| hash::hash(&i.to_le_bytes()).as_ref().to_vec())
+            .collect();
+        assert_eq!(compress_frame(frame.clone(), Compression::Lz4), frame);
+    }
+
+    #[test]
+    fn test_decompression_limits() {
+        let lz4 = Some(Compression::Lz4);
+        let mut frame = vec![COMPRESSED_FRAME_TAG];
+        Compression::Lz4.serialize(&mut frame).unwrap();
+        assert_eq!(decompress_frame(frame.clone(), lz4), Err(ReasonForBan::DecompressionFailed));
+        frame.extend_from_slice(&(NETWORK_MESSAGE_MAX_SIZE + 1).to_le_bytes());
+        assert_eq!(decompress_frame(frame.clone(), lz4), Err(ReasonForBan::Abusive));
+
+        // A short frame can't claim a size it couldn't have been compressed from.
+        frame.truncate(2);
+        frame.extend_from_slice(&NETWORK_MESSAGE_MAX_SIZE.to_le_bytes());
+        frame.extend_from_slice(&[0; 4]);
+        assert_eq!(decompress_frame(frame.clone(), lz4), Err(ReasonForBan::Abusive));
+
+        frame.truncate(2);
+        frame.extend_from_slice(&100u32.to_le_bytes());
+        frame.extend_from_slice(&[0xff; 10]);
+        assert_eq!(decompress_frame(frame, lz4), Err(ReasonForBan::DecompressionFailed));
+    }
+
     #[test]
     fn test_peer_message_handshake_v2() {
         let peer_info = PeerInfo::random();
@@ -0,0 +1,406 @@
+//! Encrypted and authenticated transport between peers.
+//!
+//! Peers supporting `ProtocolFeature::EncryptedTransport` run a Noise-style handshake before
+//! exchanging `Handshake`:
+//!
+//! 1. The initiator sends `SessionInit` with an ephemeral x25519 key.
+//! 2. The responder sends `SessionAccept` with its own ephemeral key and its `PeerId`, signing the
+//!    transcript of the handshake with the key of its `PeerId`.
+//! 3. Both peers derive a key per direction from the Diffie-Hellman secret and the transcript,
+//!    and the initiator proves its `PeerId` the same way in `SessionConfirm`, the first encrypted
+//!    frame.
+//!
+//! From then on every frame is sealed with ChaCha20-Poly1305, the number of frames sent in the
+//! direction being the nonce.
+//!
+//! `SessionInit` starts with the same header as `PeerMessage::Handshake`, so peers which don't
+//! know the encrypted transport answer it with `HandshakeFailureReason::ProtocolVersionMismatch`
+//! and the initiator falls back to plaintext at their version.
+use borsh::{BorshDeserialize, BorshSerialize};
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+
+use near_crypto::{SecretKey, Signature};
+use near_primitives::checked_feature;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::network::PeerId;
+use near_primitives::version::{ProtocolVersion, OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION};
+
+use crate::types::ReasonForBan;
+
+/// Borsh tag of `PeerMessage::Handshake`.
+const SESSION_INIT_TAG: u8 = 0;
+/// Tag of `SessionAccept`, which is not a tag of `PeerMessage`.
+const SESSION_ACCEPT_TAG: u8 = u8::MAX;
+
+const TRANSCRIPT_DOMAIN: &[u8] = b"near-network-session";
+const INITIATOR_ROLE: &[u8] = b"initiator";
+const RESPONDER_ROLE: &[u8] = b"responder";
+
+/// First message of the handshake, sent by the initiator in plaintext.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct SessionInit {
+    pub version: ProtocolVersion,
+    pub oldest_supported_version: ProtocolVersion,
+    pub ephemeral_key: [u8; 32],
+}
+
+/// Answer of the responder to `SessionInit`, sent in plaintext.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct SessionAccept {
+    pub version: ProtocolVersion,
+    pub ephemeral_key: [u8; 32],
+    pub peer_id: PeerId,
+    /// Signature of the transcript by the responder.
+    pub signature: Signature,
+}
+
+/// Last message of the handshake, sent encrypted by the initiator.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct SessionConfirm {
+    pub peer_id: PeerId,
+    /// Signature of the transcript by the initiator.
+    pub signature: Signature,
+}
+
+impl SessionInit {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_frame(SESSION_INIT_TAG, self)
+    }
+
+    /// Reads a `SessionInit`, `None` if the frame is something else, e.g. a plaintext
+    /// `PeerMessage::Handshake` of a peer not using the encrypted transport.
+    pub fn from_bytes(frame: &[u8]) -> Option<Self> {
+        from_frame::<Self>(SESSION_INIT_TAG, frame)
+            .filter(|init| is_encrypted_transport_version(init.version))
+    }
+}
+
+impl SessionAccept {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_frame(SESSION_ACCEPT_TAG, self)
+    }
+
+    /// Reads a `SessionAccept`, `None` if the frame is something else, e.g. a plaintext
+    /// `PeerMessage::HandshakeFailure` of a peer not using the encrypted transport.
+    pub fn from_bytes(frame: &[u8]) -> Option<Self> {
+        from_frame(SESSION_ACCEPT_TAG, frame)
+    }
+}
+
+fn to_frame<T: BorshSerialize>(tag: u8, message: &T) -> Vec<u8> {
+    let mut frame = vec![tag];
+    message.serialize(&mut frame).expect("Serializing to a vector doesn't fail");
+    frame
+}
+
+fn from_frame<T: BorshDeserialize>(tag: u8, frame: &[u8]) -> Option<T> {
+    match frame.split_first() {
+        Some((first, rest)) if *first == tag => T::try_from_slice(rest).ok(),
+        _ => None,
+    }
+}
+
+/// Encrypts the frames sent and decrypts the frames received through a connection.
+pub struct SessionCipher {
+    sealing: ChaCha20Poly1305,
+    sent: u64,
+    opening: ChaCha20Poly1305,
+    received: u64,
+}
+
+impl SessionCipher {
+    fn new(
+        ephemeral_secret: EphemeralSecret,
+        their_ephemeral_key: [u8; 32],
+        transcript: &CryptoHash,
+        is_initiator: bool,
+    ) -> Result<Self, ReasonForBan> {
+        let shared_secret =
+            ephemeral_secret.diffie_hellman(&X25519PublicKey::from(their_ephemeral_key));
+        // A low order point of the peer makes the secret known to anyone.
+        if shared_secret.as_bytes() == &[0; 32] {
+            return Err(ReasonForBan::BadHandshake);
+        }
+        let derive_key = |role: &[u8]| {
+            let key = hash(&[shared_secret.as_bytes(), transcript.as_ref(), role].concat());
+            ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+        };
+        let (sealing, opening) = if is_initiator {
+            (derive_key(INITIATOR_ROLE), derive_key(RESPONDER_ROLE))
+        } else {
+            (derive_key(RESPONDER_ROLE), derive_key(INITIATOR_ROLE))
+        };
+        Ok(Self { sealing, sent: 0, opening, received: 0 })
+    }
+
+    pub fn seal(&mut self, frame: &[u8]) -> Vec<u8> {
+        let nonce = nonce(self.sent);
+        self.sent += 1;
+        self.sealing.encrypt(&nonce, frame).expect("Frames are small enough to be encrypted")
+    }
+
+    pub fn open(&mut self, frame: &[u8]) -> Result<Vec<u8>, ReasonForBan> {
+        let nonce = nonce(self.received);
+        self.received += 1;
+        self.opening.decrypt(&nonce, frame).map_err(|_| ReasonForBan::DecryptionFailed)
+    }
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0; 12];
+    nonce[4..].copy_from_slice(&counter.to_le_bytes());
+    Nonce::clone_from_slice(&nonce)
+}
+
+/// Hash of everything exchanged in plaintext, signed by both peers.
+fn transcript(
+    init: &SessionInit,
+    accept_version: ProtocolVersion,
+    accept_key: &[u8; 32],
+) -> CryptoHash {
+    let init = init.try_to_vec().expect("Serializing to a vector doesn't fail");
+    hash(&[TRANSCRIPT_DOMAIN, &init, &accept_version.to_le_bytes(), accept_key].concat())
+}
+
+/// Whether peers talking at `version` encrypt their connections.
+pub fn is_encrypted_transport_version(version: ProtocolVersion) -> bool {
+    checked_feature!("protocol_feature_encrypted_transport", EncryptedTransport, version)
+}
+
+fn sign_transcript(secret_key: &SecretKey, transcript: &CryptoHash, role: &[u8]) -> Signature {
+    secret_key.sign(&[transcript.as_ref(), role].concat())
+}
+
+fn verify_transcript(
+    peer_id: &PeerId,
+    signature: &Signature,
+    transcript: &CryptoHash,
+    role: &[u8],
+) -> bool {
+    signature.verify(&[transcript.as_ref(), role].concat(), &peer_id.public_key())
+}
+
+/// Initiator side of the handshake, waiting for `SessionAccept`.
+pub struct SessionInitiator {
+    ephemeral_secret: EphemeralSecret,
+    init: SessionInit,
+}
+
+impl SessionInitiator {
+    pub fn new(version: ProtocolVersion) -> Self {
+        let ephemeral_secret = EphemeralSecret::new(OsRng);
+        let init = SessionInit {
+            version,
+            oldest_supported_version: OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION,
+            ephemeral_key: *X25519PublicKey::from(&ephemeral_secret).as_bytes(),
+        };
+        Self { ephemeral_secret, init }
+    }
+
+    pub fn init(&self) -> &SessionInit {
+        &self.init
+    }
+
+    /// Checks the answer of the responder and returns the cipher of the session along with
+    /// `SessionConfirm`, which must be the first frame sealed by the cipher.
+    pub fn finish(
+        self,
+        accept: &SessionAccept,
+        peer_id: PeerId,
+        secret_key: &SecretKey,
+    ) -> Result<(SessionCipher, Vec<u8>), ReasonForBan> {
+        if !is_encrypted_transport_version(accept.version) {
+            return Err(ReasonForBan::BadHandshake);
+        }
+        let transcript = transcript(&self.init, accept.version, &accept.ephemeral_key);
+        if !verify_transcript(&accept.peer_id, &accept.signature, &transcript, RESPONDER_ROLE) {
+            return Err(ReasonForBan::InvalidSignature);
+        }
+        let cipher =
+            SessionCipher::new(self.ephemeral_secret, accept.ephemeral_key, &transcript, true)?;
+        let confirm = SessionConfirm {
+            peer_id,
+            signature: sign_transcript(secret_key, &transcript, INITIATOR_ROLE),
+        };
+        Ok((cipher, confirm.try_to_vec().expect("Serializing to a vector doesn't fail")))
+    }
+}
+
+/// Responder side of the handshake, waiting for `SessionConfirm`.
+pub struct SessionResponder {
+    cipher: SessionCipher,
+    transcript: CryptoHash,
+}
+
+impl SessionResponder {
+    /// Answers `init`, returns the responder along with `SessionAccept` to send.
+    pub fn new(
+        init: &SessionInit,
+        version: ProtocolVersion,
+        peer_id: PeerId,
+        secret_key: &SecretKey,
+    ) -> Result<(Self, Vec<u8>), ReasonForBan> {
+        let ephemeral_secret = EphemeralSecret::new(OsRng);
+        let ephemeral_key = *X25519PublicKey::from(&ephemeral_secret).as_bytes();
+        let transcript = transcript(init, version, &ephemeral_key);
+        let cipher = SessionCipher::new(ephemeral_secret, init.ephemeral_key, &transcript, false)?;
+        let accept = SessionAccept {
+            version,
+            ephemeral_key,
+            peer_id,
+            signature: sign_transcript(secret_key, &transcript, RESPONDER_ROLE),
+        };
+        Ok((Self { cipher, transcript }, accept.to_bytes()))
+    }
+
+    /// Checks `SessionConfirm` and returns the cipher of the session along with the `PeerId`
+    /// the initiator proved to own.
+    pub fn finish(mut self, frame: &[u8]) -> Result<(SessionCipher, PeerId), ReasonForBan> {
+        let confirm = SessionConfirm::try_from_slice(&self.cipher.open(frame)?)
+            .map_err(|_| ReasonForBan::BadHandshake)?;
+        if !verify_transcript(
+            &confirm.peer_id,
+            &confirm.signature,
+            &self.transcript,
+            INITIATOR_ROLE,
+        ) {
+            return Err(ReasonForBan::InvalidSignature);
+        }
+        Ok((self.cipher, confirm.peer_id))
+    }
+}
+
+/// Progress of the encrypted session of a connection.
+pub enum SessionState {
+    /// Frames are sent in plaintext, either the handshake hasn't started or the peer doesn't
+    /// support the encrypted transport.
+    Plaintext,
+    /// The initiator sent `SessionInit`.
+    Initiated(SessionInitiator),
+    /// The responder sent `SessionAccept`.
+    Accepted(SessionResponder),
+    /// Frames are encrypted, the peer proved to own `peer_id`.
+    Established { cipher: SessionCipher, peer_id: PeerId },
+}
+
+#[cfg(all(test, feature = "protocol_feature_encrypted_transport"))]
+mod test {
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::network::PeerId;
+    use near_primitives::version::{
+        ProtocolFeature, OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION,
+        PROTOCOL_FEATURES_TO_VERSION_MAPPING, PROTOCOL_VERSION,
+    };
+
+    use crate::codec::bytes_to_peer_message;
+    use crate::encryption::{
+        is_encrypted_transport_version, SessionAccept, SessionCipher, SessionInit,
+        SessionInitiator, SessionResponder,
+    };
+    use crate::types::{HandshakeFailureReason, ReasonForBan};
+
+    struct Node {
+        secret_key: SecretKey,
+        peer_id: PeerId,
+    }
+
+    impl Node {
+        fn new(seed: &str) -> Self {
+            let secret_key = SecretKey::from_seed(KeyType::ED25519, seed);
+            let peer_id = PeerId::new(secret_key.public_key());
+            Self { secret_key, peer_id }
+        }
+    }
+
+    fn establish(initiator: &Node, responder: &Node) -> (SessionCipher, SessionCipher) {
+        let session_initiator = SessionInitiator::new(PROTOCOL_VERSION);
+        let init = SessionInit::from_bytes(&session_initiator.init().to_bytes()).unwrap();
+        let (session_responder, accept) = SessionResponder::new(
+            &init,
+            PROTOCOL_VERSION,
+            responder.peer_id.clone(),
+            &responder.secret_key,
+        )
+        .unwrap();
+        let accept = SessionAccept::from_bytes(&accept).unwrap();
+        assert_eq!(accept.peer_id, responder.peer_id);
+        let (mut initiator_cipher, confirm) = session_initiator
+            .finish(&accept, initiator.peer_id.clone(), &initiator.secret_key)
+            .unwrap();
+        let (responder_cipher, peer_id) =
+            session_responder.finish(&initiator_cipher.seal(&confirm)).unwrap();
+        assert_eq!(peer_id, initiator.peer_id);
+        (initiator_cipher, responder_cipher)
+    }
+
+    #[test]
+    fn test_session() {
+        let (mut initiator, mut responder) = establish(&Node::new("test1"), &Node::new("test2"));
+        for message in [b"first".as_ref(), b"second", b""].iter() {
+            let frame = initiator.seal(message);
+            assert_ne!(frame.as_slice(), *message);
+            assert_eq!(responder.open(&frame).unwrap().as_slice(), *message);
+            assert_eq!(initiator.open(&responder.seal(message)).unwrap().as_slice(), *message);
+        }
+
+        // Frames can't be tampered with, replayed or reordered.
+        let mut frame = initiator.seal(b"message");
+        frame[0] ^= 1;
+        assert_eq!(responder.open(&frame), Err(ReasonForBan::DecryptionFailed));
+        let first = initiator.seal(b"first");
+        let second = initiator.seal(b"second");
+        assert!(responder.open(&second).is_err());
+        assert!(responder.open(&first).is_err());
+    }
+
+    #[test]
+    fn test_session_invalid_signature() {
+        let responder = Node::new("test2");
+        let session_initiator = SessionInitiator::new(PROTOCOL_VERSION);
+        let (_, accept) = SessionResponder::new(
+            session_initiator.init(),
+            PROTOCOL_VERSION,
+            responder.peer_id.clone(),
+            &responder.secret_key,
+        )
+        .unwrap();
+        // The responder claims a `PeerId` it doesn't own.
+        let mut accept = SessionAccept::from_bytes(&accept).unwrap();
+        accept.peer_id = Node::new("test3").peer_id;
+        let initiator = Node::new("test1");
+        assert_eq!(
+            session_initiator.finish(&accept, initiator.peer_id, &initiator.secret_key).err(),
+            Some(ReasonForBan::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_session_init_is_version_mismatch_for_old_peers() {
+        assert!(is_encrypted_transport_version(PROTOCOL_VERSION));
+        let init = SessionInitiator::new(PROTOCOL_VERSION).init().to_bytes();
+        // Peers decode `SessionInit` as a `Handshake` and can't support its version, here
+        // simulated by increasing the version past the one supported by this binary.
+        let mut init_from_future = init.clone();
+        init_from_future[1..5].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        let err = bytes_to_peer_message(&init_from_future).unwrap_err();
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<HandshakeFailureReason>(),
+            Some(&HandshakeFailureReason::ProtocolVersionMismatch {
+                version: PROTOCOL_VERSION + 1,
+                oldest_supported_version: PROTOCOL_VERSION + 1,
+            })
+        );
+
+        // Plaintext handshakes are not mistaken for `SessionInit`.
+        let old_init = SessionInit {
+            version: PROTOCOL_FEATURES_TO_VERSION_MAPPING[&ProtocolFeature::EncryptedTransport] - 1,
+            oldest_supported_version: OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION,
+            ephemeral_key: [0; 32],
+        };
+        assert_eq!(SessionInit::from_bytes(&old_init.to_bytes()), None);
+        assert_eq!(SessionAccept::from_bytes(&init), None);
+    }
+}
@@ -8,7 +8,9 @@ pub use types::{
 };
 
 mod cache;
+pub mod capture;
 mod codec;
+mod encryption;
 pub mod metrics;
 mod peer;
 mod peer_manager;
@@ -14,6 +14,7 @@ use actix::{
 };
 use tracing::{debug, error, info, trace, warn};
 
+use near_crypto::SecretKey;
 use near_metrics;
 use near_performance_metrics;
 use near_primitives::block::GenesisId;
@@ -25,19 +26,26 @@ use near_primitives::version::{
     ProtocolVersion, OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 
-use crate::codec::{self, bytes_to_peer_message, peer_message_to_bytes, Codec};
+use crate::capture::{CaptureDirection, MessageCapture};
+use crate::codec::{
+    self, bytes_to_peer_message, peer_message_to_bytes, Codec, SUPPORTED_COMPRESSION,
+};
+use crate::encryption::{
+    is_encrypted_transport_version, SessionAccept, SessionInit, SessionInitiator, SessionResponder,
+    SessionState,
+};
 use crate::rate_counter::RateCounter;
 #[cfg(feature = "metric_recorder")]
 use crate::recorder::{PeerMessageMetadata, Status};
 use crate::routing::{Edge, EdgeInfo};
 use crate::types::{
-    Ban, Consolidate, ConsolidateResponse, Handshake, HandshakeFailureReason, HandshakeV2,
-    NetworkClientMessages, NetworkClientResponses, NetworkRequests, NetworkViewClientMessages,
-    NetworkViewClientResponses, PeerChainInfo, PeerChainInfoV2, PeerInfo, PeerManagerRequest,
-    PeerMessage, PeerRequest, PeerResponse, PeerStatsResult, PeerStatus, PeerType, PeersRequest,
-    PeersResponse, QueryPeerStats, ReasonForBan, RoutedMessage, RoutedMessageBody,
-    RoutedMessageFrom, SendMessage, StateResponseInfo, Unregister,
-    UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE,
+    Ban, Compression, Consolidate, ConsolidateResponse, Handshake, HandshakeFailureReason,
+    HandshakeV2, NetworkClientMessages, NetworkClientResponses, NetworkRequests,
+    NetworkViewClientMessages, NetworkViewClientResponses, PeerChainInfo, PeerChainInfoV2,
+    PeerInfo, PeerManagerRequest, PeerMessage, PeerRequest, PeerResponse, PeerScoreEvent,
+    PeerStatsResult, PeerStatus, PeerType, PeersRequest, PeersResponse, QueryPeerStats,
+    ReasonForBan, RoutedMessage, RoutedMessageBody, RoutedMessageFrom, ScoredRequest, SendMessage,
+    StateResponseInfo, Unregister, UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE,
 };
 use crate::PeerManagerActor;
 use crate::{metrics, NetworkResponses};
@@ -158,6 +166,12 @@ pub struct Peer {
     pub protocol_version: ProtocolVersion,
     /// Framed wrapper to send messages through the TCP connection.
     framed: FramedWrite<Vec<u8>, WriteHalf, Codec>,
+    /// Encryption of the connection.
+    session: SessionState,
+    /// This node's secret key, used to prove its id when encrypting the connection.
+    secret_key: SecretKey,
+    /// Compression of the frames sent to the peer, if it supports any.
+    compression: Option<Compression>,
     /// Handshake timeout.
     handshake_timeout: Duration,
     /// Peer manager recipient to break the dependency loop.
@@ -182,6 +196,8 @@ pub struct Peer {
     txns_since_last_block: Arc<AtomicUsize>,
     /// How many peer actors are created
     peer_counter: Arc<AtomicUsize>,
+    /// Capture of the messages exchanged with peers, if enabled.
+    capture: Option<MessageCapture>,
 }
 
 impl Peer {
@@ -191,6 +207,7 @@ impl Peer {
         peer_info: Option<PeerInfo>,
         peer_type: PeerType,
         framed: FramedWrite<Vec<u8>, WriteHalf, Codec>,
+        secret_key: SecretKey,
         handshake_timeout: Duration,
         peer_manager_addr: Addr<PeerManagerActor>,
         client_addr: Recipient<NetworkClientMessages>,
@@ -199,6 +216,7 @@ impl Peer {
         network_metrics: NetworkMetrics,
         txns_since_last_block: Arc<AtomicUsize>,
         peer_counter: Arc<AtomicUsize>,
+        capture: Option<MessageCapture>,
     ) -> Self {
         Peer {
             node_info,
@@ -208,6 +226,9 @@ impl Peer {
             peer_status: PeerStatus::Connecting,
             protocol_version: PROTOCOL_VERSION,
             framed,
+            session: SessionState::Plaintext,
+            secret_key,
+            compression: None,
             handshake_timeout,
             peer_manager_addr,
             client_addr,
@@ -220,6 +241,7 @@ impl Peer {
             network_metrics,
             txns_since_last_block,
             peer_counter,
+            capture,
         }
     }
 
@@ -252,15 +274,103 @@ impl Peer {
 
         match peer_message_to_bytes(msg) {
             Ok(bytes) => {
+                self.capture_message(CaptureDirection::Sent, msg, &bytes);
+                #[cfg(feature = "metric_recorder")]
+                let raw_size = bytes.len();
+                let bytes = match self.compression {
+                    Some(compression) => codec::compress_frame(bytes, compression),
+                    None => bytes,
+                };
                 #[cfg(feature = "metric_recorder")]
-                self.peer_manager_addr.do_send(metadata.set_size(bytes.len()));
+                self.peer_manager_addr
+                    .do_send(metadata.set_size(raw_size).set_compressed_size(bytes.len()));
                 self.tracker.increment_sent(bytes.len() as u64);
-                self.framed.write(bytes);
+                self.write_frame(bytes);
             }
             Err(err) => error!(target: "network", "Error converting message to bytes: {}", err),
         };
     }
 
+    /// Writes a frame, encrypted if the session is established.
+    fn write_frame(&mut self, frame: Vec<u8>) {
+        match &mut self.session {
+            SessionState::Established { cipher, .. } => self.framed.write(cipher.seal(&frame)),
+            _ => self.framed.write(frame),
+        }
+    }
+
+    /// Runs the handshake of the encrypted session and decrypts the frames of an established
+    /// session. Returns the frames carrying a `PeerMessage`.
+    fn receive_frame(&mut self, ctx: &mut Context<Peer>, frame: Vec<u8>) -> Option<Vec<u8>> {
+        match std::mem::replace(&mut self.session, SessionState::Plaintext) {
+            SessionState::Plaintext => {
+                if self.peer_type == PeerType::Inbound && self.peer_status == PeerStatus::Connecting
+                {
+                    if let Some(init) = SessionInit::from_bytes(&frame) {
+                        match SessionResponder::new(
+                            &init,
+                            PROTOCOL_VERSION,
+                            self.node_id(),
+                            &self.secret_key,
+                        ) {
+                            Ok((responder, accept)) => {
+                                self.framed.write(accept);
+                                self.session = SessionState::Accepted(responder);
+                            }
+                            Err(ban_reason) => self.ban_peer(ctx, ban_reason),
+                        }
+                        return None;
+                    }
+                }
+                Some(frame)
+            }
+            SessionState::Initiated(initiator) => {
+                let accept = match SessionAccept::from_bytes(&frame) {
+                    Some(accept) => accept,
+                    // The peer doesn't support the encrypted transport and answered in plaintext.
+                    None => return Some(frame),
+                };
+                let peer_id = self.peer_id().unwrap();
+                if accept.peer_id != peer_id {
+                    debug!(target: "network", "Encrypted session with {} answered by {}", peer_id, accept.peer_id);
+                    ctx.stop();
+                    return None;
+                }
+                match initiator.finish(&accept, self.node_id(), &self.secret_key) {
+                    Ok((mut cipher, confirm)) => {
+                        self.framed.write(cipher.seal(&confirm));
+                        self.session = SessionState::Established { cipher, peer_id };
+                        self.protocol_version =
+                            std::cmp::min(accept.version, self.protocol_version);
+                        self.send_handshake(ctx);
+                    }
+                    Err(ban_reason) => self.ban_peer(ctx, ban_reason),
+                }
+                None
+            }
+            SessionState::Accepted(responder) => {
+                match responder.finish(&frame) {
+                    Ok((cipher, peer_id)) => {
+                        self.session = SessionState::Established { cipher, peer_id }
+                    }
+                    Err(ban_reason) => self.ban_peer(ctx, ban_reason),
+                }
+                None
+            }
+            SessionState::Established { mut cipher, peer_id } => {
+                let frame = cipher.open(&frame);
+                self.session = SessionState::Established { cipher, peer_id };
+                match frame {
+                    Ok(frame) => Some(frame),
+                    Err(ban_reason) => {
+                        self.ban_peer(ctx, ban_reason);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
     fn fetch_client_chain_info(&mut self, ctx: &mut Context<Peer>) {
         ctx.wait(
             self.view_client_addr
@@ -286,6 +396,16 @@ impl Peer {
             return;
         }
 
+        // Encrypt the connection first, the handshake is sent once the session is established.
+        if is_encrypted_transport_version(self.protocol_version) {
+            if let SessionState::Plaintext = self.session {
+                let initiator = SessionInitiator::new(self.protocol_version);
+                self.framed.write(initiator.init().to_bytes());
+                self.session = SessionState::Initiated(initiator);
+                return;
+            }
+        }
+
         self.view_client_addr
             .send(NetworkViewClientMessages::GetChainInfo)
             .into_actor(self)
@@ -346,6 +466,24 @@ impl Peer {
         self.peer_info.as_ref().as_ref().map(|peer_info| peer_info.id.clone())
     }
 
+    /// Appends `msg`, encoded as `bytes`, to the capture if enabled.
+    fn capture_message(&self, direction: CaptureDirection, msg: &PeerMessage, bytes: &[u8]) {
+        let capture = unwrap_option_or_return!(self.capture.as_ref());
+        // The id of an inbound peer is only known once its handshake is processed.
+        let peer_id = match (self.peer_id(), msg) {
+            (Some(peer_id), _) => peer_id,
+            (None, PeerMessage::Handshake(handshake)) => handshake.peer_id.clone(),
+            (None, PeerMessage::HandshakeV2(handshake)) => handshake.peer_id.clone(),
+            (None, _) => return,
+        };
+        capture.record(direction, &peer_id, msg, bytes);
+    }
+
+    /// Reports an event changing the score of `peer_id` to the peer manager.
+    fn update_peer_score(&self, peer_id: PeerId, event: PeerScoreEvent) {
+        self.peer_manager_addr.do_send(PeerRequest::UpdatePeerScore(peer_id, event));
+    }
+
     fn receive_message(&mut self, ctx: &mut Context<Peer>, msg: PeerMessage) {
         if msg.is_view_client_message() {
             self.receive_view_client_message(ctx, msg);
@@ -471,6 +609,11 @@ impl Peer {
             PeerMessage::Block(block) => {
                 near_metrics::inc_counter(&metrics::PEER_BLOCK_RECEIVED_TOTAL);
                 let block_hash = *block.hash();
+                if self.tracker.has_received(&block_hash) {
+                    self.update_peer_score(peer_id.clone(), PeerScoreEvent::DuplicateMessage);
+                } else if self.tracker.has_request(&block_hash) {
+                    self.update_peer_score(peer_id.clone(), PeerScoreEvent::DeliveredBlock);
+                }
                 self.tracker.push_received(block_hash);
                 self.chain_info.height = max(self.chain_info.height, block.header().height());
                 NetworkClientMessages::Block(block, peer_id, self.tracker.has_request(&block_hash))
@@ -489,6 +632,12 @@ impl Peer {
             // All Routed messages received at this point are for us.
             PeerMessage::Routed(routed_message) => {
                 let msg_hash = routed_message.hash();
+                if let Some(request) = ScoredRequest::from_response(&routed_message.body) {
+                    self.peer_manager_addr.do_send(PeerRequest::ScoredResponse(
+                        routed_message.author.clone(),
+                        request,
+                    ));
+                }
 
                 match routed_message.body {
                     RoutedMessageBody::BlockApproval(approval) => {
@@ -570,6 +719,11 @@ impl Peer {
                         // TODO: count as malicious behavior?
                     }
                     Ok(NetworkClientResponses::Ban { ban_reason }) => {
+                        if let (ReasonForBan::BadBlockHeader, Some(peer_id)) =
+                            (ban_reason, act.peer_id())
+                        {
+                            act.update_peer_score(peer_id, PeerScoreEvent::InvalidHeader);
+                        }
                         act.ban_peer(ctx, ban_reason);
                     }
                     Err(err) => {
@@ -622,6 +776,9 @@ impl Actor for Peer {
             move |act, ctx| {
                 if act.peer_status != PeerStatus::Ready {
                     info!(target: "network", "Handshake timeout expired for {}", act.peer_info);
+                    if let Some(peer_id) = act.peer_id() {
+                        act.update_peer_score(peer_id, PeerScoreEvent::Timeout);
+                    }
                     ctx.stop();
                 }
             },
@@ -678,6 +835,18 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
         near_metrics::inc_counter_by(&metrics::PEER_DATA_RECEIVED_BYTES, msg.len() as u64);
         near_metrics::inc_counter(&metrics::PEER_MESSAGE_RECEIVED_TOTAL);
 
+        let msg = unwrap_option_or_return!(self.receive_frame(ctx, msg));
+
+        #[cfg(feature = "metric_recorder")]
+        let compressed_size = msg.len();
+        let msg = match codec::decompress_frame(msg, self.compression) {
+            Ok(msg) => msg,
+            Err(ban_reason) => {
+                self.ban_peer(ctx, ban_reason);
+                return;
+            }
+        };
+
         #[cfg(feature = "metric_recorder")]
         let msg_size = msg.len();
 
@@ -718,6 +887,8 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
                 return;
             }
         };
+        self.capture_message(CaptureDirection::Received, &peer_msg, &msg);
+
         if let PeerMessage::Routed(RoutedMessage {
             body: RoutedMessageBody::ForwardTx(_), ..
         }) = &peer_msg
@@ -734,8 +905,11 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
         #[cfg(feature = "metric_recorder")]
         {
             let mut metadata: PeerMessageMetadata = (&peer_msg).into();
-            metadata =
-                metadata.set_size(msg_size).set_target(self.node_id()).set_status(Status::Received);
+            metadata = metadata
+                .set_size(msg_size)
+                .set_compressed_size(compressed_size)
+                .set_target(self.node_id())
+                .set_status(Status::Received);
 
             if let Some(peer_id) = self.peer_id() {
                 metadata = metadata.set_source(peer_id);
@@ -800,6 +974,20 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
                 let target_version = std::cmp::min(handshake.version, PROTOCOL_VERSION);
                 self.protocol_version = target_version;
 
+                // Peers speaking the encrypted transport must send the handshake over an
+                // encrypted session, a plaintext one means the session handshake was stripped.
+                let encrypted = matches!(self.session, SessionState::Established { .. });
+                if !encrypted && is_encrypted_transport_version(target_version) {
+                    warn!(target: "network", "Received plaintext handshake from {} with version {}", handshake.peer_id, handshake.version);
+                    ctx.stop();
+                    return;
+                }
+                self.compression = handshake
+                    .compression
+                    .iter()
+                    .find(|compression| SUPPORTED_COMPRESSION.contains(compression))
+                    .copied();
+
                 if handshake.chain_info.genesis_id != self.genesis_id {
                     debug!(target: "network", "Received connection from node with different genesis.");
                     ctx.address().do_send(SendMessage {
@@ -812,6 +1000,14 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
                     // Connection will be closed by a handshake timeout
                 }
 
+                if let SessionState::Established { peer_id, .. } = &self.session {
+                    if handshake.peer_id != *peer_id {
+                        warn!(target: "network", "Received handshake from {} over the encrypted session of {}", handshake.peer_id, peer_id);
+                        self.ban_peer(ctx, ReasonForBan::InvalidPeerId);
+                        return;
+                    }
+                }
+
                 if handshake.peer_id == self.node_info.id {
                     near_metrics::inc_counter(&metrics::RECEIVED_INFO_ABOUT_ITSELF);
                     debug!(target: "network", "Received info about itself. Disconnecting this peer.");
@@ -867,6 +1063,7 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for Peer {
                         chain_info: handshake.chain_info.clone(),
                         this_edge_info: self.edge_info.clone(),
                         other_edge_info: handshake.edge_info.clone(),
+                        encrypted,
                     })
                     .into_actor(self)
                     .then(move |res, act, ctx| {
@@ -12,6 +12,7 @@ use actix::{
     Actor, ActorFuture, Addr, Arbiter, AsyncContext, Context, ContextFutureSpawner, Handler,
     Recipient, Running, StreamHandler, SyncArbiter, SyncContext, WrapFuture,
 };
+use cached::{Cached, SizedCache};
 use chrono::Utc;
 use futures::task::Poll;
 use futures::{future, Stream, StreamExt};
@@ -23,8 +24,10 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::AccountId;
 use near_primitives::utils::from_timestamp;
+use near_primitives::views::{KnownPeerView, PeerConnectionView};
 use near_store::Store;
 
+use crate::capture::MessageCapture;
 use crate::codec::Codec;
 use crate::metrics;
 use crate::peer::Peer;
@@ -38,7 +41,7 @@ use crate::types::{
     NetworkViewClientResponses, OutboundTcpConnect, PeerIdOrHash, PeerList, PeerManagerRequest,
     PeerMessage, PeerRequest, PeerResponse, PeerType, PeersRequest, PeersResponse, Ping, Pong,
     QueryPeerStats, RawRoutedMessage, ReasonForBan, RoutedMessage, RoutedMessageBody,
-    RoutedMessageFrom, SendMessage, StateResponseInfo, SyncData, Unregister,
+    RoutedMessageFrom, ScoredRequest, SendMessage, StateResponseInfo, SyncData, Unregister,
 };
 use crate::types::{
     EdgeList, KnownPeerState, NetworkClientMessages, NetworkConfig, NetworkRequests,
@@ -76,6 +79,8 @@ const EXPONENTIAL_BACKOFF_LIMIT: u64 = 91;
 const WAIT_BEFORE_PING: u64 = 20_000;
 /// Limit number of pending Peer actors to avoid OOM.
 const LIMIT_PENDING_PEERS: usize = 60;
+/// Maximum number of scored requests waiting for a response.
+const MAX_SCORED_REQUESTS: usize = 1024;
 
 macro_rules! unwrap_or_error(($obj: expr, $error: expr) => (match $obj {
     Ok(result) => result,
@@ -147,11 +152,15 @@ pub struct PeerManagerActor {
     /// Store all collected metrics from a node.
     #[cfg(feature = "metric_recorder")]
     metric_recorder: MetricRecorder,
+    /// Capture of the messages exchanged with peers, if enabled.
+    capture: Option<MessageCapture>,
     edge_verifier_pool: Addr<EdgeVerifier>,
     txns_since_last_block: Arc<AtomicUsize>,
     pending_incoming_connections_counter: Arc<AtomicUsize>,
     peer_counter: Arc<AtomicUsize>,
     scheduled_routing_table_update: bool,
+    /// Scored requests we sent, by the peer they were sent to, waiting for a response.
+    scored_requests: SizedCache<(PeerId, ScoredRequest), ()>,
 }
 
 impl PeerManagerActor {
@@ -179,6 +188,8 @@ impl PeerManagerActor {
 
         let txns_since_last_block = Arc::new(AtomicUsize::new(0));
 
+        let capture = config.capture.as_ref().map(MessageCapture::open).transpose()?;
+
         Ok(PeerManagerActor {
             peer_id: me,
             config,
@@ -195,10 +206,12 @@ impl PeerManagerActor {
             edge_verifier_pool,
             #[cfg(feature = "metric_recorder")]
             metric_recorder,
+            capture,
             txns_since_last_block,
             pending_incoming_connections_counter: Arc::new(AtomicUsize::new(0)),
             peer_counter: Arc::new(AtomicUsize::new(0)),
             scheduled_routing_table_update: false,
+            scored_requests: SizedCache::with_size(MAX_SCORED_REQUESTS),
         })
     }
 
@@ -410,6 +423,7 @@ impl PeerManagerActor {
         let peer_id = self.peer_id.clone();
         let account_id = self.config.account_id.clone();
         let server_addr = self.config.addr;
+        let secret_key = self.config.secret_key.clone();
         let handshake_timeout = self.config.handshake_timeout;
         let client_addr = self.client_addr.clone();
         let view_client_addr = self.view_client_addr.clone();
@@ -435,6 +449,7 @@ impl PeerManagerActor {
 
         let network_metrics = self.network_metrics.clone();
         let txns_since_last_block = Arc::clone(&self.txns_since_last_block);
+        let capture = self.capture.clone();
 
         // Start every peer actor on separate thread.
         let arbiter = Arbiter::new();
@@ -464,6 +479,7 @@ impl PeerManagerActor {
                 peer_info,
                 peer_type,
                 FramedWrite::new(write, Codec::new(), ctx),
+                secret_key,
                 handshake_timeout,
                 recipient,
                 client_addr,
@@ -472,6 +488,7 @@ impl PeerManagerActor {
                 network_metrics,
                 txns_since_last_block,
                 peer_counter,
+                capture,
             )
         });
     }
@@ -483,6 +500,29 @@ impl PeerManagerActor {
             .count()
     }
 
+    /// Known peers with the traffic of the connected ones, for the node operator.
+    fn known_peers(&self) -> Vec<KnownPeerView> {
+        let now = Utc::now();
+        self.peer_store
+            .iter()
+            .map(|(peer_id, peer_state)| KnownPeerView {
+                peer_id: peer_id.clone(),
+                addr: peer_state.peer_info.addr,
+                account_id: peer_state.peer_info.account_id.clone(),
+                status: (&peer_state.status).into(),
+                score: peer_state.score_at(now),
+                connection: self.active_peers.get(peer_id).map(|active_peer| PeerConnectionView {
+                    outbound: active_peer.peer_type == PeerType::Outbound,
+                    height: active_peer.full_peer_info.chain_info.height,
+                    archival: active_peer.full_peer_info.chain_info.archival,
+                    sent_bytes_per_sec: active_peer.sent_bytes_per_sec,
+                    received_bytes_per_sec: active_peer.received_bytes_per_sec,
+                    connected_secs: active_peer.connection_established_time.elapsed().as_secs(),
+                }),
+            })
+            .collect()
+    }
+
     fn num_archival_peers(&self) -> usize {
         self.active_peers
             .values()
@@ -544,9 +584,15 @@ impl PeerManagerActor {
     }
 
     /// Get a random peer we are not connected to from the known list.
+    /// Peers with higher score are more likely to be chosen.
     fn sample_random_peer(&self, ignore_fn: impl Fn(&KnownPeerState) -> bool) -> Option<PeerInfo> {
         let unconnected_peers = self.peer_store.unconnected_peers(ignore_fn);
-        unconnected_peers.choose(&mut rand::thread_rng()).cloned()
+        unconnected_peers
+            .choose_weighted(&mut rand::thread_rng(), |peer_info| {
+                self.peer_store.selection_weight(&peer_info.id)
+            })
+            .ok()
+            .cloned()
     }
 
     /// Query current peers for more peers.
@@ -721,12 +767,14 @@ impl PeerManagerActor {
 
     /// Select one peer and send signal to stop connection to it gracefully.
     /// Selection process:
-    ///     Create a safe set of peers, and among the remaining peers select one at random.
+    ///     Create a safe set of peers, and among the remaining peers select the one with the lowest
+    ///     score, breaking ties at random.
     ///     If the number of outbound connections is less or equal than minimum_outbound_connections,
     ///         add all outbound connections to the safe set.
     ///     While the length of the safe set is less than safe_set_size:
     ///         Among all the peers we have received a message within the last peer_recent_time_window,
-    ///             find the one we connected earlier and add it to the safe set.
+    ///             find the one with the highest score, or the one we connected earlier among
+    ///             peers with equal score, and add it to the safe set.
     ///         else break
     fn try_stop_active_connection(&self) {
         debug!(target: "network", "Trying to stop an active connection. Number of active connections: {}", self.active_peers.len());
@@ -769,10 +817,16 @@ impl PeerManagerActor {
             })
             .collect::<Vec<_>>();
 
-        // Sort by established time
-        recent_connections.sort_by(|(_, established_time_a), (_, established_time_b)| {
-            established_time_a.cmp(established_time_b)
-        });
+        // Sort by score and then by established time
+        recent_connections.sort_by(
+            |(peer_id_a, established_time_a), (peer_id_b, established_time_b)| {
+                self.peer_store
+                    .score(peer_id_b)
+                    .partial_cmp(&self.peer_store.score(peer_id_a))
+                    .unwrap_or(cmp::Ordering::Equal)
+                    .then_with(|| established_time_a.cmp(established_time_b))
+            },
+        );
 
         // Take remaining peers
         for (peer_id, _) in recent_connections
@@ -783,7 +837,7 @@ impl PeerManagerActor {
         }
 
         // Build valid candidate list to choose the peer to be removed. All peers outside the safe set.
-        let candidates = self
+        let mut candidates = self
             .active_peers
             .keys()
             .filter_map(
@@ -797,7 +851,14 @@ impl PeerManagerActor {
             )
             .collect::<Vec<_>>();
 
-        if let Some(peer_id) = candidates.choose(&mut rand::thread_rng()) {
+        candidates.shuffle(&mut rand::thread_rng());
+        if let Some((_, peer_id)) = candidates
+            .into_iter()
+            .map(|peer_id| (self.peer_store.score(&peer_id), peer_id))
+            .min_by(|(score_a, _), (score_b, _)| {
+                score_a.partial_cmp(score_b).unwrap_or(cmp::Ordering::Equal)
+            })
+        {
             if let Some(active_peer) = self.active_peers.get(&peer_id) {
                 debug!(target: "network", "Stop active connection: {:?}", peer_id);
                 active_peer.addr.do_send(PeerManagerRequest::UnregisterPeer);
@@ -859,6 +920,7 @@ impl PeerManagerActor {
             self.peer_store.remove_expired(&self.config),
             "Failed to remove expired peers"
         );
+        unwrap_or_error!(self.peer_store.save_scores(), "Failed to save peer scores");
 
         // Reschedule the bootstrap peer task, starting of as quick as possible with exponential backoff.
         let wait = if self.monitor_peers_attempts >= EXPONENTIAL_BACKOFF_LIMIT {
@@ -988,6 +1050,13 @@ impl PeerManagerActor {
                     trace!(target: "network", "initiate route back {:?}", msg);
                     self.routing_table.add_route_back(msg.hash(), self.peer_id.clone());
                 }
+                if let (PeerIdOrHash::PeerId(target), Some(request)) =
+                    (&msg.target, ScoredRequest::from_request(&msg.body))
+                {
+                    if msg.author == self.peer_id {
+                        self.scored_requests.cache_set((target.clone(), request), ());
+                    }
+                }
 
                 self.send_message(ctx, peer_id, PeerMessage::Routed(msg))
             }
@@ -1308,9 +1377,48 @@ impl Handler<NetworkRequests> for PeerManagerActor {
                 }
             }
             NetworkRequests::BanPeer { peer_id, ban_reason } => {
+                if !self.peer_store.contains(&peer_id) {
+                    return NetworkResponses::UnknownPeer;
+                }
                 self.try_ban_peer(ctx, &peer_id, ban_reason);
                 NetworkResponses::NoResponse
             }
+            NetworkRequests::UpdatePeerScore { peer_id, event } => {
+                self.peer_store.update_score(&peer_id, event);
+                NetworkResponses::NoResponse
+            }
+            NetworkRequests::UnbanPeer { peer_id } => {
+                if !self.peer_store.contains(&peer_id) {
+                    return NetworkResponses::UnknownPeer;
+                }
+                if self.peer_store.is_banned(&peer_id) {
+                    info!(target: "network", "Unbanning peer {:?}", peer_id);
+                    unwrap_or_error!(
+                        self.peer_store.peer_unban(&peer_id),
+                        "Failed to unban a peer"
+                    );
+                }
+                NetworkResponses::NoResponse
+            }
+            NetworkRequests::ConnectPeer { peer_info } => {
+                if !self.active_peers.contains_key(&peer_info.id)
+                    && !self.outgoing_peers.contains(&peer_info.id)
+                {
+                    self.outgoing_peers.insert(peer_info.id.clone());
+                    ctx.notify(OutboundTcpConnect { peer_info });
+                }
+                NetworkResponses::NoResponse
+            }
+            NetworkRequests::DisconnectPeer { peer_id } => {
+                if let Some(active_peer) = self.active_peers.get(&peer_id) {
+                    debug!(target: "network", "Stop active connection: {:?}", peer_id);
+                    active_peer.addr.do_send(PeerManagerRequest::UnregisterPeer);
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::UnknownPeer
+                }
+            }
+            NetworkRequests::FetchKnownPeers => NetworkResponses::KnownPeers(self.known_peers()),
             NetworkRequests::AnnounceAccount(announce_account) => {
                 self.announce_account(ctx, announce_account);
                 NetworkResponses::NoResponse
@@ -1725,6 +1833,15 @@ impl Handler<Consolidate> for PeerManagerActor {
             }
         }
 
+        // Peers that connected over an encrypted session before must keep doing so, otherwise the
+        // encryption could be stripped by answering the session handshake in plaintext.
+        if !msg.encrypted
+            && (self.config.require_encryption || self.peer_store.is_encrypted(&msg.peer_info.id))
+        {
+            debug!(target: "network", "Dropping plaintext connection from peer: {:?}", msg.peer_info.id);
+            return ConsolidateResponse::Reject;
+        }
+
         if msg.peer_type == PeerType::Inbound && !self.is_inbound_allowed() {
             // TODO(1896): Gracefully drop inbound connection for other peer.
             debug!(target: "network", "Inbound connection dropped (network at max capacity).");
@@ -1759,6 +1876,7 @@ impl Handler<Consolidate> for PeerManagerActor {
 
         let edge_info_response = if require_response { Some(edge_info.clone()) } else { None };
 
+        let peer_id = msg.peer_info.id.clone();
         // TODO: double check that address is connectable and add account id.
         self.register_peer(
             FullPeerInfo {
@@ -1771,6 +1889,11 @@ impl Handler<Consolidate> for PeerManagerActor {
             msg.actor,
             ctx,
         );
+        if msg.encrypted {
+            if let Err(err) = self.peer_store.peer_encrypted(&peer_id) {
+                error!(target: "network", "Failed to save peer data: {}", err);
+            }
+        }
 
         return ConsolidateResponse::Accept(edge_info_response);
     }
@@ -1912,6 +2035,18 @@ impl Handler<PeerRequest> for PeerManagerActor {
                 }
                 PeerResponse::NoResponse
             }
+            PeerRequest::UpdatePeerScore(peer_id, event) => {
+                self.peer_store.update_score(&peer_id, event);
+                PeerResponse::NoResponse
+            }
+            PeerRequest::ScoredResponse(peer_id, request) => {
+                let event = request.delivered_event();
+                // Unsolicited responses are not credited.
+                if self.scored_requests.cache_remove(&(peer_id.clone(), request)).is_some() {
+                    self.peer_store.update_score(&peer_id, event);
+                }
+                PeerResponse::NoResponse
+            }
         }
     }
 }
@@ -1,22 +1,29 @@
+use std::cmp::Ordering;
 use std::collections::{
     hash_map::{Entry, Iter},
-    HashMap,
+    HashMap, HashSet,
 };
 use std::convert::TryInto;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use borsh::BorshSerialize;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use log::{debug, error};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 
 use near_primitives::network::PeerId;
 use near_primitives::utils::to_timestamp;
 use near_store::{ColPeers, Store};
 
-use crate::types::{KnownPeerState, KnownPeerStatus, NetworkConfig, PeerInfo, ReasonForBan};
+use crate::types::{
+    KnownPeerState, KnownPeerStatus, NetworkConfig, PeerInfo, PeerScoreEvent, ReasonForBan,
+    MAX_PEER_SCORE,
+};
+
+/// How often the decay of all nonzero scores is saved, so the stored scores eventually reach zero.
+const SCORE_DECAY_FLUSH_PERIOD: Duration = Duration::from_secs(60 * 60);
 
 /// Level of trust we have about a new (PeerId, Addr) pair.
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -52,6 +59,10 @@ pub struct PeerStore {
     // It can happens that some peers don't have known address, so
     // they will not be present in this list, otherwise they will be present.
     addr_peers: HashMap<SocketAddr, VerifiedPeer>,
+    /// Peers whose score changed since it was last saved.
+    dirty_scores: HashSet<PeerId>,
+    /// Last time the decay of all scores was saved.
+    last_decay_flush: DateTime<Utc>,
 }
 
 impl PeerStore {
@@ -99,6 +110,9 @@ impl PeerStore {
                 if peer_state.status.is_banned() {
                     current_peer_state.status = peer_state.status;
                 }
+                current_peer_state.encrypted = peer_state.encrypted;
+                current_peer_state.score = peer_state.score;
+                current_peer_state.score_updated = peer_state.score_updated;
                 continue;
             }
 
@@ -109,7 +123,13 @@ impl PeerStore {
                 }
             }
         }
-        Ok(PeerStore { store, peer_states, addr_peers })
+        Ok(PeerStore {
+            store,
+            peer_states,
+            addr_peers,
+            dirty_scores: HashSet::default(),
+            last_decay_flush: Utc::now(),
+        })
     }
 
     pub fn len(&self) -> usize {
@@ -120,6 +140,10 @@ impl PeerStore {
         self.peer_states.is_empty()
     }
 
+    pub fn contains(&self, peer_id: &PeerId) -> bool {
+        self.peer_states.contains_key(peer_id)
+    }
+
     pub fn is_banned(&self, peer_id: &PeerId) -> bool {
         self.peer_states
             .get(&peer_id)
@@ -181,20 +205,103 @@ impl PeerStore {
         }
     }
 
+    /// Whether the peer was connected over an encrypted session before.
+    pub fn is_encrypted(&self, peer_id: &PeerId) -> bool {
+        self.peer_states.get(peer_id).map_or(false, |known_peer_state| known_peer_state.encrypted)
+    }
+
+    /// Records that the peer is connected over an encrypted session, plaintext connections with
+    /// it are refused from now on.
+    pub fn peer_encrypted(&mut self, peer_id: &PeerId) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(peer_state) = self.peer_states.get_mut(peer_id) {
+            if peer_state.encrypted {
+                return Ok(());
+            }
+            peer_state.encrypted = true;
+            let mut store_update = self.store.store_update();
+            store_update.set_ser(ColPeers, &peer_id.try_to_vec()?, peer_state)?;
+            store_update.commit().map_err(|err| err.into())
+        } else {
+            Err(format!("Peer {} is missing in the peer store", peer_id).into())
+        }
+    }
+
+    /// Applies `event` to the score of a known peer. Scores of unknown peers are not tracked.
+    /// The new score is saved to the storage by `save_scores`.
+    pub fn update_score(&mut self, peer_id: &PeerId, event: PeerScoreEvent) {
+        if let Some(peer_state) = self.peer_states.get_mut(peer_id) {
+            peer_state.update_score(event, Utc::now());
+            self.dirty_scores.insert(peer_id.clone());
+        }
+    }
+
+    /// Current score of the peer, zero for unknown peers.
+    pub fn score(&self, peer_id: &PeerId) -> f64 {
+        self.peer_states.get(peer_id).map_or(0.0, |peer_state| peer_state.score_at(Utc::now()))
+    }
+
+    /// Weight of the peer when choosing peers at random, from 1 for the worst possible score
+    /// to `2 * MAX_PEER_SCORE + 1` for the best one.
+    pub fn selection_weight(&self, peer_id: &PeerId) -> f64 {
+        self.score(peer_id) + MAX_PEER_SCORE + 1.0
+    }
+
+    /// Saves the scores changed since the last call. Once every `SCORE_DECAY_FLUSH_PERIOD` the
+    /// decay of all nonzero scores is saved as well.
+    pub fn save_scores(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Utc::now();
+        let flush_decay =
+            (now - self.last_decay_flush).to_std().unwrap_or_default() >= SCORE_DECAY_FLUSH_PERIOD;
+        if !flush_decay && self.dirty_scores.is_empty() {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        if flush_decay {
+            for (peer_id, peer_state) in self.peer_states.iter_mut() {
+                if peer_state.score != 0.0 || self.dirty_scores.contains(peer_id) {
+                    peer_state.decay_score(now);
+                    store_update.set_ser(ColPeers, &peer_id.try_to_vec()?, peer_state)?;
+                }
+            }
+            self.last_decay_flush = now;
+        } else {
+            for peer_id in self.dirty_scores.iter() {
+                if let Some(peer_state) = self.peer_states.get_mut(peer_id) {
+                    peer_state.decay_score(now);
+                    store_update.set_ser(ColPeers, &peer_id.try_to_vec()?, peer_state)?;
+                }
+            }
+        }
+        self.dirty_scores.clear();
+        store_update.commit().map_err(|err| err.into())
+    }
+
+    /// Returns up to `count` peers passing the filter, sampled at random without replacement
+    /// with the probability given by `selection_weight`, so low score peers still get picked
+    /// sometimes. If `count` is zero returns all of them in no particular order.
     fn find_peers<F>(&self, mut filter: F, count: u32) -> Vec<PeerInfo>
     where
         F: FnMut(&KnownPeerState) -> bool,
     {
-        let mut peers = self
-            .peer_states
-            .values()
-            .filter_map(|p| if filter(p) { Some(p.peer_info.clone()) } else { None })
-            .collect::<Vec<_>>();
-        if count == 0 {
-            return peers;
+        let mut peers = self.peer_states.values().filter(|p| filter(p)).collect::<Vec<_>>();
+        if count != 0 && peers.len() > count as usize {
+            // Weighted sampling by keeping the peers with the largest `u ^ (1 / weight)`
+            // for `u` uniform in [0, 1).
+            let mut rng = thread_rng();
+            let mut keyed_peers = peers
+                .into_iter()
+                .map(|p| {
+                    let key = rng.gen::<f64>().powf(1.0 / self.selection_weight(&p.peer_info.id));
+                    (key, p)
+                })
+                .collect::<Vec<_>>();
+            keyed_peers.sort_by(|(key_a, _), (key_b, _)| {
+                key_b.partial_cmp(key_a).unwrap_or(Ordering::Equal)
+            });
+            keyed_peers.truncate(count as usize);
+            peers = keyed_peers.into_iter().map(|(_, p)| p).collect();
         }
-        peers.shuffle(&mut thread_rng());
-        peers.iter().take(count as usize).cloned().collect::<Vec<_>>()
+        peers.into_iter().map(|p| p.peer_info.clone()).collect()
     }
 
     /// Return unconnected or peers with unknown status that we can try to connect to.
@@ -210,7 +317,8 @@ impl PeerStore {
         )
     }
 
-    /// Return healthy known peers up to given amount.
+    /// Return healthy known peers up to given amount, peers with higher score are more likely to
+    /// be returned.
     pub fn healthy_peers(&self, max_count: u32) -> Vec<PeerInfo> {
         self.find_peers(
             |p| match p.status {
@@ -378,6 +486,7 @@ mod test {
     use near_store::test_utils::create_test_store;
 
     use super::*;
+    use crate::types::PEER_SCORE_HALF_LIFE;
 
     fn get_peer_id(seed: String) -> PeerId {
         SecretKey::from_seed(KeyType::ED25519, seed.as_str()).public_key().into()
@@ -419,6 +528,124 @@ mod test {
         }
     }
 
+    #[test]
+    fn encrypted_store() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_store_encrypted").tempdir().unwrap();
+        let encrypted_peer = gen_peer_info(0);
+        let plaintext_peer = gen_peer_info(1);
+        let boot_nodes = vec![encrypted_peer.clone(), plaintext_peer.clone()];
+        {
+            let store = create_store(tmp_dir.path().to_str().unwrap());
+            let mut peer_store = PeerStore::new(store, &boot_nodes).unwrap();
+            peer_store.peer_connected(&encrypted_peer).unwrap();
+            peer_store.peer_encrypted(&encrypted_peer.id).unwrap();
+            peer_store.peer_connected(&plaintext_peer).unwrap();
+            assert!(peer_store.is_encrypted(&encrypted_peer.id));
+            assert!(!peer_store.is_encrypted(&plaintext_peer.id));
+            assert!(peer_store.peer_encrypted(&gen_peer_info(2).id).is_err());
+        }
+        {
+            let store_new = create_store(tmp_dir.path().to_str().unwrap());
+            let peer_store_new = PeerStore::new(store_new, &boot_nodes).unwrap();
+            assert!(peer_store_new.is_encrypted(&encrypted_peer.id));
+            assert!(!peer_store_new.is_encrypted(&plaintext_peer.id));
+        }
+    }
+
+    #[test]
+    fn score_store() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_store_score").tempdir().unwrap();
+        let good_peer = gen_peer_info(0);
+        let bad_peer = gen_peer_info(1);
+        let boot_nodes = vec![bad_peer.clone(), good_peer.clone()];
+        {
+            let store = create_store(tmp_dir.path().to_str().unwrap());
+            let mut peer_store = PeerStore::new(store, &boot_nodes).unwrap();
+            peer_store.update_score(&good_peer.id, PeerScoreEvent::DeliveredBlock);
+            peer_store.update_score(&bad_peer.id, PeerScoreEvent::InvalidHeader);
+            assert!(peer_store.score(&good_peer.id) > 0.0);
+            assert!(peer_store.score(&bad_peer.id) < 0.0);
+            assert!(
+                peer_store.selection_weight(&good_peer.id)
+                    > peer_store.selection_weight(&bad_peer.id)
+            );
+            peer_store.save_scores().unwrap();
+        }
+        {
+            let store_new = create_store(tmp_dir.path().to_str().unwrap());
+            let peer_store_new = PeerStore::new(store_new, &boot_nodes).unwrap();
+            assert!(peer_store_new.score(&good_peer.id) > 0.0);
+            assert!(peer_store_new.score(&bad_peer.id) < 0.0);
+        }
+    }
+
+    #[test]
+    fn healthy_peers_sampled_by_score() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_store_sample").tempdir().unwrap();
+        let good_peer = gen_peer_info(0);
+        let bad_peer = gen_peer_info(1);
+        let boot_nodes = vec![bad_peer.clone(), good_peer.clone()];
+        let store = create_store(tmp_dir.path().to_str().unwrap());
+        let mut peer_store = PeerStore::new(store, &boot_nodes).unwrap();
+        for _ in 0..100 {
+            peer_store.update_score(&good_peer.id, PeerScoreEvent::DeliveredBlock);
+            peer_store.update_score(&bad_peer.id, PeerScoreEvent::InvalidHeader);
+        }
+        assert_eq!(peer_store.healthy_peers(2).len(), 2);
+        // The good peer is 200 times more likely to be picked, yet the bad one isn't excluded.
+        let good_peer_picks =
+            (0..1000).filter(|_| peer_store.healthy_peers(1) == vec![good_peer.clone()]).count();
+        assert!(good_peer_picks > 900);
+    }
+
+    #[test]
+    fn score_save_dirty_only() {
+        let store = create_test_store();
+        let good_peer = gen_peer_info(0);
+        let bad_peer = gen_peer_info(1);
+        let boot_nodes = vec![bad_peer.clone(), good_peer.clone()];
+        let mut peer_store = PeerStore::new(store.clone(), &boot_nodes).unwrap();
+        let key = |peer: &PeerInfo| peer.id.try_to_vec().unwrap();
+        let saved_score = |peer: &PeerInfo| {
+            store.get_ser::<KnownPeerState>(ColPeers, &key(peer)).unwrap().map(|state| state.score)
+        };
+        peer_store.update_score(&good_peer.id, PeerScoreEvent::DeliveredBlock);
+        peer_store.update_score(&bad_peer.id, PeerScoreEvent::InvalidHeader);
+        peer_store.save_scores().unwrap();
+        assert!(saved_score(&good_peer).unwrap() > 0.0);
+        assert!(saved_score(&bad_peer).unwrap() < 0.0);
+
+        // Only the peer whose score changed is written again.
+        let mut store_update = store.store_update();
+        store_update.delete(ColPeers, &key(&bad_peer));
+        store_update.commit().unwrap();
+        peer_store.update_score(&good_peer.id, PeerScoreEvent::DeliveredBlock);
+        peer_store.save_scores().unwrap();
+        assert_eq!(saved_score(&bad_peer), None);
+
+        // The decay of all scores is flushed once in a while.
+        peer_store.last_decay_flush =
+            Utc::now() - chrono::Duration::from_std(SCORE_DECAY_FLUSH_PERIOD).unwrap();
+        peer_store.save_scores().unwrap();
+        assert!(saved_score(&bad_peer).unwrap() < 0.0);
+    }
+
+    #[test]
+    fn score_decay() {
+        let now = Utc::now();
+        let half_life = chrono::Duration::from_std(PEER_SCORE_HALF_LIFE).unwrap();
+        let mut peer_state = KnownPeerState::new(gen_peer_info(0));
+        peer_state.update_score(PeerScoreEvent::Timeout, now);
+        assert_eq!(peer_state.score_at(now), -2.0);
+        assert_eq!(peer_state.score_at(now + half_life), -1.0);
+        peer_state.decay_score(now + half_life * 20);
+        assert_eq!(peer_state.score, 0.0);
+        for _ in 0..20 {
+            peer_state.update_score(PeerScoreEvent::InvalidHeader, now);
+        }
+        assert_eq!(peer_state.score, -MAX_PEER_SCORE);
+    }
+
     fn check_exist(
         peer_store: &PeerStore,
         peer_id: &PeerId,
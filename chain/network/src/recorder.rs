@@ -17,12 +17,15 @@ pub enum Status {
 struct CountSize {
     count: usize,
     bytes: usize,
+    /// Bytes on the wire, smaller than `bytes` when messages are compressed.
+    compressed_bytes: usize,
 }
 
 impl CountSize {
-    fn update(&mut self, bytes: usize) {
+    fn update(&mut self, bytes: usize, compressed_bytes: usize) {
         self.count += 1;
         self.bytes += bytes;
+        self.compressed_bytes += compressed_bytes;
     }
 }
 
@@ -125,22 +128,23 @@ impl MetricRecorder {
     }
 
     pub fn handle_peer_message(&mut self, peer_message_metadata: PeerMessageMetadata) {
-        self.overall
-            .get(peer_message_metadata.status.unwrap())
-            .update(peer_message_metadata.size.unwrap());
+        let size = peer_message_metadata.size.unwrap();
+        let compressed_size = peer_message_metadata.compressed_size.unwrap_or(size);
+
+        self.overall.get(peer_message_metadata.status.unwrap()).update(size, compressed_size);
 
         self.per_type
             .entry(peer_message_metadata.message_type.clone())
             .or_insert(SentReceived::default())
             .get(peer_message_metadata.status.unwrap())
-            .update(peer_message_metadata.size.unwrap());
+            .update(size, compressed_size);
 
         if let Some(peer) = peer_message_metadata.other_peer() {
             self.per_peer
                 .entry(peer)
                 .or_insert(SentReceived::default())
                 .get(peer_message_metadata.status.unwrap())
-                .update(peer_message_metadata.size.unwrap());
+                .update(size, compressed_size);
         }
 
         match peer_message_metadata.message_type.as_str() {
@@ -168,6 +172,7 @@ pub struct PeerMessageMetadata {
     status: Option<Status>,
     message_type: String,
     size: Option<usize>,
+    compressed_size: Option<usize>,
     hash: Option<CryptoHash>,
 }
 
@@ -192,6 +197,11 @@ impl PeerMessageMetadata {
         self
     }
 
+    pub fn set_compressed_size(mut self, compressed_size: usize) -> Self {
+        self.compressed_size = Some(compressed_size);
+        self
+    }
+
     fn other_peer(&self) -> Option<PeerId> {
         match self.status {
             Some(Status::Received) => self.source.clone(),
@@ -215,6 +225,7 @@ impl From<&PeerMessage> for PeerMessageMetadata {
             status: None,
             message_type: msg.to_string(),
             size: None,
+            compressed_size: None,
             hash,
         }
     }
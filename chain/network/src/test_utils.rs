@@ -79,6 +79,8 @@ impl NetworkConfig {
             blacklist: HashMap::new(),
             outbound_disabled: false,
             archive: false,
+            capture: None,
+            require_encryption: false,
         }
     }
 }
@@ -34,8 +34,12 @@ use near_primitives::utils::{from_timestamp, to_timestamp};
 use near_primitives::version::{
     ProtocolVersion, OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
-use near_primitives::views::{FinalExecutionOutcomeView, QueryRequest, QueryResponse};
+use near_primitives::views::{
+    FinalExecutionOutcomeView, KnownPeerStatusView, KnownPeerView, QueryRequest, QueryResponse,
+};
 
+use crate::capture::CaptureConfig;
+use crate::codec::{is_compression_version, SUPPORTED_COMPRESSION};
 use crate::peer::Peer;
 #[cfg(feature = "metric_recorder")]
 use crate::recorder::MetricRecorder;
@@ -55,6 +59,12 @@ pub const ROUTED_MESSAGE_TTL: u8 = 100;
 /// but wait some "small" timeout between updates to avoid a lot of messages between
 /// Peer and PeerManager.
 pub const UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE: Duration = Duration::from_secs(60);
+/// Score of a peer is kept within `[-MAX_PEER_SCORE, MAX_PEER_SCORE]`.
+pub const MAX_PEER_SCORE: f64 = 100.0;
+/// Time it takes for the score of a peer to decay to half of its value.
+pub const PEER_SCORE_HALF_LIFE: Duration = Duration::from_secs(60 * 60);
+/// Scores closer to zero than this are rounded to zero when they decay.
+const MIN_PEER_SCORE: f64 = 0.01;
 
 /// Peer information.
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -200,7 +210,13 @@ impl fmt::Display for HandshakeFailureReason {
 
 impl std::error::Error for HandshakeFailureReason {}
 
-#[derive(BorshSerialize, Serialize, PartialEq, Eq, Clone, Debug)]
+/// Algorithm to compress frames with.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Compression {
+    Lz4,
+}
+
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
 pub struct Handshake {
     pub version: u32,
     /// Oldest supported protocol version.
@@ -215,6 +231,9 @@ pub struct Handshake {
     pub chain_info: PeerChainInfoV2,
     /// Info for new edge.
     pub edge_info: EdgeInfo,
+    /// Compression algorithms the sender decompresses, in order of preference.
+    /// Only sent from the version of `ProtocolFeature::PeerCompression`.
+    pub compression: Vec<Compression>,
 }
 
 /// Struct describing the layout for Handshake.
@@ -256,7 +275,25 @@ impl Handshake {
             listen_port,
             chain_info,
             edge_info,
+            compression: SUPPORTED_COMPRESSION.to_vec(),
+        }
+    }
+}
+
+// Use custom serializer for Handshake. Fields added by newer versions are only written for them.
+impl BorshSerialize for Handshake {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.version.serialize(writer)?;
+        self.oldest_supported_version.serialize(writer)?;
+        self.peer_id.serialize(writer)?;
+        self.target_peer_id.serialize(writer)?;
+        self.listen_port.serialize(writer)?;
+        self.chain_info.serialize(writer)?;
+        self.edge_info.serialize(writer)?;
+        if is_compression_version(self.version) {
+            self.compression.serialize(writer)?;
         }
+        Ok(())
     }
 }
 
@@ -276,7 +313,11 @@ impl BorshDeserialize for Handshake {
 
         if OLDEST_BACKWARD_COMPATIBLE_PROTOCOL_VERSION <= version && version <= PROTOCOL_VERSION {
             // If we support this version, then try to deserialize with custom deserializer
-            HandshakeAutoDes::deserialize(buf).map(Into::into)
+            let mut handshake: Handshake = HandshakeAutoDes::deserialize(buf)?.into();
+            if is_compression_version(version) {
+                handshake.compression = BorshDeserialize::deserialize(buf)?;
+            }
+            Ok(handshake)
         } else {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -299,6 +340,7 @@ impl From<HandshakeAutoDes> for Handshake {
             listen_port: handshake.listen_port,
             chain_info: handshake.chain_info,
             edge_info: handshake.edge_info,
+            compression: vec![],
         }
     }
 }
@@ -405,6 +447,7 @@ impl From<HandshakeV2> for Handshake {
             listen_port: handshake.listen_port,
             chain_info: handshake.chain_info.into(),
             edge_info: handshake.edge_info,
+            compression: vec![],
         }
     }
 }
@@ -887,6 +930,10 @@ pub struct NetworkConfig {
     pub outbound_disabled: bool,
     /// Not clear old data, set `true` for archive nodes.
     pub archive: bool,
+    /// Capture the messages exchanged with peers to a file.
+    pub capture: Option<CaptureConfig>,
+    /// Refuse connections with peers that don't encrypt the transport.
+    pub require_encryption: bool,
 }
 
 impl NetworkConfig {
@@ -972,6 +1019,121 @@ impl KnownPeerStatus {
     }
 }
 
+impl From<&KnownPeerStatus> for KnownPeerStatusView {
+    fn from(status: &KnownPeerStatus) -> Self {
+        match status {
+            KnownPeerStatus::Unknown => KnownPeerStatusView::Unknown,
+            KnownPeerStatus::NotConnected => KnownPeerStatusView::NotConnected,
+            KnownPeerStatus::Connected => KnownPeerStatusView::Connected,
+            KnownPeerStatus::Banned(reason, banned_at) => KnownPeerStatusView::Banned {
+                reason: format!("{:?}", reason),
+                banned_at: *banned_at,
+            },
+        }
+    }
+}
+
+/// Events observed from a peer which change its score.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum PeerScoreEvent {
+    /// Peer sent a block we requested from it.
+    DeliveredBlock,
+    /// Peer sent parts of a chunk we requested from it.
+    DeliveredChunkPart,
+    /// Peer sent a state header or a state part we requested from it.
+    DeliveredStatePart,
+    /// Peer didn't answer in time.
+    Timeout,
+    /// Peer sent an invalid block header.
+    InvalidHeader,
+    /// Peer sent a message it already sent before.
+    DuplicateMessage,
+}
+
+impl PeerScoreEvent {
+    /// Change of the score caused by the event.
+    pub fn weight(&self) -> f64 {
+        match self {
+            PeerScoreEvent::DeliveredBlock => 1.0,
+            PeerScoreEvent::DeliveredChunkPart => 0.5,
+            PeerScoreEvent::DeliveredStatePart => 1.0,
+            PeerScoreEvent::Timeout => -2.0,
+            PeerScoreEvent::InvalidHeader => -10.0,
+            PeerScoreEvent::DuplicateMessage => -0.5,
+        }
+    }
+}
+
+/// Routed request whose response earns score to the peer answering it. Responses are only
+/// credited if they match a request this node sent to their author.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ScoredRequest {
+    StateHeader(ShardId, CryptoHash),
+    StatePart(ShardId, CryptoHash, u64),
+    Chunk(ChunkHash),
+}
+
+impl ScoredRequest {
+    /// Request sent in `body`, if it is one whose response is scored.
+    pub fn from_request(body: &RoutedMessageBody) -> Option<Self> {
+        match body {
+            RoutedMessageBody::StateRequestHeader(shard_id, sync_hash) => {
+                Some(ScoredRequest::StateHeader(*shard_id, *sync_hash))
+            }
+            RoutedMessageBody::StateRequestPart(shard_id, sync_hash, part_id) => {
+                Some(ScoredRequest::StatePart(*shard_id, *sync_hash, *part_id))
+            }
+            RoutedMessageBody::PartialEncodedChunkRequest(request) => {
+                Some(ScoredRequest::Chunk(request.chunk_hash.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Request answered by `body`, if it is a scored response which carries any data.
+    pub fn from_response(body: &RoutedMessageBody) -> Option<Self> {
+        let (shard_id, sync_hash, header, part_id) = match body {
+            RoutedMessageBody::StateResponse(info)
+            | RoutedMessageBody::VersionedStateResponse(StateResponseInfo::V1(info)) => (
+                info.shard_id,
+                info.sync_hash,
+                info.state_response.header.is_some(),
+                info.state_response.part_id(),
+            ),
+            RoutedMessageBody::VersionedStateResponse(StateResponseInfo::V2(info)) => {
+                let header = match &info.state_response {
+                    ShardStateSyncResponse::V1(response) => response.header.is_some(),
+                    ShardStateSyncResponse::V2(response) => response.header.is_some(),
+                };
+                (info.shard_id, info.sync_hash, header, info.state_response.part_id())
+            }
+            RoutedMessageBody::PartialEncodedChunkResponse(response) => {
+                return if response.parts.is_empty() {
+                    None
+                } else {
+                    Some(ScoredRequest::Chunk(response.chunk_hash.clone()))
+                };
+            }
+            _ => return None,
+        };
+        match part_id {
+            Some(part_id) => Some(ScoredRequest::StatePart(shard_id, sync_hash, part_id)),
+            None if header => Some(ScoredRequest::StateHeader(shard_id, sync_hash)),
+            None => None,
+        }
+    }
+
+    /// Score event of delivering the response to this request.
+    pub fn delivered_event(&self) -> PeerScoreEvent {
+        match self {
+            ScoredRequest::StateHeader(_, _) | ScoredRequest::StatePart(_, _, _) => {
+                PeerScoreEvent::DeliveredStatePart
+            }
+            ScoredRequest::Chunk(_) => PeerScoreEvent::DeliveredChunkPart,
+        }
+    }
+}
+
 /// Information node stores about known peers.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone)]
 pub struct KnownPeerState {
@@ -979,6 +1141,12 @@ pub struct KnownPeerState {
     pub status: KnownPeerStatus,
     pub first_seen: u64,
     pub last_seen: u64,
+    /// Reputation of the peer built from `PeerScoreEvent`s, as of `score_updated`.
+    pub score: f64,
+    pub score_updated: u64,
+    /// Whether the peer was connected over an encrypted session. Plaintext connections with
+    /// such a peer are refused, so the encryption can't be stripped by a man in the middle.
+    pub encrypted: bool,
 }
 
 impl KnownPeerState {
@@ -988,6 +1156,9 @@ impl KnownPeerState {
             status: KnownPeerStatus::Unknown,
             first_seen: to_timestamp(Utc::now()),
             last_seen: to_timestamp(Utc::now()),
+            score: 0.0,
+            score_updated: to_timestamp(Utc::now()),
+            encrypted: false,
         }
     }
 
@@ -998,6 +1169,24 @@ impl KnownPeerState {
     pub fn last_seen(&self) -> DateTime<Utc> {
         from_timestamp(self.last_seen)
     }
+
+    /// Score of the peer at `now`, the score halves every `PEER_SCORE_HALF_LIFE`.
+    pub fn score_at(&self, now: DateTime<Utc>) -> f64 {
+        let elapsed = (now - from_timestamp(self.score_updated)).to_std().unwrap_or_default();
+        self.score * 0.5f64.powf(elapsed.as_secs_f64() / PEER_SCORE_HALF_LIFE.as_secs_f64())
+    }
+
+    /// Folds the decay up to `now` into `score`.
+    pub fn decay_score(&mut self, now: DateTime<Utc>) {
+        let score = self.score_at(now);
+        self.score = if score.abs() < MIN_PEER_SCORE { 0.0 } else { score };
+        self.score_updated = to_timestamp(now);
+    }
+
+    pub fn update_score(&mut self, event: PeerScoreEvent, now: DateTime<Utc>) {
+        self.decay_score(now);
+        self.score = (self.score + event.weight()).max(-MAX_PEER_SCORE).min(MAX_PEER_SCORE);
+    }
 }
 
 impl TryFrom<Vec<u8>> for KnownPeerState {
@@ -1050,6 +1239,8 @@ pub struct Consolidate {
     pub this_edge_info: Option<EdgeInfo>,
     // Edge information from other node.
     pub other_edge_info: EdgeInfo,
+    /// Whether the handshake was received over an encrypted session.
+    pub encrypted: bool,
 }
 
 impl Message for Consolidate {
@@ -1083,6 +1274,9 @@ pub enum PeerRequest {
     RouteBack(Box<RoutedMessageBody>, CryptoHash),
     UpdatePeerInfo(PeerInfo),
     ReceivedMessage(PeerId, Instant),
+    UpdatePeerScore(PeerId, PeerScoreEvent),
+    /// Response to a scored request received from its author.
+    ScoredResponse(PeerId, ScoredRequest),
 }
 
 impl Message for PeerRequest {
@@ -1135,6 +1329,10 @@ pub enum ReasonForBan {
     InvalidPeerId = 8,
     InvalidHash = 9,
     InvalidEdge = 10,
+    DecryptionFailed = 11,
+    DecompressionFailed = 12,
+    /// Banned by the node operator.
+    Manual = 13,
 }
 
 /// Banning signal sent from Peer instance to PeerManager
@@ -1191,6 +1389,25 @@ pub enum NetworkRequests {
         peer_id: PeerId,
         ban_reason: ReasonForBan,
     },
+    /// Update the score of given peer.
+    UpdatePeerScore {
+        peer_id: PeerId,
+        event: PeerScoreEvent,
+    },
+    /// Lift the ban of given peer. Responds with `UnknownPeer` if the peer is not known.
+    UnbanPeer {
+        peer_id: PeerId,
+    },
+    /// Open a connection to given peer.
+    ConnectPeer {
+        peer_info: PeerInfo,
+    },
+    /// Close the connection to given peer. Responds with `UnknownPeer` if it is not connected.
+    DisconnectPeer {
+        peer_id: PeerId,
+    },
+    /// Fetch the known peers with the traffic of the connected ones.
+    FetchKnownPeers,
     /// Announce account
     AnnounceAccount(AnnounceAccount),
 
@@ -1315,6 +1532,8 @@ pub enum NetworkResponses {
     BanPeer(ReasonForBan),
     EdgeUpdate(Box<Edge>),
     RouteNotFound,
+    UnknownPeer,
+    KnownPeers(Vec<KnownPeerView>),
 }
 
 impl<A, M> MessageResponse<A, M> for NetworkResponses
@@ -1442,6 +1661,8 @@ pub enum NetworkClientResponses {
     ValidTx,
     /// Invalid transaction inserted into mempool as response to Transaction.
     InvalidTx(InvalidTxError),
+    /// The transaction is valid, but the transaction pool has no room for it.
+    PoolRejected(String),
     /// The request is routed to other shards
     RequestRouted,
     /// The node being queried does not track the shard needed and therefore cannot provide userful
@@ -1688,6 +1909,8 @@ impl NetworkAdapter for NetworkRecipient {
 mod tests {
     use std::mem::size_of;
 
+    use near_primitives::syncing::ShardStateSyncResponseV2;
+
     use super::*;
 
     const ALLOWED_SIZE: usize = 1 << 20;
@@ -1753,4 +1976,54 @@ mod tests {
         assert_size!(QueryPeerStats);
         assert_size!(PartialEncodedChunkRequestMsg);
     }
+
+    #[test]
+    fn test_scored_request_matches_response() {
+        let sync_hash = hash(&[1]);
+        let state_response = |header, part| {
+            RoutedMessageBody::VersionedStateResponse(StateResponseInfo::V2(StateResponseInfoV2 {
+                shard_id: 0,
+                sync_hash,
+                state_response: ShardStateSyncResponse::V2(ShardStateSyncResponseV2 {
+                    header,
+                    part,
+                }),
+            }))
+        };
+        assert_eq!(
+            ScoredRequest::from_request(&RoutedMessageBody::StateRequestPart(0, sync_hash, 3)),
+            ScoredRequest::from_response(&state_response(None, Some((3, vec![])))),
+        );
+        assert_ne!(
+            ScoredRequest::from_request(&RoutedMessageBody::StateRequestPart(0, sync_hash, 3)),
+            ScoredRequest::from_response(&state_response(None, Some((4, vec![])))),
+        );
+        assert_eq!(ScoredRequest::from_response(&state_response(None, None)), None);
+
+        let chunk_hash = ChunkHash(hash(&[2]));
+        let request =
+            RoutedMessageBody::PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg {
+                chunk_hash: chunk_hash.clone(),
+                part_ords: vec![0],
+                tracking_shards: HashSet::new(),
+            });
+        let response = |parts| {
+            RoutedMessageBody::PartialEncodedChunkResponse(PartialEncodedChunkResponseMsg {
+                chunk_hash: chunk_hash.clone(),
+                parts,
+                receipts: vec![],
+            })
+        };
+        let part =
+            PartialEncodedChunkPart { part_ord: 0, part: vec![].into(), merkle_proof: vec![] };
+        assert_eq!(
+            ScoredRequest::from_request(&request),
+            ScoredRequest::from_response(&response(vec![part]))
+        );
+        assert_eq!(ScoredRequest::from_response(&response(vec![])), None);
+        assert_eq!(
+            ScoredRequest::from_request(&request).unwrap().delivered_event(),
+            PeerScoreEvent::DeliveredChunkPart
+        );
+    }
 }
@@ -0,0 +1,112 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use actix::actors::mocker::Mocker;
+use actix::{Actor, System};
+
+use near_client::{ClientActor, ViewClientActor};
+use near_crypto::{KeyType, SecretKey};
+use near_logger_utils::init_test_logger;
+use near_network::capture::{
+    replay_capture, CaptureConfig, CaptureDirection, CaptureReader, CapturedMessage,
+};
+use near_network::test_utils::{convert_boot_nodes, open_port, WaitOrTimeout};
+use near_network::types::{NetworkViewClientMessages, NetworkViewClientResponses, PeerMessage};
+use near_network::{NetworkClientResponses, NetworkConfig, PeerManagerActor};
+use near_store::test_utils::create_test_store;
+
+type ClientMock = Mocker<ClientActor>;
+type ViewClientMock = Mocker<ViewClientActor>;
+
+fn make_peer_manager(
+    seed: &str,
+    port: u16,
+    boot_nodes: Vec<(&str, u16)>,
+    capture: Option<CaptureConfig>,
+) -> PeerManagerActor {
+    let store = create_test_store();
+    let mut config = NetworkConfig::from_seed(seed, port);
+    config.boot_nodes = convert_boot_nodes(boot_nodes);
+    config.capture = capture;
+    let client_addr = ClientMock::mock(Box::new(move |_msg, _ctx| {
+        Box::new(Some(NetworkClientResponses::NoResponse))
+    }))
+    .start();
+    let view_client_addr = ViewClientMock::mock(Box::new(move |msg, _ctx| {
+        let msg = msg.downcast_ref::<NetworkViewClientMessages>().unwrap();
+        match msg {
+            NetworkViewClientMessages::GetChainInfo => {
+                Box::new(Some(NetworkViewClientResponses::ChainInfo {
+                    genesis_id: Default::default(),
+                    height: 1,
+                    tracked_shards: vec![],
+                    archival: false,
+                }))
+            }
+            _ => Box::new(Some(NetworkViewClientResponses::NoResponse)),
+        }
+    }))
+    .start();
+    PeerManagerActor::new(store, config, client_addr.recipient(), view_client_addr.recipient())
+        .unwrap()
+}
+
+/// Records of the capture, ignoring a record being written.
+fn read_capture(path: &Path) -> Vec<CapturedMessage> {
+    CaptureReader::open(path).map_or(vec![], |reader| reader.filter_map(Result::ok).collect())
+}
+
+/// Capture the traffic of a node with its peer and replay it against another node.
+#[test]
+fn capture_and_replay() {
+    init_test_logger();
+
+    let dir = tempfile::Builder::new().prefix("capture").tempdir().unwrap();
+    let path = dir.path().join("capture");
+
+    System::builder()
+        .stop_on_panic(true)
+        .run(move || {
+            let (port1, port2, port3) = (open_port(), open_port(), open_port());
+            let capture =
+                CaptureConfig { path: path.clone(), message_types: vec![], max_file_size: None };
+            let _pm1 = make_peer_manager("test1", port1, vec![], Some(capture)).start();
+            let _pm2 = make_peer_manager("test2", port2, vec![("test1", port1)], None).start();
+            let _pm3 = make_peer_manager("test3", port3, vec![], None).start();
+
+            let replaying = Arc::new(AtomicBool::new(false));
+            WaitOrTimeout::new(
+                Box::new(move |_| {
+                    let records = read_capture(&path);
+                    let received = records
+                        .iter()
+                        .filter(|record| record.direction == CaptureDirection::Received)
+                        .map(|record| record.peer_message().unwrap())
+                        .collect::<Vec<_>>();
+                    let has_handshake =
+                        received.iter().any(|msg| matches!(msg, PeerMessage::Handshake(_)));
+                    let has_other =
+                        received.iter().any(|msg| !matches!(msg, PeerMessage::Handshake(_)));
+                    if !has_handshake || !has_other || replaying.swap(true, Ordering::SeqCst) {
+                        return;
+                    }
+
+                    let num_records = records.len();
+                    let addr = format!("127.0.0.1:{}", port3).parse().unwrap();
+                    actix::spawn(async move {
+                        let secret_key = SecretKey::from_random(KeyType::ED25519);
+                        let stats =
+                            replay_capture(addr, &secret_key, records, false).await.unwrap();
+                        assert!(stats.sent > 0);
+                        assert_eq!(stats.sent + stats.skipped, num_records);
+                        System::current().stop();
+                    });
+                }),
+                100,
+                10000,
+            )
+            .start();
+        })
+        .unwrap();
+}
@@ -1,14 +1,20 @@
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+#[macro_use]
+extern crate lazy_static;
 
-use crate::types::{PoolIterator, PoolKey, TransactionGroup};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::types::{InsertTransactionResult, PoolIterator, PoolKey, TransactionGroup};
 use borsh::BorshSerialize;
+use near_chain_configs::TransactionPoolConfig;
 use near_crypto::PublicKey;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Gas};
 use rand::RngCore;
 use std::ops::Bound;
 
+mod metrics;
 pub mod types;
 
 /// Transaction pool: keeps track of transactions that were not yet accepted into the block chain.
@@ -23,15 +29,48 @@ pub struct TransactionPool {
     key_seed: Vec<u8>,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
     last_used_key: PoolKey,
+    /// Limits and ordering of the pool.
+    config: TransactionPoolConfig,
+    /// Number of transactions in the pool per signer.
+    signer_transactions: HashMap<AccountId, usize>,
+    /// Total size of the transactions in the pool.
+    total_size: u64,
+    /// Groups ordered for eviction, the largest group first. Ties are broken by the group key.
+    eviction_index: BTreeSet<(Reverse<usize>, PoolKey)>,
+    /// Number of transactions of every group, to find its entry in the eviction index.
+    eviction_group_len: HashMap<PoolKey, usize>,
+}
+
+/// Size of the transaction counted towards `TransactionPoolConfig::max_bytes`.
+pub(crate) fn transaction_size(transaction: &SignedTransaction) -> u64 {
+    transaction.try_to_vec().expect("Failed to serialize").len() as u64
+}
+
+/// Priority of the transaction when the pool is ordered by prepaid gas.
+fn prepaid_gas(transaction: &SignedTransaction) -> Gas {
+    transaction
+        .transaction
+        .actions
+        .iter()
+        .fold(0, |gas: Gas, action| gas.saturating_add(action.get_prepaid_gas()))
 }
 
 impl TransactionPool {
     pub fn new() -> Self {
+        Self::with_config(TransactionPoolConfig::default())
+    }
+
+    pub fn with_config(config: TransactionPoolConfig) -> Self {
         Self {
             key_seed: rand::thread_rng().next_u64().to_le_bytes().to_vec(),
             transactions: BTreeMap::new(),
             unique_transactions: HashSet::new(),
             last_used_key: CryptoHash::default(),
+            config,
+            signer_transactions: HashMap::new(),
+            total_size: 0,
+            eviction_index: BTreeSet::new(),
+            eviction_group_len: HashMap::new(),
         }
     }
 
@@ -43,17 +82,175 @@ impl TransactionPool {
     }
 
     /// Insert a signed transaction into the pool that passed validation.
-    pub fn insert_transaction(&mut self, signed_transaction: SignedTransaction) -> bool {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
-            return false;
+    /// If the pool is full, transactions of groups larger than the one of the new transaction are
+    /// evicted to make room for it, starting from the largest group. Only the transaction with the
+    /// highest nonce of a group can be evicted without invalidating the other ones.
+    /// Prepaid gas is refunded, so it is free to inflate and never decides what gets evicted.
+    pub fn insert_transaction(
+        &mut self,
+        signed_transaction: SignedTransaction,
+    ) -> InsertTransactionResult {
+        if self.unique_transactions.contains(&signed_transaction.get_hash()) {
+            return InsertTransactionResult::Duplicate;
         }
         let signer_id = &signed_transaction.transaction.signer_id;
+        if let Some(max_transactions_per_signer) = self.config.max_transactions_per_signer {
+            if self.signer_transactions.get(signer_id).copied().unwrap_or(0)
+                >= max_transactions_per_signer
+            {
+                near_metrics::inc_counter(&metrics::TRANSACTION_POOL_REJECTED_TOTAL);
+                return InsertTransactionResult::SignerLimitExceeded;
+            }
+        }
+        let size = transaction_size(&signed_transaction);
         let signer_public_key = &signed_transaction.transaction.public_key;
-        self.transactions
-            .entry(self.key(signer_id, signer_public_key))
-            .or_insert_with(Vec::new)
-            .push(signed_transaction);
-        true
+        let key = self.key(signer_id, signer_public_key);
+        let group_len = self.transactions.get(&key).map_or(0, Vec::len) + 1;
+        let victims = match self.find_eviction_victims(group_len, size) {
+            Some(victims) => victims,
+            None => {
+                near_metrics::inc_counter(&metrics::TRANSACTION_POOL_REJECTED_TOTAL);
+                return InsertTransactionResult::NoSpaceLeft;
+            }
+        };
+        for (key, victim_hash) in victims {
+            self.evict_transaction(key, victim_hash);
+        }
+
+        self.unique_transactions.insert(signed_transaction.get_hash());
+        *self.signer_transactions.entry(signer_id.clone()).or_insert(0) += 1;
+        self.total_size += size;
+        near_metrics::inc_gauge(&metrics::TRANSACTION_POOL_TRANSACTIONS);
+        near_metrics::inc_gauge_by(&metrics::TRANSACTION_POOL_SIZE, size as i64);
+        self.transactions.entry(key).or_insert_with(Vec::new).push(signed_transaction);
+        self.update_eviction_index(key);
+        InsertTransactionResult::Success
+    }
+
+    /// Returns the transactions to evict to fit a new transaction of the given size, or `None`
+    /// if there are not enough transactions in groups larger than `group_len`, the length of the
+    /// group of the new transaction once inserted.
+    fn find_eviction_victims(
+        &self,
+        group_len: usize,
+        size: u64,
+    ) -> Option<Vec<(PoolKey, CryptoHash)>> {
+        let mut count = self.unique_transactions.len() + 1;
+        let mut total_size = self.total_size + size;
+        let mut victims = vec![];
+        let mut candidates = self.eviction_index.iter().peekable();
+        // Remaining transactions of the groups that already have victims, sorted by nonce, and
+        // their next candidates, which are not in the index.
+        let mut picked_groups: HashMap<PoolKey, Vec<&SignedTransaction>> = HashMap::new();
+        let mut next_candidates = BTreeSet::new();
+        while self.config.max_transactions.map_or(false, |max| count > max)
+            || self.config.max_bytes.map_or(false, |max| total_size > max)
+        {
+            // Pick the largest group.
+            let indexed = candidates.peek().map(|candidate| **candidate);
+            let next = next_candidates.iter().next().copied();
+            let (Reverse(candidate_len), key) = match (indexed, next) {
+                (Some(indexed), Some(next)) if indexed < next => {
+                    candidates.next();
+                    indexed
+                }
+                (Some(indexed), None) => {
+                    candidates.next();
+                    indexed
+                }
+                (_, Some(next)) => {
+                    next_candidates.remove(&next);
+                    next
+                }
+                (None, None) => return None,
+            };
+            if candidate_len <= group_len {
+                return None;
+            }
+            let remaining = picked_groups.entry(key).or_insert_with(|| {
+                let mut transactions: Vec<_> = self.transactions[&key].iter().collect();
+                transactions.sort_by_key(|tx| tx.transaction.nonce);
+                transactions
+            });
+            let victim = remaining.pop().expect("candidates are in the pool");
+            if !remaining.is_empty() {
+                next_candidates.insert((Reverse(remaining.len()), key));
+            }
+            count -= 1;
+            total_size -= transaction_size(victim);
+            victims.push((key, victim.get_hash()));
+        }
+        Some(victims)
+    }
+
+    /// Replaces the eviction index entry of the group after its transactions changed.
+    fn update_eviction_index(&mut self, key: PoolKey) {
+        if let Some(len) = self.eviction_group_len.remove(&key) {
+            self.eviction_index.remove(&(Reverse(len), key));
+        }
+        if let Some(len) = self.transactions.get(&key).map(Vec::len) {
+            self.eviction_index.insert((Reverse(len), key));
+            self.eviction_group_len.insert(key, len);
+        }
+    }
+
+    fn evict_transaction(&mut self, key: PoolKey, victim_hash: CryptoHash) {
+        let transactions = self.transactions.get_mut(&key).expect("victim is in the pool");
+        let index = transactions
+            .iter()
+            .position(|tx| tx.get_hash() == victim_hash)
+            .expect("victim is in the pool");
+        let victim = transactions.swap_remove(index);
+        if transactions.is_empty() {
+            self.transactions.remove(&key);
+        }
+        self.update_eviction_index(key);
+        self.unique_transactions.remove(&victim_hash);
+        self.on_transactions_removed(&victim.transaction.signer_id, 1, transaction_size(&victim));
+        near_metrics::inc_counter(&metrics::TRANSACTION_POOL_EVICTED_TOTAL);
+    }
+
+    /// Updates the signer counters, the pool size and the metrics once transactions of the signer
+    /// are no longer in the pool.
+    fn on_transactions_removed(&mut self, signer_id: &AccountId, count: usize, size: u64) {
+        if count == 0 {
+            return;
+        }
+        if let Some(signer_count) = self.signer_transactions.get_mut(signer_id) {
+            *signer_count = signer_count.saturating_sub(count);
+            if *signer_count == 0 {
+                self.signer_transactions.remove(signer_id);
+            }
+        }
+        self.total_size -= size;
+        near_metrics::dec_gauge_by(&metrics::TRANSACTION_POOL_TRANSACTIONS, count as i64);
+        near_metrics::dec_gauge_by(&metrics::TRANSACTION_POOL_SIZE, size as i64);
+    }
+
+    /// Forgets the transactions pulled from the group, they are no longer in the pool.
+    fn remove_pulled_transactions(&mut self, group: &mut TransactionGroup) {
+        let count = group.removed_transaction_hashes.len();
+        for hash in group.removed_transaction_hashes.drain(..) {
+            self.unique_transactions.remove(&hash);
+        }
+        let size = std::mem::take(&mut group.removed_transactions_size);
+        self.on_transactions_removed(&group.signer_id, count, size);
+    }
+
+    /// Removes the group from the pool and sorts its transactions for the pool iterator.
+    fn take_group(&mut self, key: PoolKey) -> TransactionGroup {
+        let mut transactions = self.transactions.remove(&key).expect("just checked existence");
+        self.update_eviction_index(key);
+        transactions.sort_by_key(|st| Reverse(st.transaction.nonce));
+        let signer_id =
+            transactions.first().expect("groups are never empty").transaction.signer_id.clone();
+        TransactionGroup {
+            key,
+            signer_id,
+            transactions,
+            removed_transaction_hashes: vec![],
+            removed_transactions_size: 0,
+        }
     }
 
     /// Returns a pool iterator wrapper that implements an iterator like trait to iterate over
@@ -79,13 +276,30 @@ impl TransactionPool {
         }
         for (key, hashes) in grouped_transactions {
             let mut remove_entry = false;
+            let mut removed = None;
             if let Some(v) = self.transactions.get_mut(&key) {
-                v.retain(|tx| !hashes.contains(&tx.get_hash()));
+                let mut removed_count = 0;
+                let mut removed_size = 0;
+                let signer_id = v[0].transaction.signer_id.clone();
+                v.retain(|tx| {
+                    if hashes.contains(&tx.get_hash()) {
+                        removed_count += 1;
+                        removed_size += transaction_size(tx);
+                        false
+                    } else {
+                        true
+                    }
+                });
                 remove_entry = v.is_empty();
+                removed = Some((signer_id, removed_count, removed_size));
             }
             if remove_entry {
                 self.transactions.remove(&key);
             }
+            self.update_eviction_index(key);
+            if let Some((signer_id, removed_count, removed_size)) = removed {
+                self.on_transactions_removed(&signer_id, removed_count, removed_size);
+            }
             for hash in hashes {
                 self.unique_transactions.remove(&hash);
             }
@@ -106,6 +320,22 @@ impl TransactionPool {
     pub fn is_empty(&self) -> bool {
         self.unique_transactions.is_empty()
     }
+
+    /// Total size in bytes of the transactions in the pool.
+    pub fn size(&self) -> u64 {
+        self.total_size
+    }
+}
+
+/// The transactions of a dropped pool no longer count towards the pool metrics.
+impl Drop for TransactionPool {
+    fn drop(&mut self) {
+        near_metrics::dec_gauge_by(
+            &metrics::TRANSACTION_POOL_TRANSACTIONS,
+            self.unique_transactions.len() as i64,
+        );
+        near_metrics::dec_gauge_by(&metrics::TRANSACTION_POOL_SIZE, self.total_size as i64);
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -117,11 +347,54 @@ pub struct PoolIteratorWrapper<'a> {
 
     /// Queue of transaction groups. Each group there is sorted by nonce.
     sorted_groups: VecDeque<TransactionGroup>,
+
+    /// Transaction groups taken from the pool when it prioritizes transactions by prepaid gas.
+    prioritized_groups: HashMap<PoolKey, TransactionGroup>,
+    /// Keys of the prioritized groups ordered by the prepaid gas of their next transaction.
+    /// The group returned last is not in the index, as its next transaction may have been pulled.
+    prepaid_gas_index: BTreeSet<(Reverse<Gas>, PoolKey)>,
+    /// Key of the prioritized group returned last.
+    last_prioritized_key: Option<PoolKey>,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
     pub fn new(pool: &'a mut TransactionPool) -> Self {
-        Self { pool, sorted_groups: Default::default() }
+        Self {
+            pool,
+            sorted_groups: Default::default(),
+            prioritized_groups: Default::default(),
+            prepaid_gas_index: Default::default(),
+            last_prioritized_key: None,
+        }
+    }
+
+    /// Adds the group to the prepaid gas index, or forgets it once it is empty.
+    fn index_prioritized_group(&mut self, mut group: TransactionGroup) {
+        match group.transactions.last().map(prepaid_gas) {
+            Some(gas) => {
+                self.prepaid_gas_index.insert((Reverse(gas), group.key));
+                self.prioritized_groups.insert(group.key, group);
+            }
+            None => self.pool.remove_pulled_transactions(&mut group),
+        }
+    }
+
+    /// Returns the group whose next transaction has the most prepaid gas. Ties are broken by
+    /// the pool key, so the order only depends on the pool key seed.
+    fn next_by_prepaid_gas(&mut self) -> Option<&mut TransactionGroup> {
+        while let Some(key) = self.pool.transactions.keys().next().cloned() {
+            let group = self.pool.take_group(key);
+            self.index_prioritized_group(group);
+        }
+        if let Some(key) = self.last_prioritized_key.take() {
+            let group = self.prioritized_groups.remove(&key).expect("returned group is kept");
+            self.index_prioritized_group(group);
+        }
+        let best = *self.prepaid_gas_index.iter().next()?;
+        self.prepaid_gas_index.remove(&best);
+        let (_, key) = best;
+        self.last_prioritized_key = Some(key);
+        self.prioritized_groups.get_mut(&key)
     }
 }
 
@@ -140,10 +413,16 @@ impl<'a> PoolIteratorWrapper<'a> {
 ///
 /// If the sorted groups queue is empty, the iterator returns None.
 ///
+/// When the pool prioritizes transactions by prepaid gas, the iterator instead takes all the
+/// groups at once and returns the one whose next transaction has the most prepaid gas.
+///
 /// When the iterator is dropped, `unique_transactions` in the pool is updated for every group.
 /// And all non-empty group from the sorted groups queue are inserted back into the pool.
 impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
+        if self.pool.config.prioritize_by_prepaid_gas {
+            return self.next_by_prepaid_gas();
+        }
         if !self.pool.transactions.is_empty() {
             let key = *self
                 .pool
@@ -159,21 +438,13 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
                         .expect("we've just checked that the map is not empty")
                 });
             self.pool.last_used_key = key;
-            let mut transactions =
-                self.pool.transactions.remove(&key).expect("just checked existence");
-            transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce));
-            self.sorted_groups.push_back(TransactionGroup {
-                key,
-                transactions,
-                removed_transaction_hashes: vec![],
-            });
+            let group = self.pool.take_group(key);
+            self.sorted_groups.push_back(group);
             Some(self.sorted_groups.back_mut().expect("just pushed"))
         } else {
-            while let Some(sorted_group) = self.sorted_groups.pop_front() {
+            while let Some(mut sorted_group) = self.sorted_groups.pop_front() {
                 if sorted_group.transactions.is_empty() {
-                    for hash in sorted_group.removed_transaction_hashes {
-                        self.pool.unique_transactions.remove(&hash);
-                    }
+                    self.pool.remove_pulled_transactions(&mut sorted_group);
                 } else {
                     self.sorted_groups.push_back(sorted_group);
                     return Some(self.sorted_groups.back_mut().expect("just pushed"));
@@ -189,12 +460,12 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
 /// removed from the pool's unique_transactions.
 impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
-        for group in self.sorted_groups.drain(..) {
-            for hash in group.removed_transaction_hashes {
-                self.pool.unique_transactions.remove(&hash);
-            }
+        let prioritized_groups = self.prioritized_groups.drain().map(|(_, group)| group);
+        for mut group in self.sorted_groups.drain(..).chain(prioritized_groups) {
+            self.pool.remove_pulled_transactions(&mut group);
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
+                self.pool.update_eviction_index(group.key);
             }
         }
     }
@@ -450,4 +721,208 @@ mod tests {
         new_nonces.sort();
         assert_ne!(nonces, new_nonces);
     }
+
+    fn call_transaction(signer_id: &str, nonce: u64, gas: Gas) -> SignedTransaction {
+        let signer = InMemorySigner::from_seed(signer_id, KeyType::ED25519, signer_id);
+        SignedTransaction::call(
+            nonce,
+            signer_id.to_string(),
+            "bob.near".to_string(),
+            &signer,
+            0,
+            "method".to_string(),
+            vec![],
+            gas,
+            CryptoHash::default(),
+        )
+    }
+
+    /// Test the pool rejects transactions of a signer over the per signer limit.
+    #[test]
+    fn test_max_transactions_per_signer() {
+        let mut pool = TransactionPool::with_config(TransactionPoolConfig {
+            max_transactions_per_signer: Some(3),
+            ..Default::default()
+        });
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 4);
+        let results: Vec<_> =
+            transactions.iter().cloned().map(|tx| pool.insert_transaction(tx)).collect();
+        assert_eq!(
+            results,
+            vec![
+                InsertTransactionResult::Success,
+                InsertTransactionResult::Success,
+                InsertTransactionResult::Success,
+                InsertTransactionResult::SignerLimitExceeded
+            ]
+        );
+        assert_eq!(
+            pool.insert_transaction(transactions[0].clone()),
+            InsertTransactionResult::Duplicate
+        );
+
+        // Pulled transactions no longer count towards the limit
+        assert_eq!(prepare_transactions(&mut pool, 1).len(), 1);
+        assert_eq!(
+            pool.insert_transaction(transactions[3].clone()),
+            InsertTransactionResult::Success
+        );
+        assert_eq!(
+            pool.insert_transaction(generate_transactions("bob.near", "bob.near", 1, 1).remove(0)),
+            InsertTransactionResult::Success
+        );
+    }
+
+    /// Test the full pool evicts the transaction with the highest nonce of the largest group, and
+    /// rejects transactions whose group would be at least as large as the other ones, whatever
+    /// their prepaid gas.
+    #[test]
+    fn test_eviction() {
+        let mut pool = TransactionPool::with_config(TransactionPoolConfig {
+            max_transactions: Some(3),
+            ..Default::default()
+        });
+        for tx in vec![
+            call_transaction("alice.near", 1, 10),
+            call_transaction("alice.near", 2, 30),
+            call_transaction("bob.near", 1, 20),
+        ] {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        assert_eq!(
+            pool.insert_transaction(call_transaction("alice.near", 3, 100)),
+            InsertTransactionResult::NoSpaceLeft
+        );
+        assert_eq!(
+            pool.insert_transaction(call_transaction("carol.near", 1, 1)),
+            InsertTransactionResult::Success
+        );
+        assert_eq!(
+            pool.insert_transaction(call_transaction("david.near", 1, 100)),
+            InsertTransactionResult::NoSpaceLeft
+        );
+        assert_eq!(pool.len(), 3);
+        let mut remaining: Vec<_> = prepare_transactions(&mut pool, 3)
+            .into_iter()
+            .map(|tx| (tx.transaction.signer_id, tx.transaction.nonce))
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                ("alice.near".to_string(), 1),
+                ("bob.near".to_string(), 1),
+                ("carol.near".to_string(), 1)
+            ]
+        );
+        assert!(pool.is_empty());
+        assert_eq!(pool.size(), 0);
+    }
+
+    /// Test the pool bounded by size evicts as many transactions as needed.
+    #[test]
+    fn test_max_bytes() {
+        let small = call_transaction("alice.near", 1, 10);
+        let size = transaction_size(&small);
+        let mut pool = TransactionPool::with_config(TransactionPoolConfig {
+            max_bytes: Some(size * 3),
+            ..Default::default()
+        });
+        pool.insert_transaction(small);
+        pool.insert_transaction(call_transaction("alice.near", 2, 10));
+        pool.insert_transaction(call_transaction("alice.near", 3, 10));
+        assert_eq!(pool.size(), size * 3);
+        let large = SignedTransaction::from_actions(
+            1,
+            "carol.near".to_string(),
+            "bob.near".to_string(),
+            &InMemorySigner::from_seed("carol.near", KeyType::ED25519, "carol.near"),
+            vec![
+                call_transaction("carol.near", 1, 20).transaction.actions[0].clone(),
+                call_transaction("carol.near", 1, 0).transaction.actions[0].clone(),
+            ],
+            CryptoHash::default(),
+        );
+        let large_size = transaction_size(&large);
+        assert!(large_size > size && large_size <= size * 2);
+        assert_eq!(pool.insert_transaction(large), InsertTransactionResult::Success);
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.size(), size + large_size);
+    }
+
+    /// Test the pool evicts the next transaction of a group once its highest nonce transaction is
+    /// evicted, also after the groups were returned to the pool by an iterator.
+    #[test]
+    fn test_eviction_same_group() {
+        let small = call_transaction("alice.near", 1, 6);
+        let size = transaction_size(&small);
+        let mut pool = TransactionPool::with_config(TransactionPoolConfig {
+            max_bytes: Some(size * 4),
+            ..Default::default()
+        });
+        pool.insert_transaction(small);
+        pool.insert_transaction(call_transaction("alice.near", 2, 5));
+        pool.insert_transaction(call_transaction("alice.near", 3, 7));
+        pool.insert_transaction(call_transaction("bob.near", 1, 8));
+        drop(pool.pool_iterator());
+        let large = SignedTransaction::from_actions(
+            1,
+            "carol.near".to_string(),
+            "bob.near".to_string(),
+            &InMemorySigner::from_seed("carol.near", KeyType::ED25519, "carol.near"),
+            vec![
+                call_transaction("carol.near", 1, 20).transaction.actions[0].clone(),
+                call_transaction("carol.near", 1, 0).transaction.actions[0].clone(),
+            ],
+            CryptoHash::default(),
+        );
+        assert_eq!(pool.insert_transaction(large), InsertTransactionResult::Success);
+        let mut remaining: Vec<_> = prepare_transactions(&mut pool, 3)
+            .into_iter()
+            .map(|tx| (tx.transaction.signer_id, tx.transaction.nonce))
+            .collect();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                ("alice.near".to_string(), 1),
+                ("bob.near".to_string(), 1),
+                ("carol.near".to_string(), 1)
+            ]
+        );
+    }
+
+    /// Test the pool prioritizing by prepaid gas serves the groups by the prepaid gas of their
+    /// next transaction, still respecting the nonce order within a group.
+    #[test]
+    fn test_prioritize_by_prepaid_gas() {
+        let mut pool = TransactionPool::with_config(TransactionPoolConfig {
+            prioritize_by_prepaid_gas: true,
+            ..Default::default()
+        });
+        for tx in vec![
+            call_transaction("alice.near", 1, 10),
+            call_transaction("alice.near", 2, 50),
+            call_transaction("bob.near", 1, 30),
+            call_transaction("carol.near", 1, 20),
+            call_transaction("carol.near", 2, 40),
+        ] {
+            pool.insert_transaction(tx);
+        }
+        let order: Vec<_> = prepare_transactions(&mut pool, 5)
+            .into_iter()
+            .map(|tx| (tx.transaction.signer_id, tx.transaction.nonce))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("bob.near".to_string(), 1),
+                ("carol.near".to_string(), 1),
+                ("carol.near".to_string(), 2),
+                ("alice.near".to_string(), 1),
+                ("alice.near".to_string(), 2)
+            ]
+        );
+        assert!(pool.is_empty());
+    }
 }
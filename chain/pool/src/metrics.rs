@@ -0,0 +1,23 @@
+use near_metrics::{try_create_int_counter, try_create_int_gauge, IntCounter, IntGauge};
+
+lazy_static! {
+    pub static ref TRANSACTION_POOL_TRANSACTIONS: near_metrics::Result<IntGauge> =
+        try_create_int_gauge(
+            "near_transaction_pool_transactions",
+            "Total number of transactions in the transaction pools of all shards"
+        );
+    pub static ref TRANSACTION_POOL_SIZE: near_metrics::Result<IntGauge> = try_create_int_gauge(
+        "near_transaction_pool_size_bytes",
+        "Total size in bytes of the transactions in the transaction pools of all shards"
+    );
+    pub static ref TRANSACTION_POOL_EVICTED_TOTAL: near_metrics::Result<IntCounter> =
+        try_create_int_counter(
+            "near_transaction_pool_evicted_total",
+            "Total number of transactions evicted from the full transaction pools"
+        );
+    pub static ref TRANSACTION_POOL_REJECTED_TOTAL: near_metrics::Result<IntCounter> =
+        try_create_int_counter(
+            "near_transaction_pool_rejected_total",
+            "Total number of transactions rejected by the full transaction pools or per signer limits"
+        );
+}
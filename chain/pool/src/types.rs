@@ -1,9 +1,13 @@
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+
+use crate::transaction_size;
 
 /// Trait acts like an iterator. It iterates over transactions groups by returning mutable
 /// references to them. Each transaction group implements a draining iterator to pull transactions.
-/// The order of the transaction groups is round robin scheduling.
+/// The order of the transaction groups is round robin scheduling, unless the pool prioritizes
+/// transactions by prepaid gas.
 /// When this iterator is dropped the remaining transactions are returned back to the pool.
 pub trait PoolIterator {
     fn next(&mut self) -> Option<&mut TransactionGroup>;
@@ -17,10 +21,14 @@ pub(crate) type PoolKey = CryptoHash;
 pub struct TransactionGroup {
     /// The key of the group.
     pub(crate) key: PoolKey,
+    /// The signer of all the transactions in the group.
+    pub(crate) signer_id: AccountId,
     /// Ordered transactions by nonce in non-increasing order (e.g. 3, 2, 2).
     pub(crate) transactions: Vec<SignedTransaction>,
     /// Hashes of the transactions that were pulled from the group using `.next()`.
     pub(crate) removed_transaction_hashes: Vec<CryptoHash>,
+    /// Total size of the transactions that were pulled from the group using `.next()`.
+    pub(crate) removed_transactions_size: u64,
 }
 
 impl TransactionGroup {
@@ -29,9 +37,23 @@ impl TransactionGroup {
     pub fn next(&mut self) -> Option<SignedTransaction> {
         if let Some(tx) = self.transactions.pop() {
             self.removed_transaction_hashes.push(tx.get_hash());
+            self.removed_transactions_size += transaction_size(&tx);
             Some(tx)
         } else {
             None
         }
     }
 }
+
+/// Outcome of inserting a transaction into the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertTransactionResult {
+    /// The transaction was added to the pool.
+    Success,
+    /// The transaction is already in the pool.
+    Duplicate,
+    /// The pool is full and holds no transaction with less prepaid gas to evict.
+    NoSpaceLeft,
+    /// The signer already has the max number of transactions in the pool.
+    SignerLimitExceeded,
+}
@@ -860,6 +860,9 @@ async fn construction_submit(
         near_network::NetworkClientResponses::InvalidTx(error) => {
             Err(errors::ErrorKind::InvalidInput(error.to_string()).into())
         }
+        near_network::NetworkClientResponses::PoolRejected(reason) => {
+            Err(errors::ErrorKind::InvalidInput(reason).into())
+        }
         _ => Err(errors::ErrorKind::InternalInvariantError(format!(
             "Transaction submition return unexpected result: {:?}",
             transaction_submittion
@@ -311,7 +311,7 @@ pub(crate) async fn query_account(
 > {
     let query = near_client::Query::new(
         block_id,
-        near_primitives::views::QueryRequest::ViewAccount { account_id },
+        near_primitives::views::QueryRequest::ViewAccount { account_id, include_proof: false },
     );
     let account_info_response = tokio::time::timeout(std::time::Duration::from_secs(10), async {
         loop {
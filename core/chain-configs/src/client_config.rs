@@ -17,6 +17,24 @@ pub enum LogSummaryStyle {
     Colored,
 }
 
+/// Bounds and ordering of the transaction pool of every shard.
+/// The pool is unbounded and served round robin by default. A full pool evicts transactions from
+/// the signers with the most pending transactions, never by prepaid gas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransactionPoolConfig {
+    /// Max number of transactions in the pool.
+    pub max_transactions: Option<usize>,
+    /// Max total size in bytes of the transactions in the pool.
+    pub max_bytes: Option<u64>,
+    /// Max number of transactions of a single signer in the pool.
+    pub max_transactions_per_signer: Option<usize>,
+    /// Serve transaction groups by the prepaid gas of their next transaction instead of
+    /// round robin. Unused prepaid gas is refunded, so the sender can inflate it for free and get
+    /// ahead of everyone else; only enable it when the senders are trusted.
+    pub prioritize_by_prepaid_gas: bool,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
     /// Version of the binary.
@@ -91,6 +109,12 @@ pub struct ClientConfig {
     pub archive: bool,
     /// Number of threads for ViewClientActor pool.
     pub view_client_threads: usize,
+    /// Limits and ordering of the transaction pools.
+    pub transaction_pool: TransactionPoolConfig,
+    /// Maximum depth of cross-contract calls followed by view calls, runtime default if `None`.
+    pub max_view_call_depth: Option<u32>,
+    /// Maximum number of values returned by a view state query, runtime default if `None`.
+    pub max_view_state_values: Option<u64>,
 }
 
 impl ClientConfig {
@@ -144,6 +168,9 @@ impl ClientConfig {
             archive,
             log_summary_style: LogSummaryStyle::Colored,
             view_client_threads: 1,
+            transaction_pool: TransactionPoolConfig::default(),
+            max_view_call_depth: None,
+            max_view_state_values: None,
         }
     }
 }
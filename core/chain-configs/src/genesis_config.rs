@@ -39,6 +39,16 @@ pub const TESTNET_EVM_CHAIN_ID: u64 = 1313161555;
 /// See https://github.com/ethereum-lists/chains/blob/master/_data/chains/eip155-1313161554.json
 pub const MAINNET_EVM_CHAIN_ID: u64 = 1313161554;
 
+#[cfg(feature = "protocol_feature_evm")]
+/// ID of the EVM chain of the NEAR chain with the given ID: https://github.com/ethereum-lists/chains
+pub fn evm_chain_id(chain_id: &str) -> u64 {
+    match chain_id {
+        "mainnet" => MAINNET_EVM_CHAIN_ID,
+        "testnet" => TESTNET_EVM_CHAIN_ID,
+        _ => BETANET_EVM_CHAIN_ID,
+    }
+}
+
 fn default_online_min_threshold() -> Rational {
     Rational::new(90, 100)
 }
@@ -1,9 +1,13 @@
 mod client_config;
 mod genesis_config;
 
-pub use client_config::{ClientConfig, LogSummaryStyle, TEST_STATE_SYNC_TIMEOUT};
+pub use client_config::{
+    ClientConfig, LogSummaryStyle, TransactionPoolConfig, TEST_STATE_SYNC_TIMEOUT,
+};
+#[cfg(feature = "protocol_feature_evm")]
+pub use genesis_config::{
+    evm_chain_id, BETANET_EVM_CHAIN_ID, MAINNET_EVM_CHAIN_ID, TESTNET_EVM_CHAIN_ID,
+};
 pub use genesis_config::{
     Genesis, GenesisConfig, GenesisRecords, ProtocolConfig, ProtocolConfigView,
 };
-#[cfg(feature = "protocol_feature_evm")]
-pub use genesis_config::{BETANET_EVM_CHAIN_ID, MAINNET_EVM_CHAIN_ID, TESTNET_EVM_CHAIN_ID};
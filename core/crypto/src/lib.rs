@@ -1,7 +1,8 @@
 pub use errors::{ParseKeyError, ParseSignatureError, TryFromSliceError};
 pub use key_file::KeyFile;
 pub use signature::{
-    ED25519PublicKey, KeyType, PublicKey, Secp256K1PublicKey, SecretKey, Signature,
+    ED25519PublicKey, KeyType, PublicKey, Secp256K1PublicKey, Secp256K1Signature, SecretKey,
+    Signature,
 };
 pub use signer::{EmptySigner, InMemorySigner, Signer};
 
@@ -579,6 +579,25 @@ impl From<Secp256K1Signature> for [u8; 65] {
     }
 }
 
+impl Secp256K1Signature {
+    /// Recovers the public key which signed the 32 bytes message with this signature. The last
+    /// byte of the signature is the recovery id.
+    pub fn recover(&self, msg: [u8; 32]) -> Result<Secp256K1PublicKey, crate::ParseSignatureError> {
+        let recovery_id = secp256k1::RecoveryId::from_i32(i32::from(self.0[64]))
+            .map_err(|err| crate::ParseSignatureError::InvalidData(err.to_string()))?;
+        let recoverable_sig =
+            secp256k1::RecoverableSignature::from_compact(&SECP256K1, &self.0[0..64], recovery_id)
+                .map_err(|err| crate::ParseSignatureError::InvalidData(err.to_string()))?;
+        let msg = secp256k1::Message::from_slice(&msg).expect("32 bytes");
+        let public_key = SECP256K1
+            .recover(&msg, &recoverable_sig)
+            .map_err(|err| crate::ParseSignatureError::InvalidData(err.to_string()))?;
+        // The uncompressed key is prefixed with 0x04
+        let serialized = public_key.serialize_vec(&SECP256K1, false);
+        Ok(Secp256K1PublicKey::try_from(&serialized[1..65]).expect("64 bytes"))
+    }
+}
+
 /// Signature container supporting different curves.
 #[derive(Clone, PartialEq, Eq)]
 pub enum Signature {
@@ -788,6 +807,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_secp256k1_recover() {
+        let secret_key = SecretKey::from_random(KeyType::SECP256K1);
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123");
+        let signature = match secret_key.sign(&data) {
+            Signature::SECP256K1(signature) => signature,
+            _ => unreachable!(),
+        };
+        let mut msg = [0u8; 32];
+        msg.copy_from_slice(&data);
+        let public_key = signature.recover(msg).unwrap();
+        assert_eq!(PublicKey::SECP256K1(public_key), secret_key.public_key());
+    }
+
     #[test]
     fn test_json_serialize_ed25519() {
         let sk = SecretKey::from_seed(KeyType::ED25519, "test");
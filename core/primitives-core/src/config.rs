@@ -290,12 +290,92 @@ pub struct ExtCostsConfig {
     pub validator_stake_base: Gas,
     /// Cost of calling `validator_total_stake`.
     pub validator_total_stake_base: Gas,
+
+    // ###############
+    // # Crypto API #
+    // ###############
+    /// Base cost of verifying an ed25519 signature
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::ed25519_verify_base")]
+    pub ed25519_verify_base: Gas,
+    /// Cost of verifying an ed25519 signature per byte of the message
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::ed25519_verify_byte")]
+    pub ed25519_verify_byte: Gas,
+    /// Cost of recovering a secp256k1 public key from a signature
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::ecrecover_base")]
+    pub ecrecover_base: Gas,
+    /// Cost of getting ripemd160 base
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::ripemd160_base")]
+    pub ripemd160_base: Gas,
+    /// Cost of getting ripemd160 per byte
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::ripemd160_byte")]
+    pub ripemd160_byte: Gas,
+    /// Cost of getting blake2b base
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::blake2b_base")]
+    pub blake2b_base: Gas,
+    /// Cost of getting blake2b per byte
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    #[serde(default = "default_costs::blake2b_byte")]
+    pub blake2b_byte: Gas,
 }
 
 // We multiply the actual computed costs by the fixed factor to ensure we
 // have certain reserve for further gas price variation.
 const SAFETY_MULTIPLIER: u64 = 3;
 
+/// Costs of the host functions introduced after the genesis, used when the config does not set
+/// them. The host functions that have an EVM builtin counterpart are priced like it, converting
+/// the EVM gas of the builtin with the NEAR gas per EVM gas measured by the params estimator.
+#[cfg(feature = "protocol_feature_crypto_host_functions")]
+mod default_costs {
+    use super::SAFETY_MULTIPLIER;
+    use crate::runtime::fees::EvmCostConfig;
+    use crate::types::Gas;
+
+    /// Bytes of an EVM word, the unit of the per word costs of the builtins.
+    const EVM_WORD_BYTES: u64 = 32;
+    /// Rounds of the blake2b compression function, charged one by one by the `blake2f` builtin.
+    const BLAKE2B_ROUNDS: u64 = 12;
+    /// Bytes compressed by one call of the blake2b compression function.
+    const BLAKE2B_BLOCK_BYTES: u64 = 128;
+    /// EVM gas of the ed25519 verification builtin proposed in EIP-665, which the EVM lacks.
+    const ED25519_VERIFY_EVM_GAS: u64 = 2000;
+
+    fn from_evm_gas(evm_gas: u64) -> Gas {
+        SAFETY_MULTIPLIER * evm_gas * EvmCostConfig::default().funcall_cost_per_evm_gas
+    }
+
+    pub fn ed25519_verify_base() -> Gas {
+        from_evm_gas(ED25519_VERIFY_EVM_GAS)
+    }
+    /// The message is hashed with SHA-512, priced like the `sha256` builtin.
+    pub fn ed25519_verify_byte() -> Gas {
+        from_evm_gas(EvmCostConfig::default().precompile_costs.sha256_cost.word) / EVM_WORD_BYTES
+    }
+    pub fn ecrecover_base() -> Gas {
+        from_evm_gas(EvmCostConfig::default().precompile_costs.ecrecover_cost.base)
+    }
+    pub fn ripemd160_base() -> Gas {
+        from_evm_gas(EvmCostConfig::default().precompile_costs.ripemd160_cost.base)
+    }
+    pub fn ripemd160_byte() -> Gas {
+        from_evm_gas(EvmCostConfig::default().precompile_costs.ripemd160_cost.word) / EVM_WORD_BYTES
+    }
+    /// Hashing at least one block.
+    pub fn blake2b_base() -> Gas {
+        from_evm_gas(BLAKE2B_ROUNDS * EvmCostConfig::default().precompile_costs.blake2f_cost)
+    }
+    pub fn blake2b_byte() -> Gas {
+        from_evm_gas(BLAKE2B_ROUNDS * EvmCostConfig::default().precompile_costs.blake2f_cost)
+            / BLAKE2B_BLOCK_BYTES
+    }
+}
+
 impl Default for ExtCostsConfig {
     fn default() -> ExtCostsConfig {
         ExtCostsConfig {
@@ -348,6 +428,20 @@ impl Default for ExtCostsConfig {
             promise_return: SAFETY_MULTIPLIER * 186717462,
             validator_stake_base: SAFETY_MULTIPLIER * 303944908800,
             validator_total_stake_base: SAFETY_MULTIPLIER * 303944908800,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_base: default_costs::ed25519_verify_base(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_byte: default_costs::ed25519_verify_byte(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ecrecover_base: default_costs::ecrecover_base(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_base: default_costs::ripemd160_base(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_byte: default_costs::ripemd160_byte(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_base: default_costs::blake2b_base(),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_byte: default_costs::blake2b_byte(),
         }
     }
 }
@@ -404,6 +498,20 @@ impl ExtCostsConfig {
             promise_return: 0,
             validator_stake_base: 0,
             validator_total_stake_base: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_base: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_byte: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ecrecover_base: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_base: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_byte: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_base: 0,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_byte: 0,
         }
     }
 }
@@ -461,6 +569,20 @@ pub enum ExtCosts {
     promise_return,
     validator_stake_base,
     validator_total_stake_base,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ed25519_verify_base,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ed25519_verify_byte,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ecrecover_base,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ripemd160_base,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ripemd160_byte,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    blake2b_base,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    blake2b_byte,
 }
 
 // Type of an action, used in fees logic.
@@ -565,11 +687,29 @@ impl ExtCosts {
             promise_return => config.promise_return,
             validator_stake_base => config.validator_stake_base,
             validator_total_stake_base => config.validator_total_stake_base,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_base => config.ed25519_verify_base,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ed25519_verify_byte => config.ed25519_verify_byte,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ecrecover_base => config.ecrecover_base,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_base => config.ripemd160_base,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            ripemd160_byte => config.ripemd160_byte,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_base => config.blake2b_base,
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            blake2b_byte => config.blake2b_byte,
         }
     }
 
     pub const fn count() -> usize {
-        ExtCosts::validator_total_stake_base as usize + 1
+        #[cfg(not(feature = "protocol_feature_crypto_host_functions"))]
+        let last = ExtCosts::validator_total_stake_base;
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        let last = ExtCosts::blake2b_byte;
+        last as usize + 1
     }
 
     pub fn name_of(index: usize) -> &'static str {
@@ -623,6 +763,20 @@ impl ExtCosts {
             "promise_return",
             "validator_stake_base",
             "validator_total_stake_base",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "ed25519_verify_base",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "ed25519_verify_byte",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "ecrecover_base",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "ripemd160_base",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "ripemd160_byte",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "blake2b_base",
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            "blake2b_byte",
         ][index]
     }
 }
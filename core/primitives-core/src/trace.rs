@@ -0,0 +1,214 @@
+//! Opt-in, step-level trace of function call execution.
+//!
+//! Tracing is enabled per thread with `start_tracing`: every function call executed on this thread
+//! until `finish_tracing` records the host functions it called with their arguments and the gas
+//! they charged, the storage it accessed and the receipts it created. It is meant for debugging
+//! and is never enabled while the node applies chunks for consensus.
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash::CryptoHash;
+use crate::serialize::base64_format;
+use crate::types::{AccountId, Gas};
+
+/// Event recorded while a function call is executed, in the order it happened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// Call of a host function with its arguments, lengths and pointers included. Gas is the
+    /// amount charged by this call only.
+    HostFunction {
+        name: String,
+        args: Vec<(String, u64)>,
+        gas_burnt: Gas,
+        gas_used: Gas,
+        error: Option<String>,
+    },
+    StorageRead {
+        #[serde(with = "base64_format")]
+        key: Vec<u8>,
+        /// Length of the value, `None` if the key is not present
+        value_len: Option<u64>,
+    },
+    StorageWrite {
+        #[serde(with = "base64_format")]
+        key: Vec<u8>,
+        value_len: u64,
+    },
+    StorageRemove {
+        #[serde(with = "base64_format")]
+        key: Vec<u8>,
+    },
+    StorageHasKey {
+        #[serde(with = "base64_format")]
+        key: Vec<u8>,
+        found: bool,
+    },
+    /// Receipt created by `promise_batch_create` or `promise_batch_then`
+    PromiseCreate { receipt_index: u64, receiver_id: AccountId, depends_on: Vec<u64> },
+}
+
+/// Trace of a single function call.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCallTrace {
+    /// Receipt being executed, `None` for view calls
+    pub receipt_id: Option<CryptoHash>,
+    pub account_id: AccountId,
+    pub method_name: String,
+    pub gas_burnt: Gas,
+    pub gas_used: Gas,
+    pub error: Option<String>,
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Default)]
+struct Tracer {
+    traces: Vec<FunctionCallTrace>,
+    /// Receipt executed by the following function calls
+    receipt_id: Option<CryptoHash>,
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = RefCell::new(None);
+}
+
+/// Starts collecting the traces of function calls executed on the current thread.
+pub fn start_tracing() {
+    TRACER.with(|tracer| *tracer.borrow_mut() = Some(Tracer::default()));
+}
+
+/// Stops collecting traces on the current thread and returns the ones collected so far.
+pub fn finish_tracing() -> Vec<FunctionCallTrace> {
+    TRACER.with(|tracer| tracer.borrow_mut().take().map(|tracer| tracer.traces).unwrap_or_default())
+}
+
+#[inline]
+pub fn is_tracing() -> bool {
+    TRACER.with(|tracer| tracer.borrow().is_some())
+}
+
+/// Attributes the function calls traced from now on to the receipt being executed.
+pub fn set_receipt_id(receipt_id: CryptoHash) {
+    TRACER.with(|tracer| {
+        if let Some(tracer) = tracer.borrow_mut().as_mut() {
+            tracer.receipt_id = Some(receipt_id);
+        }
+    });
+}
+
+/// Starts the trace of a function call, the following events are recorded into it.
+pub fn begin_function_call(account_id: &str, method_name: &[u8]) {
+    TRACER.with(|tracer| {
+        if let Some(tracer) = tracer.borrow_mut().as_mut() {
+            tracer.traces.push(FunctionCallTrace {
+                receipt_id: tracer.receipt_id,
+                account_id: account_id.to_string(),
+                method_name: String::from_utf8_lossy(method_name).into_owned(),
+                gas_burnt: 0,
+                gas_used: 0,
+                error: None,
+                events: vec![],
+            });
+        }
+    });
+}
+
+/// Sets the totals of the current function call trace.
+pub fn end_function_call(gas_burnt: Gas, gas_used: Gas, error: Option<String>) {
+    with_current_trace(|trace| {
+        trace.gas_burnt = gas_burnt;
+        trace.gas_used = gas_used;
+        trace.error = error;
+    });
+}
+
+/// Records an event into the current function call trace. The event is only built when tracing.
+#[inline]
+pub fn record(event: impl FnOnce() -> TraceEvent) {
+    if is_tracing() {
+        with_current_trace(|trace| trace.events.push(event()));
+    }
+}
+
+/// Records an event into the current function call trace and returns its position, so that it
+/// can be updated with `update_event` once the outcome of the step is known.
+pub fn record_pending(event: TraceEvent) -> Option<usize> {
+    let mut position = None;
+    with_current_trace(|trace| {
+        position = Some(trace.events.len());
+        trace.events.push(event);
+    });
+    position
+}
+
+pub fn update_event(position: usize, update: impl FnOnce(&mut TraceEvent)) {
+    with_current_trace(|trace| {
+        if let Some(event) = trace.events.get_mut(position) {
+            update(event);
+        }
+    });
+}
+
+fn with_current_trace(f: impl FnOnce(&mut FunctionCallTrace)) {
+    TRACER.with(|tracer| {
+        if let Some(trace) =
+            tracer.borrow_mut().as_mut().and_then(|tracer| tracer.traces.last_mut())
+        {
+            f(trace);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracing() {
+        begin_function_call("alice", b"untraced");
+        record(|| TraceEvent::StorageRemove { key: vec![1] });
+        assert!(!is_tracing());
+        assert_eq!(finish_tracing(), vec![]);
+
+        start_tracing();
+        set_receipt_id(CryptoHash::default());
+        begin_function_call("alice", b"main");
+        let position = record_pending(TraceEvent::HostFunction {
+            name: "storage_remove".to_string(),
+            args: vec![("key_len".to_string(), 1)],
+            gas_burnt: 0,
+            gas_used: 0,
+            error: None,
+        })
+        .unwrap();
+        record(|| TraceEvent::StorageRemove { key: vec![1] });
+        update_event(position, |event| {
+            if let TraceEvent::HostFunction { gas_burnt, gas_used, .. } = event {
+                *gas_burnt = 10;
+                *gas_used = 10;
+            }
+        });
+        end_function_call(20, 30, None);
+
+        let traces = finish_tracing();
+        assert!(!is_tracing());
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].receipt_id, Some(CryptoHash::default()));
+        assert_eq!(traces[0].method_name, "main");
+        assert_eq!((traces[0].gas_burnt, traces[0].gas_used), (20, 30));
+        assert_eq!(
+            traces[0].events,
+            vec![
+                TraceEvent::HostFunction {
+                    name: "storage_remove".to_string(),
+                    args: vec![("key_len".to_string(), 1)],
+                    gas_burnt: 10,
+                    gas_used: 10,
+                    error: None,
+                },
+                TraceEvent::StorageRemove { key: vec![1] },
+            ]
+        );
+    }
+}
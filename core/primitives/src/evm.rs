@@ -0,0 +1,250 @@
+//! Logs emitted by EVM contracts.
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash::CryptoHash;
+use crate::types::BlockHeight;
+
+pub type EvmAddress = [u8; 20];
+pub type EvmTopic = [u8; 32];
+
+/// Log emitted by an EVM contract with `LOG0..LOG4`. Logs are stored hex-encoded in the logs of
+/// the execution outcome of the receipt that emitted them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvmLog {
+    /// Contract that emitted the log
+    pub address: EvmAddress,
+    pub topics: Vec<EvmTopic>,
+    pub data: Vec<u8>,
+}
+
+impl EvmLog {
+    /// Encodes the log as the address, the number of topics, the topics and the data.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(20 + 1 + self.topics.len() * 32 + self.data.len());
+        result.extend_from_slice(&self.address);
+        result.push(self.topics.len() as u8);
+        for topic in self.topics.iter() {
+            result.extend_from_slice(topic);
+        }
+        result.extend_from_slice(&self.data);
+        result
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 21 {
+            return None;
+        }
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&bytes[..20]);
+        let num_topics = bytes[20] as usize;
+        let data_offset = 21 + num_topics * 32;
+        if bytes.len() < data_offset {
+            return None;
+        }
+        let topics = bytes[21..data_offset]
+            .chunks(32)
+            .map(|chunk| {
+                let mut topic = [0u8; 32];
+                topic.copy_from_slice(chunk);
+                topic
+            })
+            .collect();
+        Some(Self { address, topics, data: bytes[data_offset..].to_vec() })
+    }
+
+    /// Returns the log as it is stored in the execution outcome logs.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.encode())
+    }
+
+    /// Parses a log of an execution outcome of the EVM account.
+    pub fn from_hex(log: &str) -> Option<Self> {
+        hex::decode(log).ok().and_then(|bytes| Self::decode(&bytes))
+    }
+
+    /// Prefixes of the EVM log index entries of the log: its address and its topics.
+    pub fn index_prefixes(&self) -> Vec<Vec<u8>> {
+        let mut prefixes = vec![address_index_prefix(&self.address)];
+        prefixes.extend(
+            self.topics
+                .iter()
+                .enumerate()
+                .map(|(position, topic)| topic_index_prefix(position as u8, topic)),
+        );
+        prefixes
+    }
+}
+
+fn address_index_prefix(address: &EvmAddress) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(1 + address.len());
+    prefix.push(0);
+    prefix.extend_from_slice(address);
+    prefix
+}
+
+/// Topics are indexed by position, as filters match them by position.
+fn topic_index_prefix(position: u8, topic: &EvmTopic) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(2 + topic.len());
+    prefix.extend_from_slice(&[1, position]);
+    prefix.extend_from_slice(topic);
+    prefix
+}
+
+/// Key of the EVM log index entry recording that the block has logs with the index prefix.
+/// Entries of a prefix are ordered by height.
+pub fn get_evm_log_index_key(
+    prefix: &[u8],
+    block_height: BlockHeight,
+    block_hash: &CryptoHash,
+) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() + 8 + 32);
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(&block_height.to_be_bytes());
+    key.extend_from_slice(block_hash.as_ref());
+    key
+}
+
+/// Returns the height and the hash of the block of an EVM log index entry with the given prefix.
+pub fn parse_evm_log_index_key(prefix: &[u8], key: &[u8]) -> Option<(BlockHeight, CryptoHash)> {
+    if !key.starts_with(prefix) || key.len() != prefix.len() + 8 + 32 {
+        return None;
+    }
+    let rest = &key[prefix.len()..];
+    let mut height = [0u8; 8];
+    height.copy_from_slice(&rest[..8]);
+    let block_hash = CryptoHash::try_from(&rest[8..]).ok()?;
+    Some((BlockHeight::from_be_bytes(height), block_hash))
+}
+
+/// Selects EVM logs following the semantics of `eth_getLogs`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvmLogFilter {
+    pub from_block: BlockHeight,
+    pub to_block: BlockHeight,
+    /// Contracts that emitted the logs, any contract if empty.
+    pub addresses: Vec<EvmAddress>,
+    /// Topics by position. `None` matches any topic, otherwise the topic at that position must
+    /// be one of the listed ones. Logs with fewer topics than the filter don't match.
+    pub topics: Vec<Option<Vec<EvmTopic>>>,
+}
+
+impl EvmLogFilter {
+    pub fn matches(&self, log: &EvmLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        if log.topics.len() < self.topics.len() {
+            return false;
+        }
+        self.topics.iter().zip(log.topics.iter()).all(|(expected, topic)| match expected {
+            Some(expected) => expected.contains(topic),
+            None => true,
+        })
+    }
+
+    /// Groups of EVM log index prefixes selected by the filter. Matching logs have one of the
+    /// prefixes of every group. Empty if the filter matches logs of any contract and topics.
+    pub fn index_prefixes(&self) -> Vec<Vec<Vec<u8>>> {
+        let mut groups = vec![];
+        if !self.addresses.is_empty() {
+            groups.push(self.addresses.iter().map(address_index_prefix).collect());
+        }
+        for (position, topics) in self.topics.iter().enumerate() {
+            if let Some(topics) = topics {
+                match u8::try_from(position) {
+                    Ok(position) => groups.push(
+                        topics.iter().map(|topic| topic_index_prefix(position, topic)).collect(),
+                    ),
+                    // Logs can't have that many topics.
+                    Err(_) => groups.push(vec![]),
+                }
+            }
+        }
+        groups
+    }
+}
+
+/// EVM log together with the receipt and the block it was emitted in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvmLogView {
+    #[serde(flatten)]
+    pub log: EvmLog,
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub receipt_id: CryptoHash,
+    /// Hash of the Ethereum transaction for logs of `raw_call`, otherwise the receipt id
+    pub transaction_hash: CryptoHash,
+    /// Index of the outcome of the receipt among the outcomes of the block in its shard
+    pub transaction_index: u64,
+    /// Index of the log among the logs emitted by the receipt
+    pub log_index: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(address: u8, topics: &[u8]) -> EvmLog {
+        EvmLog {
+            address: [address; 20],
+            topics: topics.iter().map(|topic| [*topic; 32]).collect(),
+            data: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_evm_log_roundtrip() {
+        for log in vec![log(1, &[]), log(2, &[3, 4, 5, 6])] {
+            assert_eq!(EvmLog::from_hex(&log.to_hex()), Some(log));
+        }
+        assert_eq!(EvmLog::decode(&[0; 20]), None);
+        let mut truncated = log(1, &[2]).encode();
+        truncated.truncate(40);
+        assert_eq!(EvmLog::decode(&truncated), None);
+    }
+
+    #[test]
+    fn test_evm_log_filter() {
+        let filter = |addresses: Vec<EvmAddress>, topics: Vec<Option<Vec<EvmTopic>>>| {
+            EvmLogFilter { from_block: 0, to_block: 0, addresses, topics }
+        };
+        let log = log(1, &[2, 3]);
+        assert!(filter(vec![], vec![]).matches(&log));
+        assert!(filter(vec![[1; 20], [5; 20]], vec![]).matches(&log));
+        assert!(!filter(vec![[5; 20]], vec![]).matches(&log));
+        assert!(filter(vec![], vec![None, Some(vec![[4; 32], [3; 32]])]).matches(&log));
+        assert!(!filter(vec![], vec![Some(vec![[3; 32]])]).matches(&log));
+        assert!(!filter(vec![], vec![None, None, None]).matches(&log));
+    }
+
+    #[test]
+    fn test_evm_log_index() {
+        let log = log(1, &[2, 3]);
+        let prefixes = log.index_prefixes();
+        assert_eq!(prefixes.len(), 3);
+        let selected = |filter: EvmLogFilter| {
+            filter
+                .index_prefixes()
+                .iter()
+                .all(|group| group.iter().any(|prefix| prefixes.contains(prefix)))
+        };
+        let filter = |addresses: Vec<EvmAddress>, topics: Vec<Option<Vec<EvmTopic>>>| {
+            EvmLogFilter { from_block: 0, to_block: 0, addresses, topics }
+        };
+        assert!(filter(vec![], vec![None]).index_prefixes().is_empty());
+        assert!(selected(filter(vec![[5; 20], [1; 20]], vec![None, Some(vec![[3; 32]])])));
+        assert!(!selected(filter(vec![], vec![Some(vec![[3; 32]])])));
+        assert!(!selected(filter(vec![], vec![None, None, Some(vec![[2; 32]])])));
+
+        let block_hash = CryptoHash::default();
+        let key = get_evm_log_index_key(&prefixes[1], 7, &block_hash);
+        assert_eq!(parse_evm_log_index_key(&prefixes[1], &key), Some((7, block_hash)));
+        assert_eq!(parse_evm_log_index_key(&prefixes[2], &key), None);
+        assert!(
+            get_evm_log_index_key(&prefixes[1], 256, &block_hash)
+                > get_evm_log_index_key(&prefixes[1], 255, &block_hash)
+        );
+    }
+}
@@ -9,6 +9,8 @@ pub use near_primitives_core::config;
 pub use near_primitives_core::contract;
 pub mod epoch_manager;
 pub mod errors;
+#[cfg(feature = "protocol_feature_evm")]
+pub mod evm;
 pub use near_primitives_core::hash;
 pub use near_primitives_core::logging;
 pub mod merkle;
@@ -18,10 +20,12 @@ pub mod receipt;
 pub mod runtime;
 pub mod serialize;
 pub mod sharding;
+pub mod state_proof;
 pub mod state_record;
 pub mod syncing;
 pub mod telemetry;
 pub mod test_utils;
+pub use near_primitives_core::trace;
 pub mod transaction;
 pub mod trie_key;
 pub mod types;
@@ -0,0 +1,379 @@
+//! Verification of the trie nodes returned by `query` requests made with `include_proof`.
+//!
+//! The node encoding mirrors `RawTrieNodeWithSize` in `near-store`, which this crate cannot
+//! depend on.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read};
+
+use borsh::BorshSerialize;
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::account::Account;
+use crate::hash::{hash, CryptoHash};
+use crate::serialize::from_base64;
+use crate::sharding::ShardChunkHeader;
+use crate::trie_key::{trie_key_parsers, TrieKey};
+use crate::types::{AccountId, StateRoot};
+use crate::views::{AccountView, StateProofView, ViewStateResult};
+
+const LEAF_NODE: u8 = 0;
+const BRANCH_NODE_NO_VALUE: u8 = 1;
+const BRANCH_NODE_WITH_VALUE: u8 = 2;
+const EXTENSION_NODE: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateProofError {
+    /// The proof was produced for a different state root.
+    StateRootMismatch { expected: StateRoot, actual: StateRoot },
+    /// The proof or the verified value is not valid base64.
+    InvalidEncoding(String),
+    /// A trie node required to reach the verified keys is not part of the proof.
+    MissingNode(CryptoHash),
+    /// A trie node in the proof cannot be decoded.
+    InvalidNode(CryptoHash),
+    /// The key is absent from the proven state.
+    MissingValue(Vec<u8>),
+    /// The value under the key does not match the proven state.
+    ValueMismatch(Vec<u8>),
+    /// The proven state holds keys in the requested range that are absent from the response.
+    IncompleteValues,
+}
+
+impl Display for StateProofError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            StateProofError::StateRootMismatch { expected, actual } => {
+                write!(f, "Proof is for state root {}, expected {}", actual, expected)
+            }
+            StateProofError::InvalidEncoding(err) => write!(f, "Invalid base64 encoding: {}", err),
+            StateProofError::MissingNode(hash) => write!(f, "Trie node {} is missing", hash),
+            StateProofError::InvalidNode(hash) => write!(f, "Trie node {} is malformed", hash),
+            StateProofError::MissingValue(key) => write!(f, "Key {:?} is not in the state", key),
+            StateProofError::ValueMismatch(key) => {
+                write!(f, "Value under key {:?} does not match the state", key)
+            }
+            StateProofError::IncompleteValues => {
+                write!(f, "Response omits values present in the requested range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateProofError {}
+
+/// Checks that `account` is the state of `account_id` proven by `proof`, and that the proof is
+/// for the state root `chunk_header` was applied on.
+pub fn verify_account(
+    chunk_header: &ShardChunkHeader,
+    account_id: &AccountId,
+    account: &AccountView,
+    proof: &StateProofView,
+) -> Result<(), StateProofError> {
+    check_state_root(chunk_header, proof)?;
+    verify_account_in_proof(account_id, account, proof)
+}
+
+/// Same as `verify_account`, for callers that already trust `proof.state_root`.
+pub fn verify_account_in_proof(
+    account_id: &AccountId,
+    account: &AccountView,
+    proof: &StateProofView,
+) -> Result<(), StateProofError> {
+    let trie = ProofTrie::new(proof)?;
+    let key = TrieKey::Account { account_id: account_id.clone() }.to_vec();
+    let value = Account::from(account).try_to_vec().expect("Failed to serialize");
+    match trie.lookup(&proof.state_root, &key)? {
+        Some(value_hash) if value_hash == hash(&value) => Ok(()),
+        Some(_) => Err(StateProofError::ValueMismatch(key)),
+        None => Err(StateProofError::MissingValue(key)),
+    }
+}
+
+/// Checks that `result` holds exactly the contract data of `account_id` under `prefix`, starting
+/// at `from_key` and ending before `result.next_cursor`, as proven by `proof`, and that the
+/// proof is for the state root `chunk_header` was applied on.
+pub fn verify_state_items(
+    chunk_header: &ShardChunkHeader,
+    account_id: &AccountId,
+    prefix: &[u8],
+    from_key: Option<&[u8]>,
+    result: &ViewStateResult,
+    proof: &StateProofView,
+) -> Result<(), StateProofError> {
+    check_state_root(chunk_header, proof)?;
+    verify_state_items_in_proof(account_id, prefix, from_key, result, proof)
+}
+
+/// Same as `verify_state_items`, for callers that already trust `proof.state_root`.
+pub fn verify_state_items_in_proof(
+    account_id: &AccountId,
+    prefix: &[u8],
+    from_key: Option<&[u8]>,
+    result: &ViewStateResult,
+    proof: &StateProofView,
+) -> Result<(), StateProofError> {
+    let trie = ProofTrie::new(proof)?;
+    let start = match from_key {
+        Some(from_key) if from_key > prefix => from_key,
+        _ => prefix,
+    };
+    let end = match &result.next_cursor {
+        Some(cursor) => Some(to_nibbles(&trie_key_parsers::get_raw_prefix_for_contract_data(
+            account_id,
+            &decode_base64(cursor)?,
+        ))),
+        None => None,
+    };
+    let range = NibbleRange {
+        prefix: to_nibbles(&trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix)),
+        start: to_nibbles(&trie_key_parsers::get_raw_prefix_for_contract_data(account_id, start)),
+        end,
+    };
+    let mut proven = vec![];
+    trie.collect(&proof.state_root, &mut vec![], &range, &mut proven)?;
+    if proven.len() != result.values.len() {
+        return Err(StateProofError::IncompleteValues);
+    }
+    for ((key, value_hash), item) in proven.into_iter().zip(result.values.iter()) {
+        let item_key = trie_key_parsers::get_raw_prefix_for_contract_data(
+            account_id,
+            &decode_base64(&item.key)?,
+        );
+        if item_key != key {
+            return Err(StateProofError::MissingValue(item_key));
+        }
+        if hash(&decode_base64(&item.value)?) != value_hash {
+            return Err(StateProofError::ValueMismatch(item_key));
+        }
+    }
+    Ok(())
+}
+
+fn check_state_root(
+    chunk_header: &ShardChunkHeader,
+    proof: &StateProofView,
+) -> Result<(), StateProofError> {
+    let expected = chunk_header.prev_state_root();
+    if proof.state_root != expected {
+        return Err(StateProofError::StateRootMismatch { expected, actual: proof.state_root });
+    }
+    Ok(())
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, StateProofError> {
+    from_base64(value).map_err(|err| StateProofError::InvalidEncoding(err.to_string()))
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|byte| vec![byte >> 4, byte & 0x0f]).collect()
+}
+
+fn from_nibbles(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect()
+}
+
+/// Keys starting with `prefix`, at least `start` and below `end`, all as nibbles.
+struct NibbleRange {
+    prefix: Vec<u8>,
+    start: Vec<u8>,
+    end: Option<Vec<u8>>,
+}
+
+impl NibbleRange {
+    fn contains(&self, key: &[u8]) -> bool {
+        key.len() % 2 == 0
+            && key.starts_with(&self.prefix)
+            && key >= &self.start[..]
+            && self.end.as_ref().map_or(true, |end| key < &end[..])
+    }
+
+    /// Whether any key starting with `path` may be in the range.
+    fn intersects(&self, path: &[u8]) -> bool {
+        let len = path.len().min(self.prefix.len());
+        if path[..len] != self.prefix[..len] {
+            return false;
+        }
+        let len = path.len().min(self.start.len());
+        if path[..len] < self.start[..len] {
+            return false;
+        }
+        match &self.end {
+            Some(end) => {
+                let len = path.len().min(end.len());
+                path[..len] < end[..len] || (path[..len] == end[..len] && path.len() < end.len())
+            }
+            None => true,
+        }
+    }
+}
+
+enum ProofNode {
+    Leaf(Vec<u8>, CryptoHash),
+    Branch([Option<CryptoHash>; 16], Option<CryptoHash>),
+    Extension(Vec<u8>, CryptoHash),
+}
+
+/// Trie nodes from a proof, indexed by hash.
+struct ProofTrie {
+    nodes: HashMap<CryptoHash, Vec<u8>>,
+}
+
+impl ProofTrie {
+    fn new(proof: &StateProofView) -> Result<Self, StateProofError> {
+        let nodes = proof
+            .nodes
+            .iter()
+            .map(|node| decode_base64(node).map(|node| (hash(&node), node)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { nodes })
+    }
+
+    fn get_node(&self, node_hash: &CryptoHash) -> Result<ProofNode, StateProofError> {
+        let bytes = self.nodes.get(node_hash).ok_or(StateProofError::MissingNode(*node_hash))?;
+        // The last 8 bytes are the memory usage of the subtree.
+        if bytes.len() < 8 {
+            return Err(StateProofError::InvalidNode(*node_hash));
+        }
+        decode_node(&bytes[..bytes.len() - 8]).ok_or(StateProofError::InvalidNode(*node_hash))
+    }
+
+    /// Returns the hash of the value stored under `key`.
+    fn lookup(&self, root: &CryptoHash, key: &[u8]) -> Result<Option<CryptoHash>, StateProofError> {
+        let key = to_nibbles(key);
+        let mut key = &key[..];
+        let mut node_hash = *root;
+        loop {
+            if node_hash == CryptoHash::default() {
+                return Ok(None);
+            }
+            match self.get_node(&node_hash)? {
+                ProofNode::Leaf(existing_key, value_hash) => {
+                    return Ok(if existing_key == key { Some(value_hash) } else { None });
+                }
+                ProofNode::Extension(existing_key, child) => {
+                    if !key.starts_with(&existing_key) {
+                        return Ok(None);
+                    }
+                    key = &key[existing_key.len()..];
+                    node_hash = child;
+                }
+                ProofNode::Branch(children, value) => {
+                    if key.is_empty() {
+                        return Ok(value);
+                    }
+                    match children[key[0] as usize] {
+                        Some(child) => {
+                            key = &key[1..];
+                            node_hash = child;
+                        }
+                        None => return Ok(None),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Collects keys in `range` and hashes of their values, in key order, from the subtree
+    /// rooted at `node_hash` whose keys start with `path`.
+    fn collect(
+        &self,
+        node_hash: &CryptoHash,
+        path: &mut Vec<u8>,
+        range: &NibbleRange,
+        out: &mut Vec<(Vec<u8>, CryptoHash)>,
+    ) -> Result<(), StateProofError> {
+        if *node_hash == CryptoHash::default() {
+            return Ok(());
+        }
+        let path_len = path.len();
+        match self.get_node(node_hash)? {
+            ProofNode::Leaf(key, value_hash) => {
+                path.extend(key);
+                if range.contains(path) {
+                    out.push((from_nibbles(path), value_hash));
+                }
+            }
+            ProofNode::Extension(key, child) => {
+                path.extend(key);
+                if range.intersects(path) {
+                    self.collect(&child, path, range, out)?;
+                }
+            }
+            ProofNode::Branch(children, value) => {
+                if let Some(value_hash) = value {
+                    if range.contains(path) {
+                        out.push((from_nibbles(path), value_hash));
+                    }
+                }
+                for (index, child) in children.iter().enumerate() {
+                    if let Some(child) = child {
+                        path.push(index as u8);
+                        if range.intersects(path) {
+                            self.collect(child, path, range, out)?;
+                        }
+                        path.pop();
+                    }
+                }
+            }
+        }
+        path.truncate(path_len);
+        Ok(())
+    }
+}
+
+/// Decodes a `RawTrieNode`, returning node keys as nibbles.
+fn decode_node(bytes: &[u8]) -> Option<ProofNode> {
+    let mut cursor = Cursor::new(bytes);
+    match cursor.read_u8().ok()? {
+        LEAF_NODE => {
+            let key = read_key(&mut cursor)?;
+            let _value_length = cursor.read_u32::<LittleEndian>().ok()?;
+            let value_hash = read_hash(&mut cursor)?;
+            Some(ProofNode::Leaf(key, value_hash))
+        }
+        BRANCH_NODE_NO_VALUE => Some(ProofNode::Branch(read_children(&mut cursor)?, None)),
+        BRANCH_NODE_WITH_VALUE => {
+            let _value_length = cursor.read_u32::<LittleEndian>().ok()?;
+            let value_hash = read_hash(&mut cursor)?;
+            Some(ProofNode::Branch(read_children(&mut cursor)?, Some(value_hash)))
+        }
+        EXTENSION_NODE => {
+            let key = read_key(&mut cursor)?;
+            let child = read_hash(&mut cursor)?;
+            Some(ProofNode::Extension(key, child))
+        }
+        _ => None,
+    }
+}
+
+fn read_hash(cursor: &mut Cursor<&[u8]>) -> Option<CryptoHash> {
+    let mut arr = [0; 32];
+    cursor.read_exact(&mut arr).ok()?;
+    CryptoHash::try_from(&arr[..]).ok()
+}
+
+/// Reads a key in the hex-prefix encoding of `NibbleSlice::encode_nibbles`.
+fn read_key(cursor: &mut Cursor<&[u8]>) -> Option<Vec<u8>> {
+    let key_length = cursor.read_u32::<LittleEndian>().ok()?;
+    let mut key = vec![0; key_length as usize];
+    cursor.read_exact(&mut key).ok()?;
+    let first = *key.first()?;
+    let mut nibbles = to_nibbles(&key[1..]);
+    if first & 0x10 == 0x10 {
+        nibbles.insert(0, first & 0x0f);
+    }
+    Some(nibbles)
+}
+
+fn read_children(cursor: &mut Cursor<&[u8]>) -> Option<[Option<CryptoHash>; 16]> {
+    let mut children: [Option<CryptoHash>; 16] = Default::default();
+    let bitmap = cursor.read_u16::<LittleEndian>().ok()?;
+    for (index, child) in children.iter_mut().enumerate() {
+        if bitmap & (1 << index) != 0 {
+            *child = Some(read_hash(cursor)?);
+        }
+    }
+    Some(children)
+}
@@ -16,7 +16,7 @@ pub struct Version {
 pub type DbVersion = u32;
 
 /// Current version of the database.
-pub const DB_VERSION: DbVersion = 16;
+pub const DB_VERSION: DbVersion = 18;
 
 /// Protocol version type.
 pub use near_primitives_core::types::ProtocolVersion;
@@ -83,13 +83,23 @@ pub enum ProtocolFeature {
     ForwardChunkParts,
     #[cfg(feature = "protocol_feature_rectify_inflation")]
     RectifyInflation,
+    /// Adds the EVM account. Logs of its outcomes are hex-encoded `near_primitives::evm::EvmLog`.
     #[cfg(feature = "protocol_feature_evm")]
     EVM,
     #[cfg(feature = "protocol_feature_block_header_v3")]
     BlockHeaderV3,
+    /// Adds `ed25519_verify`, `ecrecover`, `ripemd160` and `blake2b` host functions.
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    CryptoHostFunctions,
     /// Decreases the storage cost of 1 byte by 10X.
     #[cfg(feature = "protocol_feature_lower_storage_cost")]
     LowerStorageCost,
+    /// Peers encrypt and authenticate their connections before exchanging the handshake.
+    #[cfg(feature = "protocol_feature_encrypted_transport")]
+    EncryptedTransport,
+    /// Peers announce the compression algorithms they support in the handshake.
+    #[cfg(feature = "protocol_feature_peer_compression")]
+    PeerCompression,
 }
 
 /// Current latest stable version of the protocol.
@@ -98,7 +108,7 @@ pub const PROTOCOL_VERSION: ProtocolVersion = 42;
 
 /// Current latest nightly version of the protocol.
 #[cfg(feature = "nightly_protocol")]
-pub const PROTOCOL_VERSION: ProtocolVersion = 104;
+pub const PROTOCOL_VERSION: ProtocolVersion = 107;
 
 lazy_static! {
     static ref STABLE_PROTOCOL_FEATURES_TO_VERSION_MAPPING: HashMap<ProtocolFeature, ProtocolVersion> =
@@ -134,6 +144,12 @@ lazy_static! {
             (ProtocolFeature::EVM, 103),
             #[cfg(feature = "protocol_feature_block_header_v3")]
             (ProtocolFeature::BlockHeaderV3, 104),
+            #[cfg(feature = "protocol_feature_crypto_host_functions")]
+            (ProtocolFeature::CryptoHostFunctions, 105),
+            #[cfg(feature = "protocol_feature_encrypted_transport")]
+            (ProtocolFeature::EncryptedTransport, 106),
+            #[cfg(feature = "protocol_feature_peer_compression")]
+            (ProtocolFeature::PeerCompression, 107),
         ]
         .into_iter()
         .collect();
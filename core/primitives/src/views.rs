@@ -5,6 +5,7 @@
 //! from the source structure in the relevant `From<SourceStruct>` impl.
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -27,6 +28,7 @@ use crate::errors::TxExecutionError;
 use crate::hash::{hash, CryptoHash};
 use crate::logging;
 use crate::merkle::MerklePath;
+use crate::network::PeerId;
 use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum};
 use crate::serialize::{
     base64_format, from_base64, option_base64_format, option_u128_dec_format, to_base64,
@@ -46,7 +48,7 @@ use crate::types::{
     StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
     StoreValue, ValidatorKickoutReason, ValidatorStake,
 };
-use crate::version::{ProtocolVersion, Version};
+use crate::version::{DbVersion, ProtocolVersion, Version};
 
 /// A view of the account
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -213,6 +215,18 @@ pub struct StateItem {
 pub struct ViewStateResult {
     pub values: Vec<StateItem>,
     pub proof: TrieProofPath,
+    /// Key (serialized in base64) to pass as `from_key_base64` to fetch the next page of values.
+    /// `None` when all the values under the requested prefix were returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Trie nodes read while answering a query, to be verified against the state root the query
+/// was answered at with `near_primitives::state_proof`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StateProofView {
+    pub state_root: StateRoot,
+    pub nodes: TrieProofPath,
 }
 
 #[derive(
@@ -263,6 +277,8 @@ pub enum QueryResponseKind {
 pub enum QueryRequest {
     ViewAccount {
         account_id: AccountId,
+        #[serde(default)]
+        include_proof: bool,
     },
     ViewCode {
         account_id: AccountId,
@@ -271,6 +287,14 @@ pub enum QueryRequest {
         account_id: AccountId,
         #[serde(rename = "prefix_base64", with = "base64_format")]
         prefix: StoreKey,
+        /// Key to start iterating from (inclusive), as returned in `next_cursor`.
+        #[serde(default, rename = "from_key_base64", with = "option_base64_format")]
+        from_key: Option<Vec<u8>>,
+        /// Maximum number of values to return.
+        #[serde(default)]
+        limit: Option<u64>,
+        #[serde(default)]
+        include_proof: bool,
     },
     ViewAccessKey {
         account_id: AccountId,
@@ -293,6 +317,9 @@ pub struct QueryResponse {
     pub kind: QueryResponseKind,
     pub block_height: BlockHeight,
     pub block_hash: CryptoHash,
+    /// Set when the request asked to `include_proof`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_proof: Option<StateProofView>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1526,3 +1553,102 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 }
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
+
+/// Predicted execution of a transaction, applied with the receipts it produced on top of a block
+/// without being persisted or broadcast.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionSimulationView {
+    /// Block the transaction was applied on top of
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Outcomes of the transaction and of its receipts in the order of execution. They are not
+    /// included in any block, so `block_hash` is the block above and `proof` is empty.
+    pub outcomes: Vec<ExecutionOutcomeWithIdView>,
+    /// Receipts sent by the transaction and by its receipts
+    pub receipts: Vec<ReceiptView>,
+    pub gas_burnt: Gas,
+    #[serde(with = "u128_dec_format")]
+    pub tokens_burnt: Balance,
+    pub state_changes: StateChangesView,
+    /// Receipts left to execute when the simulation reached its limit of blocks
+    pub pending_receipts: Vec<ReceiptView>,
+}
+
+/// Minimal prepaid gas for the function calls of a transaction, found by simulating its execution
+/// on top of a block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GasEstimationView {
+    /// Block the transaction was simulated on top of
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Prepaid gas to attach to each function call action so that the transaction and all the
+    /// receipts it produces succeed
+    pub prepaid_gas: Gas,
+    /// Tokens the signer needs to send the transaction with this prepaid gas, deposits included
+    #[serde(with = "u128_dec_format")]
+    pub total_cost: Balance,
+    pub gas_burnt: Gas,
+    #[serde(with = "u128_dec_format")]
+    pub tokens_burnt: Balance,
+    /// Gas burnt by the transaction and by each of its receipts in the order of execution
+    pub breakdown: Vec<ExecutionGasView>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecutionGasView {
+    /// Hash of the transaction or id of the receipt
+    pub id: CryptoHash,
+    pub executor_id: AccountId,
+    pub gas_burnt: Gas,
+    #[serde(with = "u128_dec_format")]
+    pub tokens_burnt: Balance,
+}
+
+/// Backup of the node database created on the node host.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupView {
+    /// Directory of the backup on the node host
+    pub path: String,
+    pub db_version: DbVersion,
+    /// Hash of the genesis config, checked when the backup is restored
+    pub genesis_hash: CryptoHash,
+    pub head_height: BlockHeight,
+    pub head_hash: CryptoHash,
+}
+
+/// Peer known to the node, as listed to the node operator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownPeerView {
+    pub peer_id: PeerId,
+    pub addr: Option<SocketAddr>,
+    pub account_id: Option<AccountId>,
+    pub status: KnownPeerStatusView,
+    /// Reputation of the peer, built from the data it delivered and its misbehaviour
+    pub score: f64,
+    /// Traffic of the connection to the peer, if it is connected
+    pub connection: Option<PeerConnectionView>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KnownPeerStatusView {
+    Unknown,
+    NotConnected,
+    Connected,
+    Banned {
+        reason: String,
+        /// Time of the ban in nanoseconds since the Unix epoch
+        banned_at: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerConnectionView {
+    /// Whether this node initiated the connection
+    pub outbound: bool,
+    pub height: BlockHeight,
+    pub archival: bool,
+    pub sent_bytes_per_sec: u64,
+    pub received_bytes_per_sec: u64,
+    /// Time since the connection was established, in seconds
+    pub connected_secs: u64,
+}
@@ -0,0 +1,209 @@
+//! Backups of the node database taken while the node runs.
+//!
+//! A backup is a directory holding a RocksDB checkpoint of the database in `data` and a
+//! `backup.json` describing it, which is validated before the backup is restored.
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_primitives::version::{DbVersion, DB_VERSION};
+
+use crate::db::{DBCol, RocksDB, VERSION_KEY};
+use crate::{get_genesis_hash, Store, HEAD_KEY};
+
+/// Name of the file with the metadata of the backup.
+pub const BACKUP_METADATA_FILE: &str = "backup.json";
+/// Name of the directory with the database snapshot.
+pub const BACKUP_DATA_DIR: &str = "data";
+
+/// Description of a backup, written next to the database snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupMetadata {
+    /// Version of the database in the snapshot
+    pub db_version: DbVersion,
+    /// Hash of the genesis config the database was initialized with
+    pub genesis_hash: CryptoHash,
+    pub head_height: BlockHeight,
+    pub head_hash: CryptoHash,
+}
+
+impl BackupMetadata {
+    /// Reads the metadata of the backup in `backup_dir`.
+    pub fn read(backup_dir: &Path) -> io::Result<Self> {
+        let content = fs::read(backup_dir.join(BACKUP_METADATA_FILE))?;
+        serde_json::from_slice(&content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Builds the metadata from the content of the database.
+    fn from_store(store: &Store) -> io::Result<Self> {
+        let db_version = store
+            .get(DBCol::ColDbVersion, VERSION_KEY)?
+            .ok_or_else(|| invalid_data("The database has no version"))?;
+        let db_version = serde_json::from_slice(&db_version)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let genesis_hash = get_genesis_hash(store)?
+            .ok_or_else(|| invalid_data("The database has no genesis hash"))?;
+        let head = store
+            .get_ser::<Tip>(DBCol::ColBlockMisc, HEAD_KEY)?
+            .ok_or_else(|| invalid_data("The database has no head"))?;
+        Ok(Self {
+            db_version,
+            genesis_hash,
+            head_height: head.height,
+            head_hash: head.last_block_hash,
+        })
+    }
+}
+
+/// Snapshots `store` into `output_dir`, which must not exist, and writes the metadata of the
+/// backup. Only stores backed by RocksDB can be backed up.
+pub fn create_backup(store: &Store, output_dir: &Path) -> io::Result<BackupMetadata> {
+    let rocksdb = store.get_rocksdb().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::Other, "Backups are only supported by the RocksDB backend")
+    })?;
+    if output_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", output_dir.display()),
+        ));
+    }
+    fs::create_dir_all(output_dir)?;
+    let data_dir = output_dir.join(BACKUP_DATA_DIR);
+    rocksdb.create_checkpoint(&data_dir).map_err(Into::<io::Error>::into)?;
+    // The metadata is read from the snapshot, the node keeps writing to `store` meanwhile.
+    let metadata = BackupMetadata::from_store(&open_store(&data_dir)?)?;
+    let content = serde_json::to_vec_pretty(&metadata)?;
+    fs::write(output_dir.join(BACKUP_METADATA_FILE), content)?;
+    Ok(metadata)
+}
+
+/// Restores the backup in `backup_dir` into `data_dir`, which must not exist. The backup must be
+/// made for `genesis_hash` and its database must be in a version this binary can migrate.
+pub fn restore_backup(
+    backup_dir: &Path,
+    data_dir: &Path,
+    genesis_hash: &CryptoHash,
+) -> io::Result<BackupMetadata> {
+    let metadata = BackupMetadata::read(backup_dir)?;
+    if metadata.genesis_hash != *genesis_hash {
+        return Err(invalid_data(format!(
+            "The backup was made for genesis {}, but the genesis of this node is {}",
+            metadata.genesis_hash, genesis_hash
+        )));
+    }
+    if metadata.db_version > DB_VERSION {
+        return Err(invalid_data(format!(
+            "The backup has database version {}, but this binary supports up to {}",
+            metadata.db_version, DB_VERSION
+        )));
+    }
+    if data_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", data_dir.display()),
+        ));
+    }
+    fs::create_dir_all(data_dir)?;
+    let result = copy_dir(&backup_dir.join(BACKUP_DATA_DIR), data_dir).and_then(|()| {
+        if BackupMetadata::from_store(&open_store(data_dir)?)? != metadata {
+            return Err(invalid_data("The database in the backup does not match its metadata"));
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        // Leave no partially restored database behind.
+        let _ = fs::remove_dir_all(data_dir);
+        return Err(err);
+    }
+    Ok(metadata)
+}
+
+fn open_store(path: &Path) -> io::Result<Store> {
+    let db = RocksDB::new(path).map_err(Into::<io::Error>::into)?;
+    Ok(Store::new(Arc::pin(db)))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir(&target)?;
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use near_primitives::block::Tip;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::types::EpochId;
+    use near_primitives::version::DB_VERSION;
+
+    use crate::backup::{create_backup, restore_backup, BackupMetadata};
+    use crate::migrations::set_store_version;
+    use crate::{create_store, set_genesis_hash, DBCol, HEAD_KEY};
+
+    #[test]
+    fn test_backup_and_restore() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_backup").tempdir().unwrap();
+        let genesis_hash = hash(b"genesis");
+        let head = Tip {
+            height: 10,
+            last_block_hash: hash(b"head"),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        let store = create_store(tmp_dir.path().join("data").to_str().unwrap());
+        set_store_version(&store, DB_VERSION);
+        let mut store_update = store.store_update();
+        set_genesis_hash(&mut store_update, &genesis_hash);
+        store_update.set_ser(DBCol::ColBlockMisc, HEAD_KEY, &head).unwrap();
+        store_update.set(DBCol::ColBlockMisc, b"before", &[1]);
+        store_update.commit().unwrap();
+
+        let backup_dir = tmp_dir.path().join("backup");
+        let metadata = create_backup(&store, &backup_dir).unwrap();
+        assert_eq!(
+            metadata,
+            BackupMetadata {
+                db_version: DB_VERSION,
+                genesis_hash,
+                head_height: 10,
+                head_hash: head.last_block_hash,
+            }
+        );
+        assert_eq!(BackupMetadata::read(&backup_dir).unwrap(), metadata);
+        assert!(create_backup(&store, &backup_dir).is_err());
+
+        let mut store_update = store.store_update();
+        store_update.set(DBCol::ColBlockMisc, b"after", &[2]);
+        store_update.commit().unwrap();
+
+        let restored_dir = tmp_dir.path().join("restored");
+        assert!(restore_backup(&backup_dir, &restored_dir, &hash(b"other")).is_err());
+        assert!(!restored_dir.exists());
+        assert_eq!(restore_backup(&backup_dir, &restored_dir, &genesis_hash).unwrap(), metadata);
+        assert!(restore_backup(&backup_dir, &restored_dir, &genesis_hash).is_err());
+
+        let restored = create_store(restored_dir.to_str().unwrap());
+        assert_eq!(restored.get(DBCol::ColBlockMisc, b"before").unwrap(), Some(vec![1]));
+        assert_eq!(restored.get(DBCol::ColBlockMisc, b"after").unwrap(), None);
+    }
+}
@@ -12,18 +12,22 @@ use rocksdb::{
     BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, Direction, IteratorMode,
     Options, ReadOptions, WriteBatch, DB,
 };
+use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 
 use near_primitives::version::DbVersion;
 
 use crate::db::refcount::merge_refcounted_records;
 
+pub use crate::db::sled_db::SledDB;
+
 pub(crate) mod migration_utils;
 pub(crate) mod refcount;
+mod sled_db;
 pub(crate) mod v6_to_v7;
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct DBError(rocksdb::Error);
+pub struct DBError(String);
 
 impl std::fmt::Display for DBError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -35,7 +39,7 @@ impl std::error::Error for DBError {}
 
 impl From<rocksdb::Error> for DBError {
     fn from(err: rocksdb::Error) -> Self {
-        DBError(err)
+        DBError(err.into_string())
     }
 }
 
@@ -114,10 +118,12 @@ pub enum DBCol {
     ColReceipts = 45,
     /// Precompiled machine code of the contract
     ColCachedContractCode = 46,
+    /// Blocks with EVM logs by log address and topics, see `near_primitives::evm`.
+    ColEvmLogIndex = 47,
 }
 
 // Do not move this line from enum DBCol
-pub const NUM_COLS: usize = 47;
+pub const NUM_COLS: usize = 48;
 
 impl std::fmt::Display for DBCol {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -169,6 +175,7 @@ impl std::fmt::Display for DBCol {
             Self::ColProcessedBlockHeights => "processed block heights",
             Self::ColReceipts => "receipts",
             Self::ColCachedContractCode => "cached code",
+            Self::ColEvmLogIndex => "evm log index",
         };
         write!(formatter, "{}", desc)
     }
@@ -180,6 +187,18 @@ impl DBCol {
     }
 }
 
+impl std::str::FromStr for DBCol {
+    type Err = String;
+
+    /// Parses the name of the column variant, e.g. `ColState`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use strum::IntoEnumIterator;
+        DBCol::iter()
+            .find(|col| format!("{:?}", col) == s)
+            .ok_or_else(|| format!("Unknown column {}", s))
+    }
+}
+
 // List of columns for which GC should be implemented
 lazy_static! {
     pub static ref SHOULD_COL_GC: Vec<bool> = {
@@ -213,6 +232,8 @@ lazy_static! {
         col_gc[DBCol::ColStateHeaders as usize] = true;
         // True until #2515
         col_gc[DBCol::ColStateParts as usize] = true;
+        // Only blocks with EVM logs have entries
+        col_gc[DBCol::ColEvmLogIndex as usize] = true;
         col_gc
     };
 }
@@ -241,6 +262,57 @@ pub const VERSION_KEY: &[u8; 7] = b"VERSION";
 pub const GENESIS_JSON_HASH_KEY: &[u8; 17] = b"GENESIS_JSON_HASH";
 pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 
+/// Storage engine keeping the node database.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DBBackend {
+    RocksDB,
+    /// Embedded pure-Rust store, see `SledDB`.
+    Sled,
+}
+
+impl Default for DBBackend {
+    fn default() -> Self {
+        DBBackend::RocksDB
+    }
+}
+
+impl std::fmt::Display for DBBackend {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DBBackend::RocksDB => write!(formatter, "rocksdb"),
+            DBBackend::Sled => write!(formatter, "sled"),
+        }
+    }
+}
+
+impl std::str::FromStr for DBBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rocksdb" => Ok(DBBackend::RocksDB),
+            "sled" => Ok(DBBackend::Sled),
+            _ => Err(format!("Unknown database backend {}, expected rocksdb or sled", s)),
+        }
+    }
+}
+
+impl DBBackend {
+    /// Returns the backend of the database at `path` judging by the files it contains, `None`
+    /// if there is no database there.
+    pub fn detect<P: AsRef<std::path::Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        if path.join("CURRENT").exists() {
+            Some(DBBackend::RocksDB)
+        } else if path.join("conf").exists() {
+            Some(DBBackend::Sled)
+        } else {
+            None
+        }
+    }
+}
+
 pub struct DBTransaction {
     pub ops: Vec<DBOp>,
 }
@@ -278,6 +350,16 @@ impl DBTransaction {
     }
 }
 
+/// Size of a column estimated by RocksDB.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColumnSizeEstimates {
+    pub num_keys: u64,
+    /// Size of the keys and values, without the data overwritten or deleted but not compacted yet
+    pub live_data_bytes: u64,
+    /// Size of the SST files on disk
+    pub sst_bytes: u64,
+}
+
 pub struct RocksDB {
     db: DB,
     cfs: Vec<*const ColumnFamily>,
@@ -485,7 +567,7 @@ fn rocksdb_options() -> Options {
 fn rocksdb_block_based_options() -> BlockBasedOptions {
     let mut block_opts = BlockBasedOptions::default();
     block_opts.set_block_size(1024 * 16);
-    // We create block_cache for each of 47 columns, so the total cache size is 32 * 47 = 1504mb
+    // We create block_cache for each of 48 columns, so the total cache size is 32 * 48 = 1536mb
     let cache_size = 1024 * 1024 * 32;
     block_opts.set_block_cache(&Cache::new_lru_cache(cache_size).unwrap());
     block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
@@ -552,6 +634,32 @@ impl RocksDB {
             cf_names.iter().map(|n| db.cf_handle(n).unwrap() as *const ColumnFamily).collect();
         Ok(Self { db, cfs, _pin: PhantomPinned })
     }
+
+    /// Returns the size of a column estimated by RocksDB from its metadata, without reading it.
+    pub fn column_size_estimates(&self, col: DBCol) -> Result<ColumnSizeEstimates, DBError> {
+        let cf_handle = unsafe { &*self.cfs[col as usize] };
+        let property = |name: &str| -> Result<u64, DBError> {
+            Ok(self.db.property_int_value_cf(cf_handle, name)?.unwrap_or_default())
+        };
+        Ok(ColumnSizeEstimates {
+            num_keys: property("rocksdb.estimate-num-keys")?,
+            live_data_bytes: property("rocksdb.estimate-live-data-size")?,
+            sst_bytes: property("rocksdb.total-sst-files-size")?,
+        })
+    }
+
+    /// Compacts the whole column in the calling thread. It never finishes with
+    /// `single_thread_rocksdb`, which has no background threads to run the compaction.
+    pub fn compact(&self, col: DBCol) {
+        self.db.compact_range_cf::<&[u8], &[u8]>(unsafe { &*self.cfs[col as usize] }, None, None);
+    }
+
+    /// Creates a consistent snapshot of the database in `path`, which must not exist. Files are
+    /// hard linked when `path` is on the same filesystem, so this is cheap while the node runs.
+    pub fn create_checkpoint<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), DBError> {
+        let checkpoint = rocksdb::checkpoint::Checkpoint::new(&self.db)?;
+        Ok(checkpoint.create_checkpoint(path)?)
+    }
 }
 
 #[cfg(feature = "single_thread_rocksdb")]
@@ -574,19 +682,10 @@ impl TestDB {
 #[cfg(test)]
 mod tests {
     use crate::db::DBCol::ColState;
-    use crate::db::{rocksdb_read_options, DBError, Database, RocksDB};
-    use crate::{create_store, DBCol};
+    use crate::db::{rocksdb_read_options, DBBackend, DBError, Database, RocksDB};
+    use crate::{create_store, create_store_with_backend, DBCol};
 
     impl RocksDB {
-        #[cfg(not(feature = "single_thread_rocksdb"))]
-        fn compact(&self, col: DBCol) {
-            self.db.compact_range_cf::<&[u8], &[u8]>(
-                unsafe { &*self.cfs[col as usize] },
-                None,
-                None,
-            );
-        }
-
         fn get_no_empty_filtering(
             &self,
             col: DBCol,
@@ -652,4 +751,100 @@ mod tests {
             assert_eq!(store.get(ColState, &[1]).unwrap(), None);
         }
     }
+
+    #[test]
+    fn column_stats() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_column_stats").tempdir().unwrap();
+        let store = create_store(tmp_dir.path().to_str().unwrap());
+        {
+            let mut store_update = store.store_update();
+            store_update.set(DBCol::ColBlockMisc, &[1], &[1, 2]);
+            store_update.set(DBCol::ColBlockMisc, &[2, 3], &[4]);
+            store_update.update_refcount(ColState, &[1], &[1], 1);
+            store_update.commit().unwrap();
+        }
+        let stats = store.column_stats(DBCol::ColBlockMisc);
+        assert_eq!((stats.num_keys, stats.key_bytes, stats.value_bytes), (2, 3, 3));
+        // Refcounted values are counted as stored, with their refcount.
+        let stats = store.column_stats(ColState);
+        assert_eq!((stats.num_keys, stats.key_bytes, stats.value_bytes), (1, 1, 9));
+        assert_eq!(store.column_stats(DBCol::ColBlock).num_keys, 0);
+
+        #[cfg(not(feature = "single_thread_rocksdb"))]
+        {
+            let rocksdb = store.get_rocksdb().unwrap();
+            rocksdb.compact(DBCol::ColBlockMisc);
+            assert!(rocksdb.column_size_estimates(DBCol::ColBlockMisc).unwrap().sst_bytes > 0);
+            assert_eq!(rocksdb.column_size_estimates(DBCol::ColBlock).unwrap().sst_bytes, 0);
+        }
+
+        assert_eq!("ColState".parse::<DBCol>(), Ok(ColState));
+        assert!("State".parse::<DBCol>().is_err());
+    }
+
+    #[test]
+    fn sled_merge_sanity() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_sled_sanity").tempdir().unwrap();
+        let path = tmp_dir.path().to_str().unwrap();
+        let store = create_store_with_backend(path, DBBackend::Sled);
+        for _ in 0..2 {
+            let mut store_update = store.store_update();
+            store_update.update_refcount(ColState, &[1], &[1], 1);
+            store_update.commit().unwrap();
+        }
+        assert_eq!(store.get(ColState, &[1]).unwrap(), Some(vec![1]));
+        assert_eq!(
+            store
+                .iter_without_rc_logic(ColState)
+                .map(|(_, value)| value.to_vec())
+                .collect::<Vec<_>>(),
+            vec![vec![1, 2, 0, 0, 0, 0, 0, 0, 0]]
+        );
+        {
+            let mut store_update = store.store_update();
+            store_update.update_refcount(ColState, &[1], &[1], -1);
+            store_update.update_refcount(ColState, &[1], &[1], -1);
+            store_update.commit().unwrap();
+        }
+        // Zero refcount records are removed right away
+        assert_eq!(store.get(ColState, &[1]).unwrap(), None);
+        assert_eq!(store.iter_without_rc_logic(ColState).count(), 0);
+        assert_eq!(DBBackend::detect(path), Some(DBBackend::Sled));
+    }
+
+    #[test]
+    fn copy_store_between_backends() {
+        let tmp_dir = tempfile::Builder::new().prefix("_test_copy_store").tempdir().unwrap();
+        let rocksdb_path = tmp_dir.path().join("rocksdb");
+        let store = create_store(rocksdb_path.to_str().unwrap());
+        {
+            let mut store_update = store.store_update();
+            store_update.set(DBCol::ColBlockMisc, &[1], &[2]);
+            store_update.update_refcount(ColState, &[1], &[1], 2);
+            store_update.update_refcount(ColState, &[2], &[2], 1);
+            store_update.commit().unwrap();
+        }
+        {
+            let mut store_update = store.store_update();
+            store_update.update_refcount(ColState, &[2], &[2], -1);
+            store_update.commit().unwrap();
+        }
+
+        let sled_store = create_store_with_backend(
+            tmp_dir.path().join("sled").to_str().unwrap(),
+            DBBackend::Sled,
+        );
+        store.copy_to(&sled_store).unwrap();
+        assert_eq!(sled_store.get(DBCol::ColBlockMisc, &[1]).unwrap(), Some(vec![2]));
+        assert_eq!(sled_store.get(ColState, &[1]).unwrap(), Some(vec![1]));
+        assert_eq!(sled_store.get(ColState, &[2]).unwrap(), None);
+        // The refcount is copied, so the value is only gone once both references are removed
+        for expected in [Some(vec![1]), None].iter() {
+            let mut store_update = sled_store.store_update();
+            store_update.update_refcount(ColState, &[1], &[1], -1);
+            store_update.commit().unwrap();
+            assert_eq!(&sled_store.get(ColState, &[1]).unwrap(), expected);
+        }
+        assert_eq!(DBBackend::detect(&rocksdb_path), Some(DBBackend::RocksDB));
+    }
 }
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use sled::transaction::{TransactionError, TransactionResult};
+use sled::{Transactional, Tree};
+use strum::IntoEnumIterator;
+
+use near_primitives::version::DbVersion;
+
+use crate::db::refcount::merge_refcounted_records;
+use crate::db::{DBError, DBOp, DBTransaction, Database, RocksDB, VERSION_KEY};
+use crate::DBCol;
+
+/// Database backed by sled, an embedded pure-Rust store. Every column is kept in its own tree.
+///
+/// Sled has no merge operator usable in transactions, so refcounted records are merged on write
+/// and removed as soon as their refcount reaches zero.
+pub struct SledDB {
+    /// Kept open for the trees, sled flushes pending writes when it is dropped.
+    _db: sled::Db,
+    trees: Vec<Tree>,
+}
+
+impl From<sled::Error> for DBError {
+    fn from(err: sled::Error) -> Self {
+        DBError(err.to_string())
+    }
+}
+
+impl SledDB {
+    /// Returns version of the database state on disk.
+    pub fn get_version<P: AsRef<std::path::Path>>(path: P) -> Result<DbVersion, DBError> {
+        let db = SledDB::new(path)?;
+        db.get(DBCol::ColDbVersion, VERSION_KEY).map(|result| {
+            serde_json::from_slice(
+                &result
+                    .expect("Failed to find version in first column. Database must be corrupted."),
+            )
+            .expect("Failed to parse version. Database must be corrupted.")
+        })
+    }
+
+    pub fn new<P: AsRef<std::path::Path>>(path: P) -> Result<Self, DBError> {
+        let db = sled::Config::new().path(path).open()?;
+        let trees = DBCol::iter()
+            .map(|col| db.open_tree(format!("col{}", col as usize)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { _db: db, trees })
+    }
+
+    fn iter_tree<'a>(
+        iterator: sled::Iter,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        Box::new(iterator.map(|item| {
+            let (key, value) = item.expect("Failed to read from the database");
+            (key.as_ref().into(), value.as_ref().into())
+        }))
+    }
+}
+
+impl Database for SledDB {
+    fn get(&self, col: DBCol, key: &[u8]) -> Result<Option<Vec<u8>>, DBError> {
+        let result = self.trees[col as usize].get(key)?.map(|value| value.to_vec());
+        Ok(RocksDB::get_with_rc_logic(col, result))
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        RocksDB::iter_with_rc_logic(col, self.iter_without_rc_logic(col))
+    }
+
+    fn iter_without_rc_logic<'a>(
+        &'a self,
+        col: DBCol,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        SledDB::iter_tree(self.trees[col as usize].iter())
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        RocksDB::iter_with_rc_logic(
+            col,
+            SledDB::iter_tree(self.trees[col as usize].scan_prefix(key_prefix)),
+        )
+    }
+
+    fn write(&self, transaction: DBTransaction) -> Result<(), DBError> {
+        if transaction.ops.is_empty() {
+            return Ok(());
+        }
+        // Only the trees of the columns being written take part in the transaction.
+        let mut positions = HashMap::new();
+        let mut trees = vec![];
+        for op in transaction.ops.iter() {
+            let col = match op {
+                DBOp::Insert { col, .. }
+                | DBOp::UpdateRefcount { col, .. }
+                | DBOp::Delete { col, .. } => *col,
+            };
+            positions.entry(col).or_insert_with(|| {
+                trees.push(self.trees[col as usize].clone());
+                trees.len() - 1
+            });
+        }
+        // The closure is retried on conflicts, so it must not consume the operations.
+        let result: TransactionResult<()> = trees.as_slice().transaction(|trees| {
+            for op in transaction.ops.iter() {
+                match op {
+                    DBOp::Insert { col, key, value } => {
+                        trees[positions[col]].insert(key.as_slice(), value.as_slice())?;
+                    }
+                    DBOp::UpdateRefcount { col, key, value } => {
+                        assert!(col.is_rc());
+                        let tree = &trees[positions[col]];
+                        let mut val =
+                            tree.get(key.as_slice())?.map(|val| val.to_vec()).unwrap_or_default();
+                        merge_refcounted_records(&mut val, value);
+                        if !val.is_empty() {
+                            tree.insert(key.as_slice(), val)?;
+                        } else {
+                            tree.remove(key.as_slice())?;
+                        }
+                    }
+                    DBOp::Delete { col, key } => {
+                        trees[positions[col]].remove(key.as_slice())?;
+                    }
+                }
+            }
+            Ok(())
+        });
+        result.map_err(|err| match err {
+            TransactionError::Abort(()) => unreachable!("Database writes are never aborted"),
+            TransactionError::Storage(err) => err.into(),
+        })
+    }
+}
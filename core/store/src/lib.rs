@@ -12,10 +12,11 @@ use std::{fmt, io};
 use borsh::{BorshDeserialize, BorshSerialize};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use cached::{Cached, SizedCache};
+use strum::IntoEnumIterator;
 
 pub use db::DBCol::{self, *};
 pub use db::{
-    CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    DBBackend, CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
     LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, NUM_COLS, SHOULD_COL_GC, SKIP_COL_GC, TAIL_KEY,
 };
 use near_crypto::PublicKey;
@@ -31,7 +32,7 @@ use near_primitives::types::{AccountId, CompiledContractCache, StateRoot};
 pub use crate::db::refcount::decode_value_with_rc;
 use crate::db::refcount::encode_value_with_rc;
 use crate::db::{
-    DBOp, DBTransaction, Database, RocksDB, GENESIS_JSON_HASH_KEY, GENESIS_STATE_ROOTS_KEY,
+    DBOp, DBTransaction, Database, RocksDB, SledDB, GENESIS_JSON_HASH_KEY, GENESIS_STATE_ROOTS_KEY,
 };
 pub use crate::trie::{
     iterator::TrieIterator, update::TrieUpdate, update::TrieUpdateIterator,
@@ -39,7 +40,9 @@ pub use crate::trie::{
     WrappedTrieChanges,
 };
 
+pub mod backup;
 pub mod db;
+mod metrics;
 pub mod migrations;
 pub mod test_utils;
 mod trie;
@@ -145,11 +148,90 @@ impl Store {
         self.storage.write(transaction).map_err(|e| e.into())
     }
 
+    /// Copies every column into `target`, usually an empty store with another backend. Values of
+    /// refcounted columns are copied with their refcounts and merged into the target, so that they
+    /// keep being counted the same way there.
+    pub fn copy_to(&self, target: &Store) -> Result<(), io::Error> {
+        for column in DBCol::iter() {
+            let mut transaction = target.storage.transaction();
+            let mut transaction_size = 0;
+            for (key, value) in self.storage.iter_without_rc_logic(column) {
+                transaction_size += key.len() + value.len();
+                if column.is_rc() {
+                    transaction.update_refcount(column, key, value);
+                } else {
+                    transaction.put(column, key, value);
+                }
+                if transaction_size >= COPY_TRANSACTION_SIZE {
+                    let full_transaction =
+                        std::mem::replace(&mut transaction, target.storage.transaction());
+                    target.storage.write(full_transaction).map_err(Into::<io::Error>::into)?;
+                    transaction_size = 0;
+                }
+            }
+            target.storage.write(transaction).map_err(Into::<io::Error>::into)?;
+        }
+        Ok(())
+    }
+
     pub fn get_rocksdb(&self) -> Option<&RocksDB> {
         self.storage.as_rocksdb()
     }
+
+    /// Counts the keys of a column and the bytes taken by the keys and values as they are stored,
+    /// refcounts included. It reads the whole column.
+    pub fn column_stats(&self, column: DBCol) -> ColumnStats {
+        let mut stats = ColumnStats::default();
+        for (key, value) in self.storage.iter_without_rc_logic(column) {
+            stats.num_keys += 1;
+            stats.key_bytes += key.len() as u64;
+            stats.value_bytes += value.len() as u64;
+        }
+        stats
+    }
+
+    /// Sets the gauges with the size of every column estimated by RocksDB. Other backends do not
+    /// report them.
+    pub fn update_metrics(&self) {
+        let rocksdb = match self.get_rocksdb() {
+            Some(rocksdb) => rocksdb,
+            None => return,
+        };
+        for column in DBCol::iter() {
+            if let Ok(estimates) = rocksdb.column_size_estimates(column) {
+                let column_name = format!("{:?}", column);
+                let label = [column_name.as_str()];
+                near_metrics::set_gauge_vec(
+                    &metrics::DB_COLUMN_ESTIMATED_KEYS,
+                    &label,
+                    estimates.num_keys as i64,
+                );
+                near_metrics::set_gauge_vec(
+                    &metrics::DB_COLUMN_LIVE_DATA_BYTES,
+                    &label,
+                    estimates.live_data_bytes as i64,
+                );
+                near_metrics::set_gauge_vec(
+                    &metrics::DB_COLUMN_SST_BYTES,
+                    &label,
+                    estimates.sst_bytes as i64,
+                );
+            }
+        }
+    }
 }
 
+/// Exact size of a column, see `Store::column_stats`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColumnStats {
+    pub num_keys: u64,
+    pub key_bytes: u64,
+    pub value_bytes: u64,
+}
+
+/// Size of the keys and values written at once when copying a store.
+const COPY_TRANSACTION_SIZE: usize = 64 * 1024 * 1024;
+
 /// Keeps track of current changes to the database and can commit all of them to the database.
 pub struct StoreUpdate {
     storage: Pin<Arc<dyn Database>>,
@@ -289,7 +371,14 @@ pub fn read_with_cache<'a, T: BorshDeserialize + 'a>(
 }
 
 pub fn create_store(path: &str) -> Arc<Store> {
-    let db = Arc::pin(RocksDB::new(path).expect("Failed to open the database"));
+    create_store_with_backend(path, DBBackend::RocksDB)
+}
+
+pub fn create_store_with_backend(path: &str, backend: DBBackend) -> Arc<Store> {
+    let db: Pin<Arc<dyn Database>> = match backend {
+        DBBackend::RocksDB => Arc::pin(RocksDB::new(path).expect("Failed to open the database")),
+        DBBackend::Sled => Arc::pin(SledDB::new(path).expect("Failed to open the database")),
+    };
     Arc::new(Store::new(db))
 }
 
@@ -0,0 +1,22 @@
+use near_metrics::{try_create_int_gauge_vec, IntGaugeVec};
+
+lazy_static! {
+    pub static ref DB_COLUMN_ESTIMATED_KEYS: near_metrics::Result<IntGaugeVec> =
+        try_create_int_gauge_vec(
+            "near_db_column_estimated_keys",
+            "Number of keys in a database column estimated by RocksDB",
+            &["column"]
+        );
+    pub static ref DB_COLUMN_LIVE_DATA_BYTES: near_metrics::Result<IntGaugeVec> =
+        try_create_int_gauge_vec(
+            "near_db_column_live_data_bytes",
+            "Size of the keys and values of a database column estimated by RocksDB",
+            &["column"]
+        );
+    pub static ref DB_COLUMN_SST_BYTES: near_metrics::Result<IntGaugeVec> =
+        try_create_int_gauge_vec(
+            "near_db_column_sst_bytes",
+            "Size of the SST files of a database column",
+            &["column"]
+        );
+}
@@ -796,6 +796,34 @@ mod tests {
         assert_eq!(trie.iter(&new_root).unwrap().fold(0, |acc, _| acc + 1), 0);
     }
 
+    #[test]
+    fn test_overlay_trie() {
+        let tries = create_tries();
+        let root = test_populate_trie(
+            &tries,
+            &Trie::empty_root(),
+            0,
+            vec![(b"dog".to_vec(), Some(b"puppy".to_vec()))],
+        );
+        let trie = tries.get_trie_for_shard(0);
+        let changes1 = trie
+            .update(&root, vec![(b"doge".to_vec(), Some(b"coin".to_vec()))].into_iter())
+            .unwrap();
+        // Nodes of uncommitted changes are only visible through the overlay.
+        assert!(trie.get(&changes1.new_root, b"doge").is_err());
+        let overlay_trie = tries.get_overlay_trie_for_shard(0, &[changes1.clone()]);
+        assert_eq!(overlay_trie.get(&changes1.new_root, b"doge"), Ok(Some(b"coin".to_vec())));
+        assert_eq!(overlay_trie.get(&changes1.new_root, b"dog"), Ok(Some(b"puppy".to_vec())));
+
+        let changes2 = overlay_trie
+            .update(&changes1.new_root, vec![(b"dog".to_vec(), None)].into_iter())
+            .unwrap();
+        let overlay_trie = tries.get_overlay_trie_for_shard(0, &[changes1, changes2.clone()]);
+        assert_eq!(overlay_trie.get(&changes2.new_root, b"dog"), Ok(None));
+        assert_eq!(overlay_trie.get(&changes2.new_root, b"doge"), Ok(Some(b"coin".to_vec())));
+        assert_eq!(trie.get(&root, b"dog"), Ok(Some(b"puppy".to_vec())));
+    }
+
     #[test]
     fn test_trie_iter() {
         let tries = create_tries();
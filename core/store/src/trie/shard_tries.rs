@@ -1,5 +1,5 @@
 use crate::db::{DBCol, DBOp, DBTransaction};
-use crate::trie::trie_storage::{TrieCache, TrieCachingStorage};
+use crate::trie::trie_storage::{TrieCache, TrieCachingStorage, TrieOverlayStorage};
 use crate::{StorageError, Store, StoreUpdate, Trie, TrieChanges, TrieUpdate};
 use borsh::BorshSerialize;
 use near_primitives::hash::CryptoHash;
@@ -60,6 +60,27 @@ impl ShardTries {
         self.get_trie_for_shard_internal(shard_id, true)
     }
 
+    /// Returns a view trie of the shard which also contains the nodes inserted by `trie_changes`.
+    /// These nodes are only kept in memory, so the resulting roots can be read and updated further
+    /// without anything being written to the store.
+    pub fn get_overlay_trie_for_shard(
+        &self,
+        shard_id: ShardId,
+        trie_changes: &[TrieChanges],
+    ) -> Trie {
+        let base = TrieCachingStorage::new(
+            self.store.clone(),
+            self.view_caches[shard_id as usize].clone(),
+            shard_id,
+        );
+        let overlay = trie_changes
+            .iter()
+            .flat_map(|changes| changes.insertions.iter())
+            .map(|(hash, value, _rc)| (*hash, value.clone()))
+            .collect();
+        Trie::new(Box::new(TrieOverlayStorage { base, overlay }), shard_id)
+    }
+
     pub fn get_store(&self) -> Arc<Store> {
         self.store.clone()
     }
@@ -114,6 +114,23 @@ impl TrieStorage for TrieMemoryPartialStorage {
     }
 }
 
+/// Storage that serves trie nodes inserted by uncommitted `TrieChanges` from memory and all the
+/// other nodes from the underlying storage.
+/// Used to apply several state transitions on top of each other without writing them to the store.
+pub struct TrieOverlayStorage {
+    pub(crate) base: TrieCachingStorage,
+    pub(crate) overlay: HashMap<CryptoHash, Vec<u8>>,
+}
+
+impl TrieStorage for TrieOverlayStorage {
+    fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Vec<u8>, StorageError> {
+        match self.overlay.get(hash) {
+            Some(val) => Ok(val.clone()),
+            None => self.base.retrieve_raw_bytes(hash),
+        }
+    }
+}
+
 /// Maximum number of cache entries.
 #[cfg(not(feature = "no_cache"))]
 const TRIE_MAX_CACHE_SIZE: usize = 10000;
@@ -14,9 +14,12 @@ use num_rational::Rational;
 use serde::{Deserialize, Serialize};
 
 use lazy_static::lazy_static;
-use near_chain_configs::{ClientConfig, Genesis, GenesisConfig, LogSummaryStyle};
+use near_chain_configs::{
+    ClientConfig, Genesis, GenesisConfig, LogSummaryStyle, TransactionPoolConfig,
+};
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
 use near_jsonrpc::RpcConfig;
+use near_network::capture::CaptureConfig;
 use near_network::test_utils::open_port;
 use near_network::types::ROUTED_MESSAGE_TTL;
 use near_network::utils::blacklist_from_iter;
@@ -34,6 +37,7 @@ use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner
 use near_primitives::version::PROTOCOL_VERSION;
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::RosettaRpcConfig;
+use near_store::DBBackend;
 use near_telemetry::TelemetryConfig;
 
 /// Initial balance used in tests.
@@ -245,6 +249,13 @@ pub struct Network {
     /// Period to check on peer status
     #[serde(default = "default_peer_stats_period")]
     pub peer_stats_period: Duration,
+    /// Capture the messages exchanged with peers to a file, to replay them with
+    /// `neard replay_capture`.
+    #[serde(default)]
+    pub capture: Option<CaptureConfig>,
+    /// Refuse connections with peers that don't encrypt the transport.
+    #[serde(default)]
+    pub require_encryption: bool,
 }
 
 impl Default for Network {
@@ -267,6 +278,8 @@ impl Default for Network {
             blacklist: vec![],
             ttl_account_id_router: default_ttl_account_id_router(),
             peer_stats_period: default_peer_stats_period(),
+            capture: None,
+            require_encryption: false,
         }
     }
 }
@@ -419,6 +432,16 @@ pub struct Config {
     pub gc_blocks_limit: NumBlocks,
     #[serde(default = "default_view_client_threads")]
     pub view_client_threads: usize,
+    pub transaction_pool: TransactionPoolConfig,
+    /// Maximum depth of cross-contract calls that view calls follow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_view_call_depth: Option<u32>,
+    /// Maximum number of values returned by a view state query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_view_state_values: Option<u64>,
+    /// Storage engine of the node database, `rocksdb` or `sled`.
+    #[serde(default)]
+    pub store_backend: DBBackend,
 }
 
 impl Default for Config {
@@ -440,6 +463,10 @@ impl Default for Config {
             log_summary_style: LogSummaryStyle::Colored,
             gc_blocks_limit: default_gc_blocks_limit(),
             view_client_threads: 4,
+            transaction_pool: TransactionPoolConfig::default(),
+            max_view_call_depth: None,
+            max_view_state_values: None,
+            store_backend: DBBackend::default(),
         }
     }
 }
@@ -606,6 +633,9 @@ impl NearConfig {
                 log_summary_style: config.log_summary_style,
                 gc_blocks_limit: config.gc_blocks_limit,
                 view_client_threads: config.view_client_threads,
+                transaction_pool: config.transaction_pool,
+                max_view_call_depth: config.max_view_call_depth,
+                max_view_state_values: config.max_view_state_values,
             },
             network_config: NetworkConfig {
                 public_key: network_key_pair.public_key,
@@ -650,6 +680,8 @@ impl NearConfig {
                 blacklist: blacklist_from_iter(config.network.blacklist),
                 outbound_disabled: false,
                 archive: config.archive,
+                capture: config.network.capture,
+                require_encryption: config.network.require_encryption,
             },
             telemetry_config: config.telemetry,
             rpc_config: config.rpc,
@@ -1031,7 +1063,12 @@ pub fn download_genesis(url: &String, path: &PathBuf) {
 }
 
 pub fn load_config(dir: &Path) -> NearConfig {
-    let config = Config::from_file(&dir.join(CONFIG_FILENAME));
+    #[allow(unused_mut)]
+    let mut config = Config::from_file(&dir.join(CONFIG_FILENAME));
+    #[cfg(feature = "protocol_feature_evm")]
+    if let Some(relayer_key_file) = config.rpc.eth_rpc.relayer_key_file.as_mut() {
+        *relayer_key_file = dir.join(&relayer_key_file);
+    }
     let genesis = if let Some(ref genesis_records_file) = config.genesis_records_file {
         Genesis::from_files(&dir.join(&config.genesis_file), &dir.join(genesis_records_file))
     } else {
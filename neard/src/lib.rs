@@ -14,11 +14,13 @@ use near_jsonrpc::start_http;
 use near_network::{NetworkRecipient, PeerManagerActor};
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::start_rosetta_rpc;
-use near_store::{create_store, Store};
+use near_store::backup::{create_backup, restore_backup, BackupMetadata};
+use near_store::db::SledDB;
+use near_store::{create_store, create_store_with_backend, DBBackend, Store};
 use near_telemetry::TelemetryActor;
 
 pub use crate::config::{init_configs, load_config, load_test_config, NearConfig, NEAR_BASE};
-use crate::migrations::migrate_12_to_13;
+use crate::migrations::{migrate_12_to_13, migrate_16_to_17, migrate_17_to_18};
 pub use crate::runtime::NightshadeRuntime;
 use near_store::migrations::{
     fill_col_outcomes_by_hash, fill_col_transaction_refcount, get_store_version, migrate_10_to_11,
@@ -177,6 +179,16 @@ pub fn apply_store_migrations(path: &String, near_config: &NearConfig) {
         let store = create_store(&path);
         set_store_version(&store, 16);
     }
+    if db_version <= 16 {
+        info!(target: "near", "Migrate DB from version 16 to 17");
+        // version 16 => 17: add score to known peers in ColPeers
+        migrate_16_to_17(&path);
+    }
+    if db_version <= 17 {
+        info!(target: "near", "Migrate DB from version 17 to 18");
+        // version 17 => 18: add encryption to known peers in ColPeers
+        migrate_17_to_18(&path);
+    }
     #[cfg(feature = "protocol_feature_rectify_inflation")]
     if db_version <= 16 {
         // version 16 => rectify inflation: add `timestamp` to `BlockInfo`
@@ -198,31 +210,100 @@ pub fn apply_store_migrations(path: &String, near_config: &NearConfig) {
 
 pub fn init_and_migrate_store(home_dir: &Path, near_config: &NearConfig) -> Arc<Store> {
     let path = get_store_path(home_dir);
+    let backend = near_config.config.store_backend;
     let store_exists = store_path_exists(&path);
     if store_exists {
-        apply_store_migrations(&path, near_config);
+        match DBBackend::detect(&path) {
+            Some(existing_backend) if existing_backend != backend => {
+                error!(target: "near", "Database at {} is a {} database while {} is configured, use `copy_db` to convert it", path, existing_backend, backend);
+                std::process::exit(1);
+            }
+            _ => {}
+        }
+        match backend {
+            DBBackend::RocksDB => apply_store_migrations(&path, near_config),
+            DBBackend::Sled => {
+                // Sled databases are created at the current version, migrations only run on
+                // RocksDB databases.
+                let db_version = SledDB::get_version(&path).expect("Failed to open the database");
+                if db_version != near_primitives::version::DB_VERSION {
+                    error!(target: "near", "DB version {} of the sled database is not supported, convert it with `copy_db` from a RocksDB database", db_version);
+                    std::process::exit(1);
+                }
+            }
+        }
     }
-    let store = create_store(&path);
+    let store = create_store_with_backend(&path, backend);
     if !store_exists {
         set_store_version(&store, near_primitives::version::DB_VERSION);
     }
     store
 }
 
+/// Copies the node database into a new database at `target_path` with the given backend. The
+/// node database is migrated to the current version first.
+pub fn copy_store(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    target_path: &Path,
+    target_backend: DBBackend,
+) -> Result<(), std::io::Error> {
+    if store_path_exists(target_path) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{:?} already exists", target_path),
+        ));
+    }
+    let store = init_and_migrate_store(home_dir, near_config);
+    let target = create_store_with_backend(target_path.to_str().unwrap(), target_backend);
+    info!(target: "near", "Copying the database into a {} database at {:?}", target_backend, target_path);
+    store.copy_to(&target)
+}
+
+/// Creates a backup of the database of a stopped node in `output_dir`. A running node creates it
+/// with the `admin_create_backup` RPC method instead.
+pub fn backup_store(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    output_dir: &Path,
+) -> Result<BackupMetadata, std::io::Error> {
+    let store = init_and_migrate_store(home_dir, near_config);
+    create_backup(&store, output_dir)
+}
+
+/// Restores the backup in `backup_dir` as the database of the node, which must not have one yet.
+/// The backup must be made by a node with the same genesis.
+pub fn restore_store(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    backup_dir: &Path,
+) -> Result<BackupMetadata, std::io::Error> {
+    if near_config.config.store_backend != DBBackend::RocksDB {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Backups can only be restored into a rocksdb database",
+        ));
+    }
+    let store_path = get_store_path(home_dir);
+    restore_backup(backup_dir, Path::new(&store_path), &near_config.genesis.json_hash())
+}
+
 pub fn start_with_config(
     home_dir: &Path,
     config: NearConfig,
 ) -> (Addr<ClientActor>, Addr<ViewClientActor>, Vec<Arbiter>) {
     let store = init_and_migrate_store(home_dir, &config);
+    let runtime = Arc::new(NightshadeRuntime::with_config(home_dir, Arc::clone(&store), &config));
+    start_with_runtime(config, store, runtime)
+}
 
-    let runtime = Arc::new(NightshadeRuntime::new(
-        home_dir,
-        Arc::clone(&store),
-        &config.genesis,
-        config.client_config.tracked_accounts.clone(),
-        config.client_config.tracked_shards.clone(),
-    ));
-
+/// Starts the node on top of the given store and runtime, which lets the caller keep reading
+/// from them directly.
+pub fn start_with_runtime(
+    config: NearConfig,
+    store: Arc<Store>,
+    runtime: Arc<NightshadeRuntime>,
+) -> (Addr<ClientActor>, Addr<ViewClientActor>, Vec<Arbiter>) {
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::from(&config.genesis);
 
@@ -256,7 +337,9 @@ pub fn start_with_config(
         config.genesis.config.clone(),
         client_actor.clone(),
         view_client.clone(),
-    );
+        network_adapter.clone(),
+    )
+    .expect("Failed to start the RPC server");
     #[cfg(feature = "rosetta_rpc")]
     if let Some(rosetta_rpc_config) = config.rosetta_rpc_config {
         start_rosetta_rpc(
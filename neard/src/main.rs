@@ -2,7 +2,9 @@ use std::convert::TryInto;
 use std::env;
 use std::fs;
 use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::Path;
+use std::str::FromStr;
 
 use actix::System;
 use clap::{crate_version, App, AppSettings, Arg, SubCommand};
@@ -13,13 +15,22 @@ use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
 use git_version::git_version;
+use near_crypto::{KeyType, PublicKey, SecretKey};
+use near_jsonrpc::client::new_client_with_auth;
+use near_jsonrpc_primitives::types::admin::RpcCreateBackupRequest;
+use near_network::capture::{replay_capture, CaptureReader};
 use near_performance_metrics;
+use near_primitives::network::PeerId;
 use near_primitives::version::{Version, PROTOCOL_VERSION};
 #[cfg(feature = "memory_stats")]
 use near_rust_allocator_proxy::allocator::MyAllocator;
+use near_store::DBBackend;
 use neard::config::init_testnet_configs;
 use neard::genesis_validate::validate_genesis;
-use neard::{get_default_home, get_store_path, init_configs, load_config, start_with_config};
+use neard::{
+    backup_store, copy_store, get_default_home, get_store_path, init_configs, load_config,
+    restore_store, start_with_config,
+};
 
 #[cfg(feature = "memory_stats")]
 #[global_allocator]
@@ -117,6 +128,22 @@ fn main() {
             .arg(Arg::with_name("telemetry-url").long("telemetry-url").help("Customize telemetry url").takes_value(true))
             .arg(Arg::with_name("archive").long("archive").help("Keep old blocks in the storage (default false)").takes_value(false))
         )
+        .subcommand(SubCommand::with_name("copy_db").about("Copies the node database into a new database, possibly with another storage engine")
+            .arg(Arg::with_name("target").long("target").help("Directory of the new database, it must not exist").takes_value(true).required(true))
+            .arg(Arg::with_name("backend").long("backend").help("Storage engine of the new database: rocksdb or sled (default sled)").takes_value(true))
+        )
+        .subcommand(SubCommand::with_name("backup").about("Creates a consistent snapshot of the database of the running node, which must enable `rpc.enable_admin_rpc` and set `rpc.admin_addr`")
+            .arg(Arg::with_name("output").long("output").help("Directory of the backup, it must not exist").takes_value(true).required(true))
+            .arg(Arg::with_name("offline").long("offline").help("Open the database directly instead of asking the running node, the node must be stopped").takes_value(false))
+        )
+        .subcommand(SubCommand::with_name("restore").about("Restores a backup as the database of the node after checking it was made for the same genesis")
+            .arg(Arg::with_name("input").long("input").help("Directory of the backup").takes_value(true).required(true))
+        )
+        .subcommand(SubCommand::with_name("replay_capture").about("Connects to the running node as a new peer and sends it the messages received in a capture of `network.capture`")
+            .arg(Arg::with_name("capture").long("capture").help("Capture file").takes_value(true).required(true))
+            .arg(Arg::with_name("peer-id").long("peer-id").help("Only replay the messages received from this peer").takes_value(true))
+            .arg(Arg::with_name("paced").long("paced").help("Send the messages with the delays they were received with, instead of as fast as possible").takes_value(false))
+        )
         .subcommand(SubCommand::with_name("unsafe_reset_data").about("(unsafe) Remove all the data, effectively resetting node to genesis state (keeps genesis and config)"))
         .subcommand(SubCommand::with_name("unsafe_reset_all").about("(unsafe) Remove all the config, keys, data and effectively removing all information about the network"))
         .get_matches();
@@ -250,6 +277,89 @@ fn main() {
                 })
                 .unwrap();
         }
+        ("copy_db", Some(args)) => {
+            let near_config = load_config(home_dir);
+            let target = Path::new(args.value_of("target").unwrap());
+            let backend = args
+                .value_of("backend")
+                .map(|x| x.parse().expect("Failed to parse the database backend"))
+                .unwrap_or(DBBackend::Sled);
+            copy_store(home_dir, &near_config, target, backend)
+                .expect("Copying the database failed");
+            info!(target: "near", "Copied the database to {:?}, set `store_backend` to \"{}\" in config.json and move it to {}", target, backend, get_store_path(home_dir));
+        }
+        ("backup", Some(args)) => {
+            let near_config = load_config(home_dir);
+            let output = Path::new(args.value_of("output").unwrap());
+            let (head_height, head_hash) = if args.is_present("offline") {
+                let metadata = backup_store(home_dir, &near_config, output)
+                    .expect("Creating the backup failed");
+                (metadata.head_height, metadata.head_hash)
+            } else {
+                // The running node holds the database, so it creates the snapshot itself. The
+                // directory is made absolute as the node may run in another working directory.
+                let output_dir = env::current_dir()
+                    .expect("Failed to get the current directory")
+                    .join(output)
+                    .to_str()
+                    .expect("Backup directory must be valid UTF-8")
+                    .to_string();
+                let rpc_config = near_config.rpc_config;
+                let mut admin_addr: SocketAddr = rpc_config
+                    .admin_addr
+                    .expect("The admin methods are only served on `rpc.admin_addr`")
+                    .parse()
+                    .expect("Failed to parse the admin RPC address");
+                if admin_addr.ip().is_unspecified() {
+                    admin_addr.set_ip(Ipv4Addr::LOCALHOST.into());
+                }
+                let auth_token =
+                    rpc_config.admin_auth_token.expect("`rpc.admin_addr` requires a token");
+                let response = System::builder()
+                    .build()
+                    .block_on(async move {
+                        let server_addr = format!("http://{}", admin_addr);
+                        let client = new_client_with_auth(&server_addr, &auth_token);
+                        client.admin_create_backup(RpcCreateBackupRequest { output_dir }).await
+                    })
+                    .expect("Creating the backup failed");
+                (response.backup.head_height, response.backup.head_hash)
+            };
+            info!(target: "near", "Created backup at height {} ({}) in {:?}", head_height, head_hash, output);
+        }
+        ("restore", Some(args)) => {
+            let near_config = load_config(home_dir);
+            let input = Path::new(args.value_of("input").unwrap());
+            let metadata =
+                restore_store(home_dir, &near_config, input).expect("Restoring the backup failed");
+            info!(target: "near", "Restored backup at height {} ({}) into {}", metadata.head_height, metadata.head_hash, get_store_path(home_dir));
+        }
+        ("replay_capture", Some(args)) => {
+            let near_config = load_config(home_dir);
+            let mut addr =
+                near_config.network_config.addr.expect("The node must listen on `network.addr`");
+            if addr.ip().is_unspecified() {
+                addr.set_ip(Ipv4Addr::LOCALHOST.into());
+            }
+            let peer_id: Option<PeerId> = args.value_of("peer-id").map(|peer_id| {
+                PublicKey::from_str(peer_id).expect("Failed to parse the peer id").into()
+            });
+            let records = CaptureReader::open(Path::new(args.value_of("capture").unwrap()))
+                .expect("Failed to open the capture")
+                .map(|record| record.expect("Failed to read the capture"))
+                .filter(|record| {
+                    peer_id.as_ref().map_or(true, |peer_id| &record.peer_id == peer_id)
+                })
+                .collect::<Vec<_>>();
+            let paced = args.is_present("paced");
+            // A fresh identity, the node must not mistake the replay for the captured peers.
+            let secret_key = SecretKey::from_random(KeyType::ED25519);
+            let stats = System::builder()
+                .build()
+                .block_on(replay_capture(addr, &secret_key, records, paced))
+                .expect("Replaying the capture failed");
+            info!(target: "near", "Replayed {} messages to {}, skipped {}", stats.sent, addr, stats.skipped);
+        }
         ("unsafe_reset_data", Some(_args)) => {
             let store_path = get_store_path(home_dir);
             info!(target: "near", "Removing all data from {}", store_path);
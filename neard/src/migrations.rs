@@ -1,8 +1,9 @@
 use crate::{NearConfig, NightshadeRuntime};
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_chain::chain::collect_receipts_from_response;
 use near_chain::types::ApplyTransactionResult;
 use near_chain::{ChainStore, ChainStoreAccess, ChainStoreUpdate, RuntimeAdapter};
+use near_network::types::{KnownPeerState, KnownPeerStatus, PeerInfo};
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader, ShardChunkV1};
 use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
 use near_primitives::types::{BlockHeight, ShardId};
@@ -95,6 +96,7 @@ pub fn migrate_12_to_13(path: &String, near_config: &NearConfig) {
             &near_config.genesis,
             near_config.client_config.tracked_accounts.clone(),
             near_config.client_config.tracked_shards.clone(),
+            near_config.client_config.max_view_call_depth,
         );
         store.get_rocksdb().unwrap().clear_column(DBCol::ColTransactionResult);
 
@@ -117,3 +119,60 @@ pub fn migrate_12_to_13(path: &String, near_config: &NearConfig) {
     }
     set_store_version(&store, 13);
 }
+
+/// `KnownPeerState` as stored in versions 17 and below.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct KnownPeerStateV17 {
+    peer_info: PeerInfo,
+    status: KnownPeerStatus,
+    first_seen: u64,
+    last_seen: u64,
+    score: f64,
+    score_updated: u64,
+}
+
+pub fn migrate_16_to_17(path: &String) {
+    #[derive(BorshDeserialize)]
+    struct OldKnownPeerState {
+        peer_info: PeerInfo,
+        status: KnownPeerStatus,
+        first_seen: u64,
+        last_seen: u64,
+    }
+    let store = create_store(path);
+    let mut store_update = store.store_update();
+    for (key, value) in store.iter(DBCol::ColPeers) {
+        let old_peer_state = OldKnownPeerState::try_from_slice(&value).unwrap();
+        let peer_state = KnownPeerStateV17 {
+            peer_info: old_peer_state.peer_info,
+            status: old_peer_state.status,
+            first_seen: old_peer_state.first_seen,
+            last_seen: old_peer_state.last_seen,
+            score: 0.0,
+            score_updated: old_peer_state.last_seen,
+        };
+        store_update.set_ser(DBCol::ColPeers, &key, &peer_state).unwrap();
+    }
+    store_update.commit().unwrap();
+    set_store_version(&store, 17);
+}
+
+pub fn migrate_17_to_18(path: &String) {
+    let store = create_store(path);
+    let mut store_update = store.store_update();
+    for (key, value) in store.iter(DBCol::ColPeers) {
+        let old_peer_state = KnownPeerStateV17::try_from_slice(&value).unwrap();
+        let peer_state = KnownPeerState {
+            peer_info: old_peer_state.peer_info,
+            status: old_peer_state.status,
+            first_seen: old_peer_state.first_seen,
+            last_seen: old_peer_state.last_seen,
+            score: old_peer_state.score,
+            score_updated: old_peer_state.score_updated,
+            encrypted: false,
+        };
+        store_update.set_ser(DBCol::ColPeers, &key, &peer_state).unwrap();
+    }
+    store_update.commit().unwrap();
+    set_store_version(&store, 18);
+}
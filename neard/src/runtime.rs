@@ -1,39 +1,42 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use borsh::ser::BorshSerialize;
 use borsh::BorshDeserialize;
 use log::{debug, error, info, warn};
 
 use near_chain::chain::NUM_EPOCHS_TO_KEEP_STORE_DATA;
-use near_chain::types::{ApplyTransactionResult, BlockHeaderInfo};
+use near_chain::types::{
+    ApplyTransactionResult, BlockHeaderInfo, GasEstimation, SimulateTransactionResult,
+};
 
 use near_chain::{BlockHeader, Error, ErrorKind, RuntimeAdapter};
 #[cfg(feature = "protocol_feature_block_header_v3")]
 use near_chain::{Doomslug, DoomslugThresholdMode};
 use near_chain_configs::{Genesis, GenesisConfig, ProtocolConfig};
-#[cfg(feature = "protocol_feature_evm")]
-use near_chain_configs::{BETANET_EVM_CHAIN_ID, MAINNET_EVM_CHAIN_ID, TESTNET_EVM_CHAIN_ID};
 use near_crypto::{PublicKey, Signature};
 use near_epoch_manager::{EpochManager, RewardCalculator};
 use near_pool::types::PoolIterator;
-use near_primitives::account::{AccessKey, Account};
+use near_primitives::account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission};
 use near_primitives::block::{Approval, ApprovalInner};
 use near_primitives::challenge::ChallengesResult;
 use near_primitives::contract::ContractCode;
 use near_primitives::epoch_manager::{BlockInfo, EpochConfig};
 use near_primitives::errors::{EpochError, InvalidTxError, RuntimeError};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::receipt::Receipt;
+use near_primitives::receipt::{DelayedReceiptIndices, Receipt, ReceiptEnum};
+use near_primitives::serialize::to_base64;
 use near_primitives::sharding::ChunkHash;
 use near_primitives::state_record::StateRecord;
-use near_primitives::transaction::SignedTransaction;
-use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::transaction::{Action, ExecutionStatus, SignedTransaction};
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas,
     MerkleHash, NumShards, ShardId, StateChangeCause, StateRoot, StateRootNode, ValidatorStake,
@@ -41,14 +44,16 @@ use near_primitives::types::{
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
     AccessKeyInfoView, CallResult, EpochValidatorInfo, QueryError, QueryRequest, QueryResponse,
-    QueryResponseKind, ViewApplyState, ViewStateResult,
+    QueryResponseKind, StateProofView, ViewApplyState, ViewStateResult,
 };
 use near_store::{
-    get_access_key_raw, get_genesis_hash, get_genesis_state_roots, set_genesis_hash,
-    set_genesis_state_roots, ColState, PartialStorage, ShardTries, Store,
-    StoreCompiledContractCache, Trie, WrappedTrieChanges,
+    get, get_access_key, get_access_key_raw, get_account, get_genesis_hash,
+    get_genesis_state_roots, set, set_access_key, set_account, set_genesis_hash,
+    set_genesis_state_roots, ColState, PartialStorage, ShardTries, StorageError, Store,
+    StoreCompiledContractCache, Trie, TrieUpdate, WrappedTrieChanges,
 };
 use node_runtime::adapter::ViewRuntimeAdapter;
+use node_runtime::config::tx_cost;
 use node_runtime::state_viewer::TrieViewer;
 use node_runtime::{
     validate_transaction, verify_and_charge_transaction, ApplyState, Runtime,
@@ -56,6 +61,7 @@ use node_runtime::{
 };
 
 use crate::shard_tracker::{account_id_to_shard_id, ShardTracker};
+use crate::NearConfig;
 use near_primitives::runtime::config::RuntimeConfig;
 
 #[cfg(feature = "protocol_feature_rectify_inflation")]
@@ -64,6 +70,20 @@ use near_epoch_manager::NUM_SECONDS_IN_A_YEAR;
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
 const STATE_DUMP_FILE: &str = "state_dump";
 const GENESIS_ROOTS_FILE: &str = "genesis_roots";
+/// Maximum number of blocks a simulated transaction is executed for.
+const MAX_SIMULATED_BLOCKS: u64 = 32;
+/// Maximum gas burnt by a simulated transaction and the receipts it produced.
+const MAX_SIMULATED_GAS: Gas = 10u64.pow(15);
+/// Maximum time spent simulating a transaction, checked after every applied chunk.
+const MAX_SIMULATION_DURATION: Duration = Duration::from_secs(2);
+/// Gas estimations are at most this much above the minimal prepaid gas.
+const GAS_ESTIMATION_PRECISION: Gas = 10u64.pow(10);
+/// Maximum time spent by all the simulations of a gas estimation. The last
+/// `MAX_SIMULATION_DURATION` of it is kept for the simulation of the estimated transaction.
+const MAX_GAS_ESTIMATION_DURATION: Duration = Duration::from_secs(6);
+/// Maximum gas burnt by all the simulations of a gas estimation, besides the simulation of the
+/// estimated transaction.
+const MAX_GAS_ESTIMATION_GAS: Gas = 4 * MAX_SIMULATED_GAS;
 
 /// Wrapper type for epoch manager to get avoid implementing trait for foreign types.
 pub struct SafeEpochManager(pub Arc<RwLock<EpochManager>>);
@@ -143,9 +163,13 @@ impl NightshadeRuntime {
         genesis: &Genesis,
         initial_tracking_accounts: Vec<AccountId>,
         initial_tracking_shards: Vec<ShardId>,
+        max_view_call_depth: Option<u32>,
     ) -> Self {
         let runtime = Runtime::new();
-        let trie_viewer = TrieViewer::new();
+        let trie_viewer = match max_view_call_depth {
+            Some(depth) => TrieViewer::with_max_call_depth(depth),
+            None => TrieViewer::new(),
+        };
         let genesis_config = genesis.config.clone();
         let genesis_runtime_config = Arc::new(genesis_config.runtime_config.clone());
         let num_shards = genesis.config.num_block_producer_seats_per_shard.len() as NumShards;
@@ -229,6 +253,27 @@ impl NightshadeRuntime {
         }
     }
 
+    /// Creates the runtime the node described by `config` runs with.
+    pub fn with_config(home_dir: &Path, store: Arc<Store>, config: &NearConfig) -> Self {
+        Self::new(
+            home_dir,
+            store,
+            &config.genesis,
+            config.client_config.tracked_accounts.clone(),
+            config.client_config.tracked_shards.clone(),
+            config.client_config.max_view_call_depth,
+        )
+        .with_max_view_state_values(config.client_config.max_view_state_values)
+    }
+
+    /// Overrides the maximum number of values returned by a view state query.
+    pub fn with_max_view_state_values(mut self, max_view_state_values: Option<u64>) -> Self {
+        if let Some(max_values) = max_view_state_values {
+            self.trie_viewer = self.trie_viewer.with_max_state_values(max_values);
+        }
+        self
+    }
+
     fn get_epoch_height_from_prev_block(
         &self,
         prev_block_hash: &CryptoHash,
@@ -529,6 +574,279 @@ impl NightshadeRuntime {
 
         Ok(result)
     }
+
+    /// Trie update to answer a view query, recording the trie nodes it reads if the query asks
+    /// to include a proof.
+    fn new_query_trie_update(
+        &self,
+        shard_id: ShardId,
+        state_root: StateRoot,
+        include_proof: bool,
+    ) -> TrieUpdate {
+        if include_proof {
+            let trie = self.get_tries().get_view_trie_for_shard(shard_id).recording_reads();
+            TrieUpdate::new(Rc::new(trie), state_root)
+        } else {
+            self.get_tries().new_trie_update_view(shard_id, state_root)
+        }
+    }
+
+    fn get_state_proof(
+        state_update: &TrieUpdate,
+        state_root: &StateRoot,
+    ) -> Option<StateProofView> {
+        state_update.trie.recorded_storage().map(|partial_storage| StateProofView {
+            state_root: *state_root,
+            nodes: partial_storage.nodes.0.iter().map(to_base64).collect(),
+        })
+    }
+
+    /// Simulates the transaction as `RuntimeAdapter::simulate_transaction`, failing once the
+    /// `deadline` passed. Unless `check_balance` is set, the signer is credited with the cost of
+    /// the transaction beforehand, so that the execution can be simulated with more prepaid gas
+    /// than the signer can afford.
+    fn simulate(
+        &self,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        block_hash: &CryptoHash,
+        gas_price: Balance,
+        random_seed: CryptoHash,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        check_balance: bool,
+        deadline: Instant,
+    ) -> Result<SimulateTransactionResult, Error> {
+        let epoch_height = self.get_epoch_height_from_prev_block(block_hash)?;
+        let epoch_id = self.get_epoch_id_from_prev_block(block_hash)?;
+        let current_protocol_version = self.get_epoch_protocol_version(&epoch_id)?;
+        let config = RuntimeConfig::from_protocol_version(
+            &self.genesis_runtime_config,
+            current_protocol_version,
+        );
+        // Every round is applied as the next block on top of the previous round.
+        let apply_state_at = |blocks: u64, prev_block_hash: CryptoHash| ApplyState {
+            block_index: block_height + blocks,
+            prev_block_hash,
+            block_hash: hash(&[block_hash.as_ref(), &blocks.to_le_bytes()[..]].concat()),
+            epoch_id: epoch_id.clone(),
+            epoch_height,
+            gas_price,
+            block_timestamp,
+            gas_limit: None,
+            random_seed,
+            current_protocol_version,
+            config: config.clone(),
+            cache: Some(Arc::new(StoreCompiledContractCache { store: self.store.clone() })),
+            #[cfg(feature = "protocol_feature_evm")]
+            evm_chain_id: self.evm_chain_id(),
+            #[cfg(feature = "costs_counting")]
+            profile: None,
+        };
+        let apply_error = |e: RuntimeError| match e {
+            RuntimeError::InvalidTxError(e) => {
+                Error::from(ErrorKind::Other(format!("Invalid transaction: {}", e)))
+            }
+            e => Error::from(ErrorKind::Other(format!("Failed to apply the transaction: {:?}", e))),
+        };
+        let storage_error = |e: StorageError| Error::from(ErrorKind::StorageError(e));
+
+        let mut result = SimulateTransactionResult::default();
+        let mut state_roots = state_roots.clone();
+        // New trie nodes of all the simulated blocks, they are only kept in memory.
+        let mut trie_changes = vec![];
+        // The delayed receipts of the shards are not part of the simulated execution, they are
+        // hidden from it so that only the transaction and the receipts it produced are applied.
+        for (shard_id, state_root) in state_roots.iter_mut() {
+            let trie = self.tries.get_overlay_trie_for_shard(*shard_id, &trie_changes);
+            let mut state_update = TrieUpdate::new(Rc::new(trie), *state_root);
+            let mut delayed_receipts_indices: DelayedReceiptIndices =
+                get(&state_update, &TrieKey::DelayedReceiptIndices)
+                    .map_err(storage_error)?
+                    .unwrap_or_default();
+            if delayed_receipts_indices.first_index == delayed_receipts_indices.next_available_index
+            {
+                continue;
+            }
+            delayed_receipts_indices.first_index = delayed_receipts_indices.next_available_index;
+            set(&mut state_update, TrieKey::DelayedReceiptIndices, &delayed_receipts_indices);
+            state_update.commit(StateChangeCause::UpdatedDelayedReceipts);
+            let (changes, _) = state_update.finalize().map_err(storage_error)?;
+            *state_root = changes.new_root;
+            trie_changes.push(changes);
+        }
+
+        // Ids of the transaction and of the receipts it produced, and the data ids they wait for.
+        let mut known_ids: HashSet<CryptoHash> = HashSet::new();
+        let mut input_data_ids = HashSet::new();
+        let mut receipts: BTreeMap<ShardId, Vec<Receipt>> = BTreeMap::new();
+
+        let shard_id = self.account_id_to_shard_id(&transaction.transaction.signer_id);
+        let state_root = state_roots.get(&shard_id).ok_or_else(|| {
+            ErrorKind::Other(format!("Shard {} is not tracked by this node", shard_id))
+        })?;
+        if !check_balance {
+            let trie = self.tries.get_overlay_trie_for_shard(shard_id, &trie_changes);
+            let mut state_update = TrieUpdate::new(Rc::new(trie), *state_root);
+            let signed_transaction = transaction;
+            let transaction = &signed_transaction.transaction;
+            let total_cost = tx_cost(
+                &config.transaction_costs,
+                transaction,
+                gas_price,
+                transaction.signer_id == transaction.receiver_id,
+                current_protocol_version,
+            )
+            .map_err(|_| ErrorKind::Other("Integer overflow during transaction cost".to_string()))?
+            .total_cost;
+            if let Some(mut account) =
+                get_account(&state_update, &transaction.signer_id).map_err(storage_error)?
+            {
+                account.amount = account.amount.saturating_add(total_cost);
+                set_account(&mut state_update, transaction.signer_id.clone(), &account);
+            }
+            if let Some(mut access_key) =
+                get_access_key(&state_update, &transaction.signer_id, &transaction.public_key)
+                    .map_err(storage_error)?
+            {
+                if let AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance: Some(allowance),
+                    ..
+                }) = &mut access_key.permission
+                {
+                    *allowance = allowance.saturating_add(total_cost);
+                    set_access_key(
+                        &mut state_update,
+                        transaction.signer_id.clone(),
+                        transaction.public_key.clone(),
+                        &access_key,
+                    );
+                }
+            }
+            state_update.commit(StateChangeCause::InitialState);
+            let (changes, _) = state_update.finalize().map_err(storage_error)?;
+            state_roots.insert(shard_id, changes.new_root);
+            trie_changes.push(changes);
+        }
+        let state_root = &state_roots[&shard_id];
+        let trie = self.tries.get_overlay_trie_for_shard(shard_id, &trie_changes);
+        let mut state_update = TrieUpdate::new(Rc::new(trie), *state_root);
+        let (receipt, outcome) = self
+            .runtime
+            .simulate_transaction_conversion(
+                &mut state_update,
+                &apply_state_at(1, *block_hash),
+                transaction,
+                verify_signature,
+            )
+            .map_err(apply_error)?;
+        let (changes, state_changes) = state_update.finalize().map_err(storage_error)?;
+        state_roots.insert(shard_id, changes.new_root);
+        trie_changes.push(changes);
+        known_ids.insert(outcome.id);
+        known_ids.insert(receipt.receipt_id);
+        let mut gas_burnt = outcome.outcome.gas_burnt;
+        result.outcomes.push(outcome);
+        result.state_changes.extend(state_changes);
+        result.receipts.push(receipt.clone());
+        receipts
+            .entry(self.account_id_to_shard_id(&receipt.receiver_id))
+            .or_default()
+            .push(receipt);
+
+        let mut prev_block_hash = *block_hash;
+        for blocks in 1..=MAX_SIMULATED_BLOCKS {
+            let apply_state = apply_state_at(blocks, prev_block_hash);
+            let shard_receipts = std::mem::take(&mut receipts);
+            for (shard_id, incoming_receipts) in shard_receipts.iter() {
+                let state_root = state_roots.get(shard_id).ok_or_else(|| {
+                    ErrorKind::Other(format!("Shard {} is not tracked by this node", shard_id))
+                })?;
+                let trie = self.tries.get_overlay_trie_for_shard(*shard_id, &trie_changes);
+                let apply_result = self
+                    .runtime
+                    .apply(
+                        trie,
+                        *state_root,
+                        &None,
+                        &apply_state,
+                        incoming_receipts,
+                        &[],
+                        &self.epoch_manager,
+                    )
+                    .map_err(apply_error)?;
+
+                state_roots.insert(*shard_id, apply_result.state_root);
+                trie_changes.push(apply_result.trie_changes);
+                for outcome in apply_result.outcomes {
+                    if known_ids.contains(&outcome.id) {
+                        known_ids.extend(outcome.outcome.receipt_ids.iter().cloned());
+                        gas_burnt = gas_burnt.saturating_add(outcome.outcome.gas_burnt);
+                        result.outcomes.push(outcome);
+                    }
+                }
+                for receipt in apply_result.outgoing_receipts {
+                    let is_known = match &receipt.receipt {
+                        ReceiptEnum::Action(action_receipt) => {
+                            let is_known = known_ids.contains(&receipt.receipt_id);
+                            if is_known {
+                                input_data_ids
+                                    .extend(action_receipt.input_data_ids.iter().cloned());
+                            }
+                            is_known
+                        }
+                        ReceiptEnum::Data(data_receipt) => {
+                            input_data_ids.contains(&data_receipt.data_id)
+                        }
+                    };
+                    if is_known {
+                        known_ids.insert(receipt.receipt_id);
+                        result.receipts.push(receipt.clone());
+                        receipts
+                            .entry(self.account_id_to_shard_id(&receipt.receiver_id))
+                            .or_insert_with(Vec::new)
+                            .push(receipt);
+                    }
+                }
+                for mut state_changes in apply_result.state_changes {
+                    state_changes.changes.retain(|change| match &change.cause {
+                        StateChangeCause::ActionReceiptProcessingStarted { receipt_hash }
+                        | StateChangeCause::ActionReceiptGasReward { receipt_hash }
+                        | StateChangeCause::ReceiptProcessing { receipt_hash }
+                        | StateChangeCause::PostponedReceipt { receipt_hash } => {
+                            known_ids.contains(receipt_hash)
+                        }
+                        _ => false,
+                    });
+                    if !state_changes.changes.is_empty() {
+                        result.state_changes.push(state_changes);
+                    }
+                }
+
+                if gas_burnt > MAX_SIMULATED_GAS {
+                    return Err(ErrorKind::Other(format!(
+                        "Simulation burnt more than the limit of {} gas",
+                        MAX_SIMULATED_GAS
+                    ))
+                    .into());
+                }
+                if Instant::now() > deadline {
+                    return Err(ErrorKind::Other(
+                        "Simulation ran out of its time limit".to_string(),
+                    )
+                    .into());
+                }
+            }
+
+            if receipts.is_empty() {
+                return Ok(result);
+            }
+            prev_block_hash = apply_state.block_hash;
+        }
+        result.pending_receipts = receipts.into_iter().flat_map(|(_, receipts)| receipts).collect();
+        Ok(result)
+    }
 }
 
 pub fn state_record_to_shard_id(state_record: &StateRecord, num_shards: NumShards) -> ShardId {
@@ -1166,6 +1484,153 @@ impl RuntimeAdapter for NightshadeRuntime {
         )
     }
 
+    fn simulate_transaction(
+        &self,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        block_hash: &CryptoHash,
+        gas_price: Balance,
+        random_seed: CryptoHash,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+    ) -> Result<SimulateTransactionResult, Error> {
+        self.simulate(
+            state_roots,
+            block_height,
+            block_timestamp,
+            block_hash,
+            gas_price,
+            random_seed,
+            transaction,
+            verify_signature,
+            true,
+            Instant::now() + MAX_SIMULATION_DURATION,
+        )
+    }
+
+    fn estimate_gas(
+        &self,
+        state_roots: &HashMap<ShardId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        block_hash: &CryptoHash,
+        gas_price: Balance,
+        random_seed: CryptoHash,
+        transaction: &SignedTransaction,
+    ) -> Result<GasEstimation, Error> {
+        let epoch_id = self.get_epoch_id_from_prev_block(block_hash)?;
+        let current_protocol_version = self.get_epoch_protocol_version(&epoch_id)?;
+        let config = RuntimeConfig::from_protocol_version(
+            &self.genesis_runtime_config,
+            current_protocol_version,
+        );
+        let with_prepaid_gas = |prepaid_gas: Gas| {
+            let mut transaction = transaction.clone();
+            for action in transaction.transaction.actions.iter_mut() {
+                if let Action::FunctionCall(function_call) = action {
+                    function_call.gas = prepaid_gas;
+                }
+            }
+            SignedTransaction::new(transaction.signature, transaction.transaction)
+        };
+        // All the simulations share a single deadline, and the search stops at the best estimation
+        // found so far once it runs out of time or gas.
+        let deadline = Instant::now() + MAX_GAS_ESTIMATION_DURATION;
+        let search_deadline = deadline - MAX_SIMULATION_DURATION;
+        // The signer can't afford the maximum prepaid gas in general, so the balance is only checked
+        // by the simulation of the estimated transaction.
+        let simulate = |transaction: &SignedTransaction, check_balance: bool, deadline: Instant| {
+            self.simulate(
+                state_roots,
+                block_height,
+                block_timestamp,
+                block_hash,
+                gas_price,
+                random_seed,
+                transaction,
+                false,
+                check_balance,
+                std::cmp::min(deadline, Instant::now() + MAX_SIMULATION_DURATION),
+            )
+        };
+        let total_gas_burnt = |simulation: &SimulateTransactionResult| {
+            simulation
+                .outcomes
+                .iter()
+                .fold(0, |gas: Gas, outcome| gas.saturating_add(outcome.outcome.gas_burnt))
+        };
+        // The transaction and all its receipts succeeded and there is nothing left to execute.
+        let succeeded = |simulation: &SimulateTransactionResult| {
+            simulation.pending_receipts.is_empty()
+                && simulation.outcomes.iter().all(|outcome| match outcome.outcome.status {
+                    ExecutionStatus::Failure(_) => false,
+                    _ => true,
+                })
+        };
+
+        let function_calls = transaction
+            .transaction
+            .actions
+            .iter()
+            .filter(|action| matches!(action, Action::FunctionCall(_)))
+            .count() as u64;
+        let mut high = if function_calls == 0 {
+            0
+        } else {
+            config.wasm_config.limit_config.max_total_prepaid_gas / function_calls
+        };
+        let mut estimated_transaction = with_prepaid_gas(high);
+        let simulation = simulate(&estimated_transaction, false, search_deadline)?;
+        let mut gas_burnt = total_gas_burnt(&simulation);
+        if !succeeded(&simulation) {
+            let failure =
+                simulation.outcomes.iter().find_map(|outcome| match &outcome.outcome.status {
+                    ExecutionStatus::Failure(err) => Some(format!("{}: {}", outcome.id, err)),
+                    _ => None,
+                });
+            return Err(ErrorKind::Other(match failure {
+                Some(failure) => {
+                    format!("Execution fails with the maximum prepaid gas, receipt {}", failure)
+                }
+                None => "Execution doesn't complete with the maximum prepaid gas".to_string(),
+            })
+            .into());
+        }
+        // Binary search, the prepaid gas `high` is always enough and `low` isn't.
+        let mut low = 0;
+        while high - low > GAS_ESTIMATION_PRECISION && gas_burnt <= MAX_GAS_ESTIMATION_GAS {
+            let prepaid_gas = low + (high - low) / 2;
+            let candidate_transaction = with_prepaid_gas(prepaid_gas);
+            let candidate_simulation =
+                match simulate(&candidate_transaction, false, search_deadline) {
+                    Ok(candidate_simulation) => candidate_simulation,
+                    Err(_) if Instant::now() > search_deadline => break,
+                    Err(err) => return Err(err),
+                };
+            gas_burnt = gas_burnt.saturating_add(total_gas_burnt(&candidate_simulation));
+            if succeeded(&candidate_simulation) {
+                high = prepaid_gas;
+                estimated_transaction = candidate_transaction;
+            } else {
+                low = prepaid_gas;
+            }
+        }
+
+        let simulation = simulate(&estimated_transaction, true, deadline)?;
+
+        let transaction = &estimated_transaction.transaction;
+        let cost = tx_cost(
+            &config.transaction_costs,
+            transaction,
+            gas_price,
+            transaction.signer_id == transaction.receiver_id,
+            current_protocol_version,
+        )
+        .map_err(|_| ErrorKind::Other("Integer overflow during transaction cost".to_string()))?;
+        Ok(GasEstimation { prepaid_gas: high, total_cost: cost.total_cost, simulation })
+    }
+
     fn query(
         &self,
         shard_id: ShardId,
@@ -1178,12 +1643,15 @@ impl RuntimeAdapter for NightshadeRuntime {
         request: &QueryRequest,
     ) -> Result<QueryResponse, Box<dyn std::error::Error>> {
         match request {
-            QueryRequest::ViewAccount { account_id } => {
-                match self.view_account(shard_id, *state_root, account_id) {
+            QueryRequest::ViewAccount { account_id, include_proof } => {
+                let state_update =
+                    self.new_query_trie_update(shard_id, *state_root, *include_proof);
+                match self.trie_viewer.view_account(&state_update, account_id) {
                     Ok(r) => Ok(QueryResponse {
                         kind: QueryResponseKind::ViewAccount(r.into()),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: Self::get_state_proof(&state_update, state_root),
                     }),
                     Err(e) => Err(e),
                 }
@@ -1194,6 +1662,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         kind: QueryResponseKind::ViewCode(r.into()),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                     Err(e) => Err(e),
                 }
@@ -1229,20 +1698,31 @@ impl RuntimeAdapter for NightshadeRuntime {
                         kind: QueryResponseKind::CallResult(CallResult { result, logs }),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                     Err(err) => Ok(QueryResponse {
                         kind: QueryResponseKind::Error(QueryError { error: err.to_string(), logs }),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                 }
             }
-            QueryRequest::ViewState { account_id, prefix } => {
-                match self.view_state(shard_id, *state_root, account_id, prefix.as_ref()) {
+            QueryRequest::ViewState { account_id, prefix, from_key, limit, include_proof } => {
+                let state_update =
+                    self.new_query_trie_update(shard_id, *state_root, *include_proof);
+                match self.trie_viewer.view_state(
+                    &state_update,
+                    account_id,
+                    prefix.as_ref(),
+                    from_key.as_deref(),
+                    *limit,
+                ) {
                     Ok(result) => Ok(QueryResponse {
                         kind: QueryResponseKind::ViewState(result),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: Self::get_state_proof(&state_update, state_root),
                     }),
                     Err(err) => Ok(QueryResponse {
                         kind: QueryResponseKind::Error(QueryError {
@@ -1251,6 +1731,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         }),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                 }
             }
@@ -1268,6 +1749,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         ),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                     Err(err) => Ok(QueryResponse {
                         kind: QueryResponseKind::Error(QueryError {
@@ -1276,6 +1758,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         }),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                 }
             }
@@ -1285,6 +1768,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         kind: QueryResponseKind::AccessKey(access_key.into()),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                     Err(err) => Ok(QueryResponse {
                         kind: QueryResponseKind::Error(QueryError {
@@ -1293,6 +1777,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         }),
                         block_height,
                         block_hash: *block_hash,
+                        state_proof: None,
                     }),
                 }
             }
@@ -1445,11 +1930,7 @@ impl RuntimeAdapter for NightshadeRuntime {
     #[cfg(feature = "protocol_feature_evm")]
     /// ID of the EVM chain: https://github.com/ethereum-lists/chains
     fn evm_chain_id(&self) -> u64 {
-        match self.genesis_config.chain_id.as_str() {
-            "mainnet" => MAINNET_EVM_CHAIN_ID,
-            "testnet" => TESTNET_EVM_CHAIN_ID,
-            _ => BETANET_EVM_CHAIN_ID,
-        }
+        near_chain_configs::evm_chain_id(&self.genesis_config.chain_id)
     }
 
     fn get_protocol_config(&self, epoch_id: &EpochId) -> Result<ProtocolConfig, Error> {
@@ -1570,9 +2051,11 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         state_root: MerkleHash,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
     ) -> Result<ViewStateResult, Box<dyn std::error::Error>> {
         let state_update = self.get_tries().new_trie_update_view(shard_id, state_root);
-        self.trie_viewer.view_state(&state_update, account_id, prefix)
+        self.trie_viewer.view_state(&state_update, account_id, prefix, from_key, limit)
     }
 }
 
@@ -1684,8 +2167,6 @@ mod test {
             initial_tracked_shards: Vec<ShardId>,
             has_reward: bool,
         ) -> Self {
-            let dir = tempfile::Builder::new().prefix(prefix).tempdir().unwrap();
-            let store = create_store(&get_store_path(dir.path()));
             let all_validators = validators.iter().fold(BTreeSet::new(), |acc, x| {
                 acc.union(&x.iter().map(|x| x.as_str()).collect()).cloned().collect()
             });
@@ -1703,6 +2184,22 @@ mod test {
             if !has_reward {
                 genesis.config.max_inflation_rate = Rational::from_integer(0);
             }
+            Self::new_with_genesis(
+                prefix,
+                genesis,
+                initial_tracked_accounts,
+                initial_tracked_shards,
+            )
+        }
+
+        pub fn new_with_genesis(
+            prefix: &str,
+            genesis: Genesis,
+            initial_tracked_accounts: Vec<AccountId>,
+            initial_tracked_shards: Vec<ShardId>,
+        ) -> Self {
+            let dir = tempfile::Builder::new().prefix(prefix).tempdir().unwrap();
+            let store = create_store(&get_store_path(dir.path()));
             let genesis_total_supply = genesis.config.total_supply;
             let genesis_protocol_version = genesis.config.protocol_version;
             let runtime = NightshadeRuntime::new(
@@ -1711,6 +2208,7 @@ mod test {
                 &genesis,
                 initial_tracked_accounts,
                 initial_tracked_shards,
+                None,
             );
             let (_store, state_roots) = runtime.genesis_state();
             let genesis_hash = hash(&vec![0]);
@@ -2986,4 +3484,143 @@ mod test {
         assert_eq!(env.last_proposals.len(), 1);
         assert_eq!(env.last_proposals[0].stake, 0);
     }
+
+    /// A simulated transaction returns its execution without changing the state.
+    #[test]
+    fn test_simulate_transaction() {
+        init_test_logger();
+        let validators = vec!["test1".to_string(), "test2".to_string()];
+        let mut env =
+            TestEnv::new("test_simulate_transaction", vec![validators], 4, vec![], vec![], false);
+        let signer = InMemorySigner::from_seed("test1", KeyType::ED25519, "test1");
+        let state_roots: HashMap<ShardId, StateRoot> = env
+            .state_roots
+            .iter()
+            .enumerate()
+            .map(|(shard_id, state_root)| (shard_id as ShardId, *state_root))
+            .collect();
+        let simulate = |env: &TestEnv, transaction: &SignedTransaction, verify_signature: bool| {
+            env.runtime.simulate_transaction(
+                &state_roots,
+                env.head.height,
+                0,
+                &env.head.last_block_hash,
+                env.runtime.genesis_config.min_gas_price,
+                CryptoHash::default(),
+                transaction,
+                verify_signature,
+            )
+        };
+
+        let mut transaction = SignedTransaction::send_money(
+            1,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            100,
+            env.head.last_block_hash,
+        );
+        let simulation = simulate(&env, &transaction, true).unwrap();
+        assert_eq!(simulation.outcomes[0].id, transaction.get_hash());
+        assert!(simulation.outcomes.len() >= 2);
+        assert!(matches!(simulation.outcomes[1].outcome.status, ExecutionStatus::SuccessValue(_)));
+        assert!(!simulation.state_changes.is_empty());
+        assert!(simulation.pending_receipts.is_empty());
+
+        // The signature is only checked when asked to.
+        transaction.signature = Signature::empty(KeyType::ED25519);
+        assert!(simulate(&env, &transaction, true).is_err());
+        let simulation = simulate(&env, &transaction, false).unwrap();
+        assert_eq!(simulation.outcomes[0].id, transaction.get_hash());
+
+        // Nothing was persisted, so the transaction can still be sent with the same nonce.
+        let balance = env.view_account("test2").amount;
+        let transaction = SignedTransaction::send_money(
+            1,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            100,
+            env.head.last_block_hash,
+        );
+        env.step_default(vec![transaction]);
+        env.step_default(vec![]);
+        assert_eq!(env.view_account("test2").amount, balance + 100);
+    }
+
+    /// The prepaid gas of a function call is estimated even if the signer can't afford the maximum
+    /// prepaid gas, and the estimated transaction succeeds.
+    #[test]
+    fn test_estimate_gas_function_call() {
+        init_test_logger();
+        let mut genesis = Genesis::test(vec!["test1", "test2"], 2);
+        testlib::runtime_utils::add_test_contract(&mut genesis, &"test2".to_string());
+        let mut env =
+            TestEnv::new_with_genesis("test_estimate_gas_function_call", genesis, vec![], vec![]);
+        let signer = InMemorySigner::from_seed("test1", KeyType::ED25519, "test1");
+        let gas_price = env.runtime.genesis_config.min_gas_price;
+        let max_prepaid_gas = env
+            .runtime
+            .genesis_config
+            .runtime_config
+            .wasm_config
+            .limit_config
+            .max_total_prepaid_gas;
+
+        // Leave the signer with less than the cost of the maximum prepaid gas.
+        let balance = 5 * 10u128.pow(22);
+        assert!(balance < max_prepaid_gas as Balance * gas_price);
+        let transfer = SignedTransaction::send_money(
+            1,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            env.view_account("test1").amount - balance,
+            env.head.last_block_hash,
+        );
+        env.step_default(vec![transfer]);
+        env.step_default(vec![]);
+        let balance = env.view_account("test1").amount;
+
+        let transaction = SignedTransaction::call(
+            2,
+            "test1".to_string(),
+            "test2".to_string(),
+            &signer,
+            0,
+            "log_something".to_string(),
+            vec![],
+            0,
+            env.head.last_block_hash,
+        );
+        let state_roots: HashMap<ShardId, StateRoot> = env
+            .state_roots
+            .iter()
+            .enumerate()
+            .map(|(shard_id, state_root)| (shard_id as ShardId, *state_root))
+            .collect();
+        let estimation = env
+            .runtime
+            .estimate_gas(
+                &state_roots,
+                env.head.height,
+                0,
+                &env.head.last_block_hash,
+                gas_price,
+                CryptoHash::default(),
+                &transaction,
+            )
+            .unwrap();
+        assert!(estimation.prepaid_gas > 0);
+        assert!(estimation.prepaid_gas < max_prepaid_gas);
+        assert!(estimation.total_cost < balance);
+        let simulation = estimation.simulation;
+        assert!(simulation.pending_receipts.is_empty());
+        assert!(simulation.outcomes.len() >= 2);
+        assert!(simulation
+            .outcomes
+            .iter()
+            .all(|outcome| !matches!(outcome.outcome.status, ExecutionStatus::Failure(_))));
+        assert_eq!(simulation.outcomes[1].outcome.logs, vec!["hello".to_string()]);
+    }
 }
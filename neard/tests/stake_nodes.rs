@@ -257,6 +257,7 @@ fn test_validator_kickout() {
                                                             account_id: test_nodes[i as usize]
                                                                 .account_id
                                                                 .clone(),
+                                                            include_proof: false,
                                                         },
                                                     ))
                                                     .then(move |res| {
@@ -292,6 +293,7 @@ fn test_validator_kickout() {
                                                             account_id: test_nodes[i as usize]
                                                                 .account_id
                                                                 .clone(),
+                                                            include_proof: false,
                                                         },
                                                     ))
                                                     .then(move |res| {
@@ -445,6 +447,7 @@ fn test_validator_join() {
                                                         account_id: test_nodes[1]
                                                             .account_id
                                                             .clone(),
+                                                        include_proof: false,
                                                     },
                                                 ))
                                                 .then(move |res| {
@@ -469,6 +472,7 @@ fn test_validator_join() {
                                                         account_id: test_nodes[2]
                                                             .account_id
                                                             .clone(),
+                                                        include_proof: false,
                                                     },
                                                 ))
                                                 .then(move |res| {
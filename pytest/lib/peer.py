@@ -13,6 +13,8 @@ from nacl.signing import SigningKey
 from typing import Optional
 
 ED_PREFIX = "ed25519:"
+# Newest protocol version spoken by this peer, it doesn't encrypt connections nor compress frames
+PROTOCOL_VERSION = 42
 
 
 class Connection:
@@ -166,7 +168,7 @@ async def run_handshake(conn: Connection,
 
     if response.enum == 'HandshakeFailure' and response.HandshakeFailure[1].enum == 'ProtocolVersionMismatch':
         pvm = response.HandshakeFailure[1].ProtocolVersionMismatch.version
-        handshake.Handshake.version = pvm
+        handshake.Handshake.version = min(pvm, PROTOCOL_VERSION)
         sign_handshake(key_pair, handshake.Handshake)
         await conn.send(handshake)
         response = await conn.recv()
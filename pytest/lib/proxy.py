@@ -38,10 +38,12 @@ import logging
 
 from messages import schema
 from messages.crypto import PublicKey, Signature
-from messages.network import PeerIdOrHash, PeerMessage
+from messages.network import HandshakeFailureReason, PeerIdOrHash, PeerInfo, PeerMessage, ProtocolVersionMismatch
 from serializer import BinarySerializer
 
 MSG_TIMEOUT = 10
+# Protocol version from which nodes encrypt their connections, only nightly nodes support it
+ENCRYPTED_TRANSPORT_PROTOCOL_VERSION = 106
 _MY_PORT = [None]
 
 logging.basicConfig(format='%(asctime)s %(message)s', level=logging.INFO)
@@ -57,6 +59,35 @@ def proxy_cleanup(proxy):
         assert False, "One of the proxy processes failed, search for the stacktraces above"
 
 
+def is_session_init(raw_message):
+    # `SessionInit` has the tag of `Handshake`, a version, an oldest supported version and a 32 bytes key
+    return len(raw_message) == 41 and raw_message[0] == 0 and struct.unpack(
+        'I', raw_message[1:5])[0] >= ENCRYPTED_TRANSPORT_PROTOCOL_VERSION
+
+
+def create_version_mismatch():
+    """
+    Create the answer of a node not supporting the encrypted transport to `SessionInit`
+    """
+    peer_info = PeerInfo()
+    peer_info.id = PublicKey()
+    peer_info.id.keyType = 0
+    peer_info.id.data = bytes([0] * 32)
+    peer_info.addr = None
+    peer_info.account_id = None
+
+    reason = HandshakeFailureReason()
+    reason.enum = 'ProtocolVersionMismatch'
+    reason.ProtocolVersionMismatch = ProtocolVersionMismatch()
+    reason.ProtocolVersionMismatch.version = ENCRYPTED_TRANSPORT_PROTOCOL_VERSION - 1
+    reason.ProtocolVersionMismatch.oldest_supported_version = ENCRYPTED_TRANSPORT_PROTOCOL_VERSION - 1
+
+    peer_message = PeerMessage()
+    peer_message.enum = 'HandshakeFailure'
+    peer_message.HandshakeFailure = (peer_info, reason)
+    return peer_message
+
+
 def port_holder_to_node_ord(holder):
     return None if holder[0] is None else (holder[0] - 24477) % 100
 
@@ -82,6 +113,16 @@ class ProxyHandler:
     async def _handle(self, raw_message, *, writer, sender_port_holder, receiver_port_holder, ordinal_to_writer):
         sender_ordinal = port_holder_to_node_ord(sender_port_holder)
         receiver_ordinal = port_holder_to_node_ord(receiver_port_holder)
+
+        if is_session_init(raw_message):
+            # Encrypted connections would hide the messages from the handler, answer as a node
+            # not supporting them so that the nodes fall back to plaintext.
+            raw_answer = BinarySerializer(schema).serialize(create_version_mismatch())
+            writer.write(struct.pack('I', len(raw_answer)))
+            writer.write(raw_answer)
+            await writer.drain()
+            return False
+
         try:
             message = BinarySerializer(schema).deserialize(
                 raw_message, PeerMessage)
@@ -15,7 +15,7 @@ sys.path.append('lib')
 import base58
 import nacl.signing
 from cluster import start_cluster
-from peer import ED_PREFIX, connect, create_handshake, sign_handshake
+from peer import ED_PREFIX, PROTOCOL_VERSION, connect, create_handshake, sign_handshake
 from utils import obj_to_string
 
 
@@ -36,7 +36,7 @@ async def main():
     assert response.enum == 'HandshakeFailure', response.enum
     assert response.HandshakeFailure[1].enum == 'ProtocolVersionMismatch', response.HandshakeFailure[1].enum
     pvm = response.HandshakeFailure[1].ProtocolVersionMismatch.version
-    handshake.Handshake.version = pvm
+    handshake.Handshake.version = min(pvm, PROTOCOL_VERSION)
 
     # Second handshake attempt. Should fail with Protocol Version Mismatch
     sign_handshake(my_key_pair_nacl, handshake.Handshake)
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use ethereum_types::{Address, H256, U256};
 use evm::ActionParams;
 use keccak_hash::keccak;
+use near_primitives::evm::EvmLog;
 use near_primitives::runtime::fees::EvmCostConfig;
 use parity_bytes::Bytes;
 use vm::{
@@ -13,8 +14,6 @@ use vm::{
 use crate::evm_state::{EvmState, SubState};
 use crate::interpreter;
 
-use crate::utils::format_log;
-
 // https://github.com/openethereum/openethereum/blob/77643c13e80ca09d9a6b10631034f5a1568ba6d3/ethcore/machine/src/externalities.rs
 pub struct NearExt<'a> {
     pub info: EnvInfo,
@@ -271,18 +270,25 @@ impl<'a> vm::Ext for NearExt<'a> {
         Ok(self.sub_state.code_at(address).unwrap_or(None).map(|c| c.len()))
     }
 
-    /// Creates log entry with given topics and data
+    /// Creates log entry with given topics and data.
+    /// The log is added to the outcome logs hex-encoded as an `EvmLog`, which starts with the
+    /// address of the contract. It used to have only the topics and the data, the format changed
+    /// while the EVM is only enabled by the nightly `ProtocolFeature::EVM`, so it is not gated
+    /// by the protocol version.
     fn log(&mut self, topics: Vec<H256>, data: &[u8]) -> EvmResult<()> {
         if self.is_static() {
             return Err(VmError::MutableCallInStaticContext);
         }
-        if topics.len() > 256 {
+        // The number of topics is encoded in a byte.
+        if topics.len() > u8::MAX as usize {
             return Err(VmError::Internal("Too many topics".to_string()));
         }
-        self.sub_state.state.logs.push(hex::encode(
-            format_log(topics, data)
-                .map_err(|err| VmError::Internal(format!("Failed to format event: {}", err)))?,
-        ));
+        let log = EvmLog {
+            address: self.context_addr.0,
+            topics: topics.into_iter().map(|topic| topic.0).collect(),
+            data: data.to_vec(),
+        };
+        self.sub_state.state.logs.push(log.to_hex());
         Ok(())
     }
 
@@ -1,7 +1,4 @@
-use std::io::Write;
-
 use borsh::BorshSerialize;
-use byteorder::WriteBytesExt;
 use ethereum_types::{Address, H160, H256, U256};
 use keccak_hash::keccak;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -172,16 +169,6 @@ impl From<Balance> for u128 {
     }
 }
 
-pub fn format_log(topics: Vec<H256>, data: &[u8]) -> std::result::Result<Vec<u8>, std::io::Error> {
-    let mut result = Vec::with_capacity(1 + topics.len() * 32 + data.len());
-    result.write_u8(topics.len() as u8)?;
-    for topic in topics.iter() {
-        result.write(&topic.0)?;
-    }
-    result.write(data)?;
-    Ok(result)
-}
-
 /// Given signature and data, validates that signature is valid for given data and returns ecrecover address.
 pub fn ecrecover_address(hash: &RawHash, signature: &[u8; 65]) -> Option<Address> {
     use sha3::Digest;
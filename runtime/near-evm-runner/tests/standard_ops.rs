@@ -13,6 +13,7 @@ use near_evm_runner::utils::{
     encode_view_call_function_args, near_account_id_to_evm_address, near_erc712_domain,
     parse_meta_call, u256_to_arr,
 };
+use near_primitives::evm::EvmLog;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_vm_errors::{EvmError, VMLogicError};
 use near_vm_logic::mocks::mock_external::MockedExternal;
@@ -161,6 +162,11 @@ fn test_deploy_and_transfer() {
 
     // The sub_addr should have been transferred 100 yoctoN.
     let sub_addr = raw[12..32].to_vec();
+    let log = EvmLog::from_hex(&context.logs[0]).unwrap();
+    assert_eq!(log.address, test_addr.0);
+    assert_eq!(log.topics.len(), 2);
+    assert_eq!(log.topics[1][12..], sub_addr[..]);
+    assert_eq!(log.data, u256_to_arr(&U256::from(100)).to_vec());
     assert_eq!(context.get_balance(test_addr.0.to_vec()).unwrap(), U256::from(100));
     assert_eq!(context.get_balance(sub_addr).unwrap(), U256::from(100));
 }
@@ -53,6 +53,10 @@ pub struct VMContext {
     pub random_seed: Vec<u8>,
     /// Whether the execution should not charge any costs.
     pub is_view: bool,
+    /// Whether a view execution may create promises that only call functions without attaching
+    /// any deposit. The caller is responsible for executing them and passing their results back.
+    #[serde(default)]
+    pub allow_view_promises: bool,
     /// How many `DataReceipt`'s should receive this execution result. This should be empty if
     /// this function call is a part of a batch and it is not the last action.
     pub output_data_receivers: Vec<AccountId>,
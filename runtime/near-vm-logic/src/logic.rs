@@ -9,6 +9,7 @@ use near_primitives_core::config::ExtCosts::*;
 use near_primitives_core::config::{ActionCosts, ExtCosts, VMConfig};
 use near_primitives_core::profile::ProfileData;
 use near_primitives_core::runtime::fees::RuntimeFeesConfig;
+use near_primitives_core::trace::{self, TraceEvent};
 use near_primitives_core::types::{
     AccountId, Balance, EpochHeight, Gas, ProtocolVersion, StorageUsage,
 };
@@ -115,7 +116,8 @@ impl<'a> VMLogic<'a> {
         let current_account_balance = context.account_balance + context.attached_deposit;
         let current_storage_usage = context.storage_usage;
         let max_gas_burnt = if context.is_view {
-            config.limit_config.max_gas_burnt_view
+            // Promises followed by a view call share its budget, so the caller can lower it.
+            std::cmp::min(config.limit_config.max_gas_burnt_view, context.prepaid_gas)
         } else {
             config.limit_config.max_gas_burnt
         };
@@ -171,6 +173,39 @@ impl<'a> VMLogic<'a> {
         )
     }
 
+    /// Calls the host function `f` on behalf of the contract and records the call with the gas
+    /// it charged. Only called by the imports when tracing is enabled, so `args` are not built
+    /// otherwise.
+    pub fn trace_host_function<T>(
+        &mut self,
+        name: &str,
+        args: &[(&str, u64)],
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let position = trace::record_pending(TraceEvent::HostFunction {
+            name: name.to_string(),
+            args: args.iter().map(|(arg, value)| (arg.to_string(), *value)).collect(),
+            gas_burnt: 0,
+            gas_used: 0,
+            error: None,
+        });
+        let (burnt_gas, used_gas) = (self.gas_counter.burnt_gas(), self.gas_counter.used_gas());
+        let result = f(self);
+        if let Some(position) = position {
+            let burnt = self.gas_counter.burnt_gas() - burnt_gas;
+            let used = self.gas_counter.used_gas() - used_gas;
+            let err = result.as_ref().err().map(|err| format!("{:?}", err));
+            trace::update_event(position, |event| {
+                if let TraceEvent::HostFunction { gas_burnt, gas_used, error, .. } = event {
+                    *gas_burnt = burnt;
+                    *gas_used = used;
+                    *error = err;
+                }
+            });
+        }
+        result
+    }
+
     // ###########################
     // # Memory helper functions #
     // ###########################
@@ -468,6 +503,15 @@ impl<'a> VMLogic<'a> {
         }
     }
 
+    /// Checks that promises can be used in the current execution. View executions can only use
+    /// them if the caller allowed it.
+    fn check_promises_allowed(&self, method_name: &str) -> Result<()> {
+        if self.context.is_view && !self.context.allow_view_promises {
+            return Err(HostError::ProhibitedInView { method_name: method_name.to_string() }.into());
+        }
+        Ok(())
+    }
+
     /// Adds a given promise to the vector of promises and returns a new promise index.
     /// Throws `NumberPromisesExceeded` if the total number of promises exceeded the limit.
     fn checked_push_promise(&mut self, promise: Promise) -> Result<PromiseIndex> {
@@ -857,6 +901,138 @@ impl<'a> VMLogic<'a> {
         self.internal_write_register(register_id, value_hash.as_slice().to_vec())
     }
 
+    /// Hashes the given value using ripemd160 and returns it into `register_id`.
+    ///
+    /// # Errors
+    ///
+    /// If `value_len + value_ptr` points outside the memory or the registers use more memory than
+    /// the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes + ripemd160_base + ripemd160_byte * num_bytes`
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    pub fn ripemd160(&mut self, value_len: u64, value_ptr: u64, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(ripemd160_base)?;
+        let value = self.get_vec_from_memory_or_register(value_ptr, value_len)?;
+        self.gas_counter.pay_per_byte(ripemd160_byte, value.len() as u64)?;
+
+        use ripemd160::Digest;
+
+        let value_hash = ripemd160::Ripemd160::digest(&value);
+        self.internal_write_register(register_id, value_hash.as_slice().to_vec())
+    }
+
+    /// Hashes the given value using blake2b with a 512 bit output and returns it into
+    /// `register_id`.
+    ///
+    /// # Errors
+    ///
+    /// If `value_len + value_ptr` points outside the memory or the registers use more memory than
+    /// the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + write_register_base + write_register_byte * num_bytes + blake2b_base + blake2b_byte * num_bytes`
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    pub fn blake2b(&mut self, value_len: u64, value_ptr: u64, register_id: u64) -> Result<()> {
+        self.gas_counter.pay_base(blake2b_base)?;
+        let value = self.get_vec_from_memory_or_register(value_ptr, value_len)?;
+        self.gas_counter.pay_per_byte(blake2b_byte, value.len() as u64)?;
+
+        use blake2::Digest;
+
+        let value_hash = blake2::Blake2b::digest(&value);
+        self.internal_write_register(register_id, value_hash.as_slice().to_vec())
+    }
+
+    /// Verifies the ed25519 `signature` of the `message` with the `public_key`. Returns 1 if the
+    /// signature is valid and 0 otherwise, including when the signature is not 64 bytes long or
+    /// the public key is not 32 bytes long.
+    ///
+    /// # Errors
+    ///
+    /// If any of the `*_len + *_ptr` points outside the memory or the registers use more memory
+    /// than the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + ed25519_verify_base + ed25519_verify_byte * message_len`
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    pub fn ed25519_verify(
+        &mut self,
+        signature_len: u64,
+        signature_ptr: u64,
+        message_len: u64,
+        message_ptr: u64,
+        public_key_len: u64,
+        public_key_ptr: u64,
+    ) -> Result<u64> {
+        use std::convert::TryFrom;
+
+        self.gas_counter.pay_base(ed25519_verify_base)?;
+        let signature = self.get_vec_from_memory_or_register(signature_ptr, signature_len)?;
+        let message = self.get_vec_from_memory_or_register(message_ptr, message_len)?;
+        self.gas_counter.pay_per_byte(ed25519_verify_byte, message.len() as u64)?;
+        let public_key = self.get_vec_from_memory_or_register(public_key_ptr, public_key_len)?;
+
+        let signature =
+            match near_crypto::Signature::from_parts(near_crypto::KeyType::ED25519, &signature) {
+                Ok(signature) => signature,
+                Err(_) => return Ok(0),
+            };
+        let public_key = match near_crypto::ED25519PublicKey::try_from(public_key.as_slice()) {
+            Ok(public_key) => near_crypto::PublicKey::ED25519(public_key),
+            Err(_) => return Ok(0),
+        };
+        Ok(signature.verify(&message, &public_key) as u64)
+    }
+
+    /// Recovers the secp256k1 public key which signed the 32 bytes `hash` with the 64 bytes
+    /// `signature` and the recovery id `v`, as Ethereum's `ecrecover` does. On success writes the
+    /// 64 bytes uncompressed public key without the prefix into `register_id` and returns 1.
+    /// Returns 0 if the key cannot be recovered, including when the hash or the signature have
+    /// the wrong length or `v` is not in `0..=3`.
+    ///
+    /// # Errors
+    ///
+    /// If `hash_len + hash_ptr` or `signature_len + signature_ptr` points outside the memory or
+    /// the registers use more memory than the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + ecrecover_base + write_register_base + write_register_byte * 64`
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    pub fn ecrecover(
+        &mut self,
+        hash_len: u64,
+        hash_ptr: u64,
+        signature_len: u64,
+        signature_ptr: u64,
+        v: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(ecrecover_base)?;
+        let hash = self.get_vec_from_memory_or_register(hash_ptr, hash_len)?;
+        let signature = self.get_vec_from_memory_or_register(signature_ptr, signature_len)?;
+        if hash.len() != 32 || signature.len() != 64 || v > 3 {
+            return Ok(0);
+        }
+
+        let mut msg = [0u8; 32];
+        msg.copy_from_slice(&hash);
+        let mut recoverable_signature = [0u8; 65];
+        recoverable_signature[..64].copy_from_slice(&signature);
+        recoverable_signature[64] = v as u8;
+        match near_crypto::Secp256K1Signature::from(recoverable_signature).recover(msg) {
+            Ok(public_key) => {
+                self.internal_write_register(register_id, public_key.as_ref().to_vec())?;
+                Ok(1)
+            }
+            Err(_) => Ok(0),
+        }
+    }
+
     /// Called by gas metering injected into Wasm. Counts both towards `burnt_gas` and `used_gas`.
     ///
     /// # Errors
@@ -1011,7 +1187,7 @@ impl<'a> VMLogic<'a> {
     ///   `MemoryAccessViolation`;
     /// * If any of the promises in the array do not correspond to existing promises returns
     ///   `InvalidPromiseIndex`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     /// * If the total number of receipt dependencies exceeds `max_number_input_data_dependencies`
     ///   limit returns `NumInputDataDependenciesExceeded`.
     /// * If the total number of promises exceeds `max_promises_per_function_call_action` limit
@@ -1031,11 +1207,7 @@ impl<'a> VMLogic<'a> {
         promise_idx_count: u64,
     ) -> Result<PromiseIndex> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
-            return Err(
-                HostError::ProhibitedInView { method_name: "promise_and".to_string() }.into()
-            );
-        }
+        self.check_promises_allowed("promise_and")?;
         self.gas_counter.pay_base(promise_and_base)?;
         self.gas_counter.pay_per_byte(
             promise_and_per_promise,
@@ -1080,7 +1252,7 @@ impl<'a> VMLogic<'a> {
     ///
     /// * If `account_id_len + account_id_ptr` points outside the memory of the guest or host
     /// returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     /// * If the total number of promises exceeds `max_promises_per_function_call_action` limit
     ///   returns `NumPromisesExceeded`.
     ///
@@ -1099,16 +1271,16 @@ impl<'a> VMLogic<'a> {
         account_id_ptr: u64,
     ) -> Result<u64> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
-            return Err(HostError::ProhibitedInView {
-                method_name: "promise_batch_create".to_string(),
-            }
-            .into());
-        }
+        self.check_promises_allowed("promise_batch_create")?;
         let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
         let sir = account_id == self.context.current_account_id;
         self.pay_gas_for_new_receipt(sir, &[])?;
         let new_receipt_idx = self.ext.create_receipt(vec![], account_id.clone())?;
+        trace::record(|| TraceEvent::PromiseCreate {
+            receipt_index: new_receipt_idx,
+            receiver_id: account_id.clone(),
+            depends_on: vec![],
+        });
         self.receipt_to_account.insert(new_receipt_idx, account_id);
 
         self.checked_push_promise(Promise::Receipt(new_receipt_idx))
@@ -1122,7 +1294,7 @@ impl<'a> VMLogic<'a> {
     /// * If `promise_idx` does not correspond to an existing promise returns `InvalidPromiseIndex`;
     /// * If `account_id_len + account_id_ptr` points outside the memory of the guest or host
     /// returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     /// * If the total number of promises exceeds `max_promises_per_function_call_action` limit
     ///   returns `NumPromisesExceeded`.
     ///
@@ -1142,12 +1314,7 @@ impl<'a> VMLogic<'a> {
         account_id_ptr: u64,
     ) -> Result<u64> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
-            return Err(HostError::ProhibitedInView {
-                method_name: "promise_batch_then".to_string(),
-            }
-            .into());
-        }
+        self.check_promises_allowed("promise_batch_then")?;
         let account_id = self.read_and_parse_account_id(account_id_ptr, account_id_len)?;
         // Update the DAG and return new promise idx.
         let promise = self
@@ -1171,7 +1338,13 @@ impl<'a> VMLogic<'a> {
             .collect();
         self.pay_gas_for_new_receipt(sir, &deps)?;
 
-        let new_receipt_idx = self.ext.create_receipt(receipt_dependencies, account_id.clone())?;
+        let new_receipt_idx =
+            self.ext.create_receipt(receipt_dependencies.clone(), account_id.clone())?;
+        trace::record(|| TraceEvent::PromiseCreate {
+            receipt_index: new_receipt_idx,
+            receiver_id: account_id.clone(),
+            depends_on: receipt_dependencies,
+        });
         self.receipt_to_account.insert(new_receipt_idx, account_id);
 
         self.checked_push_promise(Promise::Receipt(new_receipt_idx))
@@ -1304,7 +1477,8 @@ impl<'a> VMLogic<'a> {
     /// * If `method_name_len + method_name_ptr` or `arguments_len + arguments_ptr` or
     /// `amount_ptr + 16` points outside the memory of the guest or host returns
     /// `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises or with attached deposit returns
+    /// `ProhibitedInView`.
     ///
     /// # Cost
     ///
@@ -1322,13 +1496,14 @@ impl<'a> VMLogic<'a> {
         gas: Gas,
     ) -> Result<()> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
+        self.check_promises_allowed("promise_batch_action_function_call")?;
+        let amount = self.memory_get_u128(amount_ptr)?;
+        if self.context.is_view && amount > 0 {
             return Err(HostError::ProhibitedInView {
                 method_name: "promise_batch_action_function_call".to_string(),
             }
             .into());
         }
-        let amount = self.memory_get_u128(amount_ptr)?;
         let method_name = self.get_vec_from_memory_or_register(method_name_ptr, method_name_len)?;
         if method_name.is_empty() {
             return Err(HostError::EmptyMethodName.into());
@@ -1640,7 +1815,7 @@ impl<'a> VMLogic<'a> {
     /// `promise_and` returns `CannotAppendActionToJointPromise`.
     /// * If `beneficiary_id_len + beneficiary_id_ptr` points outside the memory of the guest or
     /// host returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     ///
     /// # Cost
     ///
@@ -1690,12 +1865,7 @@ impl<'a> VMLogic<'a> {
     /// `base`
     pub fn promise_results_count(&mut self) -> Result<u64> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
-            return Err(HostError::ProhibitedInView {
-                method_name: "promise_results_count".to_string(),
-            }
-            .into());
-        }
+        self.check_promises_allowed("promise_results_count")?;
         Ok(self.promise_results.len() as _)
     }
 
@@ -1716,18 +1886,14 @@ impl<'a> VMLogic<'a> {
     ///
     /// * If `result_id` does not correspond to an existing result returns `InvalidPromiseResultIndex`;
     /// * If copying the blob exhausts the memory limit it returns `MemoryAccessViolation`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     ///
     /// # Cost
     ///
     /// `base + cost of writing data into a register`
     pub fn promise_result(&mut self, result_idx: u64, register_id: u64) -> Result<u64> {
         self.gas_counter.pay_base(base)?;
-        if self.context.is_view {
-            return Err(
-                HostError::ProhibitedInView { method_name: "promise_result".to_string() }.into()
-            );
-        }
+        self.check_promises_allowed("promise_result")?;
         match self
             .promise_results
             .get(result_idx as usize)
@@ -1748,7 +1914,7 @@ impl<'a> VMLogic<'a> {
     /// # Errors
     ///
     /// * If `promise_idx` does not correspond to an existing promise returns `InvalidPromiseIndex`.
-    /// * If called as view function returns `ProhibitedInView`.
+    /// * If called as view function that can't use promises returns `ProhibitedInView`.
     ///
     /// # Cost
     ///
@@ -1756,11 +1922,7 @@ impl<'a> VMLogic<'a> {
     pub fn promise_return(&mut self, promise_idx: u64) -> Result<()> {
         self.gas_counter.pay_base(base)?;
         self.gas_counter.pay_base(promise_return)?;
-        if self.context.is_view {
-            return Err(
-                HostError::ProhibitedInView { method_name: "promise_return".to_string() }.into()
-            );
-        }
+        self.check_promises_allowed("promise_return")?;
         match self
             .promises
             .get(promise_idx as usize)
@@ -2024,6 +2186,10 @@ impl<'a> VMLogic<'a> {
         self.gas_counter
             .pay_per_byte(touching_trie_node, self.ext.get_touched_nodes_count() - nodes_before)?;
         self.ext.storage_set(&key, &value)?;
+        trace::record(|| TraceEvent::StorageWrite {
+            key: key.clone(),
+            value_len: value.len() as u64,
+        });
         let storage_config = &self.fees_config.storage_usage_config;
         match evicted {
             Some(old_value) => {
@@ -2103,6 +2269,10 @@ impl<'a> VMLogic<'a> {
         self.gas_counter
             .pay_per_byte(touching_trie_node, self.ext.get_touched_nodes_count() - nodes_before)?;
         let read = Self::deref_value(&mut self.gas_counter, storage_read_value_byte, read?)?;
+        trace::record(|| TraceEvent::StorageRead {
+            key: key.clone(),
+            value_len: read.as_ref().map(|value| value.len() as u64),
+        });
         match read {
             Some(value) => {
                 self.internal_write_register(register_id, value)?;
@@ -2154,6 +2324,7 @@ impl<'a> VMLogic<'a> {
             Self::deref_value(&mut self.gas_counter, storage_remove_ret_value_byte, removed_ptr)?;
 
         self.ext.storage_remove(&key)?;
+        trace::record(|| TraceEvent::StorageRemove { key: key.clone() });
         self.gas_counter
             .pay_per_byte(touching_trie_node, self.ext.get_touched_nodes_count() - nodes_before)?;
         let storage_config = &self.fees_config.storage_usage_config;
@@ -2203,7 +2374,9 @@ impl<'a> VMLogic<'a> {
         let res = self.ext.storage_has_key(&key);
         self.gas_counter
             .pay_per_byte(touching_trie_node, self.ext.get_touched_nodes_count() - nodes_before)?;
-        Ok(res? as u64)
+        let res = res?;
+        trace::record(|| TraceEvent::StorageHasKey { key: key.clone(), found: res });
+        Ok(res as u64)
     }
 
     /// DEPRECATED
@@ -530,6 +530,169 @@ fn test_keccak512() {
     });
 }
 
+#[cfg(feature = "protocol_feature_crypto_host_functions")]
+#[test]
+fn test_ripemd160() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let data = b"tesdsst";
+
+    logic.ripemd160(data.len() as _, data.as_ptr() as _, 0).unwrap();
+    let res = &vec![0u8; 20];
+    logic.read_register(0, res.as_ptr() as _).expect("OK");
+    assert_eq!(
+        res,
+        &[21, 102, 156, 115, 232, 3, 58, 215, 35, 84, 129, 30, 143, 86, 212, 104, 70, 97, 14, 225]
+            .to_vec()
+    );
+    let len = data.len() as u64;
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 1,
+        ExtCosts::read_memory_byte: len,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 20,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 20,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 20,
+        ExtCosts::ripemd160_base: 1,
+        ExtCosts::ripemd160_byte: len,
+    });
+}
+
+#[cfg(feature = "protocol_feature_crypto_host_functions")]
+#[test]
+fn test_blake2b() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let data = b"tesdsst";
+
+    logic.blake2b(data.len() as _, data.as_ptr() as _, 0).unwrap();
+    let res = &vec![0u8; 64];
+    logic.read_register(0, res.as_ptr() as _).expect("OK");
+    assert_eq!(
+        res,
+        &[
+            144, 12, 165, 192, 98, 246, 37, 228, 134, 61, 43, 212, 111, 32, 204, 204, 186, 212, 47,
+            44, 209, 53, 167, 80, 195, 200, 226, 84, 34, 162, 249, 135, 172, 3, 90, 122, 205, 96,
+            211, 100, 188, 18, 134, 125, 111, 130, 31, 143, 25, 108, 194, 209, 205, 73, 169, 10,
+            132, 222, 75, 219, 103, 234, 67, 180
+        ]
+        .to_vec()
+    );
+    let len = data.len() as u64;
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 1,
+        ExtCosts::read_memory_byte: len,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 64,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 64,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 64,
+        ExtCosts::blake2b_base: 1,
+        ExtCosts::blake2b_byte: len,
+    });
+}
+
+#[cfg(feature = "protocol_feature_crypto_host_functions")]
+#[test]
+fn test_ed25519_verify() {
+    use near_crypto::{KeyType, SecretKey, Signature};
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let secret_key = SecretKey::from_seed(KeyType::ED25519, "test");
+    let public_key = secret_key.public_key();
+    let public_key = public_key.key_data();
+    let message = b"tesdsst";
+    let signature = match secret_key.sign(message) {
+        Signature::ED25519(signature) => signature.to_bytes(),
+        _ => unreachable!(),
+    };
+
+    fn verify(
+        logic: &mut near_vm_logic::VMLogic<'_>,
+        signature: &[u8],
+        message: &[u8],
+        public_key: &[u8],
+    ) -> u64 {
+        logic
+            .ed25519_verify(
+                signature.len() as _,
+                signature.as_ptr() as _,
+                message.len() as _,
+                message.as_ptr() as _,
+                public_key.len() as _,
+                public_key.as_ptr() as _,
+            )
+            .unwrap()
+    }
+    assert_eq!(verify(&mut logic, &signature, message, public_key), 1);
+    let len = message.len() as u64;
+    assert_costs(map! {
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: 64 + len + 32,
+        ExtCosts::ed25519_verify_base: 1,
+        ExtCosts::ed25519_verify_byte: len,
+    });
+
+    assert_eq!(verify(&mut logic, &signature, b"tesdsss", public_key), 0);
+    assert_eq!(verify(&mut logic, &signature[..63], message, public_key), 0);
+}
+
+#[cfg(feature = "protocol_feature_crypto_host_functions")]
+#[test]
+fn test_ecrecover() {
+    use near_crypto::{KeyType, PublicKey, SecretKey, Signature};
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let secret_key = SecretKey::from_seed(KeyType::SECP256K1, "test");
+    let hash = [7u8; 32];
+    let signature: [u8; 65] = match secret_key.sign(&hash) {
+        Signature::SECP256K1(signature) => signature.into(),
+        _ => unreachable!(),
+    };
+
+    let result = logic
+        .ecrecover(
+            hash.len() as _,
+            hash.as_ptr() as _,
+            64,
+            signature.as_ptr() as _,
+            signature[64] as _,
+            0,
+        )
+        .unwrap();
+    assert_eq!(result, 1);
+    let res = &vec![0u8; 64];
+    logic.read_register(0, res.as_ptr() as _).expect("OK");
+    match secret_key.public_key() {
+        PublicKey::SECP256K1(public_key) => assert_eq!(res, &public_key.as_ref().to_vec()),
+        _ => unreachable!(),
+    }
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 2,
+        ExtCosts::read_memory_byte: 32 + 64,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 64,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 64,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 64,
+        ExtCosts::ecrecover_base: 1,
+    });
+
+    let result = logic
+        .ecrecover(hash.len() as _, hash.as_ptr() as _, 64, signature.as_ptr() as _, 4, 0)
+        .unwrap();
+    assert_eq!(result, 0);
+}
+
 #[test]
 fn test_hash256_register() {
     let mut logic_builder = VMLogicBuilder::default();
@@ -1,7 +1,10 @@
 mod fixtures;
+mod helpers;
 mod vm_logic_builder;
 
 use crate::fixtures::get_context;
+use helpers::*;
+use near_vm_logic::types::PromiseResult;
 use vm_logic_builder::VMLogicBuilder;
 
 macro_rules! test_prohibited {
@@ -51,3 +54,25 @@ fn test_allowed_view_method() {
     let mut logic = logic_builder.build(context.clone());
     assert_eq!(logic.block_index().unwrap(), context.block_index);
 }
+
+#[test]
+fn test_allowed_view_promises() {
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.promise_results = vec![PromiseResult::Successful(b"result".to_vec())];
+    let mut context = get_context(vec![], true);
+    context.allow_view_promises = true;
+    let mut logic = logic_builder.build(context);
+
+    assert_eq!(logic.promise_results_count(), Ok(1));
+    assert_eq!(logic.promise_result(0, 0), Ok(1));
+    let index = promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
+    promise_batch_action_function_call(&mut logic, index, 0, 0)
+        .expect("should call a function without deposit");
+    promise_batch_action_function_call(&mut logic, index, 1, 0)
+        .expect_err("shouldn't attach deposit in view calls");
+    logic
+        .promise_batch_action_transfer(index, 0u128.to_le_bytes().as_ptr() as _)
+        .expect_err("shouldn't transfer in view calls");
+    logic.promise_return(index).expect("should return a promise");
+    logic.storage_write(0, 0, 0, 0, 0).expect_err("shouldn't write storage in view calls");
+}
@@ -86,6 +86,7 @@ fn default_vm_context() -> VMContext {
         prepaid_gas: 10u64.pow(18),
         random_seed: vec![0, 1, 2],
         is_view: false,
+        allow_view_promises: false,
         output_data_receivers: vec![],
         epoch_height: 1,
     };
@@ -38,6 +38,7 @@ fn setup(
         prepaid_gas: 10u64.pow(15),
         random_seed: vec![0, 1, 2],
         is_view: false,
+        allow_view_promises: false,
         output_data_receivers: vec![],
     };
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -24,6 +24,25 @@ macro_rules! rust2wasm {
     };
 }
 
+/// Calls the host function on `$logic`. When tracing is enabled, the call is recorded with its
+/// arguments, except for `gas` which is called by the injected gas metering code.
+macro_rules! call_host_function {
+    ($logic:ident, gas, [ $( $arg_name:ident = $arg:expr ),* ]) => {
+        $logic.gas( $( $arg, )* )
+    };
+    ($logic:ident, $func:ident, [ $( $arg_name:ident = $arg:expr ),* ]) => {
+        if near_primitives::trace::is_tracing() {
+            $logic.trace_host_function(
+                stringify!($func),
+                &[ $( (stringify!($arg_name), $arg as u64), )* ],
+                |logic| logic.$func( $( $arg, )* ),
+            )
+        } else {
+            $logic.$func( $( $arg, )* )
+        }
+    };
+}
+
 macro_rules! wrapped_imports {
         ( $($(#[$feature_name:tt, $feature:ident])* $func:ident < [ $( $arg_name:ident : $arg_type:ident ),* ] -> [ $( $returns:ident ),* ] >, )* ) => {
             pub mod wasmer_ext {
@@ -35,7 +54,7 @@ macro_rules! wrapped_imports {
                     $(#[cfg(feature = $feature_name)])*
                     pub fn $func( ctx: &mut Ctx, $( $arg_name: $arg_type ),* ) -> VMResult<($( $returns ),*)> {
                         let logic: &mut VMLogic<'_> = unsafe { &mut *(ctx.data as *mut VMLogic<'_>) };
-                        logic.$func( $( $arg_name, )* )
+                        call_host_function!(logic, $func, [ $( $arg_name = $arg_name ),* ])
                     }
                 )*
             }
@@ -63,7 +82,7 @@ macro_rules! wrapped_imports {
                             }
                         });
                         let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
-                        match logic.$func( $( $arg_name as $arg_type, )* ) {
+                        match call_host_function!(logic, $func, [ $( $arg_name = $arg_name as $arg_type ),* ]) {
                             Ok(result) => Ok(result as ($( rust2wasm!($returns) ),* ) ),
                             Err(err) => {
                                 // Wasmtime doesn't have proper mechanism for wrapping custom errors
@@ -170,6 +189,24 @@ wrapped_imports! {
     sha256<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     keccak256<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     keccak512<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
+    #["protocol_feature_crypto_host_functions", CryptoHostFunctions] ripemd160<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
+    #["protocol_feature_crypto_host_functions", CryptoHostFunctions] blake2b<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
+    #["protocol_feature_crypto_host_functions", CryptoHostFunctions] ed25519_verify<[
+        signature_len: u64,
+        signature_ptr: u64,
+        message_len: u64,
+        message_ptr: u64,
+        public_key_len: u64,
+        public_key_ptr: u64
+    ] -> [u64]>,
+    #["protocol_feature_crypto_host_functions", CryptoHostFunctions] ecrecover<[
+        hash_len: u64,
+        hash_ptr: u64,
+        signature_len: u64,
+        signature_ptr: u64,
+        v: u64,
+        register_id: u64
+    ] -> [u64]>,
     // #####################
     // # Miscellaneous API #
     // #####################
@@ -1,5 +1,6 @@
 use near_primitives::hash::CryptoHash;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::trace;
 use near_primitives::{
     config::VMConfig, profile::ProfileData, types::CompiledContractCache, version::ProtocolVersion,
 };
@@ -19,6 +20,9 @@ use near_vm_logic::{External, VMContext, VMKind, VMOutcome};
 ///   - collects logs
 ///   - sets the return data
 ///  returns result as `VMOutcome`
+///
+/// When tracing is enabled on the current thread (see `near_primitives::trace`) the call is
+/// recorded as a new function call trace.
 pub fn run<'a>(
     code_hash: Vec<u8>,
     code: &[u8],
@@ -31,6 +35,56 @@ pub fn run<'a>(
     current_protocol_version: ProtocolVersion,
     cache: Option<&'a dyn CompiledContractCache>,
     #[cfg(feature = "costs_counting")] profile: Option<&ProfileData>,
+) -> (Option<VMOutcome>, Option<VMError>) {
+    if !trace::is_tracing() {
+        return run_untraced(
+            code_hash,
+            code,
+            method_name,
+            ext,
+            context,
+            wasm_config,
+            fees_config,
+            promise_results,
+            current_protocol_version,
+            cache,
+            #[cfg(feature = "costs_counting")]
+            profile,
+        );
+    }
+    trace::begin_function_call(&context.current_account_id, method_name);
+    let (outcome, error) = run_untraced(
+        code_hash,
+        code,
+        method_name,
+        ext,
+        context,
+        wasm_config,
+        fees_config,
+        promise_results,
+        current_protocol_version,
+        cache,
+        #[cfg(feature = "costs_counting")]
+        profile,
+    );
+    let (gas_burnt, gas_used) =
+        outcome.as_ref().map_or((0, 0), |outcome| (outcome.burnt_gas, outcome.used_gas));
+    trace::end_function_call(gas_burnt, gas_used, error.as_ref().map(ToString::to_string));
+    (outcome, error)
+}
+
+fn run_untraced<'a>(
+    code_hash: Vec<u8>,
+    code: &[u8],
+    method_name: &[u8],
+    ext: &mut dyn External,
+    context: VMContext,
+    wasm_config: &'a VMConfig,
+    fees_config: &'a RuntimeFeesConfig,
+    promise_results: &'a [PromiseResult],
+    current_protocol_version: ProtocolVersion,
+    cache: Option<&'a dyn CompiledContractCache>,
+    #[cfg(feature = "costs_counting")] profile: Option<&ProfileData>,
 ) -> (Option<VMOutcome>, Option<VMError>) {
     #[cfg(feature = "costs_counting")]
     if let Some(profile) = profile {
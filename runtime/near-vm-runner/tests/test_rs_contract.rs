@@ -1,9 +1,10 @@
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::trace::{self, TraceEvent};
 use near_primitives::types::Balance;
 use near_vm_errors::{FunctionCallError, VMError};
 use near_vm_logic::mocks::mock_external::MockedExternal;
 use near_vm_logic::{types::ReturnData, VMConfig, VMContext, VMKind, VMOutcome};
-use near_vm_runner::{run_vm, with_vm_variants};
+use near_vm_runner::{run, run_vm, with_vm_variants};
 use std::mem::size_of;
 
 pub mod test_utils;
@@ -90,6 +91,155 @@ pub fn test_read_write() {
     });
 }
 
+#[test]
+pub fn test_trace_write() {
+    let mut fake_external = MockedExternal::new();
+    let context = create_context(&arr_u64_to_u8(&[10u64, 20u64]));
+    let config = VMConfig::default();
+    let fees = RuntimeFeesConfig::default();
+
+    trace::start_tracing();
+    let (outcome, err) = run(
+        vec![],
+        &TEST_CONTRACT,
+        b"write_key_value",
+        &mut fake_external,
+        context,
+        &config,
+        &fees,
+        &[],
+        LATEST_PROTOCOL_VERSION,
+        None,
+        #[cfg(feature = "costs_counting")]
+        None,
+    );
+    let traces = trace::finish_tracing();
+    assert_eq!(err, None);
+    let outcome = outcome.unwrap();
+
+    assert_eq!(traces.len(), 1);
+    let trace = &traces[0];
+    assert_eq!(trace.account_id, CURRENT_ACCOUNT_ID);
+    assert_eq!(trace.method_name, "write_key_value");
+    assert_eq!((trace.gas_burnt, trace.gas_used), (outcome.burnt_gas, outcome.used_gas));
+    let names: Vec<_> = trace
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::HostFunction { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec!["input", "register_len", "read_register", "storage_write", "value_return"]
+    );
+    let position = trace
+        .events
+        .iter()
+        .position(|event| matches!(event, TraceEvent::HostFunction { name, .. } if name == "storage_write"))
+        .unwrap();
+    match &trace.events[position] {
+        TraceEvent::HostFunction { args, gas_burnt, error, .. } => {
+            assert_eq!(args[0], ("key_len".to_string(), 8));
+            assert!(*gas_burnt > 0);
+            assert_eq!(*error, None);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(
+        trace.events[position + 1],
+        TraceEvent::StorageWrite { key: 10u64.to_le_bytes().to_vec(), value_len: 8 }
+    );
+}
+
+#[test]
+pub fn test_trace_read() {
+    with_vm_variants(|vm_kind: VMKind| {
+        let mut fake_external = MockedExternal::new();
+        let config = VMConfig::default();
+        let fees = RuntimeFeesConfig::default();
+        let context = create_context(&arr_u64_to_u8(&[10u64, 20u64]));
+        let result = run_vm(
+            vec![],
+            &TEST_CONTRACT,
+            b"write_key_value",
+            &mut fake_external,
+            context,
+            &config,
+            &fees,
+            &[],
+            vm_kind.clone(),
+            LATEST_PROTOCOL_VERSION,
+            None,
+        );
+        assert_run_result(result, 0);
+
+        trace::start_tracing();
+        trace::begin_function_call(CURRENT_ACCOUNT_ID, b"read_value");
+        let context = create_context(&arr_u64_to_u8(&[10u64]));
+        let result = run_vm(
+            vec![],
+            &TEST_CONTRACT,
+            b"read_value",
+            &mut fake_external,
+            context,
+            &config,
+            &fees,
+            &[],
+            vm_kind,
+            LATEST_PROTOCOL_VERSION,
+            None,
+        );
+        let traces = trace::finish_tracing();
+        assert_run_result(result, 20);
+
+        // Every host function called by the contract is recorded with its arguments, in order.
+        // The `gas` calls of the injected metering code are not recorded.
+        assert_eq!(traces.len(), 1);
+        let events: Vec<(String, Vec<(String, u64)>)> = traces[0]
+            .events
+            .iter()
+            .map(|event| match event {
+                TraceEvent::HostFunction { name, args, gas_burnt, error, .. } => {
+                    assert!(*gas_burnt > 0);
+                    assert_eq!(*error, None);
+                    // Pointers depend on the compiled contract.
+                    let args = args
+                        .iter()
+                        .map(|(arg, value)| {
+                            (arg.clone(), if arg.ends_with("ptr") { 0 } else { *value })
+                        })
+                        .collect();
+                    (name.clone(), args)
+                }
+                event => (format!("{:?}", event), vec![]),
+            })
+            .collect();
+        let host_function = |name: &str, args: &[(&str, u64)]| {
+            let args = args.iter().map(|(arg, value)| (arg.to_string(), *value)).collect();
+            (name.to_string(), args)
+        };
+        let storage_read =
+            TraceEvent::StorageRead { key: 10u64.to_le_bytes().to_vec(), value_len: Some(8) };
+        assert_eq!(
+            events,
+            vec![
+                host_function("input", &[("register_id", 0)]),
+                host_function("register_len", &[("register_id", 0)]),
+                host_function("read_register", &[("register_id", 0), ("ptr", 0)]),
+                host_function(
+                    "storage_read",
+                    &[("key_len", 8), ("key_ptr", 0), ("register_id", 1)]
+                ),
+                (format!("{:?}", storage_read), vec![]),
+                host_function("read_register", &[("register_id", 1), ("ptr", 0)]),
+                host_function("value_return", &[("value_len", 8), ("value_ptr", 0)]),
+            ]
+        );
+    });
+}
+
 macro_rules! def_test_ext {
     ($name:ident, $method:expr, $expected:expr, $input:expr, $validator:expr) => {
         #[test]
@@ -37,6 +37,7 @@ pub fn create_context(input: Vec<u8>) -> VMContext {
         prepaid_gas: 10_u64.pow(14),
         random_seed: vec![0, 1, 2],
         is_view: false,
+        allow_view_promises: false,
         output_data_receivers: vec![],
     }
 }
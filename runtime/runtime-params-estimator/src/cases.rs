@@ -182,6 +182,20 @@ pub enum Metric {
     keccak256_10kib_10k,
     keccak512_10b_10k,
     keccak512_10kib_10k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ripemd160_10b_10k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ripemd160_10kib_10k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    blake2b_10b_10k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    blake2b_10kib_10k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ed25519_verify_32b_1k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ed25519_verify_10kib_1k,
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    ecrecover_1k,
     storage_write_10b_key_10b_value_1k,
     storage_write_10kib_key_10b_value_1k,
     storage_write_10b_key_10kib_value_1k,
@@ -534,7 +548,8 @@ pub fn run(mut config: Config, only_compile: bool, only_evm: bool) -> RuntimeCon
     config.block_sizes = vec![2];
 
     // When adding new functions do not forget to rebuild the test contract by running `test-contract/build.sh`.
-    let v = calls_helper! {
+    #[allow(unused_mut)]
+    let mut v = calls_helper! {
     cpu_ram_soak_test => cpu_ram_soak_test,
     base_1M => base_1M,
     read_memory_10b_10k => read_memory_10b_10k,
@@ -582,6 +597,18 @@ pub fn run(mut config: Config, only_compile: bool, only_evm: bool) -> RuntimeCon
     data_receipt_100kib_1000 => data_receipt_100kib_1000
         };
 
+    // Requires the test contract to be built with the `crypto_host_functions` feature.
+    #[cfg(feature = "protocol_feature_crypto_host_functions")]
+    v.extend(calls_helper! {
+    ripemd160_10b_10k => ripemd160_10b_10k,
+    ripemd160_10kib_10k => ripemd160_10kib_10k,
+    blake2b_10b_10k => blake2b_10b_10k,
+    blake2b_10kib_10k => blake2b_10kib_10k,
+    ed25519_verify_32b_1k => ed25519_verify_32b_1k,
+    ed25519_verify_10kib_1k => ed25519_verify_10kib_1k,
+    ecrecover_1k => ecrecover_1k
+        });
+
     // Measure the speed of all extern function calls.
     for (metric, method_name) in v {
         testbed = measure_function(
@@ -717,6 +744,20 @@ fn get_ext_costs_config(measurement: &Measurements, config: &Config) -> ExtCosts
         keccak256_byte: measured_to_gas(metric, &measured, keccak256_byte),
         keccak512_base: measured_to_gas(metric, &measured, keccak512_base),
         keccak512_byte: measured_to_gas(metric, &measured, keccak512_byte),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        ed25519_verify_base: measured_to_gas(metric, &measured, ed25519_verify_base),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        ed25519_verify_byte: measured_to_gas(metric, &measured, ed25519_verify_byte),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        ecrecover_base: measured_to_gas(metric, &measured, ecrecover_base),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        ripemd160_base: measured_to_gas(metric, &measured, ripemd160_base),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        ripemd160_byte: measured_to_gas(metric, &measured, ripemd160_byte),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        blake2b_base: measured_to_gas(metric, &measured, blake2b_base),
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        blake2b_byte: measured_to_gas(metric, &measured, blake2b_byte),
         log_base: measured_to_gas(metric, &measured, log_base),
         log_byte: measured_to_gas(metric, &measured, log_byte),
         storage_write_base: measured_to_gas(metric, &measured, storage_write_base),
@@ -65,6 +65,20 @@ impl ExtCostsGenerator {
         self.extract(keccak512_10b_10k, keccak512_base);
         self.extract(keccak512_10kib_10k, keccak512_byte);
 
+        #[cfg(feature = "protocol_feature_crypto_host_functions")]
+        {
+            self.extract(ripemd160_10b_10k, ripemd160_base);
+            self.extract(ripemd160_10kib_10k, ripemd160_byte);
+
+            self.extract(blake2b_10b_10k, blake2b_base);
+            self.extract(blake2b_10kib_10k, blake2b_byte);
+
+            self.extract(ed25519_verify_32b_1k, ed25519_verify_base);
+            self.extract(ed25519_verify_10kib_1k, ed25519_verify_byte);
+
+            self.extract(ecrecover_1k, ecrecover_base);
+        }
+
         // TODO: Redo storage costs once we have counting of nodes and we have size peek.
         self.extract(storage_write_10b_key_10b_value_1k, storage_write_base);
         self.extract(storage_write_10kib_key_10b_value_1k, storage_write_key_byte);
@@ -44,6 +44,28 @@ extern "C" {
     fn sha256(value_len: u64, value_ptr: u64, register_id: u64);
     fn keccak256(value_len: u64, value_ptr: u64, register_id: u64);
     fn keccak512(value_len: u64, value_ptr: u64, register_id: u64);
+    #[cfg(feature = "crypto_host_functions")]
+    fn ripemd160(value_len: u64, value_ptr: u64, register_id: u64);
+    #[cfg(feature = "crypto_host_functions")]
+    fn blake2b(value_len: u64, value_ptr: u64, register_id: u64);
+    #[cfg(feature = "crypto_host_functions")]
+    fn ed25519_verify(
+        signature_len: u64,
+        signature_ptr: u64,
+        message_len: u64,
+        message_ptr: u64,
+        public_key_len: u64,
+        public_key_ptr: u64,
+    ) -> u64;
+    #[cfg(feature = "crypto_host_functions")]
+    fn ecrecover(
+        hash_len: u64,
+        hash_ptr: u64,
+        signature_len: u64,
+        signature_ptr: u64,
+        v: u64,
+        register_id: u64,
+    ) -> u64;
     // #####################
     // # Miscellaneous API #
     // #####################
@@ -439,6 +461,144 @@ pub unsafe fn keccak512_10kib_10k() {
     }
 }
 
+// Function to measure `ripemd160_base` and `ripemd160_byte`. Also measures `base`, `write_register_base`,
+// and `write_register_byte`. However `ripemd160` computation is more expensive than register writing
+// so we are okay overcharging it.
+// Compute ripemd160 on 10b 10k times.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn ripemd160_10b_10k() {
+    let buffer = [65u8; 10];
+    for _ in 0..10_000 {
+        ripemd160(buffer.len() as u64, buffer.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
+// Function to measure `ripemd160_base` and `ripemd160_byte`. Also measures `base`, `write_register_base`,
+// and `write_register_byte`. However `ripemd160` computation is more expensive than register writing
+// so we are okay overcharging it.
+// Compute ripemd160 on 10kib 10k times.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn ripemd160_10kib_10k() {
+    let buffer = [65u8; 10240];
+    for _ in 0..10_000 {
+        ripemd160(buffer.len() as u64, buffer.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
+// Function to measure `blake2b_base` and `blake2b_byte`. Also measures `base`, `write_register_base`,
+// and `write_register_byte`. However `blake2b` computation is more expensive than register writing
+// so we are okay overcharging it.
+// Compute blake2b on 10b 10k times.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn blake2b_10b_10k() {
+    let buffer = [65u8; 10];
+    for _ in 0..10_000 {
+        blake2b(buffer.len() as u64, buffer.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
+// Function to measure `blake2b_base` and `blake2b_byte`. Also measures `base`, `write_register_base`,
+// and `write_register_byte`. However `blake2b` computation is more expensive than register writing
+// so we are okay overcharging it.
+// Compute blake2b on 10kib 10k times.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn blake2b_10kib_10k() {
+    let buffer = [65u8; 10240];
+    for _ in 0..10_000 {
+        blake2b(buffer.len() as u64, buffer.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
+// Function to measure `ed25519_verify_base` and `ed25519_verify_byte`. Also measures `base`,
+// `read_memory_base` and `read_memory_byte`.
+// Verify a signature of a 32b message 1k times. The key and the signature are generated from
+// the seed `[7u8; 32]` so the signature is valid.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn ed25519_verify_32b_1k() {
+    let signature: [u8; 64] = [
+        134, 31, 123, 182, 114, 207, 124, 119, 253, 253, 139, 114, 65, 31, 142, 104, 38, 198, 79,
+        72, 193, 149, 15, 158, 57, 226, 98, 235, 15, 92, 222, 120, 12, 184, 173, 21, 80, 177, 158,
+        241, 35, 59, 14, 131, 56, 138, 30, 0, 254, 229, 33, 183, 225, 191, 110, 125, 189, 48, 66,
+        22, 20, 179, 132, 5,
+    ];
+    let public_key: [u8; 32] = [
+        234, 74, 108, 99, 226, 156, 82, 10, 190, 245, 80, 123, 19, 46, 197, 249, 149, 71, 118, 174,
+        190, 190, 123, 146, 66, 30, 234, 105, 20, 70, 210, 44,
+    ];
+    let message = [65u8; 32];
+    for _ in 0..1_000 {
+        ed25519_verify(
+            signature.len() as u64,
+            signature.as_ptr() as *const u64 as u64,
+            message.len() as u64,
+            message.as_ptr() as *const u64 as u64,
+            public_key.len() as u64,
+            public_key.as_ptr() as *const u64 as u64,
+        );
+    }
+}
+
+// Function to measure `ed25519_verify_base` and `ed25519_verify_byte`. Also measures `base`,
+// `read_memory_base` and `read_memory_byte`.
+// Verify a signature of a 10kib message 1k times. The key and the signature are generated from
+// the seed `[7u8; 32]` so the signature is valid.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn ed25519_verify_10kib_1k() {
+    let signature: [u8; 64] = [
+        139, 159, 58, 133, 17, 236, 159, 122, 208, 205, 45, 78, 50, 78, 98, 160, 58, 240, 106, 131,
+        136, 253, 167, 6, 169, 244, 225, 224, 131, 20, 238, 183, 29, 34, 136, 194, 172, 18, 132,
+        153, 90, 225, 148, 174, 26, 240, 45, 142, 79, 87, 103, 226, 12, 98, 231, 50, 151, 110, 170,
+        180, 34, 217, 184, 11,
+    ];
+    let public_key: [u8; 32] = [
+        234, 74, 108, 99, 226, 156, 82, 10, 190, 245, 80, 123, 19, 46, 197, 249, 149, 71, 118, 174,
+        190, 190, 123, 146, 66, 30, 234, 105, 20, 70, 210, 44,
+    ];
+    let message = [65u8; 10240];
+    for _ in 0..1_000 {
+        ed25519_verify(
+            signature.len() as u64,
+            signature.as_ptr() as *const u64 as u64,
+            message.len() as u64,
+            message.as_ptr() as *const u64 as u64,
+            public_key.len() as u64,
+            public_key.as_ptr() as *const u64 as u64,
+        );
+    }
+}
+
+// Function to measure `ecrecover_base`. Also measures `base`, `read_memory_base`, `read_memory_byte`,
+// `write_register_base` and `write_register_byte`.
+// Recover a public key 1k times. `r` is the x coordinate of the secp256k1 generator point so a key
+// is always recovered.
+#[cfg(feature = "crypto_host_functions")]
+#[no_mangle]
+pub unsafe fn ecrecover_1k() {
+    let hash = [65u8; 32];
+    let mut signature = [1u8; 64];
+    signature[..32].copy_from_slice(&[
+        0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b,
+        0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8,
+        0x17, 0x98,
+    ]);
+    for _ in 0..1_000 {
+        ecrecover(
+            hash.len() as u64,
+            hash.as_ptr() as *const u64 as u64,
+            signature.len() as u64,
+            signature.as_ptr() as *const u64 as u64,
+            0,
+            0,
+        );
+    }
+}
+
 // ###############
 // # Storage API #
 // ###############
@@ -9,6 +9,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::trace;
 use near_primitives::transaction::{
     Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
     FunctionCallAction, StakeAction, TransferAction,
@@ -52,6 +53,7 @@ pub(crate) fn execute_function_call(
     config: &RuntimeConfig,
     is_last_action: bool,
     is_view: bool,
+    allow_view_promises: bool,
 ) -> (Option<VMOutcome>, Option<VMError>) {
     let account_id = runtime_ext.account_id();
     if checked_feature!("protocol_feature_evm", EVM, runtime_ext.protocol_version())
@@ -129,6 +131,7 @@ pub(crate) fn execute_function_call(
             prepaid_gas: function_call.gas,
             random_seed,
             is_view,
+            allow_view_promises,
             output_data_receivers,
         };
 
@@ -183,6 +186,7 @@ pub(crate) fn action_function_call(
         epoch_info_provider,
         apply_state.current_protocol_version,
     );
+    trace::set_receipt_id(receipt.receipt_id);
     let (outcome, err) = execute_function_call(
         apply_state,
         &mut runtime_ext,
@@ -195,6 +199,7 @@ pub(crate) fn action_function_call(
         config,
         is_last_action,
         false,
+        false,
     );
     let execution_succeeded = match err {
         Some(VMError::FunctionCallError(err)) => {
@@ -64,5 +64,7 @@ pub trait ViewRuntimeAdapter {
         state_root: MerkleHash,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
     ) -> Result<ViewStateResult, Box<dyn std::error::Error>>;
 }
@@ -210,16 +210,51 @@ impl Runtime {
         stats: &mut ApplyStats,
     ) -> Result<(Receipt, ExecutionOutcomeWithId), RuntimeError> {
         near_metrics::inc_counter(&metrics::TRANSACTION_PROCESSED_TOTAL);
+        let result =
+            self.convert_transaction(state_update, apply_state, signed_transaction, true, stats);
+        match result {
+            Ok(_) => near_metrics::inc_counter(&metrics::TRANSACTION_PROCESSED_SUCCESSFULLY_TOTAL),
+            Err(_) => near_metrics::inc_counter(&metrics::TRANSACTION_PROCESSED_FAILED_TOTAL),
+        }
+        result
+    }
+
+    /// Converts one signed transaction to a receipt like `apply` does, verifying its signature
+    /// only if `verify_signature` is set. Transactions that are not signed yet are only converted
+    /// to simulate them, on a state that is never committed to the chain.
+    pub fn simulate_transaction_conversion(
+        &self,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        signed_transaction: &SignedTransaction,
+        verify_signature: bool,
+    ) -> Result<(Receipt, ExecutionOutcomeWithId), RuntimeError> {
+        self.convert_transaction(
+            state_update,
+            apply_state,
+            signed_transaction,
+            verify_signature,
+            &mut ApplyStats::default(),
+        )
+    }
+
+    fn convert_transaction(
+        &self,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        signed_transaction: &SignedTransaction,
+        verify_signature: bool,
+        stats: &mut ApplyStats,
+    ) -> Result<(Receipt, ExecutionOutcomeWithId), RuntimeError> {
         match verify_and_charge_transaction(
             &apply_state.config,
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            verify_signature,
             apply_state.current_protocol_version,
         ) {
             Ok(verification_result) => {
-                near_metrics::inc_counter(&metrics::TRANSACTION_PROCESSED_SUCCESSFULLY_TOTAL);
                 state_update.commit(StateChangeCause::TransactionProcessing {
                     tx_hash: signed_transaction.get_hash(),
                 });
@@ -259,7 +294,6 @@ impl Runtime {
                 Ok((receipt, outcome))
             }
             Err(e) => {
-                near_metrics::inc_counter(&metrics::TRANSACTION_PROCESSED_FAILED_TOTAL);
                 state_update.rollback();
                 return Err(e);
             }
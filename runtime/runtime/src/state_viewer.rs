@@ -4,26 +4,57 @@ use near_primitives::{
     account::{AccessKey, Account},
     contract::ContractCode,
     hash::CryptoHash,
-    receipt::ActionReceipt,
+    receipt::{ActionReceipt, Receipt, ReceiptEnum},
     runtime::{apply_state::ApplyState, config::RuntimeConfig},
     serialize::to_base64,
-    transaction::FunctionCallAction,
+    transaction::{Action, FunctionCallAction},
     trie_key::trie_key_parsers,
-    types::{AccountId, EpochInfoProvider},
+    types::{AccountId, EpochInfoProvider, Gas},
     views::{StateItem, ViewApplyState, ViewStateResult},
 };
 use near_runtime_utils::is_valid_account_id;
 use near_store::{get_access_key, get_account, get_code, TrieUpdate};
+use near_vm_logic::types::PromiseResult;
 use near_vm_logic::ReturnData;
-use std::{str, sync::Arc, time::Instant};
+use std::{
+    cmp::{max, min},
+    collections::HashMap,
+    str,
+    sync::Arc,
+    time::Instant,
+};
 
 use crate::{actions::execute_function_call, ext::RuntimeExt};
 
-pub struct TrieViewer {}
+/// Maximum depth of the cross-contract calls followed by view calls, unless configured.
+pub const DEFAULT_MAX_VIEW_CALL_DEPTH: u32 = 3;
+
+/// Maximum number of values returned by a single view state query, unless configured.
+pub const DEFAULT_MAX_VIEW_STATE_VALUES: u64 = 10_000;
+
+pub struct TrieViewer {
+    /// Maximum depth of the cross-contract calls followed by view calls. View functions called at
+    /// this depth can't create promises, so `0` forbids them entirely.
+    max_call_depth: u32,
+    /// Maximum number of values returned by a view state query. Requested limits are clamped to
+    /// it and the rest of the state is reachable through the returned cursor.
+    max_state_values: u64,
+}
 
 impl TrieViewer {
     pub fn new() -> Self {
-        Self {}
+        Self::with_max_call_depth(DEFAULT_MAX_VIEW_CALL_DEPTH)
+    }
+
+    pub fn with_max_call_depth(max_call_depth: u32) -> Self {
+        Self { max_call_depth, max_state_values: DEFAULT_MAX_VIEW_STATE_VALUES }
+    }
+
+    /// Sets the maximum number of values returned by a view state query. A page needs at least
+    /// one value for the cursor to advance, so `0` is treated as `1`.
+    pub fn with_max_state_values(mut self, max_state_values: u64) -> Self {
+        self.max_state_values = max(max_state_values, 1);
+        self
     }
 
     pub fn view_account(
@@ -69,20 +100,37 @@ impl TrieViewer {
         state_update: &TrieUpdate,
         account_id: &AccountId,
         prefix: &[u8],
+        from_key: Option<&[u8]>,
+        limit: Option<u64>,
     ) -> Result<ViewStateResult, Box<dyn std::error::Error>> {
         if !is_valid_account_id(account_id) {
             return Err(format!("Account ID '{}' is not valid", account_id).into());
         }
+        if limit == Some(0) {
+            return Err("View state limit must be greater than 0".into());
+        }
+        let limit = limit.map_or(self.max_state_values, |limit| min(limit, self.max_state_values));
         let mut values = vec![];
+        let mut next_cursor = None;
         let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
         let acc_sep_len = query.len() - prefix.len();
         let mut iter = state_update.trie.iter(&state_update.get_root())?;
-        iter.seek(&query)?;
+        match from_key {
+            // Keys under the prefix are contiguous in the trie, so seeking past the prefix
+            // yields no values and seeking before it is the same as seeking to the prefix.
+            Some(from_key) if from_key > prefix => iter
+                .seek(&trie_key_parsers::get_raw_prefix_for_contract_data(account_id, from_key))?,
+            _ => iter.seek(&query)?,
+        }
         for item in iter {
             let (key, value) = item?;
             if !key.starts_with(&query.as_ref()) {
                 break;
             }
+            if values.len() as u64 >= limit {
+                next_cursor = Some(to_base64(&key[acc_sep_len..]));
+                break;
+            }
             values.push(StateItem {
                 key: to_base64(&key[acc_sep_len..]),
                 value: to_base64(&value),
@@ -90,7 +138,7 @@ impl TrieViewer {
             });
         }
         // TODO(2076): Add proofs for the storage items.
-        Ok(ViewStateResult { values, proof: vec![] })
+        Ok(ViewStateResult { values, proof: vec![], next_cursor })
     }
 
     pub fn call_function(
@@ -103,30 +151,10 @@ impl TrieViewer {
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
     ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        let now = Instant::now();
         if !is_valid_account_id(contract_id) {
             return Err(format!("Contract ID {:?} is not valid", contract_id).into());
         }
         let root = state_update.get_root();
-        let mut account = get_account(&state_update, contract_id)?
-            .ok_or_else(|| format!("Account {:?} doesn't exist", contract_id))?;
-        // TODO(#1015): Add ability to pass public key and originator_id
-        let originator_id = contract_id;
-        let public_key = PublicKey::empty(KeyType::ED25519);
-        let empty_hash = CryptoHash::default();
-        let mut runtime_ext = RuntimeExt::new(
-            &mut state_update,
-            contract_id,
-            originator_id,
-            &public_key,
-            0,
-            &empty_hash,
-            &view_state.epoch_id,
-            &view_state.prev_block_hash,
-            &view_state.block_hash,
-            epoch_info_provider,
-            view_state.current_protocol_version,
-        );
         let config = Arc::new(RuntimeConfig::default());
         let apply_state = ApplyState {
             block_index: view_state.block_height,
@@ -147,8 +175,71 @@ impl TrieViewer {
             #[cfg(feature = "costs_counting")]
             profile: None,
         };
+        let function_call = FunctionCallAction {
+            method_name: method_name.to_string(),
+            args: args.to_vec(),
+            gas: config.wasm_config.limit_config.max_gas_burnt_view,
+            deposit: 0,
+        };
+        // The view call and the promises it follows share the same gas budget.
+        let mut gas_left = function_call.gas;
+        self.execute_view_function(
+            &mut state_update,
+            &apply_state,
+            epoch_info_provider,
+            contract_id,
+            contract_id,
+            contract_id,
+            &function_call,
+            &[],
+            0,
+            &mut gas_left,
+            logs,
+        )
+        .map_err(Into::into)
+    }
+
+    /// Executes a view function. If it returns a promise, the promise and the promises it depends
+    /// on are executed synchronously as view functions, in the order they were created, and the
+    /// result of the returned one is the result of the function. Only functions called less than
+    /// `max_call_depth` levels deep can create promises. Functions called by promises see the
+    /// contract that created the promise as their predecessor.
+    fn execute_view_function(
+        &self,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        contract_id: &AccountId,
+        signer_id: &AccountId,
+        predecessor_id: &AccountId,
+        function_call: &FunctionCallAction,
+        promise_results: &[PromiseResult],
+        depth: u32,
+        gas_left: &mut Gas,
+        logs: &mut Vec<String>,
+    ) -> Result<Vec<u8>, String> {
+        let now = Instant::now();
+        let mut account = get_account(state_update, contract_id)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("Account {:?} doesn't exist", contract_id))?;
+        // TODO(#1015): Add ability to pass public key and originator_id
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let empty_hash = CryptoHash::default();
+        let mut runtime_ext = RuntimeExt::new(
+            state_update,
+            contract_id,
+            signer_id,
+            &public_key,
+            0,
+            &empty_hash,
+            &apply_state.epoch_id,
+            &apply_state.prev_block_hash,
+            &apply_state.block_hash,
+            epoch_info_provider,
+            apply_state.current_protocol_version,
+        );
         let action_receipt = ActionReceipt {
-            signer_id: originator_id.clone(),
+            signer_id: signer_id.clone(),
             signer_public_key: public_key.clone(),
             gas_price: 0,
             output_data_receivers: vec![],
@@ -156,24 +247,24 @@ impl TrieViewer {
             actions: vec![],
         };
         let function_call = FunctionCallAction {
-            method_name: method_name.to_string(),
-            args: args.to_vec(),
-            gas: config.wasm_config.limit_config.max_gas_burnt_view,
-            deposit: 0,
+            gas: std::cmp::min(function_call.gas, *gas_left),
+            ..function_call.clone()
         };
         let (outcome, err) = execute_function_call(
-            &apply_state,
+            apply_state,
             &mut runtime_ext,
             &mut account,
-            &originator_id,
+            predecessor_id,
             &action_receipt,
-            &[],
+            promise_results,
             &function_call,
             &empty_hash,
-            &config,
+            &apply_state.config,
             true,
             true,
+            depth < self.max_call_depth,
         );
+        let receipts = runtime_ext.into_receipts(contract_id);
         let elapsed = now.elapsed();
         let time_ms =
             (elapsed.as_secs() as f64 / 1_000.0) + f64::from(elapsed.subsec_nanos()) / 1_000_000.0;
@@ -185,17 +276,112 @@ impl TrieViewer {
             }
             let message = format!("wasm execution failed with error: {:?}", err);
             debug!(target: "runtime", "(exec time {}) {}", time_str, message);
-            Err(message.into())
-        } else {
-            let outcome = outcome.unwrap();
-            debug!(target: "runtime", "(exec time {}) result of execution: {:#?}", time_str, outcome);
-            logs.extend(outcome.logs);
-            let mut result = vec![];
-            if let ReturnData::Value(buf) = &outcome.return_data {
-                result = buf.clone();
+            return Err(message);
+        }
+        let outcome = outcome.unwrap();
+        debug!(target: "runtime", "(exec time {}) result of execution: {:#?}", time_str, outcome);
+        *gas_left = gas_left.saturating_sub(outcome.burnt_gas);
+        logs.extend(outcome.logs);
+        match outcome.return_data {
+            ReturnData::Value(buf) => Ok(buf),
+            ReturnData::ReceiptIndex(receipt_index) => self.execute_view_receipts(
+                state_update,
+                apply_state,
+                epoch_info_provider,
+                &receipts,
+                receipt_index as usize,
+                depth + 1,
+                gas_left,
+                logs,
+            ),
+            ReturnData::None => Ok(vec![]),
+        }
+    }
+
+    /// Executes the receipt at `receipt_index` and the receipts it depends on, and returns its
+    /// result. Receipts only depend on receipts created before them, so executing the needed ones
+    /// in order makes the results of their dependencies available to them.
+    fn execute_view_receipts(
+        &self,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        receipts: &[Receipt],
+        receipt_index: usize,
+        depth: u32,
+        gas_left: &mut Gas,
+        logs: &mut Vec<String>,
+    ) -> Result<Vec<u8>, String> {
+        let action_receipt = |receipt: &Receipt| match &receipt.receipt {
+            ReceiptEnum::Action(action_receipt) => action_receipt,
+            ReceiptEnum::Data(_) => unreachable!("Function calls only create action receipts"),
+        };
+        let mut producers = HashMap::new();
+        for (index, receipt) in receipts.iter().enumerate() {
+            for data_receiver in &action_receipt(receipt).output_data_receivers {
+                producers.insert(data_receiver.data_id, index);
+            }
+        }
+        let mut needed = vec![false; receipt_index + 1];
+        needed[receipt_index] = true;
+        for index in (0..=receipt_index).rev() {
+            if needed[index] {
+                for data_id in &action_receipt(&receipts[index]).input_data_ids {
+                    needed[producers[data_id]] = true;
+                }
+            }
+        }
+
+        let mut data = HashMap::new();
+        let mut result = Ok(vec![]);
+        for (index, receipt) in receipts[..=receipt_index].iter().enumerate() {
+            if !needed[index] {
+                continue;
+            }
+            let action_receipt = action_receipt(receipt);
+            let promise_results: Vec<_> = action_receipt
+                .input_data_ids
+                .iter()
+                .map(|data_id| match data.get(data_id) {
+                    Some(Ok(value)) => PromiseResult::Successful(Vec::clone(value)),
+                    _ => PromiseResult::Failed,
+                })
+                .collect();
+            result = Ok(vec![]);
+            for action in &action_receipt.actions {
+                let function_call = match action {
+                    Action::FunctionCall(function_call) => function_call,
+                    _ => unreachable!("View functions can only create function calls"),
+                };
+                result = self
+                    .execute_view_function(
+                        state_update,
+                        apply_state,
+                        epoch_info_provider,
+                        &receipt.receiver_id,
+                        &action_receipt.signer_id,
+                        &receipt.predecessor_id,
+                        function_call,
+                        &promise_results,
+                        depth,
+                        gas_left,
+                        logs,
+                    )
+                    .map_err(|err| {
+                        format!(
+                            "call of {} on {} failed: {}",
+                            function_call.method_name, receipt.receiver_id, err
+                        )
+                    });
+                if result.is_err() {
+                    break;
+                }
+            }
+            for data_receiver in &action_receipt.output_data_receivers {
+                data.insert(data_receiver.data_id, result.clone());
             }
-            Ok(result)
         }
+        result
     }
 }
 
@@ -204,11 +390,16 @@ mod tests {
     #[cfg(feature = "protocol_feature_evm")]
     use near_chain_configs::TESTNET_EVM_CHAIN_ID;
     use near_primitives::{
+        account::Account,
+        state_proof::{verify_account_in_proof, verify_state_items_in_proof, StateProofError},
         test_utils::MockEpochInfoProvider,
         trie_key::TrieKey,
         types::{EpochId, StateChangeCause},
         version::PROTOCOL_VERSION,
+        views::{AccountView, StateProofView},
     };
+    use near_store::{set_account, set_code};
+    use std::rc::Rc;
     use testlib::runtime_utils::{
         alice_account, encode_int, get_runtime_and_trie, get_test_trie_viewer,
     };
@@ -368,7 +559,8 @@ mod tests {
 
         let state_update = tries.new_trie_update(0, new_root);
         let trie_viewer = TrieViewer::new();
-        let result = trie_viewer.view_state(&state_update, &alice_account(), b"").unwrap();
+        let result =
+            trie_viewer.view_state(&state_update, &alice_account(), b"", None, None).unwrap();
         assert_eq!(result.proof, Vec::<String>::new());
         assert_eq!(
             result.values,
@@ -385,9 +577,47 @@ mod tests {
                 }
             ]
         );
-        let result = trie_viewer.view_state(&state_update, &alice_account(), b"xyz").unwrap();
+        let result =
+            trie_viewer.view_state(&state_update, &alice_account(), b"xyz", None, None).unwrap();
         assert_eq!(result.values, []);
-        let result = trie_viewer.view_state(&state_update, &alice_account(), b"test123").unwrap();
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), b"test123", None, None)
+            .unwrap();
+        assert_eq!(
+            result.values,
+            [StateItem {
+                key: "dGVzdDEyMw==".to_string(),
+                value: "MTIz".to_string(),
+                proof: vec![]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_view_state_pagination() {
+        let (_, tries, root) = get_runtime_and_trie();
+        let mut state_update = tries.new_trie_update(0, root);
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: b"test123".to_vec() },
+            b"123".to_vec(),
+        );
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: b"test321".to_vec() },
+            b"321".to_vec(),
+        );
+        state_update.set(
+            TrieKey::ContractData { account_id: "alina".to_string(), key: b"qqq".to_vec() },
+            b"321".to_vec(),
+        );
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (db_changes, new_root) = tries.apply_all(&trie_changes, 0).unwrap();
+        db_changes.commit().unwrap();
+
+        let state_update = tries.new_trie_update(0, new_root);
+        let trie_viewer = TrieViewer::new();
+        let result =
+            trie_viewer.view_state(&state_update, &alice_account(), b"", None, Some(1)).unwrap();
         assert_eq!(
             result.values,
             [StateItem {
@@ -396,6 +626,137 @@ mod tests {
                 proof: vec![]
             }]
         );
+        assert_eq!(result.next_cursor, Some("dGVzdDMyMQ==".to_string()));
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), b"", Some(b"test321"), Some(1))
+            .unwrap();
+        assert_eq!(
+            result.values,
+            [StateItem {
+                key: "dGVzdDMyMQ==".to_string(),
+                value: "MzIx".to_string(),
+                proof: vec![]
+            }]
+        );
+        assert_eq!(result.next_cursor, None);
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), b"test1", Some(b"test2"), None)
+            .unwrap();
+        assert_eq!(result.values, []);
+        assert_eq!(result.next_cursor, None);
+    }
+
+    #[test]
+    fn test_view_state_limits() {
+        let (_, tries, root) = get_runtime_and_trie();
+        let mut state_update = tries.new_trie_update(0, root);
+        for key in [b"test123", b"test321", b"test456"].iter() {
+            state_update.set(
+                TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+                key.to_vec(),
+            );
+        }
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (db_changes, new_root) = tries.apply_all(&trie_changes, 0).unwrap();
+        db_changes.commit().unwrap();
+
+        let state_update = tries.new_trie_update(0, new_root);
+        let trie_viewer = TrieViewer::new().with_max_state_values(2);
+        assert!(trie_viewer
+            .view_state(&state_update, &alice_account(), b"", None, Some(0))
+            .is_err());
+        for limit in [None, Some(2), Some(10)].iter() {
+            let result =
+                trie_viewer.view_state(&state_update, &alice_account(), b"", None, *limit).unwrap();
+            assert_eq!(result.values.len(), 2);
+            assert_eq!(result.next_cursor, Some("dGVzdDQ1Ng==".to_string()));
+        }
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), b"", Some(b"test456"), Some(10))
+            .unwrap();
+        assert_eq!(result.values.len(), 1);
+        assert_eq!(result.next_cursor, None);
+
+        let trie_viewer = TrieViewer::new().with_max_state_values(0);
+        let result =
+            trie_viewer.view_state(&state_update, &alice_account(), b"", None, None).unwrap();
+        assert_eq!(result.values.len(), 1);
+        assert_eq!(result.next_cursor, Some("dGVzdDMyMQ==".to_string()));
+    }
+
+    #[test]
+    fn test_view_state_proof() {
+        let (_, tries, root) = get_runtime_and_trie();
+        let mut state_update = tries.new_trie_update(0, root);
+        for key in [b"test123", b"test321", b"test456"].iter() {
+            state_update.set(
+                TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+                key.to_vec(),
+            );
+        }
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (db_changes, new_root) = tries.apply_all(&trie_changes, 0).unwrap();
+        db_changes.commit().unwrap();
+
+        let trie_viewer = TrieViewer::new();
+        let get_proof = |state_update: &TrieUpdate| StateProofView {
+            state_root: new_root,
+            nodes: state_update
+                .trie
+                .recorded_storage()
+                .unwrap()
+                .nodes
+                .0
+                .iter()
+                .map(to_base64)
+                .collect(),
+        };
+
+        let state_update =
+            TrieUpdate::new(Rc::new(tries.get_trie_for_shard(0).recording_reads()), new_root);
+        let account = trie_viewer.view_account(&state_update, &alice_account()).unwrap();
+        let proof = get_proof(&state_update);
+        verify_account_in_proof(&alice_account(), &account.clone().into(), &proof).unwrap();
+        let mut tampered_account: AccountView = account.into();
+        tampered_account.amount += 1;
+        assert!(matches!(
+            verify_account_in_proof(&alice_account(), &tampered_account, &proof),
+            Err(StateProofError::ValueMismatch(_))
+        ));
+
+        let state_update =
+            TrieUpdate::new(Rc::new(tries.get_trie_for_shard(0).recording_reads()), new_root);
+        let result = trie_viewer
+            .view_state(&state_update, &alice_account(), b"", Some(b"test2"), Some(1))
+            .unwrap();
+        let proof = get_proof(&state_update);
+        verify_state_items_in_proof(&alice_account(), b"", Some(b"test2"), &result, &proof)
+            .unwrap();
+        let mut tampered_result = result.clone();
+        tampered_result.values[0].value = to_base64(b"test322");
+        assert!(matches!(
+            verify_state_items_in_proof(
+                &alice_account(),
+                b"",
+                Some(b"test2"),
+                &tampered_result,
+                &proof
+            ),
+            Err(StateProofError::ValueMismatch(_))
+        ));
+        tampered_result.values.clear();
+        assert_eq!(
+            verify_state_items_in_proof(
+                &alice_account(),
+                b"",
+                Some(b"test2"),
+                &tampered_result,
+                &proof
+            ),
+            Err(StateProofError::IncompleteValues)
+        );
     }
 
     #[test]
@@ -428,4 +789,117 @@ mod tests {
 
         assert_eq!(logs, vec!["hello".to_string()]);
     }
+
+    /// Contract calling `test.contract` and itself through promises.
+    const CALLER_CONTRACT: &str = r#"
+        (module
+          (import "env" "promise_create"
+            (func $promise_create (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+          (import "env" "promise_then"
+            (func $promise_then (param i64 i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+          (import "env" "promise_return" (func $promise_return (param i64)))
+          (import "env" "promise_result" (func $promise_result (param i64 i64) (result i64)))
+          (import "env" "value_return" (func $value_return (param i64 i64)))
+          (memory 1)
+          (data (i32.const 0) "test.contract")
+          (data (i32.const 16) "run_test")
+          (data (i32.const 32) "caller.near")
+          (data (i32.const 48) "callback")
+          (data (i32.const 64) "panic_after_logging")
+          (data (i32.const 96) "recurse")
+          (data (i32.const 136) "failed")
+          (func $call_test_contract (param $method_len i64) (param $method_ptr i64) (result i64)
+            (call $promise_create (i64.const 13) (i64.const 0)
+              (local.get $method_len) (local.get $method_ptr)
+              (i64.const 0) (i64.const 0) (i64.const 112) (i64.const 10000000000000)))
+          (func $then_callback (param $promise i64) (result i64)
+            (call $promise_then (local.get $promise) (i64.const 11) (i64.const 32)
+              (i64.const 8) (i64.const 48) (i64.const 0) (i64.const 0) (i64.const 112)
+              (i64.const 10000000000000)))
+          (func (export "call")
+            (call $promise_return (call $call_test_contract (i64.const 8) (i64.const 16))))
+          (func (export "call_with_callback")
+            (call $promise_return
+              (call $then_callback (call $call_test_contract (i64.const 8) (i64.const 16)))))
+          (func (export "call_failing_with_callback")
+            (call $promise_return
+              (call $then_callback (call $call_test_contract (i64.const 19) (i64.const 64)))))
+          (func (export "callback")
+            (if (i64.eq (call $promise_result (i64.const 0) (i64.const 0)) (i64.const 1))
+              (then (call $value_return (i64.const -1) (i64.const 0)))
+              (else (call $value_return (i64.const 6) (i64.const 136)))))
+          (func (export "recurse")
+            (call $promise_return
+              (call $promise_create (i64.const 11) (i64.const 32) (i64.const 7) (i64.const 96)
+                (i64.const 0) (i64.const 0) (i64.const 112) (i64.const 10000000000000)))))"#;
+
+    fn view_caller_contract(
+        max_call_depth: u32,
+        method_name: &str,
+        logs: &mut Vec<String>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (_, mut state_update) = get_test_trie_viewer();
+        let code = ContractCode::new(wabt::wat2wasm(CALLER_CONTRACT).unwrap(), None);
+        let account = Account { amount: 0, locked: 0, code_hash: code.hash, storage_usage: 0 };
+        set_code(&mut state_update, "caller.near".to_string(), &code);
+        set_account(&mut state_update, "caller.near".to_string(), &account);
+        let view_state = ViewApplyState {
+            block_height: 1,
+            prev_block_hash: CryptoHash::default(),
+            block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            epoch_height: 0,
+            block_timestamp: 1,
+            current_protocol_version: PROTOCOL_VERSION,
+            cache: None,
+            #[cfg(feature = "protocol_feature_evm")]
+            evm_chain_id: 0x99,
+        };
+        TrieViewer::with_max_call_depth(max_call_depth).call_function(
+            state_update,
+            view_state,
+            &AccountId::from("caller.near"),
+            method_name,
+            &[],
+            logs,
+            &MockEpochInfoProvider::default(),
+        )
+    }
+
+    #[test]
+    fn test_view_call_following_promises() {
+        let mut logs = vec![];
+        let result = view_caller_contract(DEFAULT_MAX_VIEW_CALL_DEPTH, "call", &mut logs);
+        assert_eq!(result.unwrap(), encode_int(10));
+
+        let result =
+            view_caller_contract(DEFAULT_MAX_VIEW_CALL_DEPTH, "call_with_callback", &mut logs);
+        assert_eq!(result.unwrap(), encode_int(10));
+
+        let result = view_caller_contract(
+            DEFAULT_MAX_VIEW_CALL_DEPTH,
+            "call_failing_with_callback",
+            &mut logs,
+        );
+        assert_eq!(result.unwrap(), b"failed".to_vec());
+        assert_eq!(logs, vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn test_view_call_promises_depth() {
+        let mut logs = vec![];
+        let err = view_caller_contract(0, "call", &mut logs).unwrap_err();
+        assert!(
+            err.to_string().contains(r#"ProhibitedInView { method_name: "promise_batch_create" }"#),
+            format!("Got different error that doesn't match: {}", err)
+        );
+
+        let err = view_caller_contract(2, "recurse", &mut logs).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                r#"call of recurse on caller.near failed: call of recurse on caller.near failed: wasm execution failed with error: FunctionCallError(HostError(ProhibitedInView { method_name: "promise_batch_create" }))"#
+            ),
+            format!("Got different error that doesn't match: {}", err)
+        );
+    }
 }
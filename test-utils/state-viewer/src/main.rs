@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use ansi_term::Color::Red;
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
 use borsh::BorshSerialize;
 use near_chain::chain::collect_receipts_from_response;
@@ -21,10 +21,11 @@ use near_primitives::serialize::to_base;
 use near_primitives::state_record::StateRecord;
 use near_primitives::types::{BlockHeight, ChunkExtra, ShardId, StateRoot};
 use near_store::test_utils::create_test_store;
-use near_store::{create_store, Store, TrieIterator};
+use near_store::{create_store_with_backend, ColumnStats, DBCol, Store, TrieIterator};
 use neard::{get_default_home, get_store_path, load_config, NearConfig, NightshadeRuntime};
 use node_runtime::adapter::ViewRuntimeAdapter;
 use state_dump::state_dump;
+use strum::IntoEnumIterator;
 
 mod state_dump;
 
@@ -60,6 +61,7 @@ fn load_trie_stop_at_height(
         &near_config.genesis,
         near_config.client_config.tracked_accounts.clone(),
         near_config.client_config.tracked_shards.clone(),
+        near_config.client_config.max_view_call_depth,
     );
     let head = chain_store.head().unwrap();
     let last_block = match mode {
@@ -116,6 +118,7 @@ fn print_chain(
         &near_config.genesis,
         near_config.client_config.tracked_accounts.clone(),
         near_config.client_config.tracked_shards.clone(),
+        near_config.client_config.max_view_call_depth,
     );
     let mut account_id_to_blocks = HashMap::new();
     let mut cur_epoch_id = None;
@@ -185,6 +188,7 @@ fn replay_chain(
         &near_config.genesis,
         near_config.client_config.tracked_accounts.clone(),
         near_config.client_config.tracked_shards.clone(),
+        near_config.client_config.max_view_call_depth,
     );
     for height in start_height..=end_height {
         if let Ok(block_hash) = chain_store.get_block_hash_by_height(height) {
@@ -213,6 +217,7 @@ fn apply_block_at_height(
         &near_config.genesis,
         near_config.client_config.tracked_accounts.clone(),
         near_config.client_config.tracked_shards.clone(),
+        near_config.client_config.max_view_call_depth,
     );
     let block_hash = chain_store.get_block_hash_by_height(height).unwrap();
     let block = chain_store.get_block(&block_hash).unwrap().clone();
@@ -361,6 +366,57 @@ fn check_block_chunk_existence(store: Arc<Store>, near_config: &NearConfig) {
     println!("Block check succeed");
 }
 
+fn print_db_stats(store: Arc<Store>, columns: Vec<DBCol>) {
+    let rocksdb = store.get_rocksdb();
+    println!(
+        "{:<28} {:>12} {:>16} {:>16} {:>16}",
+        "column", "keys", "key bytes", "value bytes", "sst bytes"
+    );
+    let mut total = ColumnStats::default();
+    let mut total_sst_bytes = 0;
+    for col in columns {
+        let stats = store.column_stats(col);
+        // SST files are specific to RocksDB, other backends report no size.
+        let sst_bytes = rocksdb
+            .map(|rocksdb| rocksdb.column_size_estimates(col).unwrap().sst_bytes)
+            .unwrap_or_default();
+        println!(
+            "{:<28} {:>12} {:>16} {:>16} {:>16}",
+            format!("{:?}", col),
+            stats.num_keys,
+            stats.key_bytes,
+            stats.value_bytes,
+            sst_bytes
+        );
+        total.num_keys += stats.num_keys;
+        total.key_bytes += stats.key_bytes;
+        total.value_bytes += stats.value_bytes;
+        total_sst_bytes += sst_bytes;
+    }
+    println!(
+        "{:<28} {:>12} {:>16} {:>16} {:>16}",
+        "total", total.num_keys, total.key_bytes, total.value_bytes, total_sst_bytes
+    );
+}
+
+fn compact_columns(store: Arc<Store>, columns: Vec<DBCol>) {
+    let rocksdb = store.get_rocksdb().expect("Only RocksDB databases can be compacted");
+    for col in columns {
+        let before = rocksdb.column_size_estimates(col).unwrap().sst_bytes;
+        println!("Compacting {:?}", col);
+        rocksdb.compact(col);
+        let after = rocksdb.column_size_estimates(col).unwrap().sst_bytes;
+        println!("Compacted {:?}, sst bytes {} -> {}", col, before, after);
+    }
+}
+
+fn parse_columns(args: &ArgMatches) -> Vec<DBCol> {
+    match args.values_of("col") {
+        Some(values) => values.map(|value| value.parse().unwrap()).collect(),
+        None => DBCol::iter().collect(),
+    }
+}
+
 fn dump_code(account: &str, contract_code: ContractCode, output: &str) {
     let mut file = File::create(output).unwrap();
     file.write_all(&contract_code.code).unwrap();
@@ -485,12 +541,37 @@ fn main() {
                 )
                 .help("dump deployed contract code of given account to wasm file"),
         )
+        .subcommand(
+            SubCommand::with_name("db_stats")
+                .alias("db-stats")
+                .arg(
+                    Arg::with_name("col")
+                        .long("col")
+                        .help("Column to report, e.g. ColState (default all)")
+                        .takes_value(true)
+                        .multiple(true),
+                )
+                .help("print number of keys, key and value bytes and sst size of database columns"),
+        )
+        .subcommand(
+            SubCommand::with_name("compact")
+                .arg(
+                    Arg::with_name("col")
+                        .long("col")
+                        .help("Column to compact, e.g. ColState")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(true),
+                )
+                .help("compact database columns, the node must be stopped"),
+        )
         .get_matches();
 
     let home_dir = matches.value_of("home").map(|dir| Path::new(dir)).unwrap();
     let near_config = load_config(home_dir);
 
-    let store = create_store(&get_store_path(&home_dir));
+    let store =
+        create_store_with_backend(&get_store_path(&home_dir), near_config.config.store_backend);
 
     match matches.subcommand() {
         ("peers", Some(_args)) => {
@@ -583,6 +664,12 @@ fn main() {
                 account_id
             );
         }
+        ("db_stats", Some(args)) => {
+            print_db_stats(store, parse_columns(args));
+        }
+        ("compact", Some(args)) => {
+            compact_columns(store, parse_columns(args));
+        }
         (_, _) => unreachable!(),
     }
 }
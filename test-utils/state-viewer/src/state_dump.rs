@@ -91,7 +91,7 @@ mod test {
         genesis.config.epoch_length = epoch_length;
         let store = create_test_store();
         let nightshade_runtime =
-            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![]);
+            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![], None);
         let runtimes: Vec<Arc<dyn RuntimeAdapter>> = vec![Arc::new(nightshade_runtime)];
         let mut chain_genesis = ChainGenesis::test();
         chain_genesis.epoch_length = epoch_length;
@@ -133,7 +133,7 @@ mod test {
         let last_block = env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
         let state_roots = last_block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
         let runtime =
-            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![]);
+            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![], None);
         let new_genesis =
             state_dump(runtime, state_roots, last_block.header().clone(), &genesis.config);
         assert_eq!(new_genesis.config.validators.len(), 2);
@@ -163,7 +163,7 @@ mod test {
         let last_block = env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
         let state_roots = last_block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
         let runtime =
-            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![]);
+            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![], None);
         let new_genesis =
             state_dump(runtime, state_roots, last_block.header().clone(), &genesis.config);
         assert_eq!(
@@ -191,7 +191,7 @@ mod test {
         let store1 = create_test_store();
         let store2 = create_test_store();
         let create_runtime = |store| -> NightshadeRuntime {
-            NightshadeRuntime::new(Path::new("."), store, &genesis, vec![], vec![])
+            NightshadeRuntime::new(Path::new("."), store, &genesis, vec![], vec![], None)
         };
         let runtimes: Vec<Arc<dyn RuntimeAdapter>> = vec![
             Arc::new(create_runtime(store1.clone())),
@@ -240,7 +240,7 @@ mod test {
         genesis.config.epoch_length = epoch_length;
         let store = create_test_store();
         let nightshade_runtime =
-            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![]);
+            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![], None);
         let runtimes: Vec<Arc<dyn RuntimeAdapter>> = vec![Arc::new(nightshade_runtime)];
         let mut chain_genesis = ChainGenesis::test();
         chain_genesis.epoch_length = epoch_length;
@@ -273,7 +273,7 @@ mod test {
         let last_block = env.clients[0].chain.get_block(&head.last_block_hash).unwrap().clone();
         let state_roots = last_block.chunks().iter().map(|chunk| chunk.prev_state_root()).collect();
         let runtime =
-            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![]);
+            NightshadeRuntime::new(Path::new("."), store.clone(), &genesis, vec![], vec![], None);
         let new_genesis =
             state_dump(runtime, state_roots, last_block.header().clone(), &genesis.config);
         assert_eq!(new_genesis.config.validators.len(), 2);
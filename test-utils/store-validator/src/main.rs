@@ -8,7 +8,7 @@ use clap::{App, Arg, SubCommand};
 use near_chain::store_validator::StoreValidator;
 use near_chain::RuntimeAdapter;
 use near_logger_utils::init_integration_logger;
-use near_store::create_store;
+use near_store::create_store_with_backend;
 use neard::{get_default_home, get_store_path, load_config};
 
 fn main() {
@@ -29,7 +29,8 @@ fn main() {
     let home_dir = matches.value_of("home").map(|dir| Path::new(dir)).unwrap();
     let near_config = load_config(home_dir);
 
-    let store = create_store(&get_store_path(&home_dir));
+    let store =
+        create_store_with_backend(&get_store_path(&home_dir), near_config.config.store_backend);
 
     let runtime_adapter: Arc<dyn RuntimeAdapter> = Arc::new(neard::NightshadeRuntime::new(
         &home_dir,
@@ -37,6 +38,7 @@ fn main() {
         &near_config.genesis,
         near_config.client_config.tracked_accounts.clone(),
         near_config.client_config.tracked_shards.clone(),
+        near_config.client_config.max_view_call_depth,
     ));
 
     let mut store_validator = StoreValidator::new(
@@ -295,6 +295,7 @@ fn main() {
                 home_dir,
                 sync_mode: near_indexer::SyncModeEnum::FromInterruption,
                 await_for_node_synced: near_indexer::AwaitForNodeSyncedEnum::WaitForFullSync,
+                filter: None,
             };
             actix::System::builder()
                 .stop_on_panic(true)